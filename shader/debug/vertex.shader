@@ -0,0 +1,29 @@
+#version 450
+
+layout(location=0) in vec3 a_position;
+layout(location=1) in vec2 a_offset;
+layout(location=2) in vec4 a_color;
+layout(location=3) in float a_on_top;
+
+layout(location=0) out vec4 v_color;
+
+layout(set=0, binding=0)
+uniform Uniforms {
+    mat4 projection;
+};
+layout(set=0, binding=1)
+uniform Uniforms2 {
+    mat4 view;
+};
+
+void main() {
+    //Text labels face the camera, the offset is in view space
+    vec4 view_pos = view * vec4(a_position, 1.0);
+    view_pos.xy += a_offset;
+    gl_Position = projection * view_pos;
+    //Lines without depth test are moved to the near plane
+    if (a_on_top > 0.5) {
+        gl_Position.z = 0.0;
+    }
+    v_color = a_color;
+}
\ No newline at end of file
@@ -0,0 +1,44 @@
+//Average luminance of the histogram (without the black pixels), adapted over time. Clears the histogram for the next frame.
+struct ExposureData {
+    //min log2 luminance, 1 / log2 luminance range, adaptation speed, delta time
+    params: vec4<f32>,
+    //pixel count
+    params2: vec4<f32>,
+};
+
+struct Exposure {
+    luminance: f32,
+    exposure: f32,
+};
+
+@group(0) @binding(0) var t_hdr: texture_2d<f32>;
+@group(0) @binding(1) var<storage, read_write> histogram: array<atomic<u32>, 256>;
+@group(0) @binding(2) var<uniform> data: ExposureData;
+@group(0) @binding(3) var<storage, read_write> exposure: Exposure;
+
+var<workgroup> weighted: array<f32, 256>;
+
+@compute @workgroup_size(256)
+fn main(@builtin(local_invocation_index) index: u32) {
+    let count = atomicLoad(&histogram[index]);
+    weighted[index] = f32(count) * f32(index);
+    atomicStore(&histogram[index], 0u);
+    workgroupBarrier();
+
+    for (var stride = 128u; stride > 0u; stride = stride >> 1u) {
+        if (index < stride) {
+            weighted[index] = weighted[index] + weighted[index + stride];
+        }
+        workgroupBarrier();
+    }
+
+    //count is the amount of black pixels for index 0
+    let pixels = data.params2.x - f32(count);
+    if (index == 0u && pixels >= 1.0) {
+        let bin = weighted[0] / pixels - 1.0;
+        let average = exp2(bin / 254.0 / data.params.y + data.params.x);
+        let adapted = exposure.luminance + (average - exposure.luminance) * (1.0 - exp(-data.params.w * data.params.z));
+        exposure.luminance = adapted;
+        exposure.exposure = 0.18 / max(adapted, 0.0001);
+    }
+}
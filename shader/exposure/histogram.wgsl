@@ -0,0 +1,43 @@
+//Luminance histogram of the hdr image, bin 0 counts the (nearly) black pixels
+struct ExposureData {
+    //min log2 luminance, 1 / log2 luminance range, adaptation speed, delta time
+    params: vec4<f32>,
+    //pixel count
+    params2: vec4<f32>,
+};
+
+struct Exposure {
+    luminance: f32,
+    exposure: f32,
+};
+
+@group(0) @binding(0) var t_hdr: texture_2d<f32>;
+@group(0) @binding(1) var<storage, read_write> histogram: array<atomic<u32>, 256>;
+@group(0) @binding(2) var<uniform> data: ExposureData;
+@group(0) @binding(3) var<storage, read_write> exposure: Exposure;
+
+var<workgroup> local_bins: array<atomic<u32>, 256>;
+
+fn get_bin(color: vec3<f32>) -> u32 {
+    let luminance = dot(color, vec3<f32>(0.2126, 0.7152, 0.0722));
+    if (luminance < 0.005) {
+        return 0u;
+    }
+    let log_luminance = clamp((log2(luminance) - data.params.x) * data.params.y, 0.0, 1.0);
+    return u32(log_luminance * 254.0 + 1.0);
+}
+
+@compute @workgroup_size(16, 16)
+fn main(@builtin(global_invocation_id) global_id: vec3<u32>, @builtin(local_invocation_index) local_index: u32) {
+    atomicStore(&local_bins[local_index], 0u);
+    workgroupBarrier();
+
+    let size = textureDimensions(t_hdr);
+    if (global_id.x < size.x && global_id.y < size.y) {
+        let color = textureLoad(t_hdr, vec2<i32>(global_id.xy), 0).rgb;
+        atomicAdd(&local_bins[get_bin(color)], 1u);
+    }
+    workgroupBarrier();
+
+    atomicAdd(&histogram[local_index], atomicLoad(&local_bins[local_index]));
+}
@@ -0,0 +1,24 @@
+#version 450
+
+layout(location=0) in vec2 v_tex_coords;
+layout(location=0) out vec4 f_color;
+
+layout(set = 0, binding = 0) uniform texture2D t_input;
+layout(set = 0, binding = 1) uniform texture2D t_extra;
+layout(set = 0, binding = 2) uniform sampler s_input;
+layout(set = 0, binding = 3)
+uniform EffectData {
+    //width, height, 1 / width, 1 / height of the input
+    vec4 screen;
+    //time in seconds, frame, 1 if the input is linear
+    vec4 time;
+    vec4 params;
+    vec4 params2;
+};
+
+//Adds the blurred bright parts (extra) to the scene, params: intensity
+void main() {
+    vec4 color = texture(sampler2D(t_input, s_input), v_tex_coords);
+    vec3 bloom = texture(sampler2D(t_extra, s_input), v_tex_coords).rgb;
+    f_color = vec4(color.rgb + bloom * params.x, color.a);
+}
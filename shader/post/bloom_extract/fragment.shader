@@ -0,0 +1,36 @@
+#version 450
+
+layout(location=0) in vec2 v_tex_coords;
+layout(location=0) out vec4 f_color;
+
+layout(set = 0, binding = 0) uniform texture2D t_input;
+layout(set = 0, binding = 1) uniform texture2D t_extra;
+layout(set = 0, binding = 2) uniform sampler s_input;
+layout(set = 0, binding = 3)
+uniform EffectData {
+    //width, height, 1 / width, 1 / height of the input
+    vec4 screen;
+    //time in seconds, frame, 1 if the input is linear
+    vec4 time;
+    vec4 params;
+    vec4 params2;
+};
+
+//Renders into a half size target, params: threshold, knee
+void main() {
+    vec2 texel = screen.zw * 0.5;
+    vec3 color = texture(sampler2D(t_input, s_input), v_tex_coords + vec2(-texel.x, -texel.y)).rgb;
+    color += texture(sampler2D(t_input, s_input), v_tex_coords + vec2(texel.x, -texel.y)).rgb;
+    color += texture(sampler2D(t_input, s_input), v_tex_coords + vec2(-texel.x, texel.y)).rgb;
+    color += texture(sampler2D(t_input, s_input), v_tex_coords + vec2(texel.x, texel.y)).rgb;
+    color *= 0.25;
+
+    float threshold = params.x;
+    float knee = max(params.y, 0.0001);
+    float brightness = max(color.r, max(color.g, color.b));
+    float soft = clamp(brightness - threshold + knee, 0.0, 2.0 * knee);
+    soft = soft * soft / (4.0 * knee);
+    float contribution = max(soft, brightness - threshold) / max(brightness, 0.0001);
+
+    f_color = vec4(color * contribution, 1.0);
+}
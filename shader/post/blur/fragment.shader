@@ -0,0 +1,28 @@
+#version 450
+
+layout(location=0) in vec2 v_tex_coords;
+layout(location=0) out vec4 f_color;
+
+layout(set = 0, binding = 0) uniform texture2D t_input;
+layout(set = 0, binding = 1) uniform texture2D t_extra;
+layout(set = 0, binding = 2) uniform sampler s_input;
+layout(set = 0, binding = 3)
+uniform EffectData {
+    //width, height, 1 / width, 1 / height of the input
+    vec4 screen;
+    //time in seconds, frame, 1 if the input is linear
+    vec4 time;
+    vec4 params;
+    vec4 params2;
+};
+
+//9 tap gaussian blur using linear filtering, params: direction * radius
+void main() {
+    vec2 offset = params.xy * screen.zw;
+    vec3 color = texture(sampler2D(t_input, s_input), v_tex_coords).rgb * 0.2270270270;
+    color += texture(sampler2D(t_input, s_input), v_tex_coords + offset * 1.3846153846).rgb * 0.3162162162;
+    color += texture(sampler2D(t_input, s_input), v_tex_coords - offset * 1.3846153846).rgb * 0.3162162162;
+    color += texture(sampler2D(t_input, s_input), v_tex_coords + offset * 3.2307692308).rgb * 0.0702702703;
+    color += texture(sampler2D(t_input, s_input), v_tex_coords - offset * 3.2307692308).rgb * 0.0702702703;
+    f_color = vec4(color, 1.0);
+}
@@ -0,0 +1,26 @@
+#version 450
+
+layout(location=0) in vec2 v_tex_coords;
+layout(location=0) out vec4 f_color;
+
+layout(set = 0, binding = 0) uniform texture2D t_input;
+layout(set = 0, binding = 1) uniform texture2D t_extra;
+layout(set = 0, binding = 2) uniform sampler s_input;
+layout(set = 0, binding = 3)
+uniform EffectData {
+    //width, height, 1 / width, 1 / height of the input
+    vec4 screen;
+    //time in seconds, frame, 1 if the input is linear
+    vec4 time;
+    vec4 params;
+    vec4 params2;
+};
+
+//params: offset in pixels at the edges of the screen
+void main() {
+    vec2 offset = (v_tex_coords - 0.5) * 2.0 * params.x * screen.zw;
+    vec4 color = texture(sampler2D(t_input, s_input), v_tex_coords);
+    float r = texture(sampler2D(t_input, s_input), v_tex_coords + offset).r;
+    float b = texture(sampler2D(t_input, s_input), v_tex_coords - offset).b;
+    f_color = vec4(r, color.g, b, color.a);
+}
@@ -0,0 +1,63 @@
+#version 450
+
+layout(location=0) in vec2 v_tex_coords;
+layout(location=0) out vec4 f_color;
+
+layout(set = 0, binding = 0) uniform texture2D t_input;
+layout(set = 0, binding = 1) uniform texture2D t_extra;
+layout(set = 0, binding = 2) uniform sampler s_input;
+layout(set = 0, binding = 3)
+uniform EffectData {
+    //width, height, 1 / width, 1 / height of the input
+    vec4 screen;
+    //time in seconds, frame, 1 if the input is linear
+    vec4 time;
+    vec4 params;
+    vec4 params2;
+};
+
+/*
+params: lut strength, exposure (stops), contrast, saturation, params2: 1 if extra is a lut
+The lut is a strip of size * size by size pixels, blue selects the square, red grows to the right and green to the bottom.
+ */
+vec3 to_srgb(vec3 color) {
+    return mix(color * 12.92, 1.055 * pow(color, vec3(1.0 / 2.4)) - 0.055, step(vec3(0.0031308), color));
+}
+
+vec3 from_srgb(vec3 color) {
+    return mix(color / 12.92, pow((color + 0.055) / 1.055, vec3(2.4)), step(vec3(0.04045), color));
+}
+
+vec3 apply_lut(vec3 color) {
+    float size = float(textureSize(sampler2D(t_extra, s_input), 0).y);
+    vec3 scaled = clamp(color, 0.0, 1.0) * (size - 1.0);
+    float slice = floor(scaled.b);
+    float next = min(slice + 1.0, size - 1.0);
+    vec2 uv = vec2((scaled.r + 0.5) / (size * size), (scaled.g + 0.5) / size);
+    vec3 a = texture(sampler2D(t_extra, s_input), uv + vec2(slice / size, 0.0)).rgb;
+    vec3 b = texture(sampler2D(t_extra, s_input), uv + vec2(next / size, 0.0)).rgb;
+    return mix(a, b, scaled.b - slice);
+}
+
+void main() {
+    vec4 input_color = texture(sampler2D(t_input, s_input), v_tex_coords);
+    vec3 color = input_color.rgb * exp2(params.y);
+
+    //Grading is done on the gamma encoded color
+    bool linear = time.z > 0.5;
+    if (linear) {
+        color = to_srgb(max(color, vec3(0.0)));
+    }
+    color = (color - 0.5) * params.z + 0.5;
+    float gray = dot(color, vec3(0.2126, 0.7152, 0.0722));
+    color = mix(vec3(gray), color, params.w);
+
+    //Lut textures are sRGB, sampling them returns linear colors
+    if (params2.x > 0.5) {
+        color = mix(color, to_srgb(apply_lut(color)), params.x);
+    }
+    if (linear) {
+        color = from_srgb(max(color, vec3(0.0)));
+    }
+    f_color = vec4(color, input_color.a);
+}
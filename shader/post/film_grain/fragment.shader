@@ -0,0 +1,31 @@
+#version 450
+
+layout(location=0) in vec2 v_tex_coords;
+layout(location=0) out vec4 f_color;
+
+layout(set = 0, binding = 0) uniform texture2D t_input;
+layout(set = 0, binding = 1) uniform texture2D t_extra;
+layout(set = 0, binding = 2) uniform sampler s_input;
+layout(set = 0, binding = 3)
+uniform EffectData {
+    //width, height, 1 / width, 1 / height of the input
+    vec4 screen;
+    //time in seconds, frame, 1 if the input is linear
+    vec4 time;
+    vec4 params;
+    vec4 params2;
+};
+
+//params: intensity, grain size in pixels
+float hash(vec2 p) {
+    vec3 p3 = fract(vec3(p.xyx) * 0.1031);
+    p3 += dot(p3, p3.yzx + 33.33);
+    return fract((p3.x + p3.y) * p3.z);
+}
+
+void main() {
+    vec4 color = texture(sampler2D(t_input, s_input), v_tex_coords);
+    vec2 pixel = floor(v_tex_coords * screen.xy / max(params.y, 1.0));
+    float noise = hash(pixel + fract(time.y * 0.6180339) * 1000.0) - 0.5;
+    f_color = vec4(max(color.rgb + noise * params.x, vec3(0.0)), color.a);
+}
@@ -0,0 +1,52 @@
+#version 450
+
+layout(location=0) in vec2 v_tex_coords;
+layout(location=0) out vec4 f_color;
+
+layout(set = 0, binding = 0) uniform texture2D t_input;
+layout(set = 0, binding = 1) uniform texture2D t_extra;
+layout(set = 0, binding = 2) uniform sampler s_input;
+layout(set = 0, binding = 3)
+uniform EffectData {
+    //width, height, 1 / width, 1 / height of the input
+    vec4 screen;
+    //time in seconds, frame, 1 if the input is linear
+    vec4 time;
+    vec4 params;
+    vec4 params2;
+};
+
+vec3 sample_input(vec2 uv) {
+    return texture(sampler2D(t_input, s_input), uv).rgb;
+}
+
+//params: span max, reduce mul, reduce min
+void main() {
+    vec2 texel = screen.zw;
+    vec4 center = texture(sampler2D(t_input, s_input), v_tex_coords);
+    vec3 luma = vec3(0.299, 0.587, 0.114);
+
+    float luma_nw = dot(sample_input(v_tex_coords + vec2(-1.0, -1.0) * texel), luma);
+    float luma_ne = dot(sample_input(v_tex_coords + vec2(1.0, -1.0) * texel), luma);
+    float luma_sw = dot(sample_input(v_tex_coords + vec2(-1.0, 1.0) * texel), luma);
+    float luma_se = dot(sample_input(v_tex_coords + vec2(1.0, 1.0) * texel), luma);
+    float luma_m = dot(center.rgb, luma);
+
+    float luma_min = min(luma_m, min(min(luma_nw, luma_ne), min(luma_sw, luma_se)));
+    float luma_max = max(luma_m, max(max(luma_nw, luma_ne), max(luma_sw, luma_se)));
+
+    vec2 dir = vec2(-((luma_nw + luma_ne) - (luma_sw + luma_se)), (luma_nw + luma_sw) - (luma_ne + luma_se));
+    float dir_reduce = max((luma_nw + luma_ne + luma_sw + luma_se) * 0.25 * params.y, params.z);
+    float rcp_dir_min = 1.0 / (min(abs(dir.x), abs(dir.y)) + dir_reduce);
+    dir = clamp(dir * rcp_dir_min, vec2(-params.x), vec2(params.x)) * texel;
+
+    vec3 rgb_a = 0.5 * (sample_input(v_tex_coords + dir * (1.0 / 3.0 - 0.5)) + sample_input(v_tex_coords + dir * (2.0 / 3.0 - 0.5)));
+    vec3 rgb_b = rgb_a * 0.5 + 0.25 * (sample_input(v_tex_coords - dir * 0.5) + sample_input(v_tex_coords + dir * 0.5));
+    float luma_b = dot(rgb_b, luma);
+
+    if (luma_b < luma_min || luma_b > luma_max) {
+        f_color = vec4(rgb_a, center.a);
+    } else {
+        f_color = vec4(rgb_b, center.a);
+    }
+}
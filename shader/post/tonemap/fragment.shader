@@ -0,0 +1,84 @@
+#version 450
+
+layout(location=0) in vec2 v_tex_coords;
+layout(location=0) out vec4 f_color;
+
+layout(set = 0, binding = 0) uniform texture2D t_input;
+layout(set = 0, binding = 1) uniform texture2D t_extra;
+layout(set = 0, binding = 2) uniform sampler s_input;
+layout(set = 0, binding = 3)
+uniform EffectData {
+    //width, height, 1 / width, 1 / height of the input
+    vec4 screen;
+    //time in seconds, frame, 1 if the input is linear
+    vec4 time;
+    vec4 params;
+    vec4 params2;
+};
+layout(set = 1, binding = 0) readonly buffer Exposure {
+    float luminance;
+    float auto_exposure;
+};
+
+/*
+params: operator (0 none, 1 reinhard, 2 aces, 3 agx), exposure (stops), 1 for automatic exposure, white point (reinhard)
+params2: 1 if the output has to be gamma encoded by the shader (not an sRGB format)
+ */
+vec3 reinhard(vec3 color, float white) {
+    return color * (1.0 + color / (white * white)) / (1.0 + color);
+}
+
+vec3 aces(vec3 color) {
+    return clamp((color * (2.51 * color + 0.03)) / (color * (2.43 * color + 0.59) + 0.14), 0.0, 1.0);
+}
+
+vec3 agx_contrast(vec3 x) {
+    vec3 x2 = x * x;
+    vec3 x4 = x2 * x2;
+    return 15.5 * x4 * x2 - 40.14 * x4 * x + 31.96 * x4 - 6.868 * x2 * x + 0.4298 * x2 + 0.1191 * x - 0.00232;
+}
+
+vec3 agx(vec3 color) {
+    const mat3 agx_mat = mat3(
+        0.842479062253094, 0.0423282422610123, 0.0423756549057051,
+        0.0784335999999992, 0.878468636469772, 0.0784336,
+        0.0792237451477643, 0.0791661274605434, 0.879142973793104);
+    const mat3 agx_mat_inv = mat3(
+        1.19687900512017, -0.0528968517574562, -0.0529716355144438,
+        -0.0980208811401368, 1.15190312990417, -0.0980434501171241,
+        -0.0990297440797205, -0.0989611768448433, 1.15107367264116);
+    const float min_ev = -12.47393;
+    const float max_ev = 4.026069;
+
+    color = agx_mat * color;
+    color = clamp(log2(max(color, vec3(1e-10))), min_ev, max_ev);
+    color = (color - min_ev) / (max_ev - min_ev);
+    color = agx_contrast(color);
+    color = agx_mat_inv * color;
+    return pow(max(color, vec3(0.0)), vec3(2.2));
+}
+
+vec3 to_srgb(vec3 color) {
+    return mix(color * 12.92, 1.055 * pow(color, vec3(1.0 / 2.4)) - 0.055, step(vec3(0.0031308), color));
+}
+
+void main() {
+    vec4 hdr = texture(sampler2D(t_input, s_input), v_tex_coords);
+    float exposure = params.z > 0.5 ? auto_exposure * exp2(params.y) : exp2(params.y);
+    vec3 color = max(hdr.rgb * exposure, vec3(0.0));
+
+    int operator = int(params.x);
+    if (operator == 1) {
+        color = reinhard(color, max(params.w, 0.0001));
+    } else if (operator == 2) {
+        color = aces(color);
+    } else if (operator == 3) {
+        color = agx(color);
+    }
+    color = clamp(color, 0.0, 1.0);
+
+    if (params2.x > 0.5) {
+        color = to_srgb(color);
+    }
+    f_color = vec4(color, clamp(hdr.a, 0.0, 1.0));
+}
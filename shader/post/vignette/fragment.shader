@@ -0,0 +1,25 @@
+#version 450
+
+layout(location=0) in vec2 v_tex_coords;
+layout(location=0) out vec4 f_color;
+
+layout(set = 0, binding = 0) uniform texture2D t_input;
+layout(set = 0, binding = 1) uniform texture2D t_extra;
+layout(set = 0, binding = 2) uniform sampler s_input;
+layout(set = 0, binding = 3)
+uniform EffectData {
+    //width, height, 1 / width, 1 / height of the input
+    vec4 screen;
+    //time in seconds, frame, 1 if the input is linear
+    vec4 time;
+    vec4 params;
+    vec4 params2;
+};
+
+//params: intensity, radius, softness, params2: color
+void main() {
+    vec4 color = texture(sampler2D(t_input, s_input), v_tex_coords);
+    float dist = distance(v_tex_coords, vec2(0.5));
+    float vignette = smoothstep(params.y, params.y - max(params.z, 0.0001), dist);
+    f_color = vec4(mix(params2.rgb, color.rgb, mix(1.0, vignette, params.x)), color.a);
+}
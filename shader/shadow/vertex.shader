@@ -0,0 +1,16 @@
+#version 450
+
+layout(location=0) in vec3 a_position;
+
+layout(set=0, binding=0)
+uniform LightMatrix {
+    mat4 light_view_proj;
+};
+layout(set=1, binding=0)
+readonly buffer Uniforms3 {
+    mat4 transform;
+};
+
+void main() {
+    gl_Position = light_view_proj * transform * vec4(a_position, 1.0);
+}
\ No newline at end of file
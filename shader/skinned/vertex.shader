@@ -0,0 +1,45 @@
+#version 450
+
+layout(location=0) in vec3 a_position;
+layout(location=1) in vec2 tex_coords;
+layout(location=2) in vec3 a_normal;
+layout(location=3) in uvec4 a_joints;
+layout(location=4) in vec4 a_weights;
+
+layout(location=0) out vec2 tex_coords_out;
+layout(location=1) out vec3 normal;
+layout(location=2) out vec3 world_pos;
+layout(location=3) out vec3 camera_pos;
+layout(location=4) out float view_depth;
+
+layout(set=1, binding=0)
+uniform Uniforms {
+    mat4 projection;
+};
+layout(set=1, binding=1)
+uniform Uniforms2 {
+    mat4 view;
+};
+layout(set=2, binding=0)
+readonly buffer Uniforms3 {
+    mat4 transform;
+};
+layout(set=3, binding=3)
+readonly buffer Joints {
+    mat4 joints[128];
+};
+
+void main() {
+    mat4 skin = a_weights.x * joints[a_joints.x]
+              + a_weights.y * joints[a_joints.y]
+              + a_weights.z * joints[a_joints.z]
+              + a_weights.w * joints[a_joints.w];
+
+    vec4 world = transform * skin * vec4(a_position, 1.0);
+    gl_Position = projection * view * world;
+    view_depth = -(view * world).z;
+    tex_coords_out = tex_coords;
+    normal = mat3(transform) * mat3(skin) * a_normal;
+    world_pos = world.xyz;
+    camera_pos = -(transpose(mat3(view)) * view[3].xyz);
+}
\ No newline at end of file
@@ -0,0 +1,45 @@
+#version 450
+
+layout(location=0) in vec2 v_tex_coords;
+layout(location=0) out vec4 f_color;
+
+layout(set = 0, binding = 0) uniform texture2D t_from;
+layout(set = 0, binding = 1) uniform texture2D t_to;
+layout(set = 0, binding = 2) uniform sampler s_scene;
+layout(set = 0, binding = 3)
+uniform TransitionData {
+    vec4 color;
+    //progress, effect, softness, aspect ratio
+    vec4 params;
+    //direction of the wipe
+    vec4 direction;
+};
+
+void main() {
+    vec4 from = texture(sampler2D(t_from, s_scene), v_tex_coords);
+    vec4 to = texture(sampler2D(t_to, s_scene), v_tex_coords);
+    float progress = params.x;
+    int effect = int(params.y);
+    float softness = max(params.z, 0.0001);
+
+    if (effect == 0) {
+        //Fade to color and back
+        if (progress < 0.5) {
+            f_color = mix(from, color, progress * 2.0);
+        } else {
+            f_color = mix(color, to, (progress - 0.5) * 2.0);
+        }
+    } else if (effect == 1) {
+        f_color = mix(from, to, progress);
+    } else if (effect == 2) {
+        float t = dot(v_tex_coords - vec2(0.5), direction.xy) + 0.5;
+        float k = clamp((progress * (1.0 + softness) - t) / softness, 0.0, 1.0);
+        f_color = mix(from, to, k);
+    } else {
+        vec2 centered = (v_tex_coords - vec2(0.5)) * vec2(params.w, 1.0);
+        float max_radius = length(vec2(params.w, 1.0) * 0.5);
+        float radius = progress * (max_radius + softness);
+        float k = clamp((radius - length(centered)) / softness, 0.0, 1.0);
+        f_color = mix(from, to, k);
+    }
+}
@@ -0,0 +1,10 @@
+#version 450
+
+layout(location=0) out vec2 v_tex_coords;
+
+//Fullscreen triangle, no vertex buffer needed
+void main() {
+    vec2 pos = vec2(float((gl_VertexIndex << 1) & 2), float(gl_VertexIndex & 2));
+    v_tex_coords = vec2(pos.x, 1.0 - pos.y);
+    gl_Position = vec4(pos * 2.0 - 1.0, 0.0, 1.0);
+}
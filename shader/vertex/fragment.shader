@@ -4,17 +4,147 @@ layout(location=0) out vec4 f_color;
 
 layout(location=0) in vec2 tex_coords_out;
 layout(location=1) in vec3 normal;
+layout(location=2) in vec3 world_pos;
+layout(location=3) in vec3 camera_pos;
+layout(location=4) in float view_depth;
 
 layout(set = 0, binding = 0) uniform texture2D t_diffuse;
 layout(set = 0, binding = 1) uniform sampler s_diffuse;
 
+struct Light {
+    vec4 pos; // w: 0 point, 1 directional, 2 spot
+    vec4 direction; // w: intensity
+    vec4 color; // w: range
+    vec4 attenuation; // constant, linear, quadratic
+    vec4 cone; // cos of the inner and outer angle
+    vec4 shadow; // first shadow map, amount of shadow maps (cascades)
+};
+
+struct ShadowMap {
+    mat4 view_proj;
+    vec4 rect; // region in the shadow atlas (uv)
+    vec4 params; // bias, pcf radius, far distance of the cascade, texel size
+};
+
 layout(set = 3, binding = 0)
-uniform Light {
-    vec4 pos;
-    vec4 color;
+readonly buffer Lights {
+    vec4 ambient_light;
+    ivec4 num_lights;
+    Light lights[];
+};
+
+layout(set = 3, binding = 4) uniform texture2D shadow_atlas;
+layout(set = 3, binding = 5) uniform samplerShadow shadow_sampler;
+layout(set = 3, binding = 6)
+readonly buffer ShadowMaps {
+    ShadowMap shadow_maps[];
 };
 
+layout(set = 3, binding = 1)
+uniform Material {
+    vec4 ambient_color;
+    vec4 diffuse_color;
+    vec4 specular_color;
+    float specular_highlights;
+    float optical_density;
+    float dissolve;
+    float illumination_model;
+};
+
+// 1 = lit, 0 = in shadow
+float sample_shadow(Light light) {
+    int count = int(light.shadow.y);
+    if (count == 0) {
+        return 1.0;
+    }
+
+    // Directional lights pick the first cascade that reaches the fragment
+    int index = int(light.shadow.x);
+    for (int c = 0; c < count - 1; c++) {
+        if (view_depth <= shadow_maps[index].params.z) {
+            break;
+        }
+        index++;
+    }
+
+    ShadowMap map = shadow_maps[index];
+    vec4 light_space = map.view_proj * vec4(world_pos, 1.0);
+    vec3 coords = light_space.xyz / light_space.w;
+    if (coords.z > 1.0 || coords.z < 0.0) {
+        return 1.0;
+    }
+
+    vec2 uv = vec2(coords.x * 0.5 + 0.5, 0.5 - coords.y * 0.5);
+    if (uv.x < 0.0 || uv.x > 1.0 || uv.y < 0.0 || uv.y > 1.0) {
+        return 1.0;
+    }
+    uv = map.rect.xy + uv * map.rect.zw;
+
+    // Percentage closer filtering, the samples are clamped to the region of the map
+    int radius = int(map.params.y);
+    vec2 texel = vec2(map.params.w);
+    vec2 low = map.rect.xy + texel * 0.5;
+    vec2 high = map.rect.xy + map.rect.zw - texel * 0.5;
+    float lit = 0.0;
+    for (int x = -radius; x <= radius; x++) {
+        for (int y = -radius; y <= radius; y++) {
+            vec2 sample_uv = clamp(uv + vec2(x, y) * texel, low, high);
+            lit += textureLod(sampler2DShadow(shadow_atlas, shadow_sampler), vec3(sample_uv, coords.z - map.params.x), 0.0);
+        }
+    }
+    float samples = float((radius * 2 + 1) * (radius * 2 + 1));
+    return lit / samples;
+}
+
 void main() {
     vec4 texel = texture(sampler2D(t_diffuse, s_diffuse), tex_coords_out);
-    f_color = texel;
+    vec3 albedo = texel.rgb * diffuse_color.rgb;
+    float alpha = texel.a * dissolve;
+
+    // illum 0: color without lighting
+    if (illumination_model < 0.5) {
+        f_color = vec4(albedo, alpha);
+        return;
+    }
+
+    vec3 n = normalize(normal);
+    vec3 view_dir = normalize(camera_pos - world_pos);
+    vec3 color = ambient_light.rgb * ambient_color.rgb * texel.rgb;
+
+    for (int i = 0; i < num_lights.x; i++) {
+        Light light = lights[i];
+        vec3 light_dir;
+        float attenuation = 1.0;
+
+        if (light.pos.w > 0.5 && light.pos.w < 1.5) {
+            light_dir = normalize(-light.direction.xyz);
+        } else {
+            vec3 to_light = light.pos.xyz - world_pos;
+            float distance = length(to_light);
+            if (light.color.w > 0.0 && distance > light.color.w) {
+                continue;
+            }
+            light_dir = to_light / max(distance, 0.0001);
+            attenuation = 1.0 / (light.attenuation.x + light.attenuation.y * distance + light.attenuation.z * distance * distance);
+
+            if (light.pos.w > 1.5) {
+                float theta = dot(light_dir, normalize(-light.direction.xyz));
+                attenuation *= clamp((theta - light.cone.y) / max(light.cone.x - light.cone.y, 0.0001), 0.0, 1.0);
+            }
+        }
+
+        float shadow = sample_shadow(light);
+        vec3 radiance = light.color.rgb * light.direction.w * attenuation * shadow;
+        float diffuse = max(dot(n, light_dir), 0.0);
+        color += albedo * diffuse * radiance;
+
+        // illum 1: no highlights
+        if (illumination_model > 1.5 && diffuse > 0.0) {
+            vec3 halfway = normalize(light_dir + view_dir);
+            float specular = pow(max(dot(n, halfway), 0.0), max(specular_highlights, 1.0));
+            color += specular_color.rgb * specular * radiance;
+        }
+    }
+
+    f_color = vec4(color, alpha);
 }
\ No newline at end of file
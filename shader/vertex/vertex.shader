@@ -6,6 +6,9 @@ layout(location=2) in vec3 a_normal;
 
 layout(location=0) out vec2 tex_coords_out;
 layout(location=1) out vec3 normal;
+layout(location=2) out vec3 world_pos;
+layout(location=3) out vec3 camera_pos;
+layout(location=4) out float view_depth;
 
 layout(set=1, binding=0) // 1.
 uniform Uniforms {
@@ -21,7 +24,13 @@ readonly buffer Uniforms3 {
 };
 
 void main() {
-    gl_Position = projection * view * transform * vec4(a_position, 1.0);
+    vec4 world = transform * vec4(a_position, 1.0);
+    gl_Position = projection * view * world;
+    view_depth = -(view * world).z;
     tex_coords_out = tex_coords;
-    normal = a_normal;
+    // Only correct for uniform scaling, which is enough for the models of the engine
+    normal = mat3(transform) * a_normal;
+    world_pos = world.xyz;
+    // The view matrix is rotation * translation(-camera), so the camera position can be restored without inverse()
+    camera_pos = -(transpose(mat3(view)) * view[3].xyz);
 }
\ No newline at end of file
@@ -1,13 +1,132 @@
-use crate::animation::key_frame::KeyFrame;
+use nalgebra_glm::{Qua, TVec3, vec3};
+use crate::animation::key_frame::{Interpolation, KeyFrame};
 
 pub struct Animation {
     pub key_frames: Vec<KeyFrame>,
-    pub name: String
+    pub name: String,
+    pub channels: Vec<JointChannel>
 }
 
 impl Animation {
+    pub fn new(name:String,key_frames:Vec<KeyFrame>) -> Animation {
+        return Animation { key_frames, name, channels: vec![] };
+    }
+
+    pub fn new_skeletal(name:String,channels:Vec<JointChannel>) -> Animation {
+        return Animation { key_frames: vec![], name, channels };
+    }
+
     pub fn get_next_key_frame(&self, current:usize) -> Option<&KeyFrame> {
         return self.key_frames.get(current+1)
     }
+
+    pub fn get_duration(&self) -> f32 {
+        let channels = self.channels.iter().map(|c| c.get_duration()).fold(0.0,f32::max);
+        return self.key_frames.last().map_or(channels,|k| k.time.max(channels));
+    }
+
+    pub fn sample(&self, time:f32) -> (TVec3<f32>,Qua<f32>,TVec3<f32>) {
+        return sample_key_frames(&self.key_frames,time);
+    }
+}
+
+/*
+Key frames of one joint of a Skeleton, they hold the local pos/rotation/scale of the joint.
+ */
+pub struct JointChannel {
+    pub joint: usize,
+    pub key_frames: Vec<KeyFrame>
+}
+
+impl JointChannel {
+    pub fn new(joint:usize,key_frames:Vec<KeyFrame>) -> JointChannel {
+        return JointChannel { joint, key_frames };
+    }
+
+    pub fn get_duration(&self) -> f32 {
+        return self.key_frames.last().map_or(0.0,|k| k.time);
+    }
+
+    pub fn sample(&self, time:f32) -> (TVec3<f32>,Qua<f32>,TVec3<f32>) {
+        return sample_key_frames(&self.key_frames,time);
+    }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PlaybackMode {
+    Once,
+    Loop,
+    PingPong
+}
+
+impl PlaybackMode {
+    //Maps the time since the start of the playback to the time inside the animation, true if the playback has finished
+    pub fn resolve(&self, time:f32, duration:f32) -> (f32,bool) {
+        if duration <= 0.0 {
+            return (0.0,*self == PlaybackMode::Once);
+        }
+
+        return match self {
+            PlaybackMode::Once => if time >= duration { (duration,true) } else { (time.max(0.0),false) },
+            PlaybackMode::Loop => (time.rem_euclid(duration),false),
+            PlaybackMode::PingPong => {
+                let t = time.rem_euclid(duration * 2.0);
+                (if t > duration { duration * 2.0 - t } else { t },false)
+            }
+        }
+    }
+}
+
+//Samples absolute key frames at the given time, before the first/after the last key frame the values are held
+pub fn sample_key_frames(frames:&[KeyFrame], time:f32) -> (TVec3<f32>,Qua<f32>,TVec3<f32>) {
+    if frames.is_empty() {
+        return (vec3(0.0,0.0,0.0),nalgebra_glm::quat_identity(),vec3(1.0,1.0,1.0));
+    }
+
+    let next = frames.iter().position(|k| k.time > time);
+
+    return match next {
+        Some(0) => (frames[0].pos,frames[0].rotation,frames[0].scale),
+        None => {
+            let last = frames.last().unwrap();
+            (last.pos,last.rotation,last.scale)
+        },
+        Some(i) => {
+            let a = &frames[i - 1];
+            let b = &frames[i];
+            let t = a.easing.apply((time - a.time) / (b.time - a.time));
+
+            match a.interpolation {
+                Interpolation::Step => (a.pos,a.rotation,a.scale),
+                Interpolation::Linear => (a.pos.lerp(&b.pos,t),slerp(&a.rotation,&b.rotation,t),a.scale.lerp(&b.scale,t)),
+                Interpolation::CubicHermite => {
+                    let before = if i >= 2 { &frames[i - 2] } else { a };
+                    let after = frames.get(i + 1).unwrap_or(b);
+                    let pos = hermite(before,a,b,after,t,|k| k.pos);
+                    let scale = hermite(before,a,b,after,t,|k| k.scale);
+                    (pos,slerp(&a.rotation,&b.rotation,t),scale)
+                }
+            }
+        }
+    }
+}
+
+//Takes the shorter way, quaternions q and -q are the same rotation
+fn slerp(a:&Qua<f32>, b:&Qua<f32>, t:f32) -> Qua<f32> {
+    let b = if a.coords.dot(&b.coords) < 0.0 { -b } else { *b };
+    return nalgebra_glm::quat_slerp(a,&b,t);
+}
+
+fn hermite(before:&KeyFrame, a:&KeyFrame, b:&KeyFrame, after:&KeyFrame, t:f32, value:fn(&KeyFrame) -> TVec3<f32>) -> TVec3<f32> {
+    let dt = b.time - a.time;
+    let tangent = |p:&KeyFrame,n:&KeyFrame| {
+        let span = n.time - p.time;
+        if span <= 0.0 { vec3(0.0,0.0,0.0) } else { (value(n) - value(p)) * (dt / span) }
+    };
+    let m0 = tangent(before,b);
+    let m1 = tangent(a,after);
+
+    let t2 = t * t;
+    let t3 = t2 * t;
+    return value(a) * (2.0 * t3 - 3.0 * t2 + 1.0) + m0 * (t3 - 2.0 * t2 + t) + value(b) * (-2.0 * t3 + 3.0 * t2) + m1 * (t3 - t2);
+}
\ No newline at end of file
@@ -1,3 +1,5 @@
+use crate::animation::animation::PlaybackMode;
+use crate::animation::easing::Easing;
 use crate::engine::game_engine::GameEngine;
 use crate::ui::bounds::Bounds;
 
@@ -137,9 +139,15 @@ impl AnimationSettings {
     }
 }
 
+/*
+Animates one value, either to an absolute value (to) or by a distance (dis) in the given time.
+The progress is shaped by the easing, Loop/PingPong repeat the animation until it is disabled.
+ */
 pub struct AnimationDirection {
-    pub dis_per_sec:f32,
+    pub start:f32,
     pub target:f32,
+    pub time:f32,
+    pub elapsed:f32,
     pub current:f32,
     pub change:f32,
     pub disabled:bool,
@@ -148,14 +156,18 @@ pub struct AnimationDirection {
     pub original:f32,
     pub revert: bool,
     pub delay:f32,
-    pub running: f32
+    pub running: f32,
+    pub easing: Easing,
+    pub mode: PlaybackMode
 }
 
 impl AnimationDirection {
     pub fn to(now:f32,to:f32,time:f32) -> AnimationDirection{
         AnimationDirection {
-            dis_per_sec: (to-now) / time,
+            start: now,
             target: to,
+            time,
+            elapsed: 0.0,
             current: now,
             change: 0.0,
             disabled: false,
@@ -164,14 +176,18 @@ impl AnimationDirection {
             original: now,
             revert: false,
             delay: 0.0,
-            running: 0.0
+            running: 0.0,
+            easing: Easing::Linear,
+            mode: PlaybackMode::Once
         }
     }
 
     pub fn dis(now:f32,dis:f32,time:f32) -> AnimationDirection{
         AnimationDirection {
-            dis_per_sec: dis/time,
+            start: 0.0,
             target: dis,
+            time,
+            elapsed: 0.0,
             current: 0.0,
             change: 0.0,
             disabled: false,
@@ -180,7 +196,9 @@ impl AnimationDirection {
             original: now,
             revert: false,
             delay: 0.0,
-            running: 0.0
+            running: 0.0,
+            easing: Easing::Linear,
+            mode: PlaybackMode::Once
         }
     }
 
@@ -199,30 +217,44 @@ impl AnimationDirection {
         self
     }
 
+    pub fn easing(mut self, easing:Easing) -> Self {
+        self.easing = easing;
+        self
+    }
+
+    pub fn mode(mut self, mode:PlaybackMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    //Jumps to the given time (in seconds, without the delay)
+    pub fn seek(&mut self,time:f32) {
+        let (local,finished) = self.mode.resolve(time,self.time);
+        self.elapsed = time;
+        if self.mode != PlaybackMode::Once && self.time > 0.0 {
+            let period = if self.mode == PlaybackMode::PingPong { self.time * 2.0 } else { self.time };
+            self.elapsed = time.rem_euclid(period);
+        }
+
+        let t = if self.time > 0.0 { local / self.time } else { 1.0 };
+        let value = self.start + (self.target - self.start) * self.easing.apply(t);
+        self.change = value - self.current;
+        self.current = value;
+
+        if finished {
+            self.finished = true;
+            if self.running == 0.0 {
+                self.disabled = true;
+            }
+        }
+    }
+
     pub fn update(&mut self,delta:f32) {
         if self.delay <= 0.0 {
             if !self.finished {
-                self.current += self.dis_per_sec * delta;
-                self.change = self.dis_per_sec * delta;
-
-                if self.dis_per_sec > 0.0 {
-                    if self.current >= self.target {
-                        self.current = self.original;
-                        self.finished = true;
-                        if self.running == 0.0 {
-                            self.disabled = true;
-                        }
-                    }
-                } else {
-                    if self.current <= self.target {
-                        self.current = self.target;
-                        self.finished = true;
-                        if self.running == 0.0 {
-                            self.disabled = true;
-                        }
-                    }
-                }
+                self.seek(self.elapsed + delta);
             } else {
+                self.change = 0.0;
                 self.running -= delta;
 
                 if self.running <= 0.0 {
@@ -236,22 +268,12 @@ impl AnimationDirection {
 
     fn apply(&self,v:&mut f32) {
         if self.delay <= 0.0{
-            if !self.finished {
-                if self.to {
-                    *v = self.current;
-                } else {
-                    *v += self.change;
-                }
+            if self.finished && self.revert {
+                *v = self.original;
+            } else if self.to {
+                *v = self.current;
             } else {
-                if self.revert {
-                    *v = self.original;
-                } else {
-                    if self.to {
-                        *v = self.target;
-                    } else {
-                        *v += self.change;
-                    }
-                }
+                *v += self.change;
             }
         }
     }
@@ -0,0 +1,148 @@
+use std::f32::consts::PI;
+
+/*
+Easing curves, they map the progress t (0..1) of an animation segment to the progress of the value.
+Used by the key frames of an Animation and by the AnimationDirection of the ui Animator.
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Easing {
+    Linear,
+    InQuad,
+    OutQuad,
+    InOutQuad,
+    InCubic,
+    OutCubic,
+    InOutCubic,
+    InSine,
+    OutSine,
+    InOutSine,
+    InExpo,
+    OutExpo,
+    InOutExpo,
+    InBack,
+    OutBack,
+    InOutBack,
+    InElastic,
+    OutElastic,
+    InOutElastic,
+    InBounce,
+    OutBounce,
+    InOutBounce,
+    //Control points (x1,y1,x2,y2) of a cubic bezier from (0,0) to (1,1), like css cubic-bezier()
+    CubicBezier(f32,f32,f32,f32)
+}
+
+impl Easing {
+    pub fn apply(&self,t:f32) -> f32 {
+        let t = t.clamp(0.0,1.0);
+        const C1: f32 = 1.70158;
+        const C2: f32 = C1 * 1.525;
+        const C3: f32 = C1 + 1.0;
+
+        return match self {
+            Easing::Linear => t,
+            Easing::InQuad => t * t,
+            Easing::OutQuad => 1.0 - (1.0 - t) * (1.0 - t),
+            Easing::InOutQuad => if t < 0.5 { 2.0 * t * t } else { 1.0 - (-2.0 * t + 2.0).powi(2) / 2.0 },
+            Easing::InCubic => t * t * t,
+            Easing::OutCubic => 1.0 - (1.0 - t).powi(3),
+            Easing::InOutCubic => if t < 0.5 { 4.0 * t * t * t } else { 1.0 - (-2.0 * t + 2.0).powi(3) / 2.0 },
+            Easing::InSine => 1.0 - (t * PI / 2.0).cos(),
+            Easing::OutSine => (t * PI / 2.0).sin(),
+            Easing::InOutSine => -((PI * t).cos() - 1.0) / 2.0,
+            Easing::InExpo => if t == 0.0 { 0.0 } else { 2.0f32.powf(10.0 * t - 10.0) },
+            Easing::OutExpo => if t == 1.0 { 1.0 } else { 1.0 - 2.0f32.powf(-10.0 * t) },
+            Easing::InOutExpo => {
+                if t == 0.0 || t == 1.0 { t }
+                else if t < 0.5 { 2.0f32.powf(20.0 * t - 10.0) / 2.0 }
+                else { (2.0 - 2.0f32.powf(-20.0 * t + 10.0)) / 2.0 }
+            },
+            Easing::InBack => C3 * t * t * t - C1 * t * t,
+            Easing::OutBack => 1.0 + C3 * (t - 1.0).powi(3) + C1 * (t - 1.0).powi(2),
+            Easing::InOutBack => {
+                if t < 0.5 { (2.0 * t).powi(2) * ((C2 + 1.0) * 2.0 * t - C2) / 2.0 }
+                else { ((2.0 * t - 2.0).powi(2) * ((C2 + 1.0) * (t * 2.0 - 2.0) + C2) + 2.0) / 2.0 }
+            },
+            Easing::InElastic => {
+                if t == 0.0 || t == 1.0 { t }
+                else { -(2.0f32.powf(10.0 * t - 10.0)) * ((t * 10.0 - 10.75) * (2.0 * PI / 3.0)).sin() }
+            },
+            Easing::OutElastic => {
+                if t == 0.0 || t == 1.0 { t }
+                else { 2.0f32.powf(-10.0 * t) * ((t * 10.0 - 0.75) * (2.0 * PI / 3.0)).sin() + 1.0 }
+            },
+            Easing::InOutElastic => {
+                let c5 = 2.0 * PI / 4.5;
+                if t == 0.0 || t == 1.0 { t }
+                else if t < 0.5 { -(2.0f32.powf(20.0 * t - 10.0) * ((20.0 * t - 11.125) * c5).sin()) / 2.0 }
+                else { 2.0f32.powf(-20.0 * t + 10.0) * ((20.0 * t - 11.125) * c5).sin() / 2.0 + 1.0 }
+            },
+            Easing::InBounce => 1.0 - bounce_out(1.0 - t),
+            Easing::OutBounce => bounce_out(t),
+            Easing::InOutBounce => if t < 0.5 { (1.0 - bounce_out(1.0 - 2.0 * t)) / 2.0 } else { (1.0 + bounce_out(2.0 * t - 1.0)) / 2.0 },
+            Easing::CubicBezier(x1,y1,x2,y2) => cubic_bezier(*x1,*y1,*x2,*y2,t)
+        }
+    }
+}
+
+impl Default for Easing {
+    fn default() -> Self {
+        return Easing::Linear;
+    }
+}
+
+fn bounce_out(t:f32) -> f32 {
+    const N: f32 = 7.5625;
+    const D: f32 = 2.75;
+
+    return if t < 1.0 / D {
+        N * t * t
+    } else if t < 2.0 / D {
+        let t = t - 1.5 / D;
+        N * t * t + 0.75
+    } else if t < 2.5 / D {
+        let t = t - 2.25 / D;
+        N * t * t + 0.9375
+    } else {
+        let t = t - 2.625 / D;
+        N * t * t + 0.984375
+    }
+}
+
+fn bezier(a:f32,b:f32,t:f32) -> f32 {
+    let u = 1.0 - t;
+    return 3.0 * u * u * t * a + 3.0 * u * t * t * b + t * t * t;
+}
+
+fn bezier_slope(a:f32,b:f32,t:f32) -> f32 {
+    let u = 1.0 - t;
+    return 3.0 * u * u * a + 6.0 * u * t * (b - a) + 3.0 * t * t * (1.0 - b);
+}
+
+//Finds the curve parameter for x (newton, bisection as fallback) and returns y of that point
+fn cubic_bezier(x1:f32,y1:f32,x2:f32,y2:f32,x:f32) -> f32 {
+    let mut t = x;
+    for _ in 0..8 {
+        let error = bezier(x1,x2,t) - x;
+        if error.abs() < 1e-5 {
+            return bezier(y1,y2,t);
+        }
+        let slope = bezier_slope(x1,x2,t);
+        if slope.abs() < 1e-6 {
+            break;
+        }
+        t -= error / slope;
+    }
+
+    let (mut low,mut high) = (0.0,1.0);
+    t = x;
+    for _ in 0..32 {
+        let value = bezier(x1,x2,t);
+        if (value - x).abs() < 1e-5 {
+            break;
+        }
+        if value < x { low = t; } else { high = t; }
+        t = (low + high) / 2.0;
+    }
+    return bezier(y1,y2,t);
+}
\ No newline at end of file
@@ -1,9 +1,47 @@
-use nalgebra_glm::TVec3;
+use nalgebra_glm::{Qua, TVec3};
+use crate::animation::easing::Easing;
+use crate::objects::transform::Transform;
 
+//How the values change between a key frame and the next one
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Interpolation {
+    Step,
+    Linear,
+    //Catmull-Rom tangents from the neighbouring key frames
+    CubicHermite
+}
+
+/*
+Absolute pos/scale/rotation at the given time (seconds since the start of the animation).
+interpolation and easing describe the way to the next key frame.
+ */
 #[derive(Clone)]
 pub struct KeyFrame {
     pub time: f32,
     pub pos:TVec3<f32>,
     pub scale:TVec3<f32>,
-    pub rotation:TVec3<f32>
+    pub rotation:Qua<f32>,
+    pub interpolation: Interpolation,
+    pub easing: Easing
+}
+
+impl KeyFrame {
+    pub fn new(time:f32,pos:TVec3<f32>,scale:TVec3<f32>,rotation:Qua<f32>) -> KeyFrame {
+        return KeyFrame { time, pos, scale, rotation, interpolation: Interpolation::Linear, easing: Easing::Linear };
+    }
+
+    //yaw/pitch/roll in degrees like Transform
+    pub fn from_euler(time:f32,pos:TVec3<f32>,scale:TVec3<f32>,yaw:f32,pitch:f32,roll:f32) -> KeyFrame {
+        return KeyFrame::new(time,pos,scale,Transform::euler_to_quat(yaw,pitch,roll));
+    }
+
+    pub fn interpolation(mut self, interpolation:Interpolation) -> Self {
+        self.interpolation = interpolation;
+        self
+    }
+
+    pub fn easing(mut self, easing:Easing) -> Self {
+        self.easing = easing;
+        self
+    }
 }
\ No newline at end of file
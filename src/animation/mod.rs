@@ -1,4 +1,7 @@
 pub mod key_frame;
 pub mod animation;
 pub mod animation_instance;
-pub mod animator;
\ No newline at end of file
+pub mod animator;
+pub mod easing;
+pub mod skeleton;
+pub mod skeletal_animation_instance;
\ No newline at end of file
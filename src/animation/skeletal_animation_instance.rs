@@ -0,0 +1,81 @@
+use std::rc::Rc;
+use nalgebra_glm::Mat4;
+use crate::animation::animation::{Animation, PlaybackMode};
+use crate::animation::skeleton::{JointPose, Skeleton};
+use crate::engine::game_engine::GameEngine;
+
+/*
+Plays the joint channels of an Animation on a Skeleton. The resulting joint matrices are uploaded
+to the joint slot of this instance, which is passed to VertexRenderer::render_skinned.
+ */
+pub struct SkeletalAnimationInstance {
+    pub skeleton: Rc<Skeleton>,
+    pub animation: Option<Rc<Animation>>,
+    pub time: f32,
+    pub speed: f32,
+    pub mode: PlaybackMode,
+    pub activated: bool,
+    pub finished: bool,
+    pub pose: Vec<JointPose>,
+    pub joint_matrices: Vec<Mat4>,
+    pub joint_offset: u32
+}
+
+impl SkeletalAnimationInstance {
+    pub fn new(skeleton:Rc<Skeleton>) -> Self {
+        let pose = skeleton.rest_pose();
+        let joint_matrices = skeleton.compute_joint_matrices(&pose);
+        Self { skeleton, animation: None, time: 0.0, speed: 1.0, mode: PlaybackMode::Once, activated: false, finished: false, pose, joint_matrices, joint_offset: 0 }
+    }
+
+    pub fn init(&mut self, engine:&mut GameEngine) {
+        self.joint_offset = engine.vertex_renderer.shader.joint_offset_handler.get_offset() as u32;
+        engine.update_offset_buffers();
+        engine.vertex_renderer.write_joints(&engine.queue,self.joint_offset,&self.joint_matrices);
+    }
+
+    pub fn destroy(&mut self, engine:&mut GameEngine) {
+        engine.vertex_renderer.shader.joint_offset_handler.remove(self.joint_offset as u64);
+    }
+
+    pub fn play(&mut self, animation:Rc<Animation>, mode:PlaybackMode) {
+        self.animation = Some(animation);
+        self.mode = mode;
+        self.activated = true;
+        self.finished = false;
+        self.seek(0.0);
+    }
+
+    pub fn stop(&mut self) {
+        self.activated = false;
+    }
+
+    //Poses the skeleton at the given time (in seconds, resolved with the playback mode), write_joints uploads it
+    pub fn seek(&mut self, time:f32) {
+        if let Some(animation) = &self.animation {
+            let duration = animation.get_duration();
+            let (local,finished) = self.mode.resolve(time,duration);
+            self.time = match self.mode {
+                PlaybackMode::Once => time,
+                PlaybackMode::Loop => time.rem_euclid(duration.max(f32::EPSILON)),
+                PlaybackMode::PingPong => time.rem_euclid((duration * 2.0).max(f32::EPSILON))
+            };
+            self.finished = finished;
+            if finished {
+                self.activated = false;
+            }
+
+            self.pose = self.skeleton.sample(animation,local);
+            self.joint_matrices = self.skeleton.compute_joint_matrices(&self.pose);
+        }
+    }
+
+    pub fn update(&mut self, engine:&GameEngine) {
+        if !self.activated || self.finished || self.animation.is_none() {
+            return;
+        }
+
+        self.seek(self.time + engine.delta_time * self.speed);
+        engine.vertex_renderer.write_joints(&engine.queue,self.joint_offset,&self.joint_matrices);
+    }
+}
\ No newline at end of file
@@ -0,0 +1,90 @@
+use log::warn;
+use nalgebra_glm::{Mat4, Qua, TVec3};
+use crate::animation::animation::Animation;
+
+pub const MAX_JOINTS: usize = 128;
+
+pub struct Joint {
+    pub name: String,
+    pub parent: Option<usize>,
+    pub inverse_bind_matrix: Mat4,
+    pub rest_pose: JointPose
+}
+
+#[derive(Clone)]
+pub struct JointPose {
+    pub pos: TVec3<f32>,
+    pub rotation: Qua<f32>,
+    pub scale: TVec3<f32>
+}
+
+impl JointPose {
+    pub fn new(pos:TVec3<f32>,rotation:Qua<f32>,scale:TVec3<f32>) -> JointPose {
+        return JointPose { pos, rotation, scale };
+    }
+
+    pub fn get_matrix(&self) -> Mat4 {
+        let matrix = nalgebra_glm::translation(&self.pos) * nalgebra_glm::quat_to_mat4(&self.rotation);
+        return nalgebra_glm::scale(&matrix,&self.scale);
+    }
+}
+
+/*
+Joint hierarchy of a skinned mesh. root_transform places the skeleton inside the model
+(transforms of the parents of the root joints).
+ */
+pub struct Skeleton {
+    pub joints: Vec<Joint>,
+    pub root_transform: Mat4
+}
+
+impl Skeleton {
+    pub fn new(joints:Vec<Joint>,root_transform:Mat4) -> Skeleton {
+        if joints.len() > MAX_JOINTS {
+            warn!("Skeleton has {} joints, only {} are supported by the skinning shader",joints.len(),MAX_JOINTS);
+        }
+        return Skeleton { joints, root_transform };
+    }
+
+    pub fn find_joint(&self,name:&str) -> Option<usize> {
+        return self.joints.iter().position(|j| j.name == name);
+    }
+
+    pub fn rest_pose(&self) -> Vec<JointPose> {
+        return self.joints.iter().map(|j| j.rest_pose.clone()).collect();
+    }
+
+    //Poses all joints at the given time, joints without a channel keep their rest pose
+    pub fn sample(&self,animation:&Animation,time:f32) -> Vec<JointPose> {
+        let mut pose = self.rest_pose();
+        for channel in animation.channels.iter() {
+            if channel.joint < pose.len() {
+                let (pos,rotation,scale) = channel.sample(time);
+                pose[channel.joint] = JointPose::new(pos,rotation,scale);
+            }
+        }
+        return pose;
+    }
+
+    //Model space joint matrices (global * inverse bind), like they are uploaded for the skinning shader
+    pub fn compute_joint_matrices(&self,pose:&[JointPose]) -> Vec<Mat4> {
+        let mut globals: Vec<Option<Mat4>> = vec![None; self.joints.len()];
+        for i in 0..self.joints.len() {
+            self.compute_global(i,pose,&mut globals);
+        }
+        return self.joints.iter().enumerate().map(|(i,j)| globals[i].unwrap() * j.inverse_bind_matrix).collect();
+    }
+
+    fn compute_global(&self,joint:usize,pose:&[JointPose],globals:&mut Vec<Option<Mat4>>) -> Mat4 {
+        if let Some(m) = globals[joint] {
+            return m;
+        }
+        let parent = match self.joints[joint].parent {
+            Some(p) => self.compute_global(p,pose,globals),
+            None => self.root_transform
+        };
+        let global = parent * pose[joint].get_matrix();
+        globals[joint] = Some(global);
+        return global;
+    }
+}
\ No newline at end of file
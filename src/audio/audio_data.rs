@@ -72,6 +72,14 @@ impl AudioData {
         return AudioData { buffer_id: buffer }
     }
 
+    pub fn get_size(&self) -> u64 {
+        let mut size = 0;
+        unsafe {
+            openal_sys::alGetBufferi(self.buffer_id,openal_sys::AL_SIZE as i32,&mut size);
+        }
+        return size.max(0) as u64;
+    }
+
     pub fn destroy(&self) {
         unsafe {
             openal_sys::alDeleteBuffers(1,&self.buffer_id);
@@ -2,13 +2,12 @@ use openal_sys::{ALCdevice, ALCcontext};
 use std::collections::HashMap;
 use crate::audio::audio_source::AudioSource;
 use std::rc::Rc;
-use crate::audio::mp3_file::MP3File;
-use crate::audio::wave_file::WaveFile;
-use crate::audio::vorbis_file::VorbisFile;
 use std::sync::{Arc, Mutex};
-use std::sync::atomic::{AtomicPtr, AtomicBool, Ordering};
+use std::sync::atomic::{AtomicPtr, Ordering};
 use std::time::Duration;
 use crate::audio::audio_data::AudioData;
+use crate::engine::resource_handle::{Handle, ResourceGroup, ResourceInfo, ResourceStore, Sound};
+use log::warn;
 
 pub struct AudioHandler {
     device : AtomicPtr<ALCdevice>,
@@ -17,9 +16,9 @@ pub struct AudioHandler {
     listener_vel : [f32;3],
     listener_ori : [f32;6],
     audio_sources: Arc<Mutex<HashMap<String,Arc<Mutex<AudioSource>>>>>,
-    audio_data: Arc<Mutex<HashMap<String,Arc<AudioData>>>>,
-    is_loading: Arc<Mutex<AtomicBool>>,
-    loading_max : Arc<Mutex<usize>>,
+    audio_data: Arc<Mutex<ResourceStore<Sound>>>,
+    //Group the loaded audio is added to, see ResourceLoader::active_group
+    pub active_group: Option<ResourceGroup>,
     keep_updating: Arc<Mutex<bool>>
 }
 
@@ -45,10 +44,23 @@ impl AudioHandler {
             openal_sys::alListenerfv(openal_sys::AL_VELOCITY as i32,listener_vel.as_ptr());
             openal_sys::alListenerfv(openal_sys::AL_ORIENTATION as i32,listener_ori.as_ptr());
 
-            return AudioHandler { device: AtomicPtr::new(device),context: AtomicPtr::new(context), listener_pos, listener_vel, listener_ori, audio_sources: Arc::new(Mutex::new(HashMap::new())), audio_data: Arc::new(Mutex::new(HashMap::new())), is_loading: Arc::new(Mutex::new(AtomicBool::new(false))), loading_max: Arc::new(Mutex::new(0)), keep_updating: Arc::new(Mutex::new(true)) }
+            return AudioHandler { device: AtomicPtr::new(device),context: AtomicPtr::new(context), listener_pos, listener_vel, listener_ori, audio_sources: Arc::new(Mutex::new(HashMap::new())), audio_data: Arc::new(Mutex::new(ResourceStore::new())), active_group: None, keep_updating: Arc::new(Mutex::new(true)) }
         }
     }
 
+    //Used for headless engines, where no sound device might be available (CI)
+    pub fn new_muted() -> AudioHandler {
+        let listener_pos : [f32;3] = [0.0,0.0,0.0];
+        let listener_vel : [f32;3] = [0.0,0.0,0.0];
+        let listener_ori : [f32;6] = [0.0,0.0,-1.0,0.0,1.0,0.0];
+
+        return AudioHandler { device: AtomicPtr::new(std::ptr::null_mut()),context: AtomicPtr::new(std::ptr::null_mut()), listener_pos, listener_vel, listener_ori, audio_sources: Arc::new(Mutex::new(HashMap::new())), audio_data: Arc::new(Mutex::new(ResourceStore::new())), active_group: None, keep_updating: Arc::new(Mutex::new(true)) }
+    }
+
+    pub fn is_muted(&self) -> bool {
+        return self.device.load(Ordering::Relaxed).is_null();
+    }
+
     pub fn set_pos(&mut self, pos:[f32;3]) {
         unsafe {
             self.listener_pos = pos;
@@ -77,13 +89,15 @@ impl AudioHandler {
                 s.lock().unwrap().destroy();
             }
 
-            for data in self.audio_data.lock().unwrap().iter() {
-                data.1.destroy();
+            for (_,_,data) in self.audio_data.lock().unwrap().iter() {
+                data.destroy();
             }
 
-            openal_sys::alcMakeContextCurrent(std::ptr::null_mut());
-            openal_sys::alcDestroyContext(*self.context.get_mut());
-            openal_sys::alcCloseDevice(*self.device.get_mut());
+            if !self.device.get_mut().is_null() {
+                openal_sys::alcMakeContextCurrent(std::ptr::null_mut());
+                openal_sys::alcDestroyContext(*self.context.get_mut());
+                openal_sys::alcCloseDevice(*self.device.get_mut());
+            }
 
             *self.keep_updating.lock().unwrap() = false;
         }
@@ -91,7 +105,7 @@ impl AudioHandler {
 
     pub fn create_source(&mut self,id:String,data_id:String) -> Arc<Mutex<AudioSource>>{
         unsafe {
-            let data = self.audio_data.lock().unwrap()[data_id.as_str()].clone();
+            let data = self.audio_data.lock().unwrap().try_get_by_name(&data_id).unwrap_or_else(|| panic!("Audio {} is not loaded",data_id));
 
             let source = Arc::new(Mutex::new(AudioSource::new(data)));
 
@@ -101,46 +115,65 @@ impl AudioHandler {
         }
     }
 
-    pub fn load_all_audio_in_folder(&mut self,folder: String,working_dir:String) {
+    //None if the sound is not loaded (yet)
+    pub fn try_create_source(&mut self,id:String,sound:Handle<Sound>) -> Option<Arc<Mutex<AudioSource>>> {
+        let data = self.audio_data.lock().unwrap().try_get(sound)?;
 
-        let v = self.audio_sources.clone();
-        let datas = self.audio_data.clone();
-        let l = self.is_loading.clone();
-        let m = self.loading_max.clone();
+        let source = Arc::new(Mutex::new(unsafe { AudioSource::new(data) }));
 
-        self.is_loading.lock().unwrap().store(true,Ordering::Relaxed);
+        self.audio_sources.lock().unwrap().insert(id,source.clone());
 
-        std::thread::spawn(move || {
-            unsafe {
-                let p = format!("{}\\{}",working_dir,folder);
-                let paths = std::fs::read_dir(format!("{}\\{}",working_dir,folder)).unwrap();
-
-                *m.lock().unwrap() = std::fs::read_dir(format!("{}\\{}",working_dir,folder)).unwrap().count();
-
-                for path in paths {
-                    let file = path.unwrap();
-                    let path = file.path();
-                    if path.is_file() {
-                        let ext = path.extension().unwrap().to_os_string().into_string().unwrap();
-                        if ext == "mp3" {
-                            let fp = format!("{}\\{}",p,path.file_name().unwrap().to_os_string().into_string().unwrap());
-                            let data = Arc::new(AudioData::new_mp3(&MP3File::new(fp.to_string())));
-                            datas.lock().unwrap().insert(path.file_name().unwrap().to_os_string().into_string().unwrap(),data);
-                        } else if ext == "wav" {
-                            let fp = format!("{}\\{}",p,path.file_name().unwrap().to_os_string().into_string().unwrap());
-                            let data = Arc::new(AudioData::new_wav(&WaveFile::new(fp.to_string())));
-                            datas.lock().unwrap().insert(path.file_name().unwrap().to_os_string().into_string().unwrap(),data);
-                        } else if ext == "ogg" {
-                            let fp = format!("{}\\{}",p,path.file_name().unwrap().to_os_string().into_string().unwrap());
-                            let data = Arc::new(AudioData::new_vorbis(&VorbisFile::new(fp.to_string())));
-                            datas.lock().unwrap().insert(path.file_name().unwrap().to_os_string().into_string().unwrap(),data);
-                        }
-                    }
-                }
+        return Some(source);
+    }
+
+    pub fn get_sound_handle(&self,name:&str) -> Handle<Sound> {
+        return self.audio_data.lock().unwrap().handle(name);
+    }
 
-                l.lock().unwrap().store(false,Ordering::Relaxed);
+    //Adds audio data that was loaded somewhere else (see AssetLoader), replacing the data with the same name
+    pub fn add_data(&mut self, name:String, path:String, data:AudioData, group:Option<ResourceGroup>) {
+        let mut audio_data = self.audio_data.lock().unwrap();
+        let data = Arc::new(data);
+        match audio_data.replace(&name,data.clone()) {
+            //Sources that play the previous data keep it
+            Some(previous) => {
+                if let Ok(previous) = Arc::try_unwrap(previous) {
+                    previous.destroy();
+                }
+            },
+            None => {
+                audio_data.insert(&name,data,Some(path),group);
             }
-        });
+        }
+    }
+
+    pub fn get_audio_paths(&self) -> Vec<(String,String)> {
+        return self.audio_data.lock().unwrap().get_paths();
+    }
+
+    pub fn unload_sound(&mut self, sound:Handle<Sound>) {
+        if let Some(data) = self.audio_data.lock().unwrap().unload(sound) {
+            release_data(data);
+        }
+    }
+
+    //Sounds are unused once no source plays them anymore (finished sources are removed every second)
+    pub fn unload_unused(&mut self) -> usize {
+        let unused = self.audio_data.lock().unwrap().unload_unused();
+        let count = unused.len();
+        unused.into_iter().for_each(release_data);
+        return count;
+    }
+
+    pub fn free_group(&mut self, group:ResourceGroup) -> usize {
+        let freed = self.audio_data.lock().unwrap().free_group(group);
+        let count = freed.len();
+        freed.into_iter().for_each(release_data);
+        return count;
+    }
+
+    pub fn get_resource_infos(&self) -> Vec<ResourceInfo> {
+        return self.audio_data.lock().unwrap().get_infos();
     }
 
     pub fn start(&self) {
@@ -164,18 +197,18 @@ impl AudioHandler {
     }
 
     pub fn exists(&self, name:String) -> bool {
-        return self.audio_data.lock().unwrap().contains_key(&name);
-    }
-
-    pub fn is_loading(&self) -> bool{
-        return self.is_loading.lock().unwrap().load(Ordering::Relaxed);
-    }
-
-    pub fn get_loading_max(&self) -> usize {
-        return *self.loading_max.lock().unwrap();
+        return self.audio_data.lock().unwrap().contains(&name);
     }
 
     pub fn get_sources_count(&self) -> usize {
         return self.audio_sources.lock().unwrap().len();
     }
+}
+
+//The buffer can only be deleted when no source uses it anymore
+fn release_data(data:Arc<AudioData>) {
+    match Arc::try_unwrap(data) {
+        Ok(data) => data.destroy(),
+        Err(_) => warn!("Unloaded audio which is still played, its buffer is not deleted")
+    }
 }
\ No newline at end of file
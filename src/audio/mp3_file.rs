@@ -1,7 +1,9 @@
-use std::fs::File;
+use std::io::Cursor;
 use lewton::inside_ogg::OggStreamReader;
 use openal_sys::{AL_FORMAT_MONO16, AL_FORMAT_STEREO16};
 use std::path::Path;
+use crate::error::rgl_error::{RglError, RglResult};
+use crate::vfs::vfs::Vfs;
 
 pub struct MP3File{
     pub path: String,
@@ -12,8 +14,18 @@ pub struct MP3File{
 }
 
 impl MP3File {
-    pub fn new(path:String) -> MP3File {
-        let mut mp3_file = minimp3::Decoder::new(File::open(path.clone()).unwrap());
+    pub fn new(path:String,vfs:&Vfs) -> MP3File {
+        return MP3File::try_new(path,vfs).unwrap_or_else(|e| panic!("{}",e));
+    }
+
+    pub fn try_new(path:String,vfs:&Vfs) -> RglResult<MP3File> {
+        let data = vfs.read(&path)?;
+        return MP3File::from_bytes(path,data);
+    }
+
+    //path is only used for errors
+    pub fn from_bytes(path:String,bytes:Vec<u8>) -> RglResult<MP3File> {
+        let mut mp3_file = minimp3::Decoder::new(Cursor::new(bytes));
         let mut data = Vec::new();
 
         let mut sample_rate = 0;
@@ -30,12 +42,20 @@ impl MP3File {
                 }
                 data.append(&mut f.data);
             }else {
-                break;
+                match frame.unwrap_err() {
+                    minimp3::Error::SkippedData => continue,
+                    minimp3::Error::Io(e) => return Err(RglError::io(&path,e)),
+                    _ => break
+                }
             }
         }
 
+        if data.is_empty() {
+            return Err(RglError::parse(&path,None,"No mp3 frames found".to_string()));
+        }
+
         let data_len = (data.len() * 2) as u32;
 
-        return MP3File { path,buf:data ,data_len,sample_rate,channels: channels as i32 };
+        return Ok(MP3File { path,buf:data ,data_len,sample_rate,channels: channels as i32 });
     }
 }
\ No newline at end of file
@@ -1,6 +1,8 @@
-use std::fs::File;
+use std::io::Cursor;
 use lewton::inside_ogg::OggStreamReader;
 use openal_sys::{AL_FORMAT_MONO16, AL_FORMAT_STEREO16};
+use crate::error::rgl_error::{RglError, RglResult};
+use crate::vfs::vfs::Vfs;
 
 pub struct VorbisFile{
     pub path: String,
@@ -11,17 +13,27 @@ pub struct VorbisFile{
 }
 
 impl VorbisFile {
-    pub fn new(path:String) -> VorbisFile {
-        let mut vorbis_file = OggStreamReader::new(File::open(path.clone()).unwrap()).unwrap();
+    pub fn new(path:String,vfs:&Vfs) -> VorbisFile {
+        return VorbisFile::try_new(path,vfs).unwrap_or_else(|e| panic!("{}",e));
+    }
+
+    pub fn try_new(path:String,vfs:&Vfs) -> RglResult<VorbisFile> {
+        let data = vfs.read(&path)?;
+        return VorbisFile::from_bytes(path,data);
+    }
+
+    //path is only used for errors
+    pub fn from_bytes(path:String,bytes:Vec<u8>) -> RglResult<VorbisFile> {
+        let mut vorbis_file = OggStreamReader::new(Cursor::new(bytes)).map_err(|e| RglError::parse(&path,None,e.to_string()))?;
 
         let data_len = vorbis_file.ident_hdr.audio_sample_rate * vorbis_file.ident_hdr.audio_channels as u32 * 2;
 
         let mut data = Vec::new();
 
-        while let Some(mut pcks) = vorbis_file.read_dec_packet_itl().unwrap() {
+        while let Some(mut pcks) = vorbis_file.read_dec_packet_itl().map_err(|e| RglError::parse(&path,None,e.to_string()))? {
             data.append(&mut pcks);
         }
 
-        return VorbisFile { path,buf:data ,data_len,sample_rate: vorbis_file.ident_hdr.audio_sample_rate as i32,channels: vorbis_file.ident_hdr.audio_channels as i32 };
+        return Ok(VorbisFile { path,buf:data ,data_len,sample_rate: vorbis_file.ident_hdr.audio_sample_rate as i32,channels: vorbis_file.ident_hdr.audio_channels as i32 });
     }
 }
\ No newline at end of file
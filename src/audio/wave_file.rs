@@ -1,5 +1,7 @@
 use std::fs::File;
 use std::io::{BufReader, Read};
+use crate::error::rgl_error::{RglError, RglResult};
+use crate::vfs::vfs::Vfs;
 
 pub struct WaveFile {
     pub chunk_size : i32,
@@ -15,65 +17,93 @@ pub struct WaveFile {
 }
 
 impl WaveFile {
-    pub unsafe fn new(path:String) -> WaveFile {
-        let mut file = File::open(path.clone()).unwrap();
-        let mut reader = BufReader::new(file);
-        let mut array: [char;4] = WaveFile::read_char_array(&mut reader);
-        if array[0] != 'R' || array[1] != 'I' || array[2] != 'F' || array[3] != 'F' {
-            panic!("NO RIFF");
-        }
+    pub unsafe fn new(path:String,vfs:&Vfs) -> WaveFile {
+        return WaveFile::try_new(path,vfs).unwrap_or_else(|e| panic!("{}",e));
+    }
 
-        let mut size: [u8;4] = [0;4];
-        reader.read(&mut size);
+    pub fn try_new(path:String,vfs:&Vfs) -> RglResult<WaveFile> {
+        let data = vfs.read(&path)?;
+        return WaveFile::from_bytes(path,data);
+    }
 
-        let mut array: [char;4] = WaveFile::read_char_array(&mut reader);
-        if array[0] != 'W' || array[1] != 'A' || array[2] != 'V' || array[3] != 'E' {
-            panic!("NO WAVE");
-        }
+    //path is only used for errors
+    pub fn from_bytes(path:String,data:Vec<u8>) -> RglResult<WaveFile> {
+        let mut pos = 0;
 
-        let mut array: [char;4] = WaveFile::read_char_array(&mut reader);
-        if array[0] != 'f' || array[1] != 'm' || array[2] != 't' || array[3] != ' ' {
-            panic!("NO fmt");
+        if WaveFile::take(&data,&mut pos,4,&path)? != b"RIFF" {
+            return Err(RglError::parse(&path,None,"NO RIFF".to_string()));
         }
 
-        let chunk_size = WaveFile::read_i32_le(&mut reader);
-
-        let format_type = WaveFile::read_i16_le(&mut reader);
-
-        let channels = WaveFile::read_i16_le(&mut reader);
-
-        let sample_rate = WaveFile::read_i32_le(&mut reader);
-
-        let avg_bytes_per_second = WaveFile::read_i32_le(&mut reader);
+        WaveFile::take(&data,&mut pos,4,&path)?;
 
-        let bytes_per_sample = WaveFile::read_i16_le(&mut reader);
-
-        let bits_per_sample = WaveFile::read_i16_le(&mut reader);
-
-        let array = WaveFile::read_char_array(&mut reader);
-        if array[0] as char != 'd' || array[1] as char != 'a' || array[2] as char != 't' || array[3] as char != 'a' {
-            panic!("NO DATA");
+        if WaveFile::take(&data,&mut pos,4,&path)? != b"WAVE" {
+            return Err(RglError::parse(&path,None,"NO WAVE".to_string()));
         }
 
-        let data_size = WaveFile::read_i32_le(&mut reader);
-
-
-        let mut buf: Vec<u8> = Vec::with_capacity(data_size as usize);
-        buf.set_len(data_size as usize);
-        reader.read_exact(&mut buf);
+        //Skip chunks (e.g. LIST) until fmt and data are found
+        let mut format = None;
+        loop {
+            let id = WaveFile::take(&data,&mut pos,4,&path)?;
+            let size = WaveFile::take_i32(&data,&mut pos,&path)?;
+            if size < 0 {
+                return Err(RglError::parse(&path,None,"Negative chunk size".to_string()));
+            }
+
+            if id == b"fmt " {
+                if size < 16 {
+                    return Err(RglError::parse(&path,None,"fmt chunk is too small".to_string()));
+                }
+                let chunk = WaveFile::take(&data,&mut pos,size as usize,&path)?;
+                format = Some((size,chunk));
+            } else if id == b"data" {
+                let (chunk_size,fmt) = format.ok_or_else(|| RglError::parse(&path,None,"NO fmt".to_string()))?;
+
+                let format_type = i16::from_le_bytes([fmt[0],fmt[1]]);
+                let channels = i16::from_le_bytes([fmt[2],fmt[3]]);
+                let sample_rate = i32::from_le_bytes([fmt[4],fmt[5],fmt[6],fmt[7]]);
+                let avg_bytes_per_second = i32::from_le_bytes([fmt[8],fmt[9],fmt[10],fmt[11]]);
+                let bytes_per_sample = i16::from_le_bytes([fmt[12],fmt[13]]);
+                let bits_per_sample = i16::from_le_bytes([fmt[14],fmt[15]]);
+
+                if format_type != 1 {
+                    return Err(RglError::unsupported(&path,&format!("wave format type {} (only PCM is supported)",format_type)));
+                }
+                if (bits_per_sample != 8 && bits_per_sample != 16) || (channels != 1 && channels != 2) {
+                    return Err(RglError::unsupported(&path,&format!("{} bit with {} channels",bits_per_sample,channels)));
+                }
+
+                let buf = WaveFile::take(&data,&mut pos,size as usize,&path)?.to_vec();
+
+                return Ok(WaveFile {
+                    chunk_size,
+                    format_type,
+                    channels,
+                    sample_rate,
+                    avg_bytes_per_second,
+                    bytes_per_sample,
+                    bits_per_sample,
+                    path,
+                    data_size: size,
+                    buf
+                })
+            } else {
+                WaveFile::take(&data,&mut pos,(size as usize + 1) & !1,&path)?;
+            }
+        }
+    }
 
-        return WaveFile {
-            chunk_size,
-            format_type,
-            channels,
-            sample_rate,
-            avg_bytes_per_second,
-            bytes_per_sample,
-            bits_per_sample,
-            path,
-            data_size,
-            buf
+    fn take<'a>(data:&'a [u8],pos:&mut usize,len:usize,path:&str) -> RglResult<&'a [u8]> {
+        if data.len() - *pos < len {
+            return Err(RglError::parse(path,None,format!("Unexpected end of file at byte {}",*pos)));
         }
+        let slice = &data[*pos..*pos + len];
+        *pos += len;
+        return Ok(slice);
+    }
+
+    fn take_i32(data:&[u8],pos:&mut usize,path:&str) -> RglResult<i32> {
+        let b = WaveFile::take(data,pos,4,path)?;
+        return Ok(i32::from_le_bytes([b[0],b[1],b[2],b[3]]));
     }
 
     pub fn read_i32(reader:&mut BufReader<File>) -> i32 {
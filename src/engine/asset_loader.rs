@@ -0,0 +1,338 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::time::{Duration, Instant};
+use image::RgbaImage;
+use log::error;
+use crate::audio::audio_data::AudioData;
+use crate::audio::mp3_file::MP3File;
+use crate::audio::vorbis_file::VorbisFile;
+use crate::audio::wave_file::WaveFile;
+use crate::engine::game_engine::GameEngine;
+use crate::error::rgl_error::{RglError, RglResult};
+use crate::objects::obj_parser::ObjData;
+use crate::engine::resource_handle::ResourceGroup;
+use crate::vfs::vfs::{join_path, Vfs};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct LoadHandle(pub u64);
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum AssetKind {
+    Texture,
+    Obj,
+    Audio
+}
+
+impl AssetKind {
+    //Kind of the file by its extension
+    pub fn from_path(path:&str) -> Option<AssetKind> {
+        let ext = std::path::Path::new(path).extension()?.to_string_lossy().to_lowercase();
+        return match ext.as_str() {
+            "png" | "jpg" | "jpeg" => Some(AssetKind::Texture),
+            "obj" => Some(AssetKind::Obj),
+            "mp3" | "wav" | "ogg" => Some(AssetKind::Audio),
+            _ => None
+        }
+    }
+}
+
+struct LoadJob {
+    vfs: Arc<Vfs>,
+    batch: u64,
+    kind: AssetKind,
+    dir: String,
+    name: String
+}
+
+pub enum DecodedAudio {
+    Mp3(MP3File),
+    Wav(WaveFile),
+    Vorbis(VorbisFile)
+}
+
+impl DecodedAudio {
+    //Decodes the file by its extension (mp3, wav, ogg)
+    pub fn try_load(path:&str,vfs:&Vfs) -> RglResult<DecodedAudio> {
+        let ext = std::path::Path::new(path).extension().map(|e| e.to_string_lossy().to_lowercase()).unwrap_or_default();
+        return match ext.as_str() {
+            "mp3" => Ok(DecodedAudio::Mp3(MP3File::try_new(path.to_string(),vfs)?)),
+            "wav" => Ok(DecodedAudio::Wav(WaveFile::try_new(path.to_string(),vfs)?)),
+            "ogg" => Ok(DecodedAudio::Vorbis(VorbisFile::try_new(path.to_string(),vfs)?)),
+            _ => Err(RglError::unsupported(path,&ext))
+        }
+    }
+
+    //Needs the openal context of the AudioHandler
+    pub unsafe fn upload(&self) -> AudioData {
+        return match self {
+            DecodedAudio::Mp3(f) => AudioData::new_mp3(f),
+            DecodedAudio::Wav(f) => AudioData::new_wav(f),
+            DecodedAudio::Vorbis(f) => AudioData::new_vorbis(f)
+        }
+    }
+}
+
+//Result of a worker, everything that does not need the gpu or the audio device is done
+pub enum DecodedAsset {
+    Texture(RgbaImage),
+    //Obj data with the source of its material libraries (name, text)
+    Obj(ObjData,Vec<(String,String)>),
+    Audio(DecodedAudio)
+}
+
+pub struct LoadedAsset {
+    pub batch: u64,
+    pub kind: AssetKind,
+    pub dir: String,
+    pub name: String,
+    pub result: RglResult<DecodedAsset>
+}
+
+pub struct LoadReport {
+    pub loaded: usize,
+    pub errors: Vec<RglError>
+}
+
+struct LoadBatch {
+    total: usize,
+    uploaded: usize,
+    failed: usize,
+    pending_textures: usize,
+    errors: Vec<RglError>,
+    callbacks: Vec<Box<dyn FnOnce(&mut GameEngine,&LoadReport)>>,
+    group: Option<ResourceGroup>
+}
+
+/*
+Files to load in the background, submitted with GameEngine::load_async (see AssetLoader).
+Folders are listed when the request is submitted, so the total is known from the start.
+ */
+pub struct LoadRequest {
+    vfs: Arc<Vfs>,
+    files: Vec<(AssetKind,String,String)>,
+    callbacks: Vec<Box<dyn FnOnce(&mut GameEngine,&LoadReport)>>,
+    errors: Vec<RglError>,
+    group: Option<ResourceGroup>
+}
+
+impl LoadRequest {
+    //Folders are paths in the vfs (see GameEngine::new_load_request)
+    pub fn new(vfs:Arc<Vfs>) -> Self {
+        LoadRequest { vfs, files: vec![], callbacks: vec![], errors: vec![], group: None }
+    }
+
+    //Resource group the assets are added to when they are uploaded
+    pub fn group(mut self, group:Option<ResourceGroup>) -> Self {
+        self.group = group;
+        self
+    }
+
+    pub fn file(mut self, folder:&str, name:&str) -> Self {
+        match AssetKind::from_path(name) {
+            Some(kind) => self.files.push((kind,folder.to_string(),name.to_string())),
+            None => self.errors.push(RglError::unsupported(&join_path(folder,name),"unknown asset type"))
+        }
+        self
+    }
+
+    pub fn folder(self, folder:&str) -> Self {
+        return self.folder_of(folder,None);
+    }
+
+    pub fn textures_in_folder(self, folder:&str) -> Self {
+        return self.folder_of(folder,Some(AssetKind::Texture));
+    }
+
+    pub fn objs_in_folder(self, folder:&str) -> Self {
+        return self.folder_of(folder,Some(AssetKind::Obj));
+    }
+
+    pub fn audio_in_folder(self, folder:&str) -> Self {
+        return self.folder_of(folder,Some(AssetKind::Audio));
+    }
+
+    fn folder_of(mut self, folder:&str, filter:Option<AssetKind>) -> Self {
+        match self.vfs.try_list_files(folder) {
+            Ok(files) => {
+                for name in files {
+                    if let Some(kind) = AssetKind::from_path(&name) {
+                        if filter.map_or(true,|f| f == kind) {
+                            self.files.push((kind,folder.to_string(),name));
+                        }
+                    }
+                }
+            },
+            Err(e) => self.errors.push(e)
+        }
+        self
+    }
+
+    //Called on the main thread after every file of the request was uploaded (or failed)
+    pub fn on_complete(mut self, callback:impl FnOnce(&mut GameEngine,&LoadReport) + 'static) -> Self {
+        self.callbacks.push(Box::new(callback));
+        self
+    }
+}
+
+/*
+Decodes files on worker threads, the main thread uploads them to the gpu (and audio device) in GameEngine::update_assets,
+at most upload_budget per frame. Objs of a request are uploaded after its textures, so their materials find them.
+The loaded assets end up in the ResourceLoader (textures, objs) and the AudioHandler like with the blocking functions.
+ */
+pub struct AssetLoader {
+    pub upload_budget: Duration,
+    pub worker_count: usize,
+    batches: HashMap<u64,LoadBatch>,
+    next_batch: u64,
+    jobs: Option<Sender<LoadJob>>,
+    results: Receiver<LoadedAsset>,
+    result_sender: Sender<LoadedAsset>,
+    ready: VecDeque<LoadedAsset>
+}
+
+impl AssetLoader {
+    pub fn new() -> Self {
+        let (result_sender,results) = channel();
+        let worker_count = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(2).clamp(1,4);
+        AssetLoader { upload_budget: Duration::from_millis(4), worker_count, batches: HashMap::new(), next_batch: 0, jobs: None, results, result_sender, ready: VecDeque::new() }
+    }
+
+    pub fn submit(&mut self, request:LoadRequest) -> LoadHandle {
+        let id = self.next_batch;
+        self.next_batch += 1;
+
+        let pending_textures = request.files.iter().filter(|f| f.0 == AssetKind::Texture).count();
+        self.batches.insert(id,LoadBatch { total: request.files.len(), uploaded: 0, failed: 0, pending_textures, errors: request.errors, callbacks: request.callbacks, group: request.group });
+
+        let jobs = self.get_job_sender();
+        for (kind,dir,name) in request.files {
+            jobs.send(LoadJob { vfs: request.vfs.clone(), batch: id, kind, dir, name }).unwrap();
+        }
+
+        return LoadHandle(id);
+    }
+
+    //The workers are started with the first request
+    fn get_job_sender(&mut self) -> Sender<LoadJob> {
+        if self.jobs.is_none() {
+            let (sender,receiver) = channel::<LoadJob>();
+            let receiver = Arc::new(Mutex::new(receiver));
+
+            for _ in 0..self.worker_count {
+                let receiver = receiver.clone();
+                let results = self.result_sender.clone();
+                std::thread::spawn(move || {
+                    loop {
+                        let job = match receiver.lock().unwrap().recv() {
+                            Ok(job) => job,
+                            Err(_) => break
+                        };
+                        //A decoder that panics on malformed input must not take the worker (and the report of the job) down with it
+                        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| decode(&job))).unwrap_or_else(|panic| {
+                            let message = panic.downcast_ref::<&str>().map(|s| s.to_string()).or_else(|| panic.downcast_ref::<String>().cloned()).unwrap_or_default();
+                            Err(RglError::parse(&join_path(&job.dir,&job.name),None,format!("Decoder panicked: {}",message)))
+                        });
+                        if results.send(LoadedAsset { batch: job.batch, kind: job.kind, dir: job.dir, name: job.name, result }).is_err() {
+                            break;
+                        }
+                    }
+                });
+            }
+            self.jobs = Some(sender);
+        }
+        return self.jobs.as_ref().unwrap().clone();
+    }
+
+    //Next decoded asset that can be uploaded, None if there is none or the budget (measured from start) is used up
+    pub(crate) fn next_upload(&mut self, start:Instant, uploaded:usize) -> Option<LoadedAsset> {
+        if uploaded > 0 && start.elapsed() >= self.upload_budget {
+            return None;
+        }
+
+        while let Ok(asset) = self.results.try_recv() {
+            self.ready.push_back(asset);
+        }
+
+        let batches = &self.batches;
+        let index = self.ready.iter().position(|a| a.kind != AssetKind::Obj || batches.get(&a.batch).map_or(true,|b| b.pending_textures == 0))?;
+        return self.ready.remove(index);
+    }
+
+    pub(crate) fn finish_upload(&mut self, batch:u64, kind:AssetKind, result:RglResult<()>) {
+        if let Some(b) = self.batches.get_mut(&batch) {
+            b.uploaded += 1;
+            if kind == AssetKind::Texture {
+                b.pending_textures -= 1;
+            }
+            if let Err(e) = result {
+                error!("{}",e);
+                b.failed += 1;
+                b.errors.push(e);
+            }
+        }
+    }
+
+    //Removes the finished batches, their callbacks have to be called with the report
+    pub(crate) fn take_finished(&mut self) -> Vec<(Vec<Box<dyn FnOnce(&mut GameEngine,&LoadReport)>>,LoadReport)> {
+        let finished = self.batches.iter().filter(|(_,b)| b.uploaded >= b.total).map(|(id,_)| *id).collect::<Vec<_>>();
+
+        let mut result = vec![];
+        for id in finished {
+            let batch = self.batches.remove(&id).unwrap();
+            result.push((batch.callbacks,LoadReport { loaded: batch.total - batch.failed, errors: batch.errors }));
+        }
+        return result;
+    }
+
+    pub(crate) fn get_group(&self, batch:u64) -> Option<ResourceGroup> {
+        return self.batches.get(&batch).and_then(|b| b.group);
+    }
+
+    //0.0 - 1.0, finished (or unknown) requests are 1.0
+    pub fn get_progress(&self, handle:LoadHandle) -> f32 {
+        return match self.batches.get(&handle.0) {
+            Some(b) if b.total > 0 => b.uploaded as f32 / b.total as f32,
+            _ => 1.0
+        }
+    }
+
+    pub fn is_finished(&self, handle:LoadHandle) -> bool {
+        return !self.batches.contains_key(&handle.0);
+    }
+
+    //Progress of every running request
+    pub fn get_total_progress(&self) -> f32 {
+        let total = self.batches.values().map(|b| b.total).sum::<usize>();
+        if total == 0 {
+            return 1.0;
+        }
+        return self.batches.values().map(|b| b.uploaded).sum::<usize>() as f32 / total as f32;
+    }
+
+    pub fn is_loading(&self) -> bool {
+        return !self.batches.is_empty();
+    }
+}
+
+fn decode(job:&LoadJob) -> RglResult<DecodedAsset> {
+    let path = join_path(&job.dir,&job.name);
+    return match job.kind {
+        AssetKind::Texture => {
+            let data = job.vfs.read(&path)?;
+            let image = image::load_from_memory(&data).map_err(|e| RglError::from_image(&path,e))?;
+            Ok(DecodedAsset::Texture(image.to_rgba8()))
+        },
+        AssetKind::Obj => {
+            let txt = job.vfs.read_to_string(&path)?;
+            let obj = ObjData::parse(&txt,&path)?;
+            let mut libs = vec![];
+            for lib in obj.material_libs.iter() {
+                let lib_path = join_path(&job.dir,lib);
+                libs.push((lib_path.clone(),job.vfs.read_to_string(&lib_path)?));
+            }
+            Ok(DecodedAsset::Obj(obj,libs))
+        },
+        AssetKind::Audio => Ok(DecodedAsset::Audio(DecodedAudio::try_load(&path,&job.vfs)?))
+    }
+}
@@ -0,0 +1,247 @@
+use std::sync::Arc;
+use log::{info, warn};
+use wgpu::{Adapter, Backends, CompositeAlphaMode, Device, DeviceDescriptor, Dx12Compiler, Features, Instance, InstanceDescriptor, Limits, PowerPreference, PresentMode, Queue, RequestAdapterOptions, Surface, SurfaceCapabilities, TextureFormat};
+use crate::error::rgl_error::{RglError, RglResult};
+use crate::vfs::vfs::Vfs;
+
+/*
+Gpu and surface settings for GameEngine::new_with_config / new_headless_with_config.
+Everything that is requested but not supported by the adapter is an error, only the surface formats are preferences.
+ */
+#[derive(Clone, Debug)]
+pub struct EngineConfig {
+    pub backends: Backends,
+    pub dx12_shader_compiler: Dx12Compiler,
+    pub power_preference: PowerPreference,
+    //Case insensitive part of the adapter name, e.g. "nvidia"
+    pub adapter_name: Option<String>,
+    pub force_fallback_adapter: bool,
+    pub features: Features,
+    //Enabled if the adapter supports them
+    pub optional_features: Features,
+    pub limits: Limits,
+    //None: Fifo or Immediate depending on GameWindow::vsync
+    pub present_mode: Option<PresentMode>,
+    //The first one the surface supports is used, otherwise the first sRGB format
+    pub surface_formats: Vec<TextureFormat>,
+    //Needs a window created with GameWindow::new_transparent
+    pub transparent: bool,
+    //1 (off), 2, 4 or 8, can be changed later with GameEngine::set_msaa_samples
+    pub msaa_samples: u32,
+    //Render into Rgba16Float targets and tonemap them, see GameEngine::set_hdr
+    pub hdr: bool,
+    //Enables Features::TIMESTAMP_QUERY if the adapter supports it, needed for the gpu timings of the Profiler
+    pub gpu_timing: bool,
+    //Vfs the engine loads everything from (its shaders too), None mounts the working dir
+    pub vfs: Option<Arc<Vfs>>
+}
+
+impl EngineConfig {
+    pub fn new() -> Self {
+        EngineConfig {
+            backends: Backends::all(),
+            dx12_shader_compiler: Dx12Compiler::Fxc,
+            power_preference: PowerPreference::default(),
+            adapter_name: None,
+            force_fallback_adapter: false,
+            features: Features::empty(),
+            optional_features: Features::empty(),
+            limits: Limits::default(),
+            present_mode: None,
+            surface_formats: vec![],
+            transparent: false,
+            msaa_samples: 1,
+            hdr: false,
+            gpu_timing: true,
+            vfs: None
+        }
+    }
+
+    pub fn backends(mut self, backends:Backends) -> Self {
+        self.backends = backends;
+        self
+    }
+
+    pub fn dx12_shader_compiler(mut self, compiler:Dx12Compiler) -> Self {
+        self.dx12_shader_compiler = compiler;
+        self
+    }
+
+    pub fn power_preference(mut self, power_preference:PowerPreference) -> Self {
+        self.power_preference = power_preference;
+        self
+    }
+
+    pub fn adapter_name(mut self, name:&str) -> Self {
+        self.adapter_name = Some(name.to_string());
+        self
+    }
+
+    //Software adapter, mostly useful for tests
+    pub fn force_fallback_adapter(mut self, force:bool) -> Self {
+        self.force_fallback_adapter = force;
+        self
+    }
+
+    pub fn features(mut self, features:Features) -> Self {
+        self.features = features;
+        self
+    }
+
+    pub fn optional_features(mut self, features:Features) -> Self {
+        self.optional_features = features;
+        self
+    }
+
+    pub fn limits(mut self, limits:Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    pub fn present_mode(mut self, present_mode:PresentMode) -> Self {
+        self.present_mode = Some(present_mode);
+        self
+    }
+
+    //Can be called multiple times, earlier formats are preferred
+    pub fn surface_format(mut self, format:TextureFormat) -> Self {
+        self.surface_formats.push(format);
+        self
+    }
+
+    pub fn transparent(mut self, transparent:bool) -> Self {
+        self.transparent = transparent;
+        self
+    }
+
+    pub fn msaa_samples(mut self, samples:u32) -> Self {
+        self.msaa_samples = samples;
+        self
+    }
+
+    pub fn hdr(mut self, hdr:bool) -> Self {
+        self.hdr = hdr;
+        self
+    }
+
+    pub fn gpu_timing(mut self, gpu_timing:bool) -> Self {
+        self.gpu_timing = gpu_timing;
+        self
+    }
+
+    pub fn vfs(mut self, vfs:Arc<Vfs>) -> Self {
+        self.vfs = Some(vfs);
+        self
+    }
+
+    pub(crate) fn create_instance(&self) -> Instance {
+        return Instance::new(InstanceDescriptor { backends: self.backends, dx12_shader_compiler: self.dx12_shader_compiler.clone() });
+    }
+
+    /*
+    Adapters with the requested name are looked up by enumerating all of them. Headless engines (no surface)
+    fall back to the software adapter if no hardware adapter is found.
+     */
+    pub(crate) async fn request_adapter(&self, instance:&Instance, surface:Option<&Surface>) -> RglResult<Adapter> {
+        if let Some(name) = &self.adapter_name {
+            let mut names = vec![];
+            for adapter in instance.enumerate_adapters(self.backends) {
+                let info = adapter.get_info();
+                if info.name.to_lowercase().contains(&name.to_lowercase()) && surface.map_or(true,|s| adapter.is_surface_supported(s)) {
+                    return Ok(adapter);
+                }
+                names.push(format!("{} ({:?})",info.name,info.backend));
+            }
+            return Err(RglError::config(format!("No adapter named {} found, available: {}",name,names.join(", "))));
+        }
+
+        let adapter = instance.request_adapter(&RequestAdapterOptions {
+            power_preference: self.power_preference,
+            compatible_surface: surface,
+            force_fallback_adapter: self.force_fallback_adapter
+        }).await;
+
+        let adapter = match adapter {
+            None if surface.is_none() && !self.force_fallback_adapter => {
+                warn!("No hardware adapter found, using the fallback adapter");
+                instance.request_adapter(&RequestAdapterOptions {
+                    power_preference: self.power_preference,
+                    compatible_surface: None,
+                    force_fallback_adapter: true
+                }).await
+            },
+            adapter => adapter
+        };
+
+        return adapter.ok_or_else(|| RglError::config(format!("No adapter found for the backends {:?}",self.backends)));
+    }
+
+    pub(crate) async fn request_device(&self, adapter:&Adapter) -> RglResult<(Device,Queue)> {
+        let info = adapter.get_info();
+
+        let missing = self.features - adapter.features();
+        if !missing.is_empty() {
+            return Err(RglError::config(format!("{} does not support the features {:?}",info.name,missing)));
+        }
+
+        let mut exceeded = vec![];
+        self.limits.check_limits_with_fail_fn(&adapter.limits(),false,|name,requested,allowed| exceeded.push(format!("{} (requested {}, supported {})",name,requested,allowed)));
+        if !exceeded.is_empty() {
+            return Err(RglError::config(format!("{} does not support the limits {}",info.name,exceeded.join(", "))));
+        }
+
+        let mut optional = self.optional_features;
+        if self.gpu_timing {
+            optional |= Features::TIMESTAMP_QUERY;
+        }
+        let features = self.features | (optional & adapter.features());
+        info!("Using {} ({:?}) with the features {:?}",info.name,info.backend,features);
+
+        return adapter.request_device(&DeviceDescriptor {
+            features,
+            limits: self.limits.clone(),
+            label: None
+        },None).await.map_err(|e| RglError::config(format!("Could not create the device on {}: {}",info.name,e)));
+    }
+
+    //Without TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES wgpu only allows 1 and 4 samples
+    pub(crate) fn get_supported_samples(&self, adapter:&Adapter, device:&Device, format:TextureFormat) -> Vec<u32> {
+        if !device.features().contains(Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES) {
+            return vec![1,4];
+        }
+        let color = adapter.get_texture_format_features(format).flags;
+        let depth = adapter.get_texture_format_features(TextureFormat::Depth32Float).flags;
+        return [1,2,4,8].into_iter().filter(|n| color.sample_count_supported(*n) && depth.sample_count_supported(*n)).collect();
+    }
+
+    pub(crate) fn choose_surface_format(&self, caps:&SurfaceCapabilities) -> TextureFormat {
+        if let Some(format) = self.surface_formats.iter().find(|f| caps.formats.contains(f)) {
+            return *format;
+        }
+        if !self.surface_formats.is_empty() {
+            warn!("None of the surface formats {:?} is supported, supported are {:?}",self.surface_formats,caps.formats);
+        }
+        return caps.formats.iter().copied().find(|f| f.is_srgb()).unwrap_or(caps.formats[0]);
+    }
+
+    //Auto modes are always supported, wgpu picks one of the modes for them
+    pub(crate) fn choose_present_mode(&self, caps:&SurfaceCapabilities, vsync:bool) -> RglResult<PresentMode> {
+        let mode = self.present_mode.unwrap_or(if vsync { PresentMode::Fifo } else { PresentMode::Immediate });
+        return match mode {
+            PresentMode::AutoVsync | PresentMode::AutoNoVsync => Ok(mode),
+            mode if caps.present_modes.contains(&mode) => Ok(mode),
+            //Immediate was used for vsync off before, it is not available everywhere
+            PresentMode::Immediate if self.present_mode.is_none() => Ok(PresentMode::AutoNoVsync),
+            mode => Err(RglError::config(format!("The present mode {:?} is not supported, supported are {:?}",mode,caps.present_modes)))
+        }
+    }
+
+    pub(crate) fn choose_alpha_mode(&self, caps:&SurfaceCapabilities) -> RglResult<CompositeAlphaMode> {
+        if !self.transparent {
+            return Ok(CompositeAlphaMode::Auto);
+        }
+        return [CompositeAlphaMode::PreMultiplied,CompositeAlphaMode::PostMultiplied,CompositeAlphaMode::Inherit].into_iter()
+            .find(|m| caps.alpha_modes.contains(m))
+            .ok_or_else(|| RglError::config(format!("Transparent surfaces are not supported, the alpha modes are {:?}",caps.alpha_modes)));
+    }
+}
@@ -0,0 +1,232 @@
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Sender};
+use std::thread::JoinHandle;
+use image::RgbaImage;
+use log::{error, info};
+use wgpu::{Buffer, BufferDescriptor, BufferUsages, CommandEncoder, Device, Extent3d, ImageCopyBuffer, ImageCopyTexture, ImageDataLayout, MapMode, Origin3d, Texture, TextureAspect, TextureFormat, TextureUsages};
+use crate::engine::game_engine::GameEngine;
+use crate::error::rgl_error::{RglError, RglResult};
+use crate::objects::render_texture::read_buffer_to_image;
+
+//Frames which are dumped with GameEngine::start_recording
+pub struct Recording {
+    pub dir: PathBuf,
+    pub fps: u32,
+    pub frame: u32
+}
+
+struct PendingCapture {
+    buffer: Buffer,
+    width: u32,
+    height: u32,
+    format: TextureFormat
+}
+
+/*
+Copies the frame into a buffer after it was rendered (see GameEngine::capture_next_frame / screenshot / start_recording)
+and reads it back once the frame was submitted. Reading back blocks until the gpu is done, so only frames that were requested are copied.
+The png files are written by a background thread.
+ */
+pub struct FrameCapture {
+    pub recording: Option<Recording>,
+    callbacks: Vec<Box<dyn FnOnce(&mut GameEngine,RgbaImage)>>,
+    files: Vec<PathBuf>,
+    pending: Option<PendingCapture>,
+    writer: Option<(Sender<(PathBuf,RgbaImage)>,JoinHandle<()>)>
+}
+
+impl FrameCapture {
+    pub fn new() -> Self {
+        FrameCapture { recording: None, callbacks: vec![], files: vec![], pending: None, writer: None }
+    }
+
+    pub fn is_requested(&self) -> bool {
+        return !self.callbacks.is_empty() || !self.files.is_empty() || self.recording.is_some();
+    }
+
+    //While recording every frame advances the time by 1 / fps, no matter how long it took
+    pub fn get_timestep(&self) -> Option<f32> {
+        return self.recording.as_ref().map(|r| 1.0 / r.fps as f32);
+    }
+
+    pub(crate) fn add_callback(&mut self, callback:Box<dyn FnOnce(&mut GameEngine,RgbaImage)>) {
+        self.callbacks.push(callback);
+    }
+
+    pub(crate) fn add_file(&mut self, path:PathBuf) {
+        self.files.push(path);
+    }
+
+    //Adds the copy of texture to the encoder of the frame, if a capture was requested
+    pub(crate) fn copy_frame(&mut self, device:&Device, encoder:&mut CommandEncoder, texture:&Texture) {
+        if !self.is_requested() {
+            return;
+        }
+        match copy_texture_to_buffer(device,encoder,texture) {
+            Ok(buffer) => self.pending = Some(PendingCapture { buffer, width: texture.width(), height: texture.height(), format: texture.format() }),
+            Err(e) => {
+                error!("Could not capture the frame: {}",e);
+                self.callbacks.clear();
+                self.files.clear();
+                self.recording = None;
+            }
+        }
+    }
+
+    //Reads the copied frame back, returns the image and the callbacks it has to be passed to
+    pub(crate) fn finish(&mut self, device:&Device) -> Option<(RgbaImage,Vec<Box<dyn FnOnce(&mut GameEngine,RgbaImage)>>)> {
+        let pending = self.pending.take()?;
+        let image = read_texture_buffer(device,&pending.buffer,pending.width,pending.height,pending.format);
+
+        let mut files = std::mem::take(&mut self.files);
+        if let Some(recording) = &mut self.recording {
+            files.push(recording.dir.join(format!("frame_{:06}.png",recording.frame)));
+            recording.frame += 1;
+        }
+        for path in files {
+            self.write(path,image.clone());
+        }
+
+        return Some((image,std::mem::take(&mut self.callbacks)));
+    }
+
+    fn write(&mut self, path:PathBuf, image:RgbaImage) {
+        if self.writer.is_none() {
+            let (sender,receiver) = channel::<(PathBuf,RgbaImage)>();
+            let handle = std::thread::spawn(move || {
+                for (path,image) in receiver {
+                    if let Err(e) = image.save(&path) {
+                        error!("Could not save {}: {}",path.display(),e);
+                    }
+                }
+            });
+            self.writer = Some((sender,handle));
+        }
+        self.writer.as_ref().unwrap().0.send((path,image)).unwrap();
+    }
+
+    //Blocks until every captured frame is written
+    pub fn flush(&mut self) {
+        if let Some((sender,handle)) = self.writer.take() {
+            drop(sender);
+            handle.join().unwrap();
+        }
+    }
+
+    //Frames are written to dir/frame_000000.png, frame_000001.png, ...
+    pub fn start_recording(&mut self, dir:&Path, fps:u32) -> RglResult<()> {
+        let dir_name = dir.to_string_lossy().to_string();
+        std::fs::create_dir_all(dir).map_err(|e| RglError::io(&dir_name,e))?;
+        self.recording = Some(Recording { dir: dir.to_path_buf(), fps: fps.max(1), frame: 0 });
+        info!("Recording to {} at {} fps",dir_name,fps);
+        return Ok(());
+    }
+
+    //Returns how many frames were recorded
+    pub fn stop_recording(&mut self) -> u32 {
+        let frames = self.recording.take().map_or(0,|r| r.frame);
+        self.flush();
+        return frames;
+    }
+}
+
+fn get_bytes_per_pixel(format:TextureFormat) -> Option<u32> {
+    return match format {
+        TextureFormat::Rgba8Unorm | TextureFormat::Rgba8UnormSrgb | TextureFormat::Bgra8Unorm | TextureFormat::Bgra8UnormSrgb => Some(4),
+        TextureFormat::Rgba16Float => Some(8),
+        _ => None
+    }
+}
+
+//Rows of texture copies have to be aligned to 256 bytes
+pub fn get_padded_row(width:u32, bytes_per_pixel:u32) -> u32 {
+    let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+    return (width * bytes_per_pixel + align - 1) / align * align;
+}
+
+//The texture needs COPY_SRC usage, 8 bit rgba / bgra and Rgba16Float textures are supported
+pub fn copy_texture_to_buffer(device:&Device, encoder:&mut CommandEncoder, texture:&Texture) -> RglResult<Buffer> {
+    let format = texture.format();
+    let bytes_per_pixel = get_bytes_per_pixel(format).ok_or_else(|| RglError::config(format!("Textures with the format {:?} can not be captured",format)))?;
+    if !texture.usage().contains(TextureUsages::COPY_SRC) || texture.sample_count() > 1 {
+        return Err(RglError::config("The texture can not be copied (no COPY_SRC usage or multisampled)".to_string()));
+    }
+
+    let padded_row = get_padded_row(texture.width(),bytes_per_pixel);
+    let buffer = device.create_buffer(&BufferDescriptor {
+        label: Some("Capture Readback"),
+        size: (padded_row * texture.height()) as u64,
+        usage: BufferUsages::COPY_DST | BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+
+    encoder.copy_texture_to_buffer(
+        ImageCopyTexture {
+            texture,
+            mip_level: 0,
+            origin: Origin3d::ZERO,
+            aspect: TextureAspect::All,
+        },
+        ImageCopyBuffer {
+            buffer: &buffer,
+            layout: ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(padded_row),
+                rows_per_image: Some(texture.height()),
+            },
+        },
+        Extent3d {
+            width: texture.width(),
+            height: texture.height(),
+            depth_or_array_layers: 1
+        }
+    );
+
+    return Ok(buffer);
+}
+
+//Hdr images are clamped and gamma encoded, blocks until the buffer can be mapped
+pub fn read_texture_buffer(device:&Device, buffer:&Buffer, width:u32, height:u32, format:TextureFormat) -> RgbaImage {
+    if format != TextureFormat::Rgba16Float {
+        return read_buffer_to_image(device,buffer,width,height,format);
+    }
+
+    let slice = buffer.slice(..);
+    slice.map_async(MapMode::Read, |result| {
+        result.unwrap();
+    });
+    device.poll(wgpu::Maintain::Wait);
+
+    let padded_row = get_padded_row(width,8) as usize;
+    let mut values = Vec::with_capacity((width * height * 4) as usize);
+    {
+        let data = slice.get_mapped_range();
+        for y in 0..height as usize {
+            for p in data[y * padded_row..y * padded_row + width as usize * 8].chunks(2) {
+                values.push(half_to_f32(u16::from_le_bytes([p[0],p[1]])));
+            }
+        }
+    }
+    buffer.unmap();
+
+    return RgbaImage::from_fn(width,height,|x,y| {
+        let i = ((y * width + x) * 4) as usize;
+        let encode = |v:f32| (linear_to_srgb(v.clamp(0.0,1.0)) * 255.0).round() as u8;
+        image::Rgba([encode(values[i]),encode(values[i + 1]),encode(values[i + 2]),(values[i + 3].clamp(0.0,1.0) * 255.0).round() as u8])
+    });
+}
+
+fn linear_to_srgb(v:f32) -> f32 {
+    return if v <= 0.0031308 { v * 12.92 } else { 1.055 * v.powf(1.0 / 2.4) - 0.055 };
+}
+
+fn half_to_f32(bits:u16) -> f32 {
+    let sign = if bits & 0x8000 != 0 { -1.0 } else { 1.0 };
+    let exponent = ((bits >> 10) & 0x1f) as i32;
+    let mantissa = (bits & 0x3ff) as f32;
+    return sign * match exponent {
+        0 => mantissa * 2f32.powi(-24),
+        31 => if mantissa == 0.0 { f32::INFINITY } else { f32::NAN },
+        e => (1.0 + mantissa / 1024.0) * 2f32.powi(e - 15)
+    };
+}
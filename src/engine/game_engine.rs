@@ -1,33 +1,64 @@
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::time::Instant;
+use image::RgbaImage;
 use log::info;
-use wgpu::{Backends, CommandEncoder, Device, Dx12Compiler, InstanceDescriptor, LoadOp, Operations, PresentMode, Queue, RenderPass, RenderPassDepthStencilAttachment, Surface, SurfaceConfiguration, TextureView};
+use wgpu::{AdapterInfo, Backends, CommandEncoder, Device, Id, LoadOp, Operations, PresentMode, Queue, RenderPass, RenderPassColorAttachment, RenderPassDepthStencilAttachment, Surface, SurfaceConfiguration, Texture, TextureFormat, TextureView};
 use winit::dpi::PhysicalSize;
+use std::rc::Rc;
+use std::sync::Arc;
 use crate::audio::audio_handler::AudioHandler;
+use crate::engine::asset_loader::{AssetLoader, DecodedAsset, LoadHandle, LoadRequest};
+use crate::engine::engine_config::EngineConfig;
+use crate::engine::frame_capture::FrameCapture;
 use crate::engine::game_window::GameWindow;
+use crate::engine::hot_reloader::HotReloader;
 use crate::engine::input_handler::InputHandler;
 use crate::engine::material_manager::MaterialManager;
+use crate::engine::profiler::Profiler;
+use crate::engine::tick_handler::TickHandler;
+use crate::engine::resource_handle::{ResourceGroup, ResourceInfo};
 use crate::engine::resource_loader::ResourceLoader;
+use crate::error::rgl_error::{RglError, RglResult};
 use crate::objects::depth_texture::DepthTexture;
-use crate::objects::offset_handler::{OffsetHandler, StaticOffsetHandler};
+use crate::objects::render_texture::RenderTexture;
+use crate::pipeline::pipeline::RenderPipelineGroup;
+use crate::objects::obj_model::ObjModel;
+use crate::objects::obj_parser::parse_mtl;
+use crate::objects::texture_object::TextureObject;
+use crate::objects::offset_handler::{OffsetHandler, OffsetStats, StaticOffsetHandler};
 use crate::render::color_renderer::ColorRenderer;
 use crate::render::custom_vertex_renderer::CustomVertexRenderer;
 use crate::render::line_renderer::LineRenderer;
+use crate::render::shadow_renderer::ShadowRenderer;
 use crate::render::text_renderer::TextRenderer;
 use crate::render::texture_renderer::TextureRenderer;
 use crate::render::vertex_renderer::VertexRenderer;
 use crate::scene::scene::Scene;
+use crate::scene::transition::Transition;
+use crate::render::transition_renderer::TransitionRenderer;
+use crate::render::post_processor::{PostProcessor, PostShader};
+use crate::render::debug_draw::DebugDraw;
+use crate::scene::scene_handler::{LayerFlags, SceneCommand, SceneHandler};
 use crate::ui::bounds::Bounds;
+use crate::vfs::vfs::{join_path, Vfs};
+
+pub const HDR_FORMAT: TextureFormat = TextureFormat::Rgba16Float;
 
 pub struct GameEngine {
-    pub surface: Surface,
+    pub surface: Option<Surface>,
+    pub adapter_info: AdapterInfo,
     pub device: Device,
     pub queue: Queue,
     pub config: SurfaceConfiguration,
     pub size: PhysicalSize<u32>,
     pub game_window: GameWindow,
     pub scene_to_open: Option<Box<dyn Scene>>,
+    pub scene_commands: Vec<SceneCommand>,
     pub offset_handler: OffsetHandler,
     pub working_dir: String,
+    pub vfs: Arc<Vfs>,
     pub color_renderer: ColorRenderer,
     pub alignment_settings: AlignmentSettings,
     pub audio_handler: AudioHandler,
@@ -39,88 +70,177 @@ pub struct GameEngine {
     pub custom_vertex_renderer: CustomVertexRenderer,
     pub text_renderer: TextRenderer,
     pub vertex_renderer: VertexRenderer,
+    pub shadow_renderer: ShadowRenderer,
+    pub transition_renderer: TransitionRenderer,
+    pub post_processor: PostProcessor,
+    pub frame_capture: FrameCapture,
+    pub profiler: Profiler,
+    pub debug_draw: DebugDraw,
     pub time: Instant,
     pub delta_time: f32,
+    pub tick_handler: TickHandler,
+    pub tick_alpha: f32,
     pub fps: i32,
     pub material_manager: MaterialManager,
-    pub bounds: Bounds
+    pub asset_loader: AssetLoader,
+    pub hot_reloader: HotReloader,
+    pub bounds: Bounds,
+    next_resource_group: u64,
+    supported_msaa_samples: Vec<u32>,
+    msaa_samples: u32,
+    msaa_request: Option<u32>,
+    msaa_target: Option<RenderTexture>,
+    msaa_targets: HashMap<Id<TextureView>,MsaaTarget>,
+    layer_target: Cell<Option<Id<TextureView>>>,
+    hdr: bool,
+    hdr_request: Option<bool>
 }
 
 impl GameEngine {
     pub async fn new(game_window:GameWindow, backend:Backends) -> GameEngine{
-        let window = &game_window.window;
+        return GameEngine::new_with_config(game_window,EngineConfig::new().backends(backend)).await;
+    }
 
-        let size = window.inner_size();
+    pub async fn new_with_config(game_window:GameWindow, engine_config:EngineConfig) -> GameEngine{
+        return GameEngine::try_new_with_config(game_window,engine_config).await.unwrap_or_else(|e| panic!("{}",e));
+    }
 
-        let bounds = Bounds::new(0.0,0.0,size.width as f32,size.height as f32);
+    pub async fn try_new_with_config(game_window:GameWindow, engine_config:EngineConfig) -> RglResult<GameEngine>{
+        let window = game_window.window.as_ref().expect("GameEngine::new needs a window, use GameEngine::new_headless instead");
 
-        let instance = wgpu::Instance::new(InstanceDescriptor { backends: backend, dx12_shader_compiler: Dx12Compiler::Fxc });
-        let surface = unsafe { instance.create_surface(window).unwrap() };
-        let adapter = instance.request_adapter(
-            &wgpu::RequestAdapterOptions {
-                power_preference: wgpu::PowerPreference::default(),
-                compatible_surface: Some(&surface),
-                force_fallback_adapter: false,
-            },
-        ).await.unwrap();
+        if engine_config.transparent && !game_window.transparent {
+            return Err(RglError::config("Transparent surfaces need a window created with GameWindow::new_transparent".to_string()));
+        }
+
+        let size = window.inner_size();
+
+        let instance = engine_config.create_instance();
+        let surface = unsafe { instance.create_surface(window) }.map_err(|e| RglError::config(format!("Could not create the surface: {}",e)))?;
+        let adapter = engine_config.request_adapter(&instance,Some(&surface)).await?;
 
         info!("{:?}",adapter.get_info());
 
-        let (device, queue) = adapter.request_device(
-            &wgpu::DeviceDescriptor {
-                features: wgpu::Features::empty(),
-                limits: wgpu::Limits::default(),
-                label: None,
-            },
-            None, // Trace path
-        ).await.unwrap();
+        let (device, queue) = engine_config.request_device(&adapter).await?;
 
         let surface_caps = surface.get_capabilities(&adapter);
 
-        let surface_format = surface_caps.formats.iter()
-            .copied()
-            .find(|f| f.is_srgb())
-            .unwrap_or(surface_caps.formats[0]);
+        //Copying the frame is needed for screenshots, see FrameCapture
+        let config = wgpu::SurfaceConfiguration {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | (surface_caps.usages & wgpu::TextureUsages::COPY_SRC),
+            format: engine_config.choose_surface_format(&surface_caps),
+            width: size.width,
+            height: size.height,
+            present_mode: engine_config.choose_present_mode(&surface_caps,game_window.vsync)?,
+            alpha_mode: engine_config.choose_alpha_mode(&surface_caps)?,
+            view_formats: vec![],
+        };
+
+        surface.configure(&device,&config);
+
+        let samples = engine_config.get_supported_samples(&adapter,&device,config.format);
+        let mut engine = GameEngine::create(game_window,Some(surface),adapter.get_info(),samples,device,queue,config,AudioHandler::new(),engine_config.vfs.clone());
+        engine.set_msaa_samples(engine_config.msaa_samples)?;
+        engine.set_hdr(engine_config.hdr);
+        engine.update_render_settings();
+        return Ok(engine);
+    }
+
+    /*
+    Creates an engine without a window or surface. Everything gets rendered into offscreen textures
+    (see HeadlessRunner), if no hardware adapter is found the software fallback adapter is used.
+     */
+    pub async fn new_headless(game_window:GameWindow, backend:Backends) -> GameEngine{
+        return GameEngine::new_headless_with_config(game_window,EngineConfig::new().backends(backend)).await;
+    }
+
+    pub async fn new_headless_with_config(game_window:GameWindow, engine_config:EngineConfig) -> GameEngine{
+        return GameEngine::try_new_headless_with_config(game_window,engine_config).await.unwrap_or_else(|e| panic!("{}",e));
+    }
+
+    //The present mode and transparency are ignored, the first surface format is used for the render targets
+    pub async fn try_new_headless_with_config(game_window:GameWindow, engine_config:EngineConfig) -> RglResult<GameEngine>{
+        let size = game_window.get_size();
+
+        let instance = engine_config.create_instance();
+        let adapter = engine_config.request_adapter(&instance,None).await?;
+
+        info!("{:?}",adapter.get_info());
+
+        let (device, queue) = engine_config.request_device(&adapter).await?;
 
         let config = wgpu::SurfaceConfiguration {
             usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-            format: surface_format,
+            format: engine_config.surface_formats.first().copied().unwrap_or(TextureFormat::Rgba8UnormSrgb),
             width: size.width,
             height: size.height,
-            present_mode: game_window.get_present_mode(),
+            present_mode: PresentMode::Fifo,
             alpha_mode: Default::default(),
             view_formats: vec![],
         };
 
-        surface.configure(&device,&config);
+        let samples = engine_config.get_supported_samples(&adapter,&device,config.format);
+        let mut engine = GameEngine::create(game_window,None,adapter.get_info(),samples,device,queue,config,AudioHandler::new_muted(),engine_config.vfs.clone());
+        engine.set_msaa_samples(engine_config.msaa_samples)?;
+        engine.set_hdr(engine_config.hdr);
+        engine.update_render_settings();
+        return Ok(engine);
+    }
+
+    fn create(game_window:GameWindow,surface:Option<Surface>,adapter_info:AdapterInfo,supported_msaa_samples:Vec<u32>,device:Device,queue:Queue,config:SurfaceConfiguration,audio_handler:AudioHandler,vfs:Option<Arc<Vfs>>) -> GameEngine {
+        let size = PhysicalSize::new(config.width,config.height);
+
+        let bounds = Bounds::new(0.0,0.0,size.width as f32,size.height as f32);
 
         let wd = get_working_dir();
 
-        let cr = ColorRenderer::new(wd.clone(),&device,&config);
-        let texr = TextureRenderer::new(wd.clone(),&device,&config);
-        let liner = LineRenderer::new(wd.clone(),&device,&config);
-        let cvr = CustomVertexRenderer::new(wd.clone(),&device,&config);
-        let textr = TextRenderer::new(wd.clone(),&device,&config);
-        let vr = VertexRenderer::new(wd.clone(),&device,&config,&queue);
+        //Everything is loaded through the vfs, by default it only contains the working dir
+        let vfs = vfs.unwrap_or_else(|| {
+            let vfs = Arc::new(Vfs::new());
+            vfs.mount_dir("",&wd,0);
+            vfs
+        });
+
+        let cr = ColorRenderer::new(&vfs,&device,&config);
+        let texr = TextureRenderer::new(&vfs,&device,&config);
+        let liner = LineRenderer::new(&vfs,&device,&config);
+        let cvr = CustomVertexRenderer::new(&vfs,&device,&config);
+        let textr = TextRenderer::new(&vfs,&device,&config);
+        let vr = VertexRenderer::new(&vfs,&device,&config,&queue);
+        let shadowr = ShadowRenderer::new(&vfs,&device);
+        let transr = TransitionRenderer::new(&vfs,&device,&config);
 
-        let rsc_loader = ResourceLoader::new(&device);
+        let mut rsc_loader = ResourceLoader::new(&device,&queue);
 
         let a_settings = AlignmentSettings { uniform_offset: device.limits().min_uniform_buffer_offset_alignment, storage_offset: device.limits().min_storage_buffer_offset_alignment };
 
+        //The start of the transform buffer is used by the per frame OffsetHandler
+        let offset_handler = OffsetHandler::new_aligned(100,a_settings.uniform_offset);
+        let transform_stride = crate::objects::offset_handler::align_to(64,a_settings.storage_offset as u64);
+        let transform_start = (offset_handler.get_required_size() + transform_stride - 1) / transform_stride;
+        let static_offset_handler = StaticOffsetHandler::new_with_stride(5000 - transform_start as i32,transform_start,transform_stride);
+        let mut material_manager = MaterialManager::new_aligned(&a_settings);
+        let post_processor = PostProcessor::new(&vfs,&device,&config,a_settings.uniform_offset);
+        let profiler = Profiler::new(&device,&queue);
+        let debug_draw = DebugDraw::new(&vfs,&device,&config);
+        rsc_loader.create_placeholder_model(&device,&mut material_manager,&vr.shader.material_buffer,&queue);
+
         return GameEngine {
             surface,
+            adapter_info,
             device,
             queue,
             config,
             size,
             game_window,
             scene_to_open: None,
-            offset_handler: OffsetHandler::new(),
+            scene_commands: vec![],
+            offset_handler,
             working_dir: wd,
+            vfs,
             color_renderer: cr,
             alignment_settings: a_settings,
-            audio_handler: AudioHandler::new(),
-            static_offset_handler: StaticOffsetHandler::new(4900,100),
+            audio_handler,
+            static_offset_handler,
             resource_loader: rsc_loader,
             texture_renderer: texr,
             input_handler: InputHandler::new(),
@@ -128,56 +248,97 @@ impl GameEngine {
             custom_vertex_renderer: cvr,
             text_renderer: textr,
             vertex_renderer: vr,
+            shadow_renderer: shadowr,
+            transition_renderer: transr,
+            post_processor,
+            frame_capture: FrameCapture::new(),
+            profiler,
+            debug_draw,
             time: Instant::now(),
             delta_time: 0.0,
+            tick_handler: TickHandler::new(60),
+            tick_alpha: 0.0,
             fps: 0,
-            material_manager: MaterialManager::new(),
-            bounds
+            material_manager,
+            asset_loader: AssetLoader::new(),
+            hot_reloader: HotReloader::new(),
+            bounds,
+            next_resource_group: 0,
+            supported_msaa_samples,
+            msaa_samples: 1,
+            msaa_request: None,
+            msaa_target: None,
+            msaa_targets: HashMap::new(),
+            layer_target: Cell::new(None),
+            hdr: false,
+            hdr_request: None
+        }
+    }
+
+    pub fn is_headless(&self) -> bool {
+        return self.surface.is_none();
+    }
+
+    pub fn resize(&mut self,size:PhysicalSize<u32>) {
+        self.size = size;
+        self.config.width = size.width;
+        self.config.height = size.height;
+        self.game_window.headless_size = size;
+        if let Some(surface) = &self.surface {
+            surface.configure(&self.device,&self.config);
         }
+        self.create_msaa_target();
+    }
+
+    /*
+    With msaa the passes render into a multisampled texture and resolve into the frame. Registered render targets
+    have their own one (see register_render_target), every other frame has to have the size and format of the surface.
+     */
+    fn color_attachment<'a>(&'a self,frame:&'a TextureView,load:LoadOp<wgpu::Color>) -> RenderPassColorAttachment<'a> {
+        //The SceneHandler already cleared the target of the layers, see set_layer_target
+        let load = if self.layer_target.get() == Some(frame.global_id()) { LoadOp::Load } else { load };
+        let msaa_target = match self.msaa_targets.get(&frame.global_id()) {
+            Some(target) => target.texture.as_ref(),
+            None => self.msaa_target.as_ref()
+        };
+        let (view,resolve_target) = match msaa_target {
+            Some(target) => (&target.view,Some(frame)),
+            None => (frame,None)
+        };
+        return RenderPassColorAttachment { view, resolve_target, ops: Operations { load, store: true } };
     }
 
-    pub fn create_render_pass<'a>(&self,encoder:&'a mut CommandEncoder,view:&'a TextureView) -> RenderPass<'a>{
+    //While the SceneHandler renders its layers into target, the passes into it load the frame instead of clearing it
+    pub(crate) fn set_layer_target(&self, target:Option<&TextureView>) {
+        self.layer_target.set(target.map(|t| t.global_id()));
+    }
+
+    fn get_clear_color(&self) -> wgpu::Color {
+        return wgpu::Color {
+            r: self.game_window.clear_color.r as f64,
+            g: self.game_window.clear_color.g as f64,
+            b: self.game_window.clear_color.b as f64,
+            a: self.game_window.clear_color.a as f64,
+        };
+    }
+
+    pub fn create_render_pass<'a>(&'a self,encoder:&'a mut CommandEncoder,view:&'a TextureView) -> RenderPass<'a>{
+        self.profiler.write_timestamp(encoder,"Render Pass");
         let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
             label: None,
-            color_attachments: &[
-                Some(wgpu::RenderPassColorAttachment {
-                    view,
-                    resolve_target: None,
-                    ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(wgpu::Color {
-                            r: self.game_window.clear_color.r as f64,
-                            g: self.game_window.clear_color.g as f64,
-                            b: self.game_window.clear_color.b as f64,
-                            a: self.game_window.clear_color.a as f64,
-                        }),
-                        store: true,
-                    }
-                })
-            ],
+            color_attachments: &[Some(self.color_attachment(view,LoadOp::Clear(self.get_clear_color())))],
             depth_stencil_attachment: None,
         });
 
         return render_pass;
     }
 
-    pub fn create_render_pass_with_depth<'a>(&self,encoder:&'a mut CommandEncoder,frame:&'a TextureView,depth_texture:&'a DepthTexture) -> RenderPass<'a>{
+    //The depth texture needs the sample count of the engine, see create_depth_texture
+    pub fn create_render_pass_with_depth<'a>(&'a self,encoder:&'a mut CommandEncoder,frame:&'a TextureView,depth_texture:&'a DepthTexture) -> RenderPass<'a>{
+        self.profiler.write_timestamp(encoder,"Render Pass");
         let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
             label: None,
-            color_attachments: &[
-                Some(wgpu::RenderPassColorAttachment {
-                    view: frame,
-                    resolve_target: None,
-                    ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(wgpu::Color {
-                            r: self.game_window.clear_color.r as f64,
-                            g: self.game_window.clear_color.g as f64,
-                            b: self.game_window.clear_color.b as f64,
-                            a: self.game_window.clear_color.a as f64,
-                        }),
-                        store: true,
-                    }
-                })
-            ],
+            color_attachments: &[Some(self.color_attachment(frame,LoadOp::Clear(self.get_clear_color())))],
             depth_stencil_attachment: Some(RenderPassDepthStencilAttachment {
                 view: &depth_texture.view,
                 depth_ops: Some(Operations {
@@ -191,38 +352,22 @@ impl GameEngine {
         return render_pass;
     }
 
-    pub fn create_render_pass_load<'a>(&self,encoder:&'a mut CommandEncoder,frame:&'a TextureView) -> RenderPass<'a>{
+    pub fn create_render_pass_load<'a>(&'a self,encoder:&'a mut CommandEncoder,frame:&'a TextureView) -> RenderPass<'a>{
+        self.profiler.write_timestamp(encoder,"Render Pass");
         let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
             label: None,
-            color_attachments: &[
-                Some(wgpu::RenderPassColorAttachment {
-                    view: frame,
-                    resolve_target: None,
-                    ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Load,
-                        store: true,
-                    }
-                })
-            ],
+            color_attachments: &[Some(self.color_attachment(frame,LoadOp::Load))],
             depth_stencil_attachment: None,
         });
 
         return render_pass;
     }
 
-    pub fn create_render_pass_load_with_depth<'a>(&self,encoder:&'a mut CommandEncoder,frame:&'a TextureView,depth_texture:&'a DepthTexture) -> RenderPass<'a>{
+    pub fn create_render_pass_load_with_depth<'a>(&'a self,encoder:&'a mut CommandEncoder,frame:&'a TextureView,depth_texture:&'a DepthTexture) -> RenderPass<'a>{
+        self.profiler.write_timestamp(encoder,"Render Pass");
         let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
             label: None,
-            color_attachments: &[
-                Some(wgpu::RenderPassColorAttachment {
-                    view: frame,
-                    resolve_target: None,
-                    ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Load,
-                        store: true,
-                    }
-                })
-            ],
+            color_attachments: &[Some(self.color_attachment(frame,LoadOp::Load))],
             depth_stencil_attachment: Some(RenderPassDepthStencilAttachment {
             view: &depth_texture.view,
             depth_ops: Some(Operations {
@@ -236,6 +381,366 @@ impl GameEngine {
         return render_pass;
     }
 
+    pub fn create_depth_texture(&self) -> DepthTexture {
+        return DepthTexture::new_multisampled(&self.device,self.config.width,self.config.height,self.msaa_samples);
+    }
+
+    pub fn get_msaa_samples(&self) -> u32 {
+        return self.msaa_samples;
+    }
+
+    /*
+    1 turns msaa off. The change is applied at the start of the next frame: every built-in pipeline is built again and
+    the scenes get window_resized, so depth textures can be created again (World does it for its own).
+    Pipelines created by the game have to be updated with RenderPipelineGroup::set_target.
+     */
+    pub fn set_msaa_samples(&mut self, samples:u32) -> RglResult<()> {
+        if !self.supported_msaa_samples.contains(&samples) {
+            return Err(RglError::config(format!("{} msaa samples are not supported, supported are {:?}",samples,self.supported_msaa_samples)));
+        }
+        self.msaa_request = Some(samples);
+        return Ok(());
+    }
+
+    pub fn is_hdr(&self) -> bool {
+        return self.hdr;
+    }
+
+    /*
+    With hdr the scenes render into Rgba16Float targets (get_render_format) which are tonemapped into the frame,
+    see Tonemapping (post_processor.tonemapping). Colors and materials mean the same as before (linear), values above 1 are kept
+    until tonemapping instead of clipping. Applied at the start of the next frame like set_msaa_samples.
+     */
+    pub fn set_hdr(&mut self, hdr:bool) {
+        self.hdr_request = Some(hdr);
+    }
+
+    //Format of the targets the scenes render into, pipelines for scenes have to use it instead of config.format
+    pub fn get_render_format(&self) -> TextureFormat {
+        return if self.hdr { HDR_FORMAT } else { self.config.format };
+    }
+
+    //Applies set_msaa_samples and set_hdr, returns true if something changed
+    pub(crate) fn update_render_settings(&mut self) -> bool {
+        let samples = self.msaa_request.take().filter(|s| *s != self.msaa_samples);
+        let hdr = self.hdr_request.take().filter(|h| *h != self.hdr);
+        if samples.is_none() && hdr.is_none() {
+            return false;
+        }
+
+        if let Some(samples) = samples {
+            self.msaa_samples = samples;
+            info!("Using {}x msaa",samples);
+        }
+        if let Some(hdr) = hdr {
+            self.hdr = hdr;
+            info!("Hdr {}",if hdr { "enabled" } else { "disabled" });
+        }
+
+        let format = self.get_render_format();
+        let samples = self.msaa_samples;
+        let (device,groups) = self.get_pipeline_groups();
+        for group in groups {
+            group.set_target(device,format,samples);
+        }
+        self.transition_renderer.shader.set_format(&self.device,format);
+        self.post_processor.set_hdr(&self.device,self.hdr,format);
+        self.create_msaa_target();
+        for target in self.msaa_targets.values_mut() {
+            *target = MsaaTarget::new(&self.device,samples,target.width,target.height,target.format);
+        }
+        return true;
+    }
+
+    /*
+    Render targets (besides the frame) that scenes render into with the render passes of the engine have to be registered,
+    with msaa each one gets its own multisampled texture. Sharing the one of the frame would fail for other sizes or formats
+    and passes which load the target would get the samples of another one. The targets of transitions and post processing
+    are registered by the engine, unregister a target before dropping it.
+     */
+    pub fn register_render_target(&mut self, target:&RenderTexture) {
+        let (device,samples) = (&self.device,self.msaa_samples);
+        self.msaa_targets.entry(target.view.global_id()).or_insert_with(|| MsaaTarget::new(device,samples,target.width,target.height,target.format));
+    }
+
+    pub fn unregister_render_target(&mut self, target:&RenderTexture) {
+        self.msaa_targets.remove(&target.view.global_id());
+    }
+
+    fn create_msaa_target(&mut self) {
+        self.msaa_target = if self.msaa_samples > 1 {
+            Some(RenderTexture::new_multisampled(&self.device,self.config.width,self.config.height,self.get_render_format(),self.msaa_samples))
+        } else {
+            None
+        };
+    }
+
+    //The device is returned too, it can't be borrowed from the engine while the groups are
+    pub(crate) fn get_pipeline_groups(&mut self) -> (&Device,Vec<&mut RenderPipelineGroup>) {
+        return (&self.device,vec![
+            &mut self.color_renderer.color_shader.group,
+            &mut self.texture_renderer.basic_2d_shader.group,
+            &mut self.line_renderer.line_shader.group,
+            &mut self.custom_vertex_renderer.shader.group,
+            &mut self.custom_vertex_renderer.shader.group_depth,
+            &mut self.text_renderer.shader_program.group,
+            &mut self.vertex_renderer.shader.group,
+            &mut self.vertex_renderer.shader.skinned_group,
+            &mut self.debug_draw.group
+        ]);
+    }
+
+    //Called right before rendering, see PostProcessor
+    pub fn update_post_processing(&mut self) {
+        let previous = self.post_processor.get_scene_target().map(|t| t.view.global_id());
+        self.post_processor.update(&self.device,&self.queue,&self.config,self.delta_time);
+
+        let current = self.post_processor.get_scene_target();
+        if previous.is_some() && previous != current.map(|t| t.view.global_id()) {
+            self.msaa_targets.remove(&previous.unwrap());
+        }
+        if let Some(target) = current {
+            let (device,samples) = (&self.device,self.msaa_samples);
+            self.msaa_targets.entry(target.view.global_id()).or_insert_with(|| MsaaTarget::new(device,samples,target.width,target.height,target.format));
+        }
+    }
+
+    //Uploads the queued debug shapes, called right before rendering
+    pub fn update_debug_draw(&mut self) {
+        self.debug_draw.update(&self.device,&self.queue,self.delta_time);
+    }
+
+    //Fragment shader for PostEffect::custom, it has to use the bindings of the built-in effects (see PostProcessLayout)
+    pub fn load_post_shader(&mut self, fragment_shader_path:&str) -> RglResult<PostShader> {
+        return self.post_processor.try_load_shader(&self.device,&self.vfs,fragment_shader_path);
+    }
+
+    //The callback gets the next frame once it was rendered
+    pub fn capture_next_frame(&mut self, callback:impl FnOnce(&mut GameEngine,RgbaImage) + 'static) {
+        self.frame_capture.add_callback(Box::new(callback));
+    }
+
+    //Saves the next frame as png (written in the background)
+    pub fn screenshot(&mut self, path:&str) {
+        self.frame_capture.add_file(PathBuf::from(path));
+    }
+
+    /*
+    Saves every frame as png into dir until stop_recording. The time advances by 1 / fps every frame, so the recording
+    plays at the right speed no matter how slow the frames are rendered.
+     */
+    pub fn start_recording(&mut self, dir:&str, fps:u32) -> RglResult<()> {
+        return self.frame_capture.start_recording(Path::new(dir),fps);
+    }
+
+    //Returns how many frames were recorded, blocks until they are written
+    pub fn stop_recording(&mut self) -> u32 {
+        return self.frame_capture.stop_recording();
+    }
+
+    //Reads back any render target (e.g. of a World or the post processor), blocks until the gpu is done
+    pub fn capture_target(&self, target:&RenderTexture) -> RglResult<RgbaImage> {
+        return target.read_pixels(&self.device,&self.queue);
+    }
+
+    //Called by the runners with the rendered frame, before the encoder is submitted
+    pub(crate) fn capture_frame(&mut self, encoder:&mut CommandEncoder, texture:&Texture) {
+        self.frame_capture.copy_frame(&self.device,encoder,texture);
+    }
+
+    //Called after the frame was submitted
+    pub(crate) fn finish_capture(&mut self) {
+        if let Some((image,callbacks)) = self.frame_capture.finish(&self.device) {
+            for callback in callbacks {
+                callback(self,image.clone());
+            }
+        }
+    }
+    //Depth only pass into the shadow atlas, see ShadowRenderer
+    pub fn create_shadow_pass<'a>(&'a self,encoder:&'a mut CommandEncoder) -> RenderPass<'a>{
+        self.profiler.write_timestamp(encoder,"Shadow Pass");
+        let render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Shadow Pass"),
+            color_attachments: &[],
+            depth_stencil_attachment: Some(RenderPassDepthStencilAttachment {
+                view: &self.vertex_renderer.shader.shadow_atlas.view,
+                depth_ops: Some(Operations {
+                    load: LoadOp::Clear(1.0),
+                    store: true
+                }),
+                stencil_ops: None
+            }),
+        });
+
+        return render_pass;
+    }
+
+    /*
+    Frees the offsets of dropped handles and grows the buffers whose offset handlers ran out of slots.
+    Called at the start of every frame, the transform buffers belong to the cameras and grow when they are written (see Camera::write_transform).
+     */
+    pub fn update_offset_buffers(&mut self) {
+        self.static_offset_handler.update();
+        self.material_manager.offset_handler.update();
+        self.text_renderer.static_offset_handler.update();
+        self.color_renderer.color_offset_handler.update();
+        self.vertex_renderer.shader.joint_offset_handler.update();
+
+        self.vertex_renderer.shader.ensure_buffer_capacity(&self.device,&self.queue,self.material_manager.offset_handler.get_required_size());
+        self.material_manager.flush(&self.vertex_renderer.shader.material_buffer,&self.queue);
+        self.text_renderer.shader_program.ensure_color_capacity(&self.device,&self.queue,self.text_renderer.static_offset_handler.get_required_size());
+        self.color_renderer.color_shader.ensure_color_capacity(&self.device,&self.queue,self.color_renderer.color_offset_handler.get_required_size());
+    }
+
+    pub fn get_offset_stats(&self) -> Vec<(&'static str,OffsetStats)> {
+        return vec![
+            ("transform",self.static_offset_handler.get_stats()),
+            ("material",self.material_manager.offset_handler.get_stats()),
+            ("text_color",self.text_renderer.static_offset_handler.get_stats()),
+            ("color",self.color_renderer.color_offset_handler.get_stats()),
+            ("joint",self.vertex_renderer.shader.joint_offset_handler.get_stats())
+        ];
+    }
+
+    pub fn new_load_request(&self) -> LoadRequest {
+        return LoadRequest::new(self.vfs.clone()).group(self.resource_loader.active_group);
+    }
+
+    //Starts decoding the files of the request in the background, see AssetLoader
+    pub fn load_async(&mut self, request:LoadRequest) -> LoadHandle {
+        return self.asset_loader.submit(request);
+    }
+
+    //Loads the mp3, wav and ogg files of the folder in the background, see load_async
+    pub fn load_all_audio_in_folder(&mut self, folder:&str) -> LoadHandle {
+        let request = self.new_load_request().audio_in_folder(folder);
+        return self.load_async(request);
+    }
+
+    /*
+    Uploads decoded assets until asset_loader.upload_budget is used up (at least one per frame) and calls the callbacks of finished requests.
+    Called at the start of every frame.
+     */
+    pub fn update_assets(&mut self) {
+        let start = Instant::now();
+        let mut uploaded = 0;
+
+        while let Some(asset) = self.asset_loader.next_upload(start,uploaded) {
+            let group = self.asset_loader.get_group(asset.batch);
+            let result = match asset.result {
+                Ok(decoded) => self.upload_asset(decoded,&asset.dir,&asset.name,group),
+                Err(e) => Err(e)
+            };
+            self.asset_loader.finish_upload(asset.batch,asset.kind,result);
+            uploaded += 1;
+        }
+
+        for (callbacks,report) in self.asset_loader.take_finished() {
+            for callback in callbacks {
+                callback(self,&report);
+            }
+        }
+    }
+
+    pub fn new_resource_group(&mut self) -> ResourceGroup {
+        self.next_resource_group += 1;
+        return ResourceGroup(self.next_resource_group);
+    }
+
+    //Resources loaded from now on are added to the group (None: they are kept until unloaded), returns the previous group
+    pub fn set_resource_group(&mut self, group:Option<ResourceGroup>) -> Option<ResourceGroup> {
+        self.audio_handler.active_group = group;
+        return std::mem::replace(&mut self.resource_loader.active_group,group);
+    }
+
+    //Unloads the resources of the group that are not in another one, returns how many
+    pub fn free_resource_group(&mut self, group:ResourceGroup) -> usize {
+        return self.resource_loader.free_group(group,&mut self.material_manager) + self.audio_handler.free_group(group);
+    }
+
+    //Unloads every resource which is only held by the resource loader / audio handler, returns how many
+    pub fn unload_unused_resources(&mut self) -> usize {
+        return self.resource_loader.unload_unused(&mut self.material_manager) + self.audio_handler.unload_unused();
+    }
+
+    //Everything that is loaded with its size in gpu (and audio) memory
+    pub fn get_resource_infos(&self) -> Vec<ResourceInfo> {
+        let mut infos = self.resource_loader.get_resource_infos();
+        infos.extend(self.audio_handler.get_resource_infos());
+        return infos;
+    }
+
+    //Development mode, see HotReloader
+    pub fn enable_hot_reload(&mut self) {
+        self.hot_reloader.enabled = true;
+    }
+
+    //Called at the start of every frame, only polls the files when hot reloading is enabled
+    pub fn update_hot_reload(&mut self) {
+        self.hot_reloader.events.clear();
+        if self.hot_reloader.enabled && self.hot_reloader.should_poll(self.delta_time) {
+            crate::engine::hot_reloader::reload_changed(self);
+        }
+    }
+
+    fn upload_asset(&mut self, decoded:DecodedAsset, dir:&str, name:&str, group:Option<ResourceGroup>) -> RglResult<()> {
+        let path = join_path(dir,name);
+        match decoded {
+            DecodedAsset::Texture(image) => {
+                let max = self.device.limits().max_texture_dimension_2d;
+                if image.width() > max || image.height() > max {
+                    return Err(RglError::Gpu(format!("Texture {} is {}x{}, but the device only supports up to {}x{}",path,image.width(),image.height(),max,max)));
+                }
+                let tex = TextureObject::new_from_data(&image,name.to_string(),&self.device,&self.queue,&self.resource_loader.base_layout);
+                self.resource_loader.textures.insert(name,Rc::new(tex),Some(path),group);
+            },
+            DecodedAsset::Obj(obj,libs) => {
+                let mut materials = std::collections::HashMap::new();
+                for (path,source) in libs.iter() {
+                    materials.extend(parse_mtl(source,path)?);
+                }
+                let mut obj = ObjModel::from_data(obj,materials,&self.device);
+                self.material_manager.register(&mut obj.materials,&self.vertex_renderer.shader.material_buffer,&self.queue,&self.resource_loader);
+                self.resource_loader.objs.insert(name,Rc::new(obj),Some(path),group);
+            },
+            DecodedAsset::Audio(audio) => {
+                //Without an audio device there is nothing to upload to
+                if !self.audio_handler.is_muted() {
+                    let data = unsafe { audio.upload() };
+                    self.audio_handler.add_data(name.to_string(),path,data,group);
+                }
+            }
+        }
+        return Ok(());
+    }
+
+    //Runs Scene::fixed_update (and World::fixed_update) for every tick that passed since the last frame
+    pub(crate) fn run_ticks(&mut self, scene_handler:&mut SceneHandler) {
+        self.tick_handler.update(self.delta_time as f64);
+        while self.tick_handler.tick() {
+            scene_handler.fixed_update(self);
+        }
+        self.tick_alpha = self.tick_handler.tick_alpha as f32;
+    }
+
+    //The scene stack changes at the start of the next frame, see SceneHandler
+    pub fn push_scene(&mut self, scene:Box<dyn Scene>, flags:LayerFlags) {
+        self.scene_commands.push(SceneCommand::Push(scene,flags));
+    }
+
+    pub fn pop_scene(&mut self) {
+        self.scene_commands.push(SceneCommand::Pop);
+    }
+
+    pub fn replace_scene(&mut self, scene:Box<dyn Scene>) {
+        self.scene_commands.push(SceneCommand::Replace(scene));
+    }
+
+    //Replaces the whole stack, blending from the current scenes to the new one
+    pub fn change_scene_with(&mut self, scene:Box<dyn Scene>, transition:Transition) {
+        self.scene_commands.push(SceneCommand::Transition(scene,transition));
+    }
+
     pub fn exit(&mut self) {
         self.game_window.should_close = true;
     }
@@ -251,10 +756,30 @@ impl GameEngine {
     }
 }
 
+//The directory of the executable if the assets are next to it (so it can be started from anywhere), the current dir otherwise
 fn get_working_dir() -> String {
+    if let Some(exe_dir) = std::env::current_exe().ok().and_then(|p| p.parent().map(|d| d.to_path_buf())) {
+        if exe_dir.join("assets").is_dir() {
+            return exe_dir.to_string_lossy().to_string();
+        }
+    }
     let working_dir_buf = std::env::current_dir().unwrap();
-    let working_dir = working_dir_buf.to_str().unwrap();
-    return working_dir.to_string();
+    return working_dir_buf.to_string_lossy().to_string();
+}
+
+//Multisampled texture of a registered render target, None without msaa
+struct MsaaTarget {
+    width: u32,
+    height: u32,
+    format: TextureFormat,
+    texture: Option<RenderTexture>
+}
+
+impl MsaaTarget {
+    fn new(device:&Device, samples:u32, width:u32, height:u32, format:TextureFormat) -> MsaaTarget {
+        let texture = if samples > 1 { Some(RenderTexture::new_multisampled(device,width,height,format,samples)) } else { None };
+        return MsaaTarget { width, height, format, texture };
+    }
 }
 
 pub struct AlignmentSettings {
@@ -4,24 +4,42 @@ use log::LevelFilter;
 use wgpu::{Backends, TextureViewDescriptor};
 use winit::event::{DeviceEvent, Event, MouseScrollDelta, WindowEvent};
 use winit::event_loop::ControlFlow;
+use crate::engine::engine_config::EngineConfig;
 use crate::engine::game_engine::GameEngine;
 use crate::engine::game_window::GameWindow;
+use crate::engine::profiler::scope;
 use crate::scene::scene_handler::SceneHandler;
 use chrono::Local;
 use std::io::Write;
 
 
 pub struct GameEvents {
-
+    pub ticks_per_second: i32,
+    pub max_catch_up: u32
 }
 
 impl GameEvents {
 
     pub fn new() -> GameEvents {
-        return GameEvents{};
+        return GameEvents{ ticks_per_second: 60, max_catch_up: 5 };
+    }
+
+    pub fn ticks_per_second(mut self, ticks_per_second:i32) -> Self {
+        self.ticks_per_second = ticks_per_second;
+        self
+    }
+
+    //Maximum amount of fixed updates per frame
+    pub fn max_catch_up(mut self, max_catch_up:u32) -> Self {
+        self.max_catch_up = max_catch_up;
+        self
+    }
+
+    pub async fn run(&self, scene_handler:SceneHandler, game_window:GameWindow, backend:Backends) {
+        self.run_with_config(scene_handler,game_window,EngineConfig::new().backends(backend)).await;
     }
 
-    pub async fn run(&self, mut scene_handler:SceneHandler, mut game_window:GameWindow, backend:Backends) {
+    pub async fn run_with_config(&self, mut scene_handler:SceneHandler, mut game_window:GameWindow, engine_config:EngineConfig) {
 
         if cfg!(debug_assertions) {
             Builder::new()
@@ -43,13 +61,14 @@ impl GameEvents {
         let event_loop = game_window.event_loop.take().unwrap();
 
         let mut delta_time_calc = DeltaTimeCalc::new();
-        //let mut tick_handler = TickHandler::new((1000.0 / ticks_per_second as f64) / 1000.0,ticks_per_second);
         let mut fps_calc = FpsCalc::new();
 
-        let mut engine = GameEngine::new(game_window,backend).await;
+        let mut engine = GameEngine::new_with_config(game_window,engine_config).await;
+        engine.tick_handler.set_ticks_for_second(self.ticks_per_second);
+        engine.tick_handler.set_max_catch_up(self.max_catch_up);
         //let mut gui_handler = GuiHandler::new();
 
-        scene_handler.opened_scene.loaded(&mut engine);
+        scene_handler.loaded(&mut engine);
 
         engine.audio_handler.start();
 
@@ -59,9 +78,10 @@ impl GameEvents {
                 Event::WindowEvent {
                     ref event,
                     window_id,
-                } if window_id == engine.game_window.window.id() => match event {
+                } if window_id == engine.game_window.window.as_ref().unwrap().id() => match event {
                     WindowEvent::CloseRequested => {
                         engine.audio_handler.destroy();
+                        engine.frame_capture.flush();
                         //scene_handler.opened_scene.destroy();
                         *control_flow = ControlFlow::Exit
                     },
@@ -86,11 +106,8 @@ impl GameEvents {
                     }
                     ,WindowEvent::Resized(physical_size) => {
                         if physical_size.width * physical_size.height > 0 {
-                            engine.size = physical_size.clone();
-                            engine.config.width = physical_size.width;
-                            engine.config.height = physical_size.height;
-                            engine.surface.configure(&engine.device,&engine.config);
-                            scene_handler.opened_scene.window_resized(&mut engine,physical_size);
+                            engine.resize(physical_size.clone());
+                            scene_handler.window_resized(&mut engine,physical_size);
                         }
                     }
                     _ => {}
@@ -117,39 +134,58 @@ impl GameEvents {
                     _ => {}
                 }
                 ,Event::RedrawRequested(_) => {
-                    engine.static_offset_handler.update();
+                    engine.profiler.begin_frame();
+                    engine.update_assets();
+                    if engine.update_render_settings() {
+                        let size = engine.size;
+                        scene_handler.window_resized(&mut engine,&size);
+                    }
+                    engine.update_hot_reload();
+                    engine.update_offset_buffers();
                     delta_time_calc.update(&mut engine);
                     engine.delta_time = delta_time_calc.delta;
+                    if let Some(timestep) = engine.frame_capture.get_timestep() {
+                        engine.delta_time = timestep;
+                    }
 
                     if engine.game_window.update(control_flow) {
                         engine.audio_handler.destroy();
+                        engine.frame_capture.flush();
                         //scene_handler.opened_scene.destroy();
                     }
-                    if engine.scene_to_open.is_some() {
-                        scene_handler.opened_scene.close(&mut engine);
-                        engine.delta_time = 0.0;
-                        scene_handler.opened_scene = engine.scene_to_open.take().unwrap();
-                        scene_handler.opened_scene.loaded(&mut engine);
-                    }
+                    scene_handler.apply_commands(&mut engine);
 
-                    scene_handler.opened_scene.process_input(&mut engine);
+                    {
+                        let _scope = scope("input");
+                        scene_handler.process_input(&mut engine);
+                    }
 
                     /*
                     If add returns true a second has passed!
                      */
                     if fps_calc.add(&mut engine) {
-                        scene_handler.opened_scene.handle_second(&mut engine);
+                        scene_handler.handle_second(&mut engine);
+                    }
+
+                    {
+                        let _scope = scope("fixed update");
+                        engine.run_ticks(&mut scene_handler);
                     }
 
-                    scene_handler.opened_scene.update(&mut engine);
+                    {
+                        let _scope = scope("update");
+                        scene_handler.update(&mut engine);
+                        engine.update_post_processing();
+                        engine.update_debug_draw();
+                    }
 
-                    let frame_result = engine.surface.get_current_texture();
+                    let frame_result = engine.surface.as_ref().unwrap().get_current_texture();
 
                     if frame_result.is_err() {
                         match frame_result.unwrap_err() {
                             wgpu::SurfaceError::Lost => {
                                 let size = engine.size.clone();
-                                scene_handler.opened_scene.window_resized(&mut engine,&size)
+                                scene_handler.window_resized(&mut engine,&size)
                             },
                             // The system is out of memory, we should probably quit
                             wgpu::SurfaceError::OutOfMemory => *control_flow = ControlFlow::Exit,
@@ -169,17 +205,25 @@ impl GameEvents {
 
                                 {
                                     //gui_handler.render(&mut engine,&mut scene_handler.opened_scene,&queue);
-                                    scene_handler.opened_scene.render(&engine,&mut encoder,&view);
+                                    let _scope = scope("render");
+                                    scene_handler.render(&engine,&mut encoder,&view);
                                 }
                             }
-                            engine.queue.submit(std::iter::once(encoder.finish()));
-                            frame.present();
+                            engine.capture_frame(&mut encoder,&frame.texture);
+                            engine.profiler.resolve(&mut encoder);
+                            {
+                                let _scope = scope("submit");
+                                engine.queue.submit(std::iter::once(encoder.finish()));
+                                frame.present();
+                            }
+                            engine.finish_capture();
                         }
                     }
 
+                    engine.profiler.end_frame(&engine.device);
                     engine.input_handler.reset();
                 },Event::MainEventsCleared => {
-                    engine.game_window.window.request_redraw();
+                    engine.game_window.window.as_ref().unwrap().request_redraw();
                 }
                 _ => {}
             }
@@ -187,8 +231,8 @@ impl GameEvents {
     }
 }
 
-struct DeltaTimeCalc {
-    delta: f32
+pub(crate) struct DeltaTimeCalc {
+    pub delta: f32
 }
 
 impl DeltaTimeCalc {
@@ -205,7 +249,7 @@ impl DeltaTimeCalc {
     }
 }
 
-struct FpsCalc {
+pub(crate) struct FpsCalc {
     current_fps: i32,
     time: f32
 }
@@ -1,33 +1,73 @@
 use wgpu::PresentMode;
-use winit::dpi::{LogicalSize, PhysicalPosition, Position, Size};
+use winit::dpi::{LogicalSize, PhysicalPosition, PhysicalSize, Position, Size};
 use winit::event_loop::{ControlFlow, EventLoop};
 use winit::window::{Window, WindowBuilder};
 use crate::objects::color::Color;
 
 pub struct GameWindow {
     pub title: String,
-    pub window: Window,
+    pub window: Option<Window>,
     pub event_loop: Option<EventLoop<()>>,
     pub clear_color: Color,
     pub vsync: bool,
-    pub should_close: bool
+    pub transparent: bool,
+    pub should_close: bool,
+    pub headless_size: PhysicalSize<u32>
 }
 
 impl GameWindow {
     pub fn new(title: String, mut width: i32, mut height: i32,x:i32,y:i32,clear_color:Color,vsync:bool) -> GameWindow {
+        return GameWindow::create(title,width,height,x,y,clear_color,vsync,false);
+    }
+
+    //Pixels with an alpha below 1 (e.g. the clear color) show what is behind the window, needs EngineConfig::transparent
+    pub fn new_transparent(title: String, width: i32, height: i32,x:i32,y:i32,clear_color:Color,vsync:bool) -> GameWindow {
+        return GameWindow::create(title,width,height,x,y,clear_color,vsync,true);
+    }
+
+    fn create(title: String, width: i32, height: i32,x:i32,y:i32,clear_color:Color,vsync:bool,transparent:bool) -> GameWindow {
         let event_loop = EventLoop::new();
 
         let w = WindowBuilder::new().with_title(title.clone()).with_inner_size(Size::Logical(LogicalSize::new(width as f64,height as f64)))
+            .with_transparent(transparent)
             .build(&event_loop).unwrap();
         w.set_outer_position(Position::Physical(PhysicalPosition::new(x,y)));
 
+        let size = w.inner_size();
+
         return GameWindow {
             title,
-            window: w,
+            window: Some(w),
             event_loop: Some(event_loop),
             clear_color,
             vsync,
-            should_close: false
+            transparent,
+            should_close: false,
+            headless_size: size
+        }
+    }
+
+    pub fn new_headless(title: String, width: u32, height: u32,clear_color:Color) -> GameWindow {
+        return GameWindow {
+            title,
+            window: None,
+            event_loop: None,
+            clear_color,
+            vsync: false,
+            transparent: false,
+            should_close: false,
+            headless_size: PhysicalSize::new(width,height)
+        }
+    }
+
+    pub fn is_headless(&self) -> bool {
+        return self.window.is_none();
+    }
+
+    pub fn get_size(&self) -> PhysicalSize<u32> {
+        return match &self.window {
+            Some(w) => w.inner_size(),
+            None => self.headless_size
         }
     }
 
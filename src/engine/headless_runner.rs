@@ -0,0 +1,217 @@
+use std::time::Instant;
+use image::RgbaImage;
+use wgpu::Backends;
+use winit::dpi::PhysicalSize;
+use crate::engine::engine_config::EngineConfig;
+use crate::engine::game_engine::GameEngine;
+use crate::engine::game_events::FpsCalc;
+use crate::engine::game_window::GameWindow;
+use crate::engine::profiler::scope;
+use crate::error::rgl_error::RglResult;
+use crate::objects::render_texture::RenderTexture;
+use crate::scene::scene_handler::SceneHandler;
+
+/*
+Drives a scene without a window. Every frame is rendered into an offscreen texture, which can be read back
+after any number of frames (e.g. for golden image tests). The delta time is fixed, so the results are reproducible.
+ */
+pub struct HeadlessRunner {
+    pub engine: GameEngine,
+    pub scene_handler: SceneHandler,
+    pub target: RenderTexture,
+    pub frame_delta: f32,
+    pub frame: u64,
+    fps_calc: FpsCalc
+}
+
+impl HeadlessRunner {
+    pub async fn new(scene_handler:SceneHandler, game_window:GameWindow, backend:Backends) -> HeadlessRunner {
+        return HeadlessRunner::new_with_config(scene_handler,game_window,EngineConfig::new().backends(backend)).await;
+    }
+
+    pub async fn new_with_config(scene_handler:SceneHandler, game_window:GameWindow, engine_config:EngineConfig) -> HeadlessRunner {
+        return HeadlessRunner::try_new_with_config(scene_handler,game_window,engine_config).await.unwrap_or_else(|e| panic!("{}",e));
+    }
+
+    //Fails if no adapter (not even the fallback one) or device can be created
+    pub async fn try_new_with_config(mut scene_handler:SceneHandler, game_window:GameWindow, engine_config:EngineConfig) -> RglResult<HeadlessRunner> {
+        let mut engine = GameEngine::try_new_headless_with_config(game_window,engine_config).await?;
+
+        let target = RenderTexture::new(&engine.device,engine.config.width,engine.config.height,engine.config.format);
+
+        scene_handler.loaded(&mut engine);
+
+        return Ok(HeadlessRunner {
+            engine,
+            scene_handler,
+            target,
+            frame_delta: 1.0 / 60.0,
+            frame: 0,
+            fps_calc: FpsCalc::new()
+        });
+    }
+
+    pub fn render_frame(&mut self) {
+        let engine = &mut self.engine;
+        let scene_handler = &mut self.scene_handler;
+
+        engine.profiler.begin_frame();
+        engine.update_assets();
+        if engine.update_render_settings() {
+            let size = engine.size;
+            scene_handler.window_resized(engine,&size);
+        }
+        engine.update_hot_reload();
+        engine.update_offset_buffers();
+        engine.time = Instant::now();
+        engine.delta_time = engine.frame_capture.get_timestep().unwrap_or(self.frame_delta);
+
+        scene_handler.apply_commands(engine);
+
+        {
+            let _scope = scope("input");
+            scene_handler.process_input(engine);
+        }
+
+        if self.fps_calc.add(engine) {
+            scene_handler.handle_second(engine);
+        }
+
+        {
+            let _scope = scope("fixed update");
+            engine.run_ticks(scene_handler);
+        }
+
+        {
+            let _scope = scope("update");
+            scene_handler.update(engine);
+            engine.update_post_processing();
+            engine.update_debug_draw();
+        }
+
+        let mut encoder = engine.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Render Encoder"),
+        });
+
+        {
+            let _scope = scope("render");
+            scene_handler.render(engine,&mut encoder,&self.target.view);
+        }
+        engine.capture_frame(&mut encoder,&self.target.texture);
+        engine.profiler.resolve(&mut encoder);
+
+        {
+            let _scope = scope("submit");
+            engine.queue.submit(std::iter::once(encoder.finish()));
+        }
+        engine.finish_capture();
+        engine.profiler.end_frame(&engine.device);
+
+        engine.input_handler.reset();
+
+        self.frame += 1;
+    }
+
+    //Renders the given amount of frames and returns the content of the last one
+    pub fn run_frames(&mut self,frames:u32) -> RgbaImage {
+        for _ in 0..frames {
+            self.render_frame();
+        }
+        return self.read_pixels();
+    }
+
+    //The target has the surface format, which can always be read back
+    pub fn read_pixels(&self) -> RgbaImage {
+        return self.target.read_pixels(&self.engine.device,&self.engine.queue).unwrap();
+    }
+
+    pub fn resize(&mut self,width:u32,height:u32) {
+        let size = PhysicalSize::new(width,height);
+        self.engine.resize(size);
+        self.target = RenderTexture::new(&self.engine.device,width,height,self.engine.config.format);
+        self.scene_handler.window_resized(&mut self.engine,&size);
+    }
+
+    pub fn close(mut self) {
+        self.scene_handler.close(&mut self.engine);
+        self.engine.audio_handler.destroy();
+        self.engine.frame_capture.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::any::Any;
+    use std::future::Future;
+    use std::path::Path;
+    use std::sync::Arc;
+    use std::task::{Context, Poll, Wake};
+    use wgpu::{CommandEncoder, TextureView};
+    use crate::objects::color::Color;
+    use crate::scene::scene::Scene;
+    use crate::vfs::vfs::Vfs;
+    use super::*;
+
+    //The futures of wgpu are ready right away on native backends
+    fn block_on<F:Future>(future:F) -> F::Output {
+        struct ThreadWaker(std::thread::Thread);
+        impl Wake for ThreadWaker {
+            fn wake(self:Arc<Self>) {
+                self.0.unpark();
+            }
+        }
+
+        let waker = Arc::new(ThreadWaker(std::thread::current())).into();
+        let mut context = Context::from_waker(&waker);
+        let mut future = std::pin::pin!(future);
+        loop {
+            match future.as_mut().poll(&mut context) {
+                Poll::Ready(output) => return output,
+                Poll::Pending => std::thread::park()
+            }
+        }
+    }
+
+    //Only clears the frame with the clear color of the window
+    struct ClearScene;
+
+    impl Scene for ClearScene {
+        fn update(&mut self, _engine:&mut GameEngine) {}
+
+        fn render(&self, engine:&GameEngine, encoder:&mut CommandEncoder, view:&TextureView) {
+            engine.create_render_pass(encoder,view);
+        }
+
+        fn window_resized(&mut self, _engine:&GameEngine, _size:&PhysicalSize<u32>) {}
+
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+
+        fn as_any_mut(&mut self) -> &mut dyn Any {
+            self
+        }
+    }
+
+    #[test]
+    fn renders_clear_color() {
+        //The engine loads its shaders from assets/shader, they are in shader/ in the repository
+        let vfs = Arc::new(Vfs::new());
+        vfs.mount_dir("assets/shader",Path::new(env!("CARGO_MANIFEST_DIR")).join("shader"),0);
+
+        let mut scene_handler = SceneHandler::new();
+        scene_handler.open_scene(Box::new(ClearScene));
+        let game_window = GameWindow::new_headless("test".to_string(),70,33,Color::new(255,0,255));
+        let config = EngineConfig::new().backends(Backends::all()).force_fallback_adapter(true).vfs(vfs);
+
+        //Needs a software adapter (e.g. llvmpipe or WARP) when there is no gpu
+        let mut runner = block_on(HeadlessRunner::try_new_with_config(scene_handler,game_window,config)).unwrap_or_else(|e| panic!("Could not create the headless engine: {}",e));
+
+        //70 pixels are 280 bytes per row, so the readback rows are padded to 512
+        let image = runner.run_frames(2);
+        assert_eq!(image.dimensions(),(70,33));
+        assert!(image.pixels().all(|p| p.0 == [255,0,255,255]),"{:?}",image.get_pixel(0,0));
+        assert_eq!(runner.frame,2);
+        runner.close();
+    }
+}
@@ -0,0 +1,320 @@
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::rc::Rc;
+use std::time::SystemTime;
+use log::{error, info};
+use crate::engine::asset_loader::DecodedAudio;
+use crate::engine::game_engine::GameEngine;
+use crate::objects::obj_model::ObjModel;
+use crate::objects::texture_object::TextureObject;
+use crate::vfs::vfs::{join_path, split_path, Vfs};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ReloadTarget {
+    Shader(String),
+    Texture(String),
+    Obj(String),
+    Audio(String)
+}
+
+//What was reloaded in this frame, cleared at the start of the next one
+pub enum ReloadEvent {
+    Shader(String),
+    //Textures with a new size can't be updated in place, everything holding old has to switch to new (World does it for its sprites)
+    Texture { name: String, old: Rc<TextureObject>, new: Rc<TextureObject> },
+    Obj { name: String, old: Rc<ObjModel>, new: Rc<ObjModel> },
+    Audio(String)
+}
+
+struct WatchedFile {
+    modified: Option<SystemTime>,
+    targets: Vec<ReloadTarget>,
+    //Added with HotReloader::watch, kept until unwatch even if nothing loaded uses it
+    manual: bool
+}
+
+/*
+Opt-in development mode (GameEngine::enable_hot_reload) which checks the loaded shaders, textures, objs (with their mtl files) and audio files for changes
+every poll_interval seconds and loads them again. If the new version fails to load the previous one is kept and the error logged.
+ */
+pub struct HotReloader {
+    pub enabled: bool,
+    pub poll_interval: f32,
+    pub events: Vec<ReloadEvent>,
+    timer: f32,
+    files: HashMap<String,WatchedFile>
+}
+
+impl HotReloader {
+    pub fn new() -> Self {
+        HotReloader { enabled: false, poll_interval: 0.5, events: vec![], timer: 0.0, files: HashMap::new() }
+    }
+
+    //Files that are watched already only get the target added
+    pub fn watch(&mut self, vfs:&Vfs, path:&str, target:ReloadTarget) {
+        let file = self.files.entry(path.to_string()).or_insert_with(|| WatchedFile { modified: vfs.modified(path), targets: vec![], manual: true });
+        file.manual = true;
+        if !file.targets.contains(&target) {
+            file.targets.push(target);
+        }
+    }
+
+    /*
+    Watches the files of everything that is loaded right now, files of unloaded resources are dropped (unless they were added with watch).
+    Files that were watched before keep their modified time, so a change since the last poll is still found.
+     */
+    pub(crate) fn set_loaded_files(&mut self, vfs:&Vfs, files:Vec<(String,ReloadTarget)>) {
+        let previous = std::mem::take(&mut self.files);
+        let modified = |path:&str| previous.get(path).map_or_else(|| vfs.modified(path),|f| f.modified);
+
+        for (path,target) in files {
+            let file = self.files.entry(path.clone()).or_insert_with(|| WatchedFile { modified: modified(&path), targets: vec![], manual: false });
+            if !file.targets.contains(&target) {
+                file.targets.push(target);
+            }
+        }
+
+        for (path,file) in previous.into_iter().filter(|(_,f)| f.manual) {
+            match self.files.entry(path) {
+                Entry::Occupied(mut entry) => {
+                    let watched = entry.get_mut();
+                    watched.manual = true;
+                    for target in file.targets {
+                        if !watched.targets.contains(&target) {
+                            watched.targets.push(target);
+                        }
+                    }
+                },
+                Entry::Vacant(entry) => {
+                    entry.insert(file);
+                }
+            }
+        }
+    }
+
+    pub fn unwatch(&mut self, path:&str) {
+        self.files.remove(path);
+    }
+
+    pub fn is_watched(&self, path:&str) -> bool {
+        return self.files.contains_key(path);
+    }
+
+    pub fn get_watched_count(&self) -> usize {
+        return self.files.len();
+    }
+
+    pub(crate) fn should_poll(&mut self, delta:f32) -> bool {
+        self.timer += delta;
+        if self.timer >= self.poll_interval {
+            self.timer = 0.0;
+            return true;
+        }
+        return false;
+    }
+
+    //Targets of the files that changed since the last poll, files that disappeared (e.g. while an editor saves them) are reloaded when they are back
+    pub fn poll(&mut self, vfs:&Vfs) -> Vec<ReloadTarget> {
+        let mut changed = vec![];
+        for (path,file) in self.files.iter_mut() {
+            let modified = vfs.modified(path);
+            if modified != file.modified {
+                file.modified = modified;
+                if modified.is_some() {
+                    for target in file.targets.iter() {
+                        if !changed.contains(target) {
+                            changed.push(target.clone());
+                        }
+                    }
+                }
+            }
+        }
+        return changed;
+    }
+}
+
+pub(crate) fn reload_changed(engine:&mut GameEngine) {
+    watch_loaded_files(engine);
+
+    let changed = engine.hot_reloader.poll(&engine.vfs);
+    for target in changed {
+        match target {
+            ReloadTarget::Shader(path) => reload_shader(engine,&path),
+            ReloadTarget::Texture(name) => reload_texture(engine,&name),
+            ReloadTarget::Obj(name) => reload_obj(engine,&name),
+            ReloadTarget::Audio(name) => reload_audio(engine,&name)
+        }
+    }
+}
+
+//Everything that is loaded is watched, files of resources that were unloaded since the last poll are not anymore
+fn watch_loaded_files(engine:&mut GameEngine) {
+    let mut files = vec![];
+
+    for group in engine.get_pipeline_groups().1 {
+        for path in [&group.info.vertex_shader_path,&group.info.fragment_shader_path].into_iter().flatten() {
+            files.push((path.clone(),ReloadTarget::Shader(path.clone())));
+        }
+    }
+    files.push((engine.shadow_renderer.shader.vertex_shader_path.clone(),ReloadTarget::Shader(engine.shadow_renderer.shader.vertex_shader_path.clone())));
+    for path in [&engine.transition_renderer.shader.vertex_shader_path,&engine.transition_renderer.shader.fragment_shader_path] {
+        files.push((path.clone(),ReloadTarget::Shader(path.clone())));
+    }
+
+    for path in engine.post_processor.get_shader_paths() {
+        files.push((path.clone(),ReloadTarget::Shader(path)));
+    }
+
+    for (name,path) in engine.resource_loader.textures.get_paths() {
+        files.push((path,ReloadTarget::Texture(name)));
+    }
+
+    for (name,path) in engine.resource_loader.objs.get_paths() {
+        files.push((path.clone(),ReloadTarget::Obj(name.clone())));
+        if let Some(obj) = engine.resource_loader.objs.try_get_by_name(&name) {
+            let (dir,_) = split_path(&path);
+            for lib in obj.material_libs.iter() {
+                files.push((join_path(&dir,lib),ReloadTarget::Obj(name.clone())));
+            }
+        }
+    }
+
+    for (name,path) in engine.audio_handler.get_audio_paths() {
+        files.push((path,ReloadTarget::Audio(name)));
+    }
+
+    engine.hot_reloader.set_loaded_files(&engine.vfs,files);
+}
+
+fn reload_shader(engine:&mut GameEngine, path:&str) {
+    let mut result = Ok(());
+
+    let vfs = engine.vfs.clone();
+    let (device,groups) = engine.get_pipeline_groups();
+    for group in groups.into_iter().filter(|g| g.uses_shader(path)) {
+        result = result.and(group.try_reload(device,&vfs));
+    }
+
+    let device = &engine.device;
+    if engine.shadow_renderer.shader.vertex_shader_path == path {
+        result = result.and(engine.shadow_renderer.shader.try_reload(device,&vfs));
+    }
+    let transition = &mut engine.transition_renderer.shader;
+    if transition.vertex_shader_path == path || transition.fragment_shader_path == path {
+        result = result.and(transition.try_reload(device,&vfs));
+    }
+    result = result.and(engine.post_processor.try_reload_shader(device,&vfs,path));
+
+    match result {
+        Ok(()) => engine.hot_reloader.events.push(ReloadEvent::Shader(path.to_string())),
+        Err(e) => error!("Keeping the previous version of {}: {}",path,e)
+    }
+}
+
+fn reload_texture(engine:&mut GameEngine, name:&str) {
+    let Some(path) = engine.resource_loader.textures.get_path(name) else {
+        return;
+    };
+
+    let image = match TextureObject::try_load_image(&path,&engine.device,&engine.vfs) {
+        Ok(image) => image,
+        Err(e) => {
+            error!("Keeping the previous version of {}: {}",name,e);
+            return;
+        }
+    };
+
+    match engine.resource_loader.textures.try_get_by_name(name) {
+        Some(old) if old.size.width == image.width() && old.size.height == image.height() => {
+            old.update(&engine.queue,&image);
+            info!("Reloaded texture {}",name);
+        },
+        old => {
+            let new = Rc::new(TextureObject::new_from_data(&image,name.to_string(),&engine.device,&engine.queue,&engine.resource_loader.base_layout));
+            engine.resource_loader.textures.replace(name,new.clone());
+            info!("Reloaded texture {} with a new size",name);
+
+            if let Some(old) = old {
+                engine.hot_reloader.events.push(ReloadEvent::Texture { name: name.to_string(), old, new });
+
+                //The materials hold the old texture, so the objs using it are loaded again
+                let objs = engine.resource_loader.objs.iter().filter(|(_,_,o)| o.materials.values().any(|m| m.uses_texture(name))).map(|(_,n,_)| n.to_string()).collect::<Vec<_>>();
+                for obj in objs {
+                    reload_obj(engine,&obj);
+                }
+            }
+        }
+    }
+}
+
+fn reload_obj(engine:&mut GameEngine, name:&str) {
+    let Some((dir,file)) = engine.resource_loader.objs.get_path(name).map(|p| split_path(&p)) else {
+        return;
+    };
+
+    let mut obj = match ObjModel::try_new(dir,file,&engine.device,&engine.vfs) {
+        Ok(obj) => obj,
+        Err(e) => {
+            error!("Keeping the previous version of {}: {}",name,e);
+            return;
+        }
+    };
+
+    let old = engine.resource_loader.objs.try_get_by_name(name);
+    match &old {
+        Some(old) => engine.material_manager.reregister(&mut obj.materials,&old.materials,&engine.vertex_renderer.shader.material_buffer,&engine.queue,&engine.resource_loader),
+        None => engine.material_manager.register(&mut obj.materials,&engine.vertex_renderer.shader.material_buffer,&engine.queue,&engine.resource_loader)
+    }
+
+    let new = Rc::new(obj);
+    engine.resource_loader.objs.replace(name,new.clone());
+    info!("Reloaded obj {}",name);
+
+    if let Some(old) = old {
+        engine.hot_reloader.events.push(ReloadEvent::Obj { name: name.to_string(), old, new });
+    }
+}
+
+fn reload_audio(engine:&mut GameEngine, name:&str) {
+    if engine.audio_handler.is_muted() {
+        return;
+    }
+    let Some(path) = engine.audio_handler.get_audio_paths().into_iter().find(|(n,_)| n == name).map(|(_,p)| p) else {
+        return;
+    };
+
+    match DecodedAudio::try_load(&path,&engine.vfs) {
+        Ok(audio) => {
+            //Sources that were created before keep playing the old data
+            let data = unsafe { audio.upload() };
+            engine.audio_handler.add_data(name.to_string(),path,data,None);
+            engine.hot_reloader.events.push(ReloadEvent::Audio(name.to_string()));
+            info!("Reloaded audio {}",name);
+        },
+        Err(e) => error!("Keeping the previous version of {}: {}",name,e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::vfs::backend::MemoryBackend;
+    use super::*;
+
+    #[test]
+    fn drops_files_of_unloaded_resources() {
+        let vfs = Vfs::new();
+        vfs.mount_memory("",MemoryBackend::new().with_file("a.png","").with_file("b.png","").with_file("custom.txt",""),0);
+
+        let mut reloader = HotReloader::new();
+        reloader.watch(&vfs,"custom.txt",ReloadTarget::Texture("custom".to_string()));
+        reloader.set_loaded_files(&vfs,vec![("a.png".to_string(),ReloadTarget::Texture("a".to_string())),("b.png".to_string(),ReloadTarget::Texture("b".to_string()))]);
+        assert_eq!(reloader.get_watched_count(),3);
+
+        //b was unloaded
+        reloader.set_loaded_files(&vfs,vec![("a.png".to_string(),ReloadTarget::Texture("a".to_string()))]);
+        assert!(reloader.is_watched("a.png"));
+        assert!(!reloader.is_watched("b.png"));
+        assert!(reloader.is_watched("custom.txt"));
+        assert!(reloader.poll(&vfs).is_empty());
+    }
+}
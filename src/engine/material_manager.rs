@@ -1,23 +1,70 @@
 use std::collections::HashMap;
 use wgpu::{Buffer, Queue};
+use crate::engine::game_engine::AlignmentSettings;
 use crate::engine::resource_loader::ResourceLoader;
 use crate::objects::obj_model::Material;
 use crate::objects::offset_handler::StaticOffsetHandler;
+use crate::engine::profiler::write_uniform;
 
 pub struct MaterialManager {
-    pub offset_handler: StaticOffsetHandler
+    pub offset_handler: StaticOffsetHandler,
+    //Materials that do not fit into the buffer yet, written after it grew (see GameEngine::update_offset_buffers)
+    pending: Vec<(u64,Vec<u8>)>
 }
 
 impl MaterialManager {
 
     pub fn new() -> Self {
-        MaterialManager { offset_handler: StaticOffsetHandler::new(50,0) }
+        MaterialManager { offset_handler: StaticOffsetHandler::new(50,0), pending: vec![] }
+    }
+
+    pub fn new_aligned(settings:&AlignmentSettings) -> Self {
+        MaterialManager { offset_handler: StaticOffsetHandler::new_aligned(50,0,64,settings.uniform_offset), pending: vec![] }
     }
 
     pub fn register(&mut self, mats:&mut HashMap<String,Material>, buffer:&Buffer, queue:&Queue, rsc_loader:&ResourceLoader) {
         for (id,m) in mats {
             m.offset = self.offset_handler.get_offset() as u32;
-            m.register(queue,buffer,rsc_loader);
+            self.write(m,buffer,queue);
+            m.load_textures(rsc_loader);
+        }
+    }
+
+    //For reloaded models: materials with the same name keep the offset of the previous version, the offsets of removed ones are freed
+    pub fn reregister(&mut self, mats:&mut HashMap<String,Material>, previous:&HashMap<String,Material>, buffer:&Buffer, queue:&Queue, rsc_loader:&ResourceLoader) {
+        for (id,m) in mats.iter_mut() {
+            m.offset = match previous.get(id) {
+                Some(p) => p.offset,
+                None => self.offset_handler.get_offset() as u32
+            };
+            self.write(m,buffer,queue);
+            m.load_textures(rsc_loader);
+        }
+
+        for (id,p) in previous.iter() {
+            if !mats.contains_key(id) {
+                self.offset_handler.remove(p.offset as u64);
+            }
+        }
+    }
+
+    pub fn unregister(&mut self, mats:&HashMap<String,Material>) {
+        for m in mats.values() {
+            self.offset_handler.remove(m.offset as u64);
+        }
+    }
+
+    fn write(&mut self, m:&Material, buffer:&Buffer, queue:&Queue) {
+        if m.offset as u64 + 64 <= buffer.size() {
+            m.write(queue,buffer);
+        } else {
+            self.pending.push((m.offset as u64,m.data.clone()));
+        }
+    }
+
+    pub fn flush(&mut self, buffer:&Buffer, queue:&Queue) {
+        for (offset,data) in self.pending.drain(..) {
+            write_uniform(queue,buffer,offset,&data);
         }
     }
 }
\ No newline at end of file
@@ -4,4 +4,11 @@ pub mod game_events;
 pub mod resource_loader;
 pub mod input_handler;
 pub mod tick_handler;
-pub mod material_manager;
\ No newline at end of file
+pub mod material_manager;
+pub mod headless_runner;
+pub mod asset_loader;
+pub mod hot_reloader;
+pub mod resource_handle;
+pub mod engine_config;
+pub mod frame_capture;
+pub mod profiler;
\ No newline at end of file
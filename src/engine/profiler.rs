@@ -0,0 +1,352 @@
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::time::Instant;
+use wgpu::{Buffer, BufferAddress, BufferDescriptor, BufferUsages, CommandEncoder, Device, Features, MapMode, QuerySet, QuerySetDescriptor, QueryType, Queue};
+use crate::error::rgl_error::{RglError, RglResult};
+
+const MAX_GPU_QUERIES: u32 = 64;
+const READBACK_BUFFERS: usize = 3;
+
+#[derive(Clone, Debug)]
+pub struct ScopeTiming {
+    pub name: &'static str,
+    pub depth: u32,
+    //Milliseconds since the start of the frame
+    pub start: f32,
+    pub duration: f32
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FrameCounters {
+    pub draw_calls: u32,
+    pub pipeline_switches: u32,
+    //Bytes written with write_uniform
+    pub uniform_bytes: u64
+}
+
+#[derive(Clone, Debug)]
+pub struct FrameProfile {
+    pub frame: u64,
+    //Milliseconds since the profiler was created
+    pub start: f64,
+    pub duration: f32,
+    pub cpu_scopes: Vec<ScopeTiming>,
+    //Filled in a few frames later, when the timestamps were read back. Starts are relative to the first pass
+    pub gpu_passes: Vec<ScopeTiming>,
+    pub counters: FrameCounters
+}
+
+//Scopes and counters of the current frame, kept per thread so renderers can record without access to the engine
+struct Recorder {
+    enabled: bool,
+    frame_start: Instant,
+    open: Vec<(&'static str,Instant)>,
+    scopes: Vec<ScopeTiming>,
+    counters: FrameCounters
+}
+
+thread_local! {
+    static RECORDER: RefCell<Recorder> = RefCell::new(Recorder { enabled: false, frame_start: Instant::now(), open: vec![], scopes: vec![], counters: FrameCounters::default() });
+}
+
+fn with_recorder<T>(f:impl FnOnce(&mut Recorder) -> T) -> T {
+    return RECORDER.with(|r| f(&mut r.borrow_mut()));
+}
+
+//Ends the scope when dropped
+pub struct ProfileScope {
+    active: bool
+}
+
+impl Drop for ProfileScope {
+    fn drop(&mut self) {
+        if !self.active {
+            return;
+        }
+        with_recorder(|r| {
+            if let Some((name,start)) = r.open.pop() {
+                let depth = r.open.len() as u32;
+                let start_ms = start.duration_since(r.frame_start).as_secs_f32() * 1000.0;
+                r.scopes.push(ScopeTiming { name, depth, start: start_ms, duration: start.elapsed().as_secs_f32() * 1000.0 });
+            }
+        });
+    }
+}
+
+//Measures until the returned guard is dropped, e.g. let _scope = scope("physics");
+pub fn scope(name:&'static str) -> ProfileScope {
+    let active = with_recorder(|r| {
+        if r.enabled {
+            r.open.push((name,Instant::now()));
+        }
+        r.enabled
+    });
+    return ProfileScope { active };
+}
+
+pub fn count_draw() {
+    with_recorder(|r| if r.enabled { r.counters.draw_calls += 1 });
+}
+
+pub fn count_pipeline_switch() {
+    with_recorder(|r| if r.enabled { r.counters.pipeline_switches += 1 });
+}
+
+//queue.write_buffer for uniform and storage buffers, counts the written bytes
+pub fn write_uniform(queue:&Queue, buffer:&Buffer, offset:BufferAddress, data:&[u8]) {
+    queue.write_buffer(buffer,offset,data);
+    with_recorder(|r| if r.enabled { r.counters.uniform_bytes += data.len() as u64 });
+}
+
+struct GpuReadback {
+    buffer: Buffer,
+    //Frame and pass names of the timestamps in the buffer, None if the buffer is free
+    frame: Option<(u64,Vec<&'static str>)>,
+    //One of the MAP_ constants, written by the map_async callback
+    state: Arc<AtomicU8>
+}
+
+const MAP_PENDING: u8 = 0;
+const MAP_READY: u8 = 1;
+const MAP_FAILED: u8 = 2;
+
+/*
+Every timestamp ends the previous pass and starts the next one, the last one is written by Profiler::resolve.
+The results are mapped asynchronously so reading them never waits for the gpu.
+ */
+struct GpuTimer {
+    query_set: QuerySet,
+    resolve_buffer: Buffer,
+    readbacks: Vec<GpuReadback>,
+    names: RefCell<Vec<&'static str>>,
+    next: Cell<u32>,
+    //Nanoseconds per tick
+    period: f32,
+    submitted: Option<usize>
+}
+
+impl GpuTimer {
+    fn new(device:&Device, queue:&Queue) -> GpuTimer {
+        let size = MAX_GPU_QUERIES as u64 * 8;
+        let query_set = device.create_query_set(&QuerySetDescriptor { label: Some("Profiler Timestamps"), ty: QueryType::Timestamp, count: MAX_GPU_QUERIES });
+        let resolve_buffer = device.create_buffer(&BufferDescriptor { label: Some("Profiler Resolve"), size, usage: BufferUsages::QUERY_RESOLVE | BufferUsages::COPY_SRC, mapped_at_creation: false });
+        let readbacks = (0..READBACK_BUFFERS).map(|_| GpuReadback {
+            buffer: device.create_buffer(&BufferDescriptor { label: Some("Profiler Readback"), size, usage: BufferUsages::COPY_DST | BufferUsages::MAP_READ, mapped_at_creation: false }),
+            frame: None,
+            state: Arc::new(AtomicU8::new(MAP_PENDING))
+        }).collect();
+
+        return GpuTimer { query_set, resolve_buffer, readbacks, names: RefCell::new(vec![]), next: Cell::new(0), period: queue.get_timestamp_period(), submitted: None };
+    }
+
+    fn read(&mut self, index:usize) -> Option<(u64,Vec<ScopeTiming>)> {
+        let readback = &mut self.readbacks[index];
+        match readback.state.swap(MAP_PENDING,Ordering::Acquire) {
+            MAP_READY => {}
+            MAP_FAILED => {
+                //A failed mapping leaves the buffer unmapped, the slot only has to be freed for the next frames
+                readback.frame = None;
+                return None;
+            }
+            _ => return None
+        }
+        let (frame,names) = readback.frame.take()?;
+        let ticks: Vec<u64> = {
+            let data = readback.buffer.slice(..).get_mapped_range();
+            data.chunks(8).take(names.len() + 1).map(|c| u64::from_le_bytes(c.try_into().unwrap())).collect()
+        };
+        readback.buffer.unmap();
+
+        let to_ms = |t:u64| t.wrapping_sub(ticks[0]) as f32 * self.period / 1_000_000.0;
+        let passes = names.iter().enumerate().map(|(i,name)| ScopeTiming { name: *name, depth: 0, start: to_ms(ticks[i]), duration: to_ms(ticks[i + 1]) - to_ms(ticks[i]) }).collect();
+        return Some((frame,passes));
+    }
+}
+
+/*
+Cpu scopes (see scope), gpu timestamps per render pass and per frame counters with a rolling history.
+Disabled by default, the gpu timings need Features::TIMESTAMP_QUERY (see EngineConfig::gpu_timing).
+Only scopes of the thread that runs the game loop are recorded.
+ */
+pub struct Profiler {
+    pub history_size: usize,
+    history: VecDeque<FrameProfile>,
+    gpu: Option<GpuTimer>,
+    epoch: Instant,
+    frame: u64,
+    enabled: bool,
+    enabled_request: Option<bool>
+}
+
+impl Profiler {
+    pub fn new(device:&Device, queue:&Queue) -> Profiler {
+        let gpu = if device.features().contains(Features::TIMESTAMP_QUERY) { Some(GpuTimer::new(device,queue)) } else { None };
+        return Profiler { history_size: 300, history: VecDeque::new(), gpu, epoch: Instant::now(), frame: 0, enabled: false, enabled_request: None };
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        return self.enabled_request.unwrap_or(self.enabled);
+    }
+
+    //Applied at the start of the next frame
+    pub fn set_enabled(&mut self, enabled:bool) {
+        self.enabled_request = Some(enabled);
+    }
+
+    pub fn has_gpu_timing(&self) -> bool {
+        return self.gpu.is_some();
+    }
+
+    //Oldest frame first
+    pub fn get_history(&self) -> &VecDeque<FrameProfile> {
+        return &self.history;
+    }
+
+    pub fn get_last_frame(&self) -> Option<&FrameProfile> {
+        return self.history.back();
+    }
+
+    //Average duration in milliseconds of all cpu scopes with the name over the history
+    pub fn get_average(&self, name:&str) -> Option<f32> {
+        let durations: Vec<f32> = self.history.iter().map(|f| f.cpu_scopes.iter().filter(|s| s.name == name).map(|s| s.duration).sum()).collect();
+        if durations.is_empty() {
+            return None;
+        }
+        return Some(durations.iter().sum::<f32>() / durations.len() as f32);
+    }
+
+    pub fn clear(&mut self) {
+        self.history.clear();
+    }
+
+    pub(crate) fn begin_frame(&mut self) {
+        if let Some(enabled) = self.enabled_request.take() {
+            self.enabled = enabled;
+        }
+        let enabled = self.enabled;
+        with_recorder(|r| {
+            r.enabled = enabled;
+            r.frame_start = Instant::now();
+            r.open.clear();
+            r.scopes.clear();
+            r.counters = FrameCounters::default();
+        });
+        if let Some(gpu) = &self.gpu {
+            gpu.next.set(0);
+            gpu.names.borrow_mut().clear();
+        }
+    }
+
+    //Starts the gpu timing of the next pass, does nothing if the adapter does not support timestamps
+    pub fn write_timestamp(&self, encoder:&mut CommandEncoder, name:&'static str) {
+        match &self.gpu {
+            Some(gpu) if self.enabled && gpu.next.get() < MAX_GPU_QUERIES - 1 => {
+                encoder.write_timestamp(&gpu.query_set,gpu.next.get());
+                gpu.names.borrow_mut().push(name);
+                gpu.next.set(gpu.next.get() + 1);
+            },
+            _ => {}
+        }
+    }
+
+    //Called by the runners before the encoder of the frame is submitted
+    pub(crate) fn resolve(&mut self, encoder:&mut CommandEncoder) {
+        let frame = self.frame;
+        let gpu = match &mut self.gpu {
+            Some(gpu) if gpu.next.get() > 0 => gpu,
+            _ => return
+        };
+        //Skipped if the gpu is still behind with the previous frames
+        let index = match gpu.readbacks.iter().position(|r| r.frame.is_none()) {
+            Some(index) => index,
+            None => return
+        };
+
+        let count = gpu.next.get() + 1;
+        encoder.write_timestamp(&gpu.query_set,count - 1);
+        encoder.resolve_query_set(&gpu.query_set,0..count,&gpu.resolve_buffer,0);
+        encoder.copy_buffer_to_buffer(&gpu.resolve_buffer,0,&gpu.readbacks[index].buffer,0,count as u64 * 8);
+        gpu.readbacks[index].frame = Some((frame,gpu.names.borrow_mut().drain(..).collect()));
+        gpu.submitted = Some(index);
+    }
+
+    //Called by the runners after the frame was submitted
+    pub(crate) fn end_frame(&mut self, device:&Device) {
+        if self.enabled {
+            let start = with_recorder(|r| r.frame_start);
+            let (cpu_scopes,counters) = with_recorder(|r| (std::mem::take(&mut r.scopes),r.counters));
+            self.history.push_back(FrameProfile {
+                frame: self.frame,
+                start: start.duration_since(self.epoch).as_secs_f64() * 1000.0,
+                duration: start.elapsed().as_secs_f32() * 1000.0,
+                cpu_scopes,
+                gpu_passes: vec![],
+                counters
+            });
+            while self.history.len() > self.history_size.max(1) {
+                self.history.pop_front();
+            }
+        }
+
+        if let Some(gpu) = &mut self.gpu {
+            if let Some(index) = gpu.submitted.take() {
+                let state = gpu.readbacks[index].state.clone();
+                gpu.readbacks[index].buffer.slice(..).map_async(MapMode::Read,move |result| {
+                    state.store(if result.is_ok() { MAP_READY } else { MAP_FAILED },Ordering::Release);
+                });
+            }
+            device.poll(wgpu::Maintain::Poll);
+            for index in 0..gpu.readbacks.len() {
+                if let Some((frame,passes)) = gpu.read(index) {
+                    if let Some(profile) = self.history.iter_mut().find(|f| f.frame == frame) {
+                        profile.gpu_passes = passes;
+                    }
+                }
+            }
+        }
+
+        self.frame += 1;
+    }
+
+    //Writes the history as Chrome trace json (chrome://tracing or ui.perfetto.dev), the gpu passes are on their own track
+    pub fn export_chrome_trace(&self, path:&str) -> RglResult<()> {
+        let mut events = vec![];
+        for frame in &self.history {
+            let event = |name:&str,tid:u32,start:f32,duration:f32| format!("{{\"name\":\"{}\",\"cat\":\"{}\",\"ph\":\"X\",\"ts\":{:.3},\"dur\":{:.3},\"pid\":1,\"tid\":{}}}",
+                escape_json(name),if tid == 1 { "cpu" } else { "gpu" },(frame.start + start as f64) * 1000.0,duration * 1000.0,tid);
+
+            events.push(event(&format!("Frame {}",frame.frame),1,0.0,frame.duration));
+            for scope in &frame.cpu_scopes {
+                events.push(event(scope.name,1,scope.start,scope.duration));
+            }
+            for pass in &frame.gpu_passes {
+                events.push(event(pass.name,2,pass.start,pass.duration));
+            }
+            events.push(format!("{{\"name\":\"Counters\",\"ph\":\"C\",\"ts\":{:.3},\"pid\":1,\"args\":{{\"draw_calls\":{},\"pipeline_switches\":{},\"uniform_bytes\":{}}}}}",
+                frame.start * 1000.0,frame.counters.draw_calls,frame.counters.pipeline_switches,frame.counters.uniform_bytes));
+        }
+
+        let mut json = String::from("{\"traceEvents\":[\n");
+        json.push_str(&events.join(",\n"));
+        json.push_str("\n],\"displayTimeUnit\":\"ms\"}\n");
+
+        std::fs::write(path,json).map_err(|e| RglError::io(path,e))?;
+        return Ok(());
+    }
+}
+
+fn escape_json(text:&str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            c if (c as u32) < 0x20 => { write!(escaped,"\\u{:04x}",c as u32).unwrap(); },
+            c => escaped.push(c)
+        }
+    }
+    return escaped;
+}
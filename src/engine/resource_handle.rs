@@ -0,0 +1,363 @@
+use std::collections::HashMap;
+use std::fmt::{Debug, Formatter};
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+use std::rc::Rc;
+use std::sync::Arc;
+use crate::audio::audio_data::AudioData;
+use crate::objects::gltf_model::GltfModel;
+use crate::objects::obj_model::ObjModel;
+use crate::objects::texture_object::TextureObject;
+use crate::text::render_font::RenderFont;
+
+/*
+What a ResourceStore holds. The asset is a shared pointer, its strong count tells how many users it has
+besides the store (see ResourceStore::unload_unused).
+ */
+pub trait ResourceKind: 'static {
+    type Asset: Clone;
+    const NAME: &'static str;
+
+    fn get_users(asset:&Self::Asset) -> usize;
+    fn get_gpu_size(asset:&Self::Asset) -> u64;
+}
+
+pub enum Texture {}
+pub enum Model {}
+pub enum Gltf {}
+pub enum Font {}
+pub enum Sound {}
+
+impl ResourceKind for Texture {
+    type Asset = Rc<TextureObject>;
+    const NAME: &'static str = "texture";
+
+    fn get_users(asset:&Rc<TextureObject>) -> usize {
+        return Rc::strong_count(asset) - 1;
+    }
+
+    fn get_gpu_size(asset:&Rc<TextureObject>) -> u64 {
+        return asset.get_gpu_size();
+    }
+}
+
+impl ResourceKind for Model {
+    type Asset = Rc<ObjModel>;
+    const NAME: &'static str = "model";
+
+    fn get_users(asset:&Rc<ObjModel>) -> usize {
+        return Rc::strong_count(asset) - 1;
+    }
+
+    fn get_gpu_size(asset:&Rc<ObjModel>) -> u64 {
+        return asset.get_gpu_size();
+    }
+}
+
+impl ResourceKind for Gltf {
+    type Asset = Rc<GltfModel>;
+    const NAME: &'static str = "gltf";
+
+    fn get_users(asset:&Rc<GltfModel>) -> usize {
+        return Rc::strong_count(asset) - 1;
+    }
+
+    fn get_gpu_size(asset:&Rc<GltfModel>) -> u64 {
+        return asset.get_gpu_size();
+    }
+}
+
+impl ResourceKind for Font {
+    type Asset = Rc<RenderFont>;
+    const NAME: &'static str = "font";
+
+    fn get_users(asset:&Rc<RenderFont>) -> usize {
+        return Rc::strong_count(asset) - 1;
+    }
+
+    fn get_gpu_size(asset:&Rc<RenderFont>) -> u64 {
+        return asset.texture.get_gpu_size();
+    }
+}
+
+//Audio sources hold the data too, so a sound is unused once every source playing it is gone
+impl ResourceKind for Sound {
+    type Asset = Arc<AudioData>;
+    const NAME: &'static str = "sound";
+
+    fn get_users(asset:&Arc<AudioData>) -> usize {
+        return Arc::strong_count(asset) - 1;
+    }
+
+    fn get_gpu_size(asset:&Arc<AudioData>) -> u64 {
+        return asset.get_size();
+    }
+}
+
+/*
+Typed reference to an entry of a ResourceStore. The generation changes when the entry is unloaded,
+so old handles never point to whatever is loaded into the slot later.
+ */
+pub struct Handle<K> {
+    index: u32,
+    generation: u32,
+    marker: PhantomData<fn() -> K>
+}
+
+impl<K> Handle<K> {
+    fn new(index:u32, generation:u32) -> Self {
+        Handle { index, generation, marker: PhantomData }
+    }
+}
+
+impl<K> Clone for Handle<K> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<K> Copy for Handle<K> {}
+
+impl<K> PartialEq for Handle<K> {
+    fn eq(&self, other:&Self) -> bool {
+        return self.index == other.index && self.generation == other.generation;
+    }
+}
+
+impl<K> Eq for Handle<K> {}
+
+impl<K> Hash for Handle<K> {
+    fn hash<H: Hasher>(&self, state:&mut H) {
+        self.index.hash(state);
+        self.generation.hash(state);
+    }
+}
+
+impl<K:ResourceKind> Debug for Handle<K> {
+    fn fmt(&self, f:&mut Formatter<'_>) -> std::fmt::Result {
+        write!(f,"Handle<{}>({}v{})",K::NAME,self.index,self.generation)
+    }
+}
+
+//Resources loaded while a group is active are unloaded with it, scenes get one each (see SceneHandler)
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ResourceGroup(pub u64);
+
+#[derive(Clone, Debug)]
+pub struct ResourceInfo {
+    pub kind: &'static str,
+    pub name: String,
+    pub path: Option<String>,
+    pub gpu_size: u64,
+    pub users: usize
+}
+
+struct Entry<K:ResourceKind> {
+    name: String,
+    asset: Option<K::Asset>,
+    path: Option<String>,
+    //Loaded outside of a group, only unloaded explicitly (or when unused)
+    global: bool,
+    groups: Vec<ResourceGroup>
+}
+
+struct Slot<K:ResourceKind> {
+    generation: u32,
+    entry: Option<Entry<K>>
+}
+
+/*
+Named assets of one kind. Handles can be taken before the asset is loaded (or after it was unloaded),
+get returns the placeholder for them.
+ */
+pub struct ResourceStore<K:ResourceKind> {
+    pub placeholder: Option<K::Asset>,
+    slots: Vec<Slot<K>>,
+    names: HashMap<String,u32>,
+    free: Vec<u32>
+}
+
+impl<K:ResourceKind> ResourceStore<K> {
+    pub fn new() -> Self {
+        ResourceStore { placeholder: None, slots: vec![], names: HashMap::new(), free: vec![] }
+    }
+
+    pub fn with_placeholder(placeholder:K::Asset) -> Self {
+        let mut store = ResourceStore::new();
+        store.placeholder = Some(placeholder);
+        store
+    }
+
+    //Reserves the name if nothing is loaded with it yet
+    pub fn handle(&mut self, name:&str) -> Handle<K> {
+        if let Some(handle) = self.find(name) {
+            return handle;
+        }
+
+        let entry = Entry { name: name.to_string(), asset: None, path: None, global: false, groups: vec![] };
+        let index = match self.free.pop() {
+            Some(index) => {
+                self.slots[index as usize].entry = Some(entry);
+                index
+            },
+            None => {
+                self.slots.push(Slot { generation: 0, entry: Some(entry) });
+                self.slots.len() as u32 - 1
+            }
+        };
+        self.names.insert(name.to_string(),index);
+        return Handle::new(index,self.slots[index as usize].generation);
+    }
+
+    pub fn find(&self, name:&str) -> Option<Handle<K>> {
+        return self.names.get(name).map(|i| Handle::new(*i,self.slots[*i as usize].generation));
+    }
+
+    //An asset with the same name is replaced, its handles stay valid
+    pub fn insert(&mut self, name:&str, asset:K::Asset, path:Option<String>, group:Option<ResourceGroup>) -> Handle<K> {
+        let handle = self.handle(name);
+        let entry = self.slots[handle.index as usize].entry.as_mut().unwrap();
+        entry.asset = Some(asset);
+        if path.is_some() {
+            entry.path = path;
+        }
+        match group {
+            Some(group) => {
+                if !entry.groups.contains(&group) {
+                    entry.groups.push(group);
+                }
+            },
+            None => entry.global = true
+        }
+        return handle;
+    }
+
+    //Swaps the asset of a loaded entry and keeps its groups (hot reloading), None if nothing is loaded with the name
+    pub fn replace(&mut self, name:&str, asset:K::Asset) -> Option<K::Asset> {
+        let index = *self.names.get(name)?;
+        let entry = self.slots[index as usize].entry.as_mut().unwrap();
+        if entry.asset.is_none() {
+            return None;
+        }
+        return entry.asset.replace(asset);
+    }
+
+    fn entry(&self, handle:Handle<K>) -> Option<&Entry<K>> {
+        let slot = self.slots.get(handle.index as usize)?;
+        if slot.generation != handle.generation {
+            return None;
+        }
+        return slot.entry.as_ref();
+    }
+
+    pub fn get(&self, handle:Handle<K>) -> Option<K::Asset> {
+        return self.try_get(handle).or_else(|| self.placeholder.clone());
+    }
+
+    //Without the placeholder
+    pub fn try_get(&self, handle:Handle<K>) -> Option<K::Asset> {
+        return self.entry(handle).and_then(|e| e.asset.clone());
+    }
+
+    pub fn get_by_name(&self, name:&str) -> Option<K::Asset> {
+        return self.try_get_by_name(name).or_else(|| self.placeholder.clone());
+    }
+
+    pub fn try_get_by_name(&self, name:&str) -> Option<K::Asset> {
+        return self.find(name).and_then(|h| self.try_get(h));
+    }
+
+    pub fn is_loaded(&self, handle:Handle<K>) -> bool {
+        return self.entry(handle).map_or(false,|e| e.asset.is_some());
+    }
+
+    pub fn contains(&self, name:&str) -> bool {
+        return self.find(name).map_or(false,|h| self.is_loaded(h));
+    }
+
+    pub fn get_name(&self, handle:Handle<K>) -> Option<&str> {
+        return self.entry(handle).map(|e| e.name.as_str());
+    }
+
+    pub fn get_path(&self, name:&str) -> Option<String> {
+        return self.find(name).and_then(|h| self.entry(h)).and_then(|e| e.path.clone());
+    }
+
+    //(name, path) of every loaded asset which was loaded from a file
+    pub fn get_paths(&self) -> Vec<(String,String)> {
+        return self.entries().filter_map(|e| e.path.clone().map(|p| (e.name.clone(),p))).collect();
+    }
+
+    /*
+    Removes the entry, its handles return the placeholder from now on.
+    The asset itself is only freed once nothing else holds it anymore.
+     */
+    pub fn unload(&mut self, handle:Handle<K>) -> Option<K::Asset> {
+        if self.entry(handle).is_none() {
+            return None;
+        }
+
+        let slot = &mut self.slots[handle.index as usize];
+        let entry = slot.entry.take().unwrap();
+        slot.generation = slot.generation.wrapping_add(1);
+        self.names.remove(&entry.name);
+        self.free.push(handle.index);
+        return entry.asset;
+    }
+
+    pub fn unload_by_name(&mut self, name:&str) -> Option<K::Asset> {
+        return self.find(name).and_then(|h| self.unload(h));
+    }
+
+    //Unloads every asset that is only held by the store
+    pub fn unload_unused(&mut self) -> Vec<K::Asset> {
+        let unused = self.handles_where(|e| e.asset.as_ref().map_or(false,|a| K::get_users(a) == 0));
+        return unused.into_iter().filter_map(|h| self.unload(h)).collect();
+    }
+
+    //Assets which are in no other group (and were never loaded without one) are unloaded
+    pub fn free_group(&mut self, group:ResourceGroup) -> Vec<K::Asset> {
+        for entry in self.slots.iter_mut().filter_map(|s| s.entry.as_mut()) {
+            entry.groups.retain(|g| *g != group);
+        }
+        let freed = self.handles_where(|e| !e.global && e.groups.is_empty() && e.asset.is_some());
+        return freed.into_iter().filter_map(|h| self.unload(h)).collect();
+    }
+
+    fn entries(&self) -> impl Iterator<Item=&Entry<K>> {
+        return self.slots.iter().filter_map(|s| s.entry.as_ref());
+    }
+
+    fn handles_where(&self, f:impl Fn(&Entry<K>) -> bool) -> Vec<Handle<K>> {
+        return self.slots.iter().enumerate()
+            .filter(|(_,s)| s.entry.as_ref().map_or(false,|e| f(e)))
+            .map(|(i,s)| Handle::new(i as u32,s.generation))
+            .collect();
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item=(Handle<K>,&str,&K::Asset)> {
+        return self.slots.iter().enumerate().filter_map(|(i,s)| {
+            let entry = s.entry.as_ref()?;
+            return entry.asset.as_ref().map(|a| (Handle::new(i as u32,s.generation),entry.name.as_str(),a));
+        });
+    }
+
+    pub fn get_infos(&self) -> Vec<ResourceInfo> {
+        return self.entries().filter_map(|e| e.asset.as_ref().map(|a| ResourceInfo {
+            kind: K::NAME,
+            name: e.name.clone(),
+            path: e.path.clone(),
+            gpu_size: K::get_gpu_size(a),
+            users: K::get_users(a)
+        })).collect();
+    }
+
+    pub fn get_gpu_size(&self) -> u64 {
+        return self.iter().map(|(_,_,a)| K::get_gpu_size(a)).sum();
+    }
+
+    //Loaded assets, reserved names are not counted
+    pub fn len(&self) -> usize {
+        return self.iter().count();
+    }
+}
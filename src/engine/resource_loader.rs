@@ -8,81 +8,287 @@ use std::sync::{Arc, Mutex};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::Duration;
 use nalgebra_glm::pi;
-use rgl_font::{BaseFont, FontLoader, ScaledFont};
+use rgl_font::{BaseFont, FontLoader};
 use crate::objects::texture_object::TextureObject;
 use wgpu::{Device, Queue, BindGroupLayout, Buffer};
 use crate::engine::game_engine::GameEngine;
 use crate::engine::material_manager::MaterialManager;
 use crate::objects::obj_model::{Material, ObjModel};
+use crate::objects::gltf_model::GltfModel;
 use crate::pipeline::pipeline::RenderPipelineGroupBuilder;
 use crate::text::render_font::RenderFont;
+use crate::error::rgl_error::{RglError, RglResult};
+use image::{Rgba, RgbaImage};
+use log::{error, warn};
+use crate::engine::resource_handle::{Font, Gltf, Handle, Model, ResourceGroup, ResourceInfo, ResourceStore, Texture};
+use crate::objects::obj_parser::ObjData;
+use crate::vfs::vfs::{join_path, Vfs};
 
+//Magenta cube, returned for models which are not loaded
+const PLACEHOLDER_OBJ: &str = "usemtl placeholder
+v -0.5 -0.5 0.5
+v 0.5 -0.5 0.5
+v 0.5 0.5 0.5
+v -0.5 0.5 0.5
+v -0.5 -0.5 -0.5
+v 0.5 -0.5 -0.5
+v 0.5 0.5 -0.5
+v -0.5 0.5 -0.5
+f 1 2 3 4
+f 6 5 8 7
+f 5 1 4 8
+f 2 6 7 3
+f 4 3 7 8
+f 5 6 2 1
+";
+
+/*
+Textures, models and fonts by name. Handles to them (e.g. textures.handle("player.png")) can be taken before they are loaded,
+get returns the placeholder until they are. Everything loaded while active_group is set is unloaded with the group (see GameEngine::free_resource_group).
+ */
 pub struct ResourceLoader {
-    pub textures: HashMap<String,Rc<TextureObject>>,
+    pub textures: ResourceStore<Texture>,
     pub base_fonts: HashMap<String,Rc<BaseFont>>,
-    pub scaled_fonts: HashMap<String,Rc<ScaledFont>>,
-    pub render_fonts: HashMap<String,Rc<RenderFont>>,
-    pub objs: HashMap<String,Rc<ObjModel>>,
+    pub fonts: ResourceStore<Font>,
+    pub objs: ResourceStore<Model>,
+    pub gltfs: ResourceStore<Gltf>,
     pub base_layout: Arc<BindGroupLayout>,
+    pub fallback_texture: Rc<TextureObject>,
+    pub active_group: Option<ResourceGroup>,
     font_loader: FontLoader
 }
 
 impl ResourceLoader {
-    pub fn new(device:&Device) -> ResourceLoader{
-        let loader = ResourceLoader {textures: HashMap::new(), base_fonts: HashMap::new(), scaled_fonts: HashMap::new(),render_fonts: HashMap::new(), objs: HashMap::new(), base_layout: Arc::new(RenderPipelineGroupBuilder::empty().create_texture_bind_group_layout(device)), font_loader: FontLoader::new() };
+    pub fn new(device:&Device,queue:&Queue) -> ResourceLoader{
+        let base_layout = RenderPipelineGroupBuilder::empty().create_texture_bind_group_layout(device);
+        let fallback = Rc::new(ResourceLoader::create_fallback_texture(device,queue,&base_layout));
+        let loader = ResourceLoader {textures: ResourceStore::with_placeholder(fallback.clone()), base_fonts: HashMap::new(), fonts: ResourceStore::new(), objs: ResourceStore::new(), gltfs: ResourceStore::new(), base_layout: Arc::new(base_layout), fallback_texture: fallback, active_group: None, font_loader: FontLoader::new() };
         return loader;
     }
 
-    pub fn load_all_textures_in_folder(&mut self,folder: String, device:&Device, queue:&Queue, working_dir:String) {
-        let paths = std::fs::read_dir(format!("{}//{}",working_dir,folder)).unwrap();
+    //Needs the material buffer, so it is created by the GameEngine after the renderers
+    pub(crate) fn create_placeholder_model(&mut self, device:&Device, m_manager:&mut MaterialManager, buffer:&Buffer, queue:&Queue) {
+        let obj = ObjData::parse(PLACEHOLDER_OBJ,"placeholder.obj").unwrap();
+
+        let mut material = Material::default();
+        material.name = "placeholder".to_string();
+        material.diffuse_color = nalgebra_glm::vec3(1.0,0.0,1.0);
+        material.ambient_color = nalgebra_glm::vec3(1.0,0.0,1.0);
+        material.data = material.get_data();
+
+        let mut model = ObjModel::from_data(obj,HashMap::from([(material.name.clone(),material)]),device);
+        m_manager.register(&mut model.materials,buffer,queue,self);
+        self.objs.placeholder = Some(Rc::new(model));
+    }
+
+    //Magenta/black checkerboard, used instead of textures which failed to load
+    fn create_fallback_texture(device:&Device,queue:&Queue,layout:&BindGroupLayout) -> TextureObject {
+        let image = RgbaImage::from_fn(8,8,|x,y| {
+            if (x / 4 + y / 4) % 2 == 0 {
+                Rgba([255,0,255,255])
+            } else {
+                Rgba([0,0,0,255])
+            }
+        });
+        return TextureObject::new_from_data(&image,"fallback".to_string(),device,queue,layout);
+    }
+
+    pub fn load_all_textures_in_folder(&mut self,folder: String, device:&Device, queue:&Queue, vfs:&Vfs) {
+        match self.try_load_all_textures_in_folder(folder,device,queue,vfs) {
+            Ok(errors) => errors.iter().for_each(|e| error!("{}",e)),
+            Err(e) => error!("{}",e)
+        }
+    }
+
+    /*
+    Loads every png in the folder. Files which fail to load are skipped and returned as errors,
+    Err is only returned if the folder itself can't be read.
+     */
+    pub fn try_load_all_textures_in_folder(&mut self,folder: String, device:&Device, queue:&Queue, vfs:&Vfs) -> RglResult<Vec<RglError>> {
+        let paths = vfs.try_list_files(&folder)?;
+
+        let mut errors = vec![];
 
-        for path in paths {
-            let file = path.unwrap();
-            if file.path().is_file() {
-                if file.path().extension().unwrap().to_str().unwrap().eq("png") {
-                    let file_name = String::from(file.file_name().to_str().unwrap());
-                    unsafe {
-                        self.textures.insert(file_name.clone(),Rc::new(TextureObject::new(format!("{}//{}//{}",working_dir,&folder,&file_name),file_name.to_string(),device,queue,&self.base_layout)));
-                    }
+        for file_name in paths {
+            if file_name.ends_with(".png") {
+                let path = join_path(&folder,&file_name);
+                match TextureObject::try_new(path.clone(),file_name.to_string(),device,queue,&self.base_layout,vfs) {
+                    Ok(tex) => {
+                        self.textures.insert(&file_name,Rc::new(tex),Some(path),self.active_group);
+                    },
+                    Err(e) => errors.push(e)
                 }
             }
         }
+
+        return Ok(errors);
+    }
+
+    pub fn load_all_objs_in_folder(&mut self,folder: String, vfs:&Vfs, device:&Device, m_manager:&mut MaterialManager, buffer:&Buffer, queue:&Queue) {
+        match self.try_load_all_objs_in_folder(folder,vfs,device,m_manager,buffer,queue) {
+            Ok(errors) => errors.iter().for_each(|e| error!("{}",e)),
+            Err(e) => error!("{}",e)
+        }
     }
 
-    pub fn load_all_objs_in_folder(&mut self,folder: String, working_dir:String, device:&Device, m_manager:&mut MaterialManager, buffer:&Buffer, queue:&Queue) {
-        let paths = std::fs::read_dir(format!("{}//{}",working_dir,folder)).unwrap();
+    pub fn try_load_all_objs_in_folder(&mut self,folder: String, vfs:&Vfs, device:&Device, m_manager:&mut MaterialManager, buffer:&Buffer, queue:&Queue) -> RglResult<Vec<RglError>> {
+        let paths = vfs.try_list_files(&folder)?;
 
-        for path in paths {
-            let file = path.unwrap();
-            if file.path().is_file() {
-                if file.path().extension().unwrap().to_str().unwrap().eq("obj") {
-                    let file_name = String::from(file.file_name().to_str().unwrap());
-                    unsafe {
-                        let mut obj = ObjModel::new(format!("{}//{}",working_dir,&folder),file_name.clone(),device);
+        let mut errors = vec![];
+
+        for file_name in paths {
+            if file_name.ends_with(".obj") {
+                match ObjModel::try_new(folder.clone(),file_name.clone(),device,vfs) {
+                    Ok(mut obj) => {
                         m_manager.register(&mut obj.materials, buffer, queue,self);
+                        self.objs.insert(&file_name,Rc::new(obj),Some(join_path(&folder,&file_name)),self.active_group);
+                    },
+                    Err(e) => errors.push(e)
+                }
+            }
+        }
 
-                        self.objs.insert(file_name.clone(),Rc::new(obj));
-                    }
+        return Ok(errors);
+    }
+
+    pub fn load_all_gltfs_in_folder(&mut self,folder: String, vfs:&Vfs, device:&Device, m_manager:&mut MaterialManager, buffer:&Buffer, queue:&Queue) {
+        match self.try_load_all_gltfs_in_folder(folder,vfs,device,m_manager,buffer,queue) {
+            Ok(errors) => errors.iter().for_each(|e| error!("{}",e)),
+            Err(e) => error!("{}",e)
+        }
+    }
+
+    //Loads .gltf and .glb files, their textures are added as "<file>#<image index>"
+    pub fn try_load_all_gltfs_in_folder(&mut self,folder: String, vfs:&Vfs, device:&Device, m_manager:&mut MaterialManager, buffer:&Buffer, queue:&Queue) -> RglResult<Vec<RglError>> {
+        let paths = vfs.try_list_files(&folder)?;
+
+        let mut errors = vec![];
+
+        for file_name in paths {
+            if file_name.ends_with(".gltf") || file_name.ends_with(".glb") {
+                match GltfModel::try_new(folder.clone(),file_name.clone(),device,queue,self,vfs) {
+                    Ok(mut model) => {
+                        m_manager.register(&mut model.materials, buffer, queue,self);
+                        self.gltfs.insert(&file_name,Rc::new(model),Some(join_path(&folder,&file_name)),self.active_group);
+                    },
+                    Err(e) => errors.push(e)
                 }
             }
         }
+
+        return Ok(errors);
     }
 
-    pub fn load_font(&mut self, path: String, name: String, working_dir:String) {
-        self.base_fonts.insert(name.clone(),Rc::new(self.font_loader.load_font(name.as_str(),format!("{}\\{}",working_dir,path).as_str())));
+    //rgl_font only loads from disk, fonts in packs are copied to the temp dir first (see Vfs::to_real_file)
+    pub fn load_font(&mut self, path: String, name: String, vfs:&Vfs) {
+        match vfs.to_real_file(&path) {
+            Ok(real) => {
+                self.base_fonts.insert(name.clone(),Rc::new(self.font_loader.load_font(name.as_str(),&real.to_string_lossy())));
+            },
+            Err(e) => error!("{}",e)
+        }
     }
 
-    pub fn load_scaled_font(&mut self,name:String, base:Rc<BaseFont>, pixel_height:i32, device:&Device,queue:&Queue) {
+    //The scaled font is kept by the render font (RenderFont::base)
+    pub fn load_scaled_font(&mut self,name:String, base:Rc<BaseFont>, pixel_height:i32, device:&Device,queue:&Queue) -> Handle<Font> {
 
         let s = Rc::new(base.scaled(pixel_height));
 
-        let render_font = RenderFont::new(s.clone(),device,queue,&self.base_layout);
+        let render_font = RenderFont::new(s,device,queue,&self.base_layout);
 
-        self.scaled_fonts.insert(name.clone(),s);
-        self.render_fonts.insert(name.clone(),Rc::new(render_font));
+        return self.fonts.insert(&name,Rc::new(render_font),None,self.active_group);
     }
 
+    //Unknown ids get the fallback texture
     pub fn get_texture(&self, id: String) -> Rc<TextureObject>{
-        return self.textures.get(&id).unwrap().clone();
+        return self.get_texture_or_fallback(&id);
+    }
+
+    pub fn try_get_texture(&self, id: &str) -> Option<Rc<TextureObject>>{
+        return self.textures.try_get_by_name(id);
+    }
+
+    pub fn get_texture_or_fallback(&self, id: &str) -> Rc<TextureObject>{
+        return self.textures.try_get_by_name(id).unwrap_or_else(|| self.fallback_texture.clone());
+    }
+
+    pub fn try_get_gltf(&self, id: &str) -> Option<Rc<GltfModel>>{
+        return self.gltfs.try_get_by_name(id);
+    }
+
+    pub fn try_get_obj(&self, id: &str) -> Option<Rc<ObjModel>>{
+        return self.objs.try_get_by_name(id);
+    }
+
+    pub fn unload_texture(&mut self, handle:Handle<Texture>) {
+        self.textures.unload(handle);
+    }
+
+    pub fn unload_font(&mut self, handle:Handle<Font>) {
+        self.fonts.unload(handle);
+    }
+
+    pub fn unload_obj(&mut self, handle:Handle<Model>, m_manager:&mut MaterialManager) {
+        if let Some(obj) = self.objs.unload(handle) {
+            release_materials(obj,|o| &o.materials,m_manager);
+        }
+    }
+
+    pub fn unload_gltf(&mut self, handle:Handle<Gltf>, m_manager:&mut MaterialManager) {
+        if let Some(model) = self.gltfs.unload(handle) {
+            release_materials(model,|m| &m.materials,m_manager);
+        }
+    }
+
+    //Models go first, the textures only they used are unused afterwards. Returns how many resources were unloaded
+    pub fn unload_unused(&mut self, m_manager:&mut MaterialManager) -> usize {
+        let mut count = 0;
+        for obj in self.objs.unload_unused() {
+            release_materials(obj,|o| &o.materials,m_manager);
+            count += 1;
+        }
+        for model in self.gltfs.unload_unused() {
+            release_materials(model,|m| &m.materials,m_manager);
+            count += 1;
+        }
+        count += self.fonts.unload_unused().len();
+        count += self.textures.unload_unused().len();
+        return count;
+    }
+
+    pub fn free_group(&mut self, group:ResourceGroup, m_manager:&mut MaterialManager) -> usize {
+        let mut count = 0;
+        for obj in self.objs.free_group(group) {
+            release_materials(obj,|o| &o.materials,m_manager);
+            count += 1;
+        }
+        for model in self.gltfs.free_group(group) {
+            release_materials(model,|m| &m.materials,m_manager);
+            count += 1;
+        }
+        count += self.fonts.free_group(group).len();
+        count += self.textures.free_group(group).len();
+        return count;
+    }
+
+    pub fn get_resource_infos(&self) -> Vec<ResourceInfo> {
+        let mut infos = self.textures.get_infos();
+        infos.extend(self.objs.get_infos());
+        infos.extend(self.gltfs.get_infos());
+        infos.extend(self.fonts.get_infos());
+        return infos;
+    }
+
+    pub fn get_gpu_size(&self) -> u64 {
+        return self.textures.get_gpu_size() + self.objs.get_gpu_size() + self.gltfs.get_gpu_size() + self.fonts.get_gpu_size();
+    }
+}
+
+//The material slots are only freed with the last user of the model, otherwise they stay taken
+fn release_materials<T>(model:Rc<T>, materials:fn(&T) -> &HashMap<String,Material>, m_manager:&mut MaterialManager) {
+    if Rc::strong_count(&model) == 1 {
+        m_manager.unregister(materials(&model));
+    } else {
+        warn!("Unloaded a model which is still used, its materials stay registered");
     }
 }
\ No newline at end of file
@@ -1,33 +1,43 @@
 use crate::engine::game_engine::GameEngine;
 
+/*
+Fixed timestep: update adds the frame time, tick returns true as long as a whole tick is left (call Scene::fixed_update for each).
+At most max_catch_up ticks are run per frame, if the game can not keep up the remaining time is dropped
+(otherwise every frame takes longer than the last one). tick_alpha is the part of the next tick that already passed,
+it is used to interpolate between the last two ticks when rendering.
+ */
 pub struct TickHandler {
     pub tick_speed: f64,
     pub accumulator: f64,
     pub current_tick: i32,
     pub ticks_for_second: i32,
     pub paused: bool,
-    pub tick_alpha: f64
+    pub tick_alpha: f64,
+    pub max_catch_up: u32,
+    pub ticks_this_frame: u32
 }
 
 impl TickHandler {
     pub fn update(&mut self,frame_time: f64) {
+        self.ticks_this_frame = 0;
         if !self.paused {
-            self.accumulator += frame_time;
+            self.accumulator = (self.accumulator + frame_time).min(self.tick_speed * self.max_catch_up as f64);
         }
     }
 
     pub fn tick(&mut self) -> bool {
         if !self.paused {
-            let b = self.accumulator >= self.tick_speed;
+            let b = self.accumulator >= self.tick_speed && self.ticks_this_frame < self.max_catch_up;
 
             if b {
                 self.accumulator -= self.tick_speed;
+                self.ticks_this_frame += 1;
+                self.current_tick += 1;
                 if self.current_tick == self.ticks_for_second {
                     self.current_tick = 0;
                 }
-                if !b {
-                    self.tick_alpha = self.accumulator / self.tick_speed;
-                }
+            } else {
+                self.tick_alpha = (self.accumulator / self.tick_speed).clamp(0.0,1.0);
             }
 
             return b
@@ -38,8 +48,9 @@ impl TickHandler {
 
     pub fn new(ticks_for_second:i32) -> TickHandler {
         let tick_speed = (1000.0 / ticks_for_second as f64) / 1000.0;
-        return TickHandler { tick_speed, accumulator: 0.0 , current_tick: 0,ticks_for_second, paused: false, tick_alpha: 0.0 }
+        return TickHandler { tick_speed, accumulator: 0.0 , current_tick: 0,ticks_for_second, paused: false, tick_alpha: 0.0, max_catch_up: 5, ticks_this_frame: 0 }
     }
+
     pub fn set_ticks_for_second(&mut self,ticks_for_second:i32) {
         if ticks_for_second > 0 {
             let tick_speed = (1000.0 / ticks_for_second as f64) / 1000.0;
@@ -47,4 +58,12 @@ impl TickHandler {
             self.tick_speed = tick_speed;
         }
     }
-}
\ No newline at end of file
+
+    pub fn set_max_catch_up(&mut self,max_catch_up:u32) {
+        self.max_catch_up = max_catch_up.max(1);
+    }
+
+    pub fn get_tick_delta(&self) -> f32 {
+        return self.tick_speed as f32;
+    }
+}
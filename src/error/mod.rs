@@ -0,0 +1 @@
+pub mod rgl_error;
\ No newline at end of file
@@ -0,0 +1,62 @@
+use std::fmt::{Display, Formatter};
+
+#[derive(Debug)]
+pub enum RglError {
+    Io { path: String, source: std::io::Error },
+    Parse { file: String, line: Option<usize>, message: String },
+    UnsupportedFormat { path: String, format: String },
+    Gpu(String),
+    //The adapter can't satisfy the EngineConfig
+    Config(String)
+}
+
+pub type RglResult<T> = Result<T,RglError>;
+
+impl RglError {
+    pub fn io(path:&str,source:std::io::Error) -> RglError {
+        return RglError::Io { path: path.to_string(), source };
+    }
+
+    pub fn parse(file:&str,line:Option<usize>,message:String) -> RglError {
+        return RglError::Parse { file: file.to_string(), line, message };
+    }
+
+    pub fn unsupported(path:&str,format:&str) -> RglError {
+        return RglError::UnsupportedFormat { path: path.to_string(), format: format.to_string() };
+    }
+
+    pub fn config(message:String) -> RglError {
+        return RglError::Config(message);
+    }
+
+    //Maps an error of the image crate to the matching variant
+    pub fn from_image(path:&str,error:image::ImageError) -> RglError {
+        return match error {
+            image::ImageError::IoError(e) => RglError::io(path,e),
+            image::ImageError::Unsupported(e) => RglError::unsupported(path,&e.to_string()),
+            e => RglError::parse(path,None,e.to_string())
+        }
+    }
+}
+
+impl Display for RglError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        return match self {
+            RglError::Io { path, source } => write!(f,"IO error on {}: {}",path,source),
+            RglError::Parse { file, line: Some(line), message } => write!(f,"Parse error in {} on line {}: {}",file,line,message),
+            RglError::Parse { file, line: None, message } => write!(f,"Parse error in {}: {}",file,message),
+            RglError::UnsupportedFormat { path, format } => write!(f,"Unsupported format of {}: {}",path,format),
+            RglError::Gpu(message) => write!(f,"GPU error: {}",message),
+            RglError::Config(message) => write!(f,"Engine config error: {}",message)
+        }
+    }
+}
+
+impl std::error::Error for RglError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        return match self {
+            RglError::Io { source, .. } => Some(source),
+            _ => None
+        }
+    }
+}
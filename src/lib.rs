@@ -9,4 +9,7 @@ pub mod audio;
 pub mod text;
 pub mod ui;
 pub mod light;
-pub mod animation;
\ No newline at end of file
+pub mod animation;
+pub mod world;
+pub mod error;
+pub mod vfs;
\ No newline at end of file
@@ -1,64 +1,238 @@
-use nalgebra_glm::{TVec3, TVec4, vec3, vec3_to_vec4, vec4};
+use log::warn;
+use nalgebra_glm::{TVec3, vec3, vec4};
 use crate::engine::game_engine::GameEngine;
+use crate::light::shadow::{compute_light_matrices, MAX_SHADOW_MAPS, ShadowAtlas, ShadowMap, ShadowSettings};
+use crate::objects::camera::Camera;
 use crate::objects::color::Color;
-
+use crate::engine::profiler::write_uniform;
+
+pub const DEFAULT_MAX_LIGHTS: usize = 16;
+//ambient (vec4) + num_lights (ivec4)
+pub const LIGHTS_HEADER_SIZE: u64 = 32;
+pub const LIGHT_SIZE: u64 = 96;
+
+/*
+All lights of the vertex pipeline, they are uploaded as storage buffer so the amount is only limited by max_lights.
+update() grows the buffer of the vertex pipeline if there are more lights than it can hold.
+update_shadows() places the shadow maps of all shadow casting lights in the shadow atlas, they have to be
+rendered with the ShadowRenderer afterwards.
+ */
 pub struct Lights {
-    pub lights: [Light;4],
-    pub data: Vec<u8>,
-    pub num_lights: i32
+    pub lights: Vec<Light>,
+    pub ambient: Color,
+    pub max_lights: usize,
+    pub shadow_maps: Vec<ShadowMap>,
+    pub data: Vec<u8>
 }
 
 impl Lights {
     pub fn new() -> Self {
-        let mut ls = Lights { lights: [Light::empty(),Light::empty(),Light::empty(),Light::empty()], data: vec![], num_lights: 0 };
+        return Lights::new_with_capacity(DEFAULT_MAX_LIGHTS);
+    }
+
+    pub fn new_with_capacity(max_lights:usize) -> Self {
+        let mut ls = Lights { lights: vec![], ambient: Color::new(25,25,25), max_lights, shadow_maps: vec![], data: vec![] };
         ls.data = ls.get_data();
 
         return ls;
     }
 
-    pub fn set_num_lights(&mut self,num:i32) {
-        self.num_lights = num;
+    pub fn add(&mut self, light:Light) -> usize {
+        self.lights.push(light);
+        return self.lights.len() - 1;
+    }
+
+    pub fn remove(&mut self, index:usize) -> Light {
+        return self.lights.remove(index);
+    }
+
+    pub fn get_num_lights(&self) -> usize {
+        return self.lights.len().min(self.max_lights);
     }
 
     pub fn get_data(&self) -> Vec<u8> {
         let mut data = vec![];
 
-        for l in self.lights.iter() {
-            data.extend_from_slice(&l.get_data());
-        }
-
-        data.extend_from_slice(&crate::objects::matrix_helper::get_bytes_from_i32(self.num_lights));
+        data.extend_from_slice(&crate::objects::matrix_helper::get_bytes_from_vec4(&vec4(self.ambient.r,self.ambient.g,self.ambient.b,0.0)));
+        data.extend_from_slice(&crate::objects::matrix_helper::get_bytes_from_i32(self.get_num_lights() as i32));
         data.extend_from_slice(&crate::objects::matrix_helper::get_bytes_from_vec3(&vec3(0.0,0.0,0.0)));
 
+        for (i,l) in self.lights.iter().enumerate().take(self.max_lights) {
+            data.extend_from_slice(&l.get_data());
+            let first = self.shadow_maps.iter().position(|m| m.light == i).unwrap_or(0);
+            let count = self.shadow_maps.iter().filter(|m| m.light == i).count();
+            data.extend(crate::objects::matrix_helper::get_bytes_from_vec4(&vec4(first as f32,count as f32,0.0,0.0)));
+        }
 
         return data;
     }
 
-    pub fn update(&mut self, engine:&GameEngine) {
+    pub fn update(&mut self, engine:&mut GameEngine) {
         self.data = self.get_data();
-        engine.queue.write_buffer(&engine.vertex_renderer.shader.light_buffer,0,&self.data);
+        engine.vertex_renderer.shader.ensure_light_capacity(&engine.device,self.max_lights.max(self.get_num_lights()));
+        write_uniform(&engine.queue,&engine.vertex_renderer.shader.light_buffer,0,&self.data);
+    }
+
+    //Directional lights fit their cascades to the view of the camera
+    pub fn update_shadows(&mut self, engine:&mut GameEngine, camera:&Camera) {
+        self.shadow_maps.clear();
+
+        let mut casters = self.lights.iter().enumerate().take(self.max_lights).filter_map(|(i,l)| Some((i,l,l.shadow.as_ref()?))).collect::<Vec<_>>();
+        casters.sort_by(|a,b| b.2.resolution.cmp(&a.2.resolution));
+
+        let mut atlas = ShadowAtlas::new();
+        for (i,light,settings) in casters {
+            let matrices = compute_light_matrices(light,settings,camera);
+            if self.shadow_maps.len() + matrices.len() > MAX_SHADOW_MAPS {
+                warn!("Too many shadow maps, light {} casts no shadows",i);
+                continue;
+            }
+
+            let mut maps = vec![];
+            for (view_proj,split_far) in matrices {
+                match atlas.allocate(settings.resolution) {
+                    Some((x,y)) => maps.push(ShadowMap { light: i, view_proj, x, y, size: settings.resolution.min(crate::light::shadow::SHADOW_ATLAS_SIZE), bias: settings.bias, pcf_radius: settings.pcf_radius, split_far }),
+                    None => break
+                }
+            }
+
+            if maps.is_empty() {
+                warn!("The shadow atlas is full, light {} casts no shadows",i);
+            }
+            self.shadow_maps.extend(maps);
+        }
+
+        let mut data = vec![];
+        for map in self.shadow_maps.iter() {
+            data.extend(map.get_data());
+        }
+        if !data.is_empty() {
+            write_uniform(&engine.queue,&engine.vertex_renderer.shader.shadow_buffer,0,&data);
+        }
+        engine.shadow_renderer.set_maps(&engine.queue,&self.shadow_maps);
+
+        self.update(engine);
     }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LightType {
+    Point,
+    Directional,
+    Spot
+}
+
+impl LightType {
+    fn id(&self) -> f32 {
+        return match self {
+            LightType::Point => 0.0,
+            LightType::Directional => 1.0,
+            LightType::Spot => 2.0
+        }
+    }
+}
+
+/*
+Point lights use pos, directional lights direction, spot lights both.
+The attenuation is 1 / (constant + linear * d + quadratic * d²), lights have no effect after range.
+The cone of a spot light fades from inner_angle to outer_angle (degrees, measured from the direction).
+ */
+#[derive(Clone)]
 pub struct Light {
+    pub light_type: LightType,
     pub pos:TVec3<f32>,
-    pub color:Color
+    pub direction:TVec3<f32>,
+    pub color:Color,
+    pub intensity: f32,
+    pub range: f32,
+    pub constant: f32,
+    pub linear: f32,
+    pub quadratic: f32,
+    pub inner_angle: f32,
+    pub outer_angle: f32,
+    pub shadow: Option<ShadowSettings>
 }
 
 impl Light {
 
     pub fn empty() -> Self {
-        Self { pos: vec3(0.0,0.0,0.0), color: Color::new(255,255,255) }
+        Self::new(vec3(0.0,0.0,0.0),Color::new(255,255,255))
     }
 
     pub fn new(pos:TVec3<f32>,color:Color) -> Self {
-        Self { pos, color }
+        Self::point(pos,color,50.0)
+    }
+
+    pub fn point(pos:TVec3<f32>,color:Color,range:f32) -> Self {
+        let mut light = Self {
+            light_type: LightType::Point,
+            pos,
+            direction: vec3(0.0,-1.0,0.0),
+            color,
+            intensity: 1.0,
+            range,
+            constant: 1.0,
+            linear: 0.0,
+            quadratic: 0.0,
+            inner_angle: 0.0,
+            outer_angle: 0.0,
+            shadow: None
+        };
+        light.set_range(range);
+        light
+    }
+
+    pub fn directional(direction:TVec3<f32>,color:Color) -> Self {
+        let mut light = Self::point(vec3(0.0,0.0,0.0),color,0.0);
+        light.light_type = LightType::Directional;
+        light.direction = direction;
+        light
+    }
+
+    pub fn spot(pos:TVec3<f32>,direction:TVec3<f32>,color:Color,range:f32,inner_angle:f32,outer_angle:f32) -> Self {
+        let mut light = Self::point(pos,color,range);
+        light.light_type = LightType::Spot;
+        light.direction = direction;
+        light.inner_angle = inner_angle;
+        light.outer_angle = outer_angle.max(inner_angle);
+        light
+    }
+
+    //Common attenuation values for the given range (the light is around 1% at the range)
+    pub fn set_range(&mut self, range:f32) {
+        self.range = range;
+        if range > 0.0 {
+            self.constant = 1.0;
+            self.linear = 4.5 / range;
+            self.quadratic = 75.0 / (range * range);
+        }
+    }
+
+    pub fn intensity(mut self, intensity:f32) -> Self {
+        self.intensity = intensity;
+        self
+    }
+
+    //Only directional and spot lights cast shadows
+    pub fn shadows(mut self, settings:ShadowSettings) -> Self {
+        self.shadow = Some(settings);
+        self
+    }
+
+    pub fn attenuation(mut self, constant:f32, linear:f32, quadratic:f32) -> Self {
+        self.constant = constant;
+        self.linear = linear;
+        self.quadratic = quadratic;
+        self
     }
 
     pub fn get_data(&self) -> Vec<u8> {
         let mut data = vec![];
-        data.extend(crate::objects::matrix_helper::get_bytes_from_vec4(&vec4(self.pos.x,self.pos.y,self.pos.z,0.0)));
-        data.extend(crate::objects::matrix_helper::get_bytes_from_vec4(&vec4(self.color.r,self.color.g,self.color.b,0.0)));
+        data.extend(crate::objects::matrix_helper::get_bytes_from_vec4(&vec4(self.pos.x,self.pos.y,self.pos.z,self.light_type.id())));
+        data.extend(crate::objects::matrix_helper::get_bytes_from_vec4(&vec4(self.direction.x,self.direction.y,self.direction.z,self.intensity)));
+        data.extend(crate::objects::matrix_helper::get_bytes_from_vec4(&vec4(self.color.r,self.color.g,self.color.b,self.range)));
+        data.extend(crate::objects::matrix_helper::get_bytes_from_vec4(&vec4(self.constant,self.linear,self.quadratic,0.0)));
+        data.extend(crate::objects::matrix_helper::get_bytes_from_vec4(&vec4(self.inner_angle.to_radians().cos(),self.outer_angle.to_radians().cos(),0.0,0.0)));
 
         return data;
     }
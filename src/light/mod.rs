@@ -1 +1,2 @@
-pub mod light;
\ No newline at end of file
+pub mod light;
+pub mod shadow;
\ No newline at end of file
@@ -0,0 +1,183 @@
+use nalgebra_glm::{Mat4, TVec3, vec3, vec4};
+use crate::light::light::{Light, LightType};
+use crate::objects::camera::Camera;
+
+pub const SHADOW_ATLAS_SIZE: u32 = 4096;
+pub const MAX_SHADOW_MAPS: usize = 32;
+pub const MAX_CASCADES: u32 = 4;
+//view_proj (mat4) + atlas rect (vec4) + params (vec4)
+pub const SHADOW_MAP_SIZE: u64 = 96;
+
+/*
+Shadow settings of a light. Only directional and spot lights cast shadows.
+resolution is the size of every shadow map (cascade) in the shadow atlas, bias is subtracted from the depth
+before comparing, pcf_radius is the amount of texels sampled in every direction.
+Directional lights split the view of the camera into cascades up to distance.
+ */
+#[derive(Clone)]
+pub struct ShadowSettings {
+    pub resolution: u32,
+    pub bias: f32,
+    pub pcf_radius: u32,
+    pub cascades: u32,
+    pub distance: f32
+}
+
+impl ShadowSettings {
+    pub fn new() -> ShadowSettings {
+        return ShadowSettings { resolution: 1024, bias: 0.002, pcf_radius: 1, cascades: 1, distance: 50.0 };
+    }
+
+    pub fn resolution(mut self, resolution:u32) -> Self {
+        self.resolution = resolution;
+        self
+    }
+
+    pub fn bias(mut self, bias:f32) -> Self {
+        self.bias = bias;
+        self
+    }
+
+    pub fn pcf_radius(mut self, pcf_radius:u32) -> Self {
+        self.pcf_radius = pcf_radius;
+        self
+    }
+
+    pub fn cascades(mut self, cascades:u32) -> Self {
+        self.cascades = cascades.clamp(1,MAX_CASCADES);
+        self
+    }
+
+    pub fn distance(mut self, distance:f32) -> Self {
+        self.distance = distance;
+        self
+    }
+}
+
+//One rendered shadow map, x/y/size are pixels in the shadow atlas
+#[derive(Clone)]
+pub struct ShadowMap {
+    pub light: usize,
+    pub view_proj: Mat4,
+    pub x: u32,
+    pub y: u32,
+    pub size: u32,
+    pub bias: f32,
+    pub pcf_radius: u32,
+    pub split_far: f32
+}
+
+impl ShadowMap {
+    pub fn get_data(&self) -> Vec<u8> {
+        let atlas = SHADOW_ATLAS_SIZE as f32;
+        let mut data = crate::objects::matrix_helper::get_bytes(&self.view_proj);
+        data.extend(crate::objects::matrix_helper::get_bytes_from_vec4(&vec4(self.x as f32 / atlas,self.y as f32 / atlas,self.size as f32 / atlas,self.size as f32 / atlas)));
+        data.extend(crate::objects::matrix_helper::get_bytes_from_vec4(&vec4(self.bias,self.pcf_radius as f32,self.split_far,1.0 / atlas)));
+        return data;
+    }
+}
+
+//Packs square maps into rows of the atlas, the biggest maps first
+pub struct ShadowAtlas {
+    x: u32,
+    y: u32,
+    row_height: u32
+}
+
+impl ShadowAtlas {
+    pub fn new() -> ShadowAtlas {
+        return ShadowAtlas { x: 0, y: 0, row_height: 0 };
+    }
+
+    pub fn allocate(&mut self, size:u32) -> Option<(u32,u32)> {
+        let size = size.min(SHADOW_ATLAS_SIZE);
+        if self.x + size > SHADOW_ATLAS_SIZE {
+            self.x = 0;
+            self.y += self.row_height;
+            self.row_height = 0;
+        }
+        if self.y + size > SHADOW_ATLAS_SIZE {
+            return None;
+        }
+        let pos = (self.x,self.y);
+        self.x += size;
+        self.row_height = self.row_height.max(size);
+        return Some(pos);
+    }
+}
+
+//Light space matrices of every map of the light, with the far distance of every cascade
+pub fn compute_light_matrices(light:&Light, settings:&ShadowSettings, camera:&Camera) -> Vec<(Mat4,f32)> {
+    return match light.light_type {
+        LightType::Spot => {
+            let fov = (light.outer_angle * 2.0).clamp(1.0,170.0).to_radians();
+            let far = if light.range > 0.0 { light.range } else { settings.distance };
+            let projection = nalgebra_glm::perspective_fov_rh_zo(fov,1.0,1.0,0.05,far);
+            let view = nalgebra_glm::look_at_rh(&light.pos,&(light.pos + light.direction),&up_vector(&light.direction));
+            vec![(projection * view,far)]
+        },
+        LightType::Directional => {
+            let near = camera.near.max(0.01);
+            let far = settings.distance.min(camera.far).max(near + 0.01);
+            let splits = cascade_splits(near,far,settings.cascades.clamp(1,MAX_CASCADES));
+
+            let mut last = near;
+            let mut matrices = vec![];
+            for split in splits {
+                matrices.push((directional_matrix(light,settings,camera,last,split),split));
+                last = split;
+            }
+            matrices
+        },
+        LightType::Point => vec![]
+    }
+}
+
+//Mix of logarithmic and uniform splits (practical split scheme)
+fn cascade_splits(near:f32, far:f32, cascades:u32) -> Vec<f32> {
+    let lambda = 0.6;
+    return (1..=cascades).map(|i| {
+        let p = i as f32 / cascades as f32;
+        let log = near * (far / near).powf(p);
+        let uniform = near + (far - near) * p;
+        lambda * log + (1.0 - lambda) * uniform
+    }).collect();
+}
+
+//Fits an orthographic projection around the bounding sphere of the camera frustum slice, snapped to texels so the shadows do not shimmer
+fn directional_matrix(light:&Light, settings:&ShadowSettings, camera:&Camera, near:f32, far:f32) -> Mat4 {
+    let projection = if camera.orto { camera.projection } else { nalgebra_glm::perspective_fov_rh_zo(camera.fov,camera.width,camera.height,near,far) };
+    let inverse = nalgebra_glm::inverse(&(projection * camera.view));
+
+    let mut corners = vec![];
+    for x in [-1.0,1.0] {
+        for y in [-1.0,1.0] {
+            for z in [0.0,1.0] {
+                let p = inverse * vec4(x,y,z,1.0);
+                corners.push(vec3(p.x / p.w,p.y / p.w,p.z / p.w));
+            }
+        }
+    }
+
+    let center = corners.iter().fold(vec3(0.0,0.0,0.0),|a,c| a + c) / corners.len() as f32;
+    let radius = corners.iter().map(|c| (c - center).magnitude()).fold(0.0,f32::max).max(0.01);
+
+    let direction = nalgebra_glm::normalize(&light.direction);
+    //Casters behind the visible area still have to be rendered into the map
+    let caster_distance = settings.distance;
+    let eye = center - direction * (radius + caster_distance);
+    let mut view = nalgebra_glm::look_at_rh(&eye,&center,&up_vector(&direction));
+
+    let texel = radius * 2.0 / settings.resolution.max(1) as f32;
+    let origin = view * vec4(0.0,0.0,0.0,1.0);
+    let snap = vec3((origin.x / texel).round() * texel - origin.x,(origin.y / texel).round() * texel - origin.y,0.0);
+    view = nalgebra_glm::translation(&snap) * view;
+
+    let projection = nalgebra_glm::ortho_rh_zo(-radius,radius,-radius,radius,0.0,radius * 2.0 + caster_distance);
+    return projection * view;
+}
+
+fn up_vector(direction:&TVec3<f32>) -> TVec3<f32> {
+    let d = nalgebra_glm::normalize(direction);
+    return if d.y.abs() > 0.99 { vec3(0.0,0.0,1.0) } else { vec3(0.0,1.0,0.0) };
+}
\ No newline at end of file
@@ -1,9 +1,11 @@
+use std::cell::OnceCell;
 use nalgebra::{Point, Point3, ArrayStorage, Perspective3, Orthographic3, TProjective};
 use nalgebra_glm::{Mat4, TMat4, TVec3, vec3, vec4};
 use crate::objects::transform::Transform;
 use wgpu::{Queue, Buffer, Device, BindGroupLayoutDescriptor, BindGroupLayoutEntry, BindingType, BindGroup, BindGroupLayout, BindGroupDescriptor, BindGroupEntry, BindingResource, BufferDescriptor, BufferSize, BufferSlice, BufferBindingType, BufferBinding, BufferUsages, ShaderStages, BufferAddress};
 use wgpu::util::{BufferInitDescriptor, DeviceExt};
 use crate::engine::game_engine::GameEngine;
+use crate::engine::profiler::write_uniform;
 
 pub struct Camera {
     pub fov: f32,
@@ -17,9 +19,68 @@ pub struct Camera {
     pub direction: TVec3<f32>,
     pub target: TVec3<f32>,
     pub orto: bool,
+    //Projection and view
     pub buffers: Vec<Buffer>,
     pub bind_group: BindGroup,
-    pub transform_bind_group: BindGroup
+    transforms: TransformBuffer
+}
+
+/*
+Storage buffer of the transforms, written at the offsets of the engine (see Camera::write_transform).
+It grows through a shared reference since transforms are also written while rendering, bind groups handed out before
+may still be borrowed, so the replaced buffers are only dropped by the next Camera::update.
+ */
+struct TransformBuffer {
+    buffer: Buffer,
+    bind_group: BindGroup,
+    grown: OnceCell<Box<TransformBuffer>>
+}
+
+impl TransformBuffer {
+    fn new(buffer:Buffer,device:&Device,label:&str) -> TransformBuffer {
+        let transform_layout = Camera::transform_bind_group(device);
+        let bind_group = device.create_bind_group(&BindGroupDescriptor {
+            label: Some(label),
+            layout: &transform_layout,
+            entries: &[BindGroupEntry { binding: 0, resource: BindingResource::Buffer {
+                0: BufferBinding {
+                    buffer: &buffer,
+                    offset: 0 /*64*/,
+                    size: BufferSize::new(64)
+                }
+            }}]
+        });
+        return TransformBuffer { buffer, bind_group, grown: OnceCell::new() };
+    }
+
+    fn latest(&self) -> &TransformBuffer {
+        return match self.grown.get() {
+            Some(grown) => grown.latest(),
+            None => self
+        }
+    }
+
+    //Doubles the buffer until size bytes fit
+    fn ensure_size(&self,device:&Device,queue:&Queue,size:BufferAddress) -> &TransformBuffer {
+        let latest = self.latest();
+        if latest.buffer.size() >= size {
+            return latest;
+        }
+
+        let mut new_size = latest.buffer.size().max(64);
+        while new_size < size {
+            new_size *= 2;
+        }
+        let buffer = crate::objects::matrix_helper::grow_buffer(device,queue,&latest.buffer,new_size);
+        return latest.grown.get_or_init(|| Box::new(TransformBuffer::new(buffer,device,"transformGrown")));
+    }
+
+    //Drops the replaced buffers
+    fn compact(&mut self) {
+        while let Some(grown) = self.grown.take() {
+            *self = *grown;
+        }
+    }
 }
 
 impl Camera {
@@ -38,7 +99,6 @@ impl Camera {
         Camera::add_buffer(&mut buffers,device,&*crate::objects::matrix_helper::get_bytes(&projection));
         Camera::add_buffer(&mut buffers,device,&*crate::objects::matrix_helper::get_bytes(&view));
         //Camera::add_buffer(&mut buffers,device,&*crate::objects::matrix_helper::get_bytes(&nalgebra_glm::translation(&vec3(0.0,0.0,0.0))));
-        Camera::add_dynamic_buffer(&mut buffers,device);
 
         let camera_layout = Camera::bind_group_layout(device);
 
@@ -62,19 +122,7 @@ impl Camera {
                 },]
         });
 
-        let transform_layout = Camera::transform_bind_group(device);
-
-        let transform_group = device.create_bind_group(&BindGroupDescriptor {
-            label: Some("transformPers"),
-            layout: &transform_layout,
-            entries: &[BindGroupEntry { binding: 0, resource: BindingResource::Buffer {
-                0: BufferBinding {
-                    buffer: &buffers[2],
-                    offset: 0 /*64*/,
-                    size: BufferSize::new(64)
-                }
-            }}]
-        });
+        let transforms = TransformBuffer::new(Camera::create_dynamic_buffer(device),device,"transformPers");
 
         let mut camera = Camera {
             fov,
@@ -90,7 +138,7 @@ impl Camera {
             orto: false,
             buffers,
             bind_group: camera_group,
-            transform_bind_group: transform_group
+            transforms
         };
         camera.update();
         return camera;
@@ -108,7 +156,6 @@ impl Camera {
         Camera::add_buffer(&mut buffers,device,&*crate::objects::matrix_helper::get_bytes(&projection));
         Camera::add_buffer(&mut buffers,device,&*crate::objects::matrix_helper::get_bytes(&view));
         //Camera::add_buffer(&mut buffers,device,&*crate::objects::matrix_helper::get_bytes(&nalgebra_glm::translation(&vec3(0.0,0.0,0.0))));
-        Camera::add_dynamic_buffer(&mut buffers,device);
 
         let camera_layout = Camera::bind_group_layout(device);
 
@@ -131,19 +178,7 @@ impl Camera {
                 } },]
         });
 
-        let transform_layout = Camera::transform_bind_group(device);
-
-        let transform_group = device.create_bind_group(&BindGroupDescriptor {
-            label: Some("transformOrto"),
-            layout: &transform_layout,
-            entries: &[BindGroupEntry { binding: 0, resource: BindingResource::Buffer {
-                0: BufferBinding {
-                    buffer: &buffers[2],
-                    offset: 0 /*64*/,
-                    size: BufferSize::new(64)
-                }
-            }}]
-        });
+        let transforms = TransformBuffer::new(Camera::create_dynamic_buffer(device),device,"transformOrto");
 
         let mut camera = Camera {
             fov: 0.0,
@@ -159,7 +194,7 @@ impl Camera {
             orto: true,
             buffers,
             bind_group: camera_group,
-            transform_bind_group: transform_group
+            transforms
         };
 
         camera.update();
@@ -175,8 +210,8 @@ impl Camera {
     }
 
     pub fn load_up(&self, queue : &Queue) {
-        queue.write_buffer(&self.buffers[0],0,&*crate::objects::matrix_helper::get_bytes(&self.projection));
-        queue.write_buffer(&self.buffers[1],0,&*crate::objects::matrix_helper::get_bytes(&self.view));
+        write_uniform(queue,&self.buffers[0],0,&*crate::objects::matrix_helper::get_bytes(&self.projection));
+        write_uniform(queue,&self.buffers[1],0,&*crate::objects::matrix_helper::get_bytes(&self.view));
     }
 
     pub fn update_projection(&mut self, fov: f32, width: f32,height : f32, near : f32, far : f32 ) {
@@ -212,6 +247,7 @@ impl Camera {
 
     pub fn update(&mut self) {
         self.view = self.create_view_matrix();
+        self.transforms.compact();
     }
     
     fn create_view_matrix(&self) -> Mat4{
@@ -226,6 +262,21 @@ impl Camera {
         return view;
     }
 
+    //Writes the matrix at a static offset (see GameEngine::static_offset_handler), the buffer grows if the offset is past its end
+    pub fn write_transform(&self, device:&Device, queue:&Queue, offset:BufferAddress, matrix:&Mat4) {
+        let transforms = self.transforms.ensure_size(device,queue,offset + 64);
+        write_uniform(queue,&transforms.buffer,offset,&*crate::objects::matrix_helper::get_bytes(matrix));
+    }
+
+    //Buffer the transforms are written to, only valid until the next write_transform
+    pub fn get_transform_buffer(&self) -> &Buffer {
+        return &self.transforms.latest().buffer;
+    }
+
+    pub fn get_transform_group(&self) -> &BindGroup {
+        return &self.transforms.latest().bind_group;
+    }
+
     pub fn add_buffer(buffers:&mut Vec<Buffer>,device:&Device,data:&[u8]) {
         let buffer = device.create_buffer_init(&BufferInitDescriptor {
             label: None,
@@ -236,13 +287,16 @@ impl Camera {
     }
 
     pub fn add_dynamic_buffer(buffers:&mut Vec<Buffer>,device:&Device) {
-        let buffer = device.create_buffer(&BufferDescriptor {
+        buffers.push(Camera::create_dynamic_buffer(device));
+    }
+
+    fn create_dynamic_buffer(device:&Device) -> Buffer {
+        return device.create_buffer(&BufferDescriptor {
             label: None,
             size: (device.limits().min_storage_buffer_offset_alignment * 5000) as u64/*5000*/,
-            usage: BufferUsages::STORAGE | BufferUsages::COPY_DST,
+            usage: BufferUsages::STORAGE | BufferUsages::COPY_DST | BufferUsages::COPY_SRC,
             mapped_at_creation: false
         });
-        buffers.push(buffer);
     }
 
     pub fn bind_group_layout(device:&Device) -> BindGroupLayout {
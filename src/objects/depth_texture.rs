@@ -3,14 +3,24 @@ use wgpu::{Device, Extent3d, TextureDescriptor, TextureDimension, TextureFormat,
 pub struct DepthTexture {
     pub texture: Texture,
     pub view: TextureView,
-    pub sampler: Sampler
+    pub sampler: Sampler,
+    pub sample_count: u32
 }
 
 impl DepthTexture {
     pub fn new(device:&Device,config:&SurfaceConfiguration) -> DepthTexture{
+        return DepthTexture::new_with_size(device,config.width,config.height);
+    }
+
+    pub fn new_with_size(device:&Device,width:u32,height:u32) -> DepthTexture{
+        return DepthTexture::new_multisampled(device,width,height,1);
+    }
+
+    //Has to match the sample count of the color target (see GameEngine::create_depth_texture)
+    pub fn new_multisampled(device:&Device,width:u32,height:u32,sample_count:u32) -> DepthTexture{
         let size = Extent3d {
-            width: config.width,
-            height: config.height,
+            width,
+            height,
             depth_or_array_layers: 1
         };
 
@@ -18,7 +28,7 @@ impl DepthTexture {
             label: Some("Depth Texture"),
             size,
             mip_level_count: 1,
-            sample_count: 1,
+            sample_count,
             dimension: TextureDimension::D2,
             format: TextureFormat::Depth32Float,
             usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING,
@@ -46,7 +56,8 @@ impl DepthTexture {
         return DepthTexture {
             texture,
             view,
-            sampler
+            sampler,
+            sample_count
         }
     }
 }
\ No newline at end of file
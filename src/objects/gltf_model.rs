@@ -0,0 +1,567 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+use log::warn;
+use nalgebra_glm::{cross, Mat4, normalize, TMat4, vec3};
+use wgpu::{Device, Queue, RenderPass};
+use crate::animation::animation::{Animation, JointChannel, PlaybackMode};
+use crate::animation::key_frame::KeyFrame;
+use crate::animation::skeletal_animation_instance::SkeletalAnimationInstance;
+use crate::animation::skeleton::{Joint, JointPose, Skeleton};
+use crate::engine::game_engine::GameEngine;
+use crate::engine::resource_loader::ResourceLoader;
+use crate::error::rgl_error::{RglError, RglResult};
+use crate::objects::camera::Camera;
+use crate::objects::obj_model::Material;
+use crate::objects::texture_object::TextureObject;
+use crate::objects::transform::Transform;
+use crate::objects::vertex::{NormalVertex, SkinnedVertex};
+use crate::objects::vertex_buffer::{NormalVertexBuffer, SkinnedVertexBuffer};
+use crate::vfs::vfs::{join_path, Vfs};
+
+/*
+Model loaded from a gltf/glb file. Meshes and materials are shared, the node hierarchy is kept
+so GltfInstance can place every mesh with its own (parent * child) transform.
+Skins are converted to a Skeleton, the animations targeting their joints to skeletal Animations.
+ */
+pub struct GltfModel {
+    pub meshes: Vec<GltfMesh>,
+    pub materials: HashMap<String,Material>,
+    pub nodes: Vec<GltfNode>,
+    pub roots: Vec<usize>,
+    pub skins: Vec<GltfSkin>
+}
+
+pub struct GltfMesh {
+    pub name: String,
+    pub primitives: Vec<GltfPrimitive>,
+    pub skinned_primitives: Vec<GltfSkinnedPrimitive>
+}
+
+pub struct GltfPrimitive {
+    pub material: String,
+    pub buffer: NormalVertexBuffer
+}
+
+pub struct GltfSkinnedPrimitive {
+    pub material: String,
+    pub buffer: SkinnedVertexBuffer
+}
+
+pub struct GltfSkin {
+    pub name: String,
+    pub skeleton: Rc<Skeleton>,
+    pub animations: Vec<Rc<Animation>>
+}
+
+impl GltfSkin {
+    pub fn find_animation(&self,name:&str) -> Option<Rc<Animation>> {
+        return self.animations.iter().find(|a| a.name == name).cloned();
+    }
+}
+
+pub struct GltfNode {
+    pub name: String,
+    pub translation: [f32;3],
+    pub rotation: [f32;4],
+    pub scale: [f32;3],
+    pub mesh: Option<usize>,
+    pub skin: Option<usize>,
+    pub parent: Option<usize>,
+    pub children: Vec<usize>
+}
+
+impl GltfNode {
+    pub fn create_transform(&self) -> Transform {
+        return Transform::from_decomposed(self.translation,self.rotation,self.scale);
+    }
+}
+
+impl GltfModel {
+    //Only the vertex and index buffers, the textures are counted by the resource loader
+    pub fn get_gpu_size(&self) -> u64 {
+        let mut size = 0;
+        for mesh in self.meshes.iter() {
+            size += mesh.primitives.iter().map(|p| p.buffer.buffer.size() + p.buffer.index_buffer.size()).sum::<u64>();
+            size += mesh.skinned_primitives.iter().map(|p| p.buffer.buffer.size() + p.buffer.index_buffer.size()).sum::<u64>();
+        }
+        return size;
+    }
+
+    //Textures are added to the resource loader as "<file>#<image index>"
+    pub fn try_new(parent:String,file:String,device:&Device,queue:&Queue,rsc:&mut ResourceLoader,vfs:&Vfs) -> RglResult<GltfModel> {
+        let path = join_path(&parent,&file);
+        //External buffers and images are only found next to real files, gltfs in packs have to be self-contained (glb or embedded data)
+        let (document, buffers, images) = match vfs.real_path(&path) {
+            Some(real) => gltf::import(real),
+            None => gltf::import_slice(vfs.read(&path)?)
+        }.map_err(|e| map_gltf_error(&path,e))?;
+
+        for (i,image) in images.iter().enumerate() {
+            let name = format!("{}#{}",file,i);
+            let rgba = image_to_rgba(&path,image)?;
+            let tex = TextureObject::new_from_data(&rgba,name.clone(),device,queue,&rsc.base_layout);
+            rsc.textures.insert(&name,Rc::new(tex),None,rsc.active_group);
+        }
+
+        let mut materials = HashMap::new();
+        for (i,m) in document.materials().enumerate() {
+            let mut material = Material::default();
+            material.name = m.name().map(|n| n.to_string()).unwrap_or(format!("material_{}",i));
+
+            let pbr = m.pbr_metallic_roughness();
+            let base = pbr.base_color_factor();
+            material.diffuse_color = vec3(base[0],base[1],base[2]);
+            material.ambient_color = vec3(base[0],base[1],base[2]);
+            material.dissolve = base[3];
+            //Rough approximation of the metallic/roughness model with the phong values of the mtl format
+            material.specular_highlights = (1.0 - pbr.roughness_factor()) * 128.0;
+            let spec = 0.04 + (1.0 - 0.04) * pbr.metallic_factor();
+            material.specular_color = vec3(spec,spec,spec);
+            material.illumination_model = 2;
+
+            if let Some(info) = pbr.base_color_texture() {
+                material.texture_name = Some(format!("{}#{}",file,info.texture().source().index()));
+            }
+            if let Some(info) = pbr.metallic_roughness_texture() {
+                material.specular_texture_name = Some(format!("{}#{}",file,info.texture().source().index()));
+            }
+            if let Some(normal) = m.normal_texture() {
+                material.bump_texture_name = Some(format!("{}#{}",file,normal.texture().source().index()));
+                material.bump_multiplier = normal.scale();
+            }
+            if let Some(occlusion) = m.occlusion_texture() {
+                material.ambient_texture_name = Some(format!("{}#{}",file,occlusion.texture().source().index()));
+            }
+
+            material.data = material.get_data();
+            materials.insert(material.name.clone(),material);
+        }
+
+        let mut meshes = vec![];
+        for mesh in document.meshes() {
+            let mut primitives = vec![];
+            let mut skinned_primitives = vec![];
+            for primitive in mesh.primitives() {
+                if primitive.mode() != gltf::mesh::Mode::Triangles {
+                    warn!("Skipping primitive of mesh {} in {}, only triangles are supported",mesh.index(),path);
+                    continue;
+                }
+
+                let reader = primitive.reader(|b| Some(&buffers[b.index()]));
+
+                let positions = reader.read_positions().ok_or_else(|| RglError::parse(&path,None,format!("Mesh {} has no positions",mesh.index())))?.collect::<Vec<[f32;3]>>();
+                let uvs = reader.read_tex_coords(0).map(|t| t.into_f32().collect::<Vec<[f32;2]>>());
+                let indices = match reader.read_indices() {
+                    Some(i) => i.into_u32().collect::<Vec<u32>>(),
+                    None => (0..positions.len() as u32).collect()
+                };
+                if let Some(index) = indices.iter().find(|i| **i as usize >= positions.len()) {
+                    return Err(RglError::parse(&path,None,format!("Index {} of mesh {} is out of range (found {} vertices)",index,mesh.index(),positions.len())));
+                }
+                let normals = match reader.read_normals() {
+                    Some(n) => n.collect::<Vec<[f32;3]>>(),
+                    None => generate_normals(&positions,&indices)
+                };
+                check_attribute(&normals,positions.len(),"NORMAL",mesh.index(),&path)?;
+                if let Some(uvs) = &uvs {
+                    check_attribute(uvs,positions.len(),"TEXCOORD_0",mesh.index(),&path)?;
+                }
+
+                let vertecies: Vec<NormalVertex> = positions.iter().enumerate().map(|(i,p)| {
+                    let uv = uvs.as_ref().map_or([0.0,0.0],|uv| uv[i]);
+                    NormalVertex::new(p[0],p[1],p[2],uv[0],uv[1],normals[i][0],normals[i][1],normals[i][2])
+                }).collect();
+
+                let material = match primitive.material().index() {
+                    Some(i) => document.materials().nth(i).unwrap().name().map(|n| n.to_string()).unwrap_or(format!("material_{}",i)),
+                    None => String::new()
+                };
+
+                if !materials.contains_key(&material) {
+                    let mut m = Material::default();
+                    m.name = material.clone();
+                    materials.insert(material.clone(),m);
+                }
+
+                match (reader.read_joints(0),reader.read_weights(0)) {
+                    (Some(joints),Some(weights)) => {
+                        let joints = joints.into_u16().collect::<Vec<[u16;4]>>();
+                        let weights = weights.into_f32().collect::<Vec<[f32;4]>>();
+                        check_attribute(&joints,positions.len(),"JOINTS_0",mesh.index(),&path)?;
+                        check_attribute(&weights,positions.len(),"WEIGHTS_0",mesh.index(),&path)?;
+                        let vertecies = vertecies.into_iter().enumerate().map(|(i,v)| {
+                            let j = joints[i];
+                            SkinnedVertex::new(v,[j[0] as u32,j[1] as u32,j[2] as u32,j[3] as u32],weights[i])
+                        }).collect();
+                        skinned_primitives.push(GltfSkinnedPrimitive { material, buffer: SkinnedVertexBuffer::new(device,vertecies,indices) });
+                    },
+                    _ => primitives.push(GltfPrimitive { material, buffer: NormalVertexBuffer::new(device,vertecies,indices,false) })
+                }
+            }
+            meshes.push(GltfMesh { name: mesh.name().unwrap_or("").to_string(), primitives, skinned_primitives });
+        }
+
+        let mut nodes = vec![];
+        for node in document.nodes() {
+            let (translation,rotation,scale) = node.transform().decomposed();
+            nodes.push(GltfNode {
+                name: node.name().unwrap_or("").to_string(),
+                translation,
+                rotation,
+                scale,
+                mesh: node.mesh().map(|m| m.index()),
+                skin: node.skin().map(|s| s.index()),
+                parent: None,
+                children: node.children().map(|c| c.index()).collect()
+            });
+        }
+
+        for i in 0..nodes.len() {
+            for c in nodes[i].children.clone() {
+                nodes[c].parent = Some(i);
+            }
+        }
+
+        let roots = match document.default_scene().or(document.scenes().next()) {
+            Some(scene) => scene.nodes().map(|n| n.index()).collect(),
+            None => (0..nodes.len()).filter(|i| nodes[*i].parent.is_none()).collect()
+        };
+
+        let mut skins = vec![];
+        for skin in document.skins() {
+            let joint_nodes = skin.joints().map(|j| j.index()).collect::<Vec<usize>>();
+            let reader = skin.reader(|b| Some(&buffers[b.index()]));
+            let inverse_bind_matrices = match reader.read_inverse_bind_matrices() {
+                Some(m) => m.map(|m| make_mat4(&m)).collect::<Vec<Mat4>>(),
+                None => vec![nalgebra_glm::identity(); joint_nodes.len()]
+            };
+
+            let joints = joint_nodes.iter().enumerate().map(|(i,n)| {
+                let node = &nodes[*n];
+                let rotation = nalgebra_glm::quat(node.rotation[0],node.rotation[1],node.rotation[2],node.rotation[3]);
+                Joint {
+                    name: node.name.clone(),
+                    parent: node.parent.and_then(|p| joint_nodes.iter().position(|j| *j == p)),
+                    inverse_bind_matrix: inverse_bind_matrices.get(i).copied().unwrap_or(nalgebra_glm::identity()),
+                    rest_pose: JointPose::new(vec3(node.translation[0],node.translation[1],node.translation[2]),rotation,vec3(node.scale[0],node.scale[1],node.scale[2]))
+                }
+            }).collect::<Vec<Joint>>();
+
+            //Nodes above the root joint still move the whole skeleton
+            let root_transform = match joints.iter().position(|j| j.parent.is_none()) {
+                Some(root) => match nodes[joint_nodes[root]].parent {
+                    Some(p) => global_matrix(&nodes,p),
+                    None => nalgebra_glm::identity()
+                },
+                None => nalgebra_glm::identity()
+            };
+
+            let mut animations = vec![];
+            for (i,animation) in document.animations().enumerate() {
+                let channels = read_joint_channels(&animation,&joint_nodes,&nodes,&buffers);
+                if !channels.is_empty() {
+                    let name = animation.name().map(|n| n.to_string()).unwrap_or(format!("animation_{}",i));
+                    animations.push(Rc::new(Animation::new_skeletal(name,channels)));
+                }
+            }
+
+            skins.push(GltfSkin {
+                name: skin.name().unwrap_or("").to_string(),
+                skeleton: Rc::new(Skeleton::new(joints,root_transform)),
+                animations
+            });
+        }
+
+        return Ok(GltfModel { meshes, materials, nodes, roots, skins });
+    }
+
+    pub fn find_node(&self,name:&str) -> Option<usize> {
+        return self.nodes.iter().position(|n| n.name == name);
+    }
+
+    pub fn render_mesh<'a>(&'a self, render_pass:&mut RenderPass<'a>, camera:&'a Camera, offset: u32, engine:&'a GameEngine, mesh:usize) {
+        for primitive in self.meshes[mesh].primitives.iter() {
+            let material = &self.materials[&primitive.material];
+            let texture = material.texture_obj.as_ref().unwrap_or(&engine.vertex_renderer.default_tex);
+            engine.vertex_renderer.render(render_pass,camera,texture,offset,&primitive.buffer,material.offset);
+        }
+    }
+
+    //Skinned primitives are not rendered into shadow maps
+    pub fn render_mesh_shadow<'a>(&'a self, render_pass:&mut RenderPass<'a>, camera:&'a Camera, offset: u32, engine:&'a GameEngine, mesh:usize) {
+        for primitive in self.meshes[mesh].primitives.iter() {
+            engine.shadow_renderer.render(render_pass,camera,offset,&primitive.buffer);
+        }
+    }
+
+    pub fn render_skinned_mesh<'a>(&'a self, render_pass:&mut RenderPass<'a>, camera:&'a Camera, offset: u32, engine:&'a GameEngine, mesh:usize, joint_offset: u32) {
+        for primitive in self.meshes[mesh].skinned_primitives.iter() {
+            let material = &self.materials[&primitive.material];
+            let texture = material.texture_obj.as_ref().unwrap_or(&engine.vertex_renderer.default_tex);
+            engine.vertex_renderer.render_skinned(render_pass,camera,texture,offset,&primitive.buffer,material.offset,joint_offset);
+        }
+    }
+}
+
+/*
+Instance of a GltfModel, every node gets its own Transform (relative to its parent) and
+every node with a mesh its own transform offset. Every skin is played by its own SkeletalAnimationInstance.
+ */
+pub struct GltfInstance {
+    pub model: Rc<GltfModel>,
+    pub transform: Transform,
+    pub node_transforms: Vec<Transform>,
+    pub world_matrices: Vec<Mat4>,
+    pub offsets: Vec<u32>,
+    pub skeletons: Vec<SkeletalAnimationInstance>
+}
+
+impl GltfInstance {
+    pub fn new(transform:Transform, model:Rc<GltfModel>) -> Self {
+        let node_transforms = model.nodes.iter().map(|n| n.create_transform()).collect();
+        let world_matrices = vec![nalgebra_glm::identity(); model.nodes.len()];
+        let skeletons = model.skins.iter().map(|s| SkeletalAnimationInstance::new(s.skeleton.clone())).collect();
+        Self { model, transform, node_transforms, world_matrices, offsets: vec![], skeletons }
+    }
+
+    pub fn init(&mut self, engine:&mut GameEngine) {
+        self.offsets = self.model.nodes.iter().map(|n| if n.mesh.is_some() { engine.static_offset_handler.get_offset() as u32 } else { 0 }).collect();
+        for s in self.skeletons.iter_mut() {
+            s.init(engine);
+        }
+    }
+
+    //Plays the animation with the given name on every skin it targets
+    pub fn play_animation(&mut self, name:&str, mode:PlaybackMode) -> bool {
+        let mut found = false;
+        for (i,skin) in self.model.skins.iter().enumerate() {
+            if let Some(animation) = skin.find_animation(name) {
+                self.skeletons[i].play(animation,mode);
+                found = true;
+            }
+        }
+        return found;
+    }
+
+    pub fn destroy(&mut self, engine:&mut GameEngine) {
+        for (i,n) in self.model.nodes.iter().enumerate() {
+            if n.mesh.is_some() {
+                engine.static_offset_handler.remove(self.offsets[i] as u64);
+            }
+        }
+        self.offsets.clear();
+        for s in self.skeletons.iter_mut() {
+            s.destroy(engine);
+        }
+    }
+
+    pub fn update(&mut self, engine:&mut GameEngine, camera:&Camera) {
+        for r in self.model.roots.clone() {
+            self.update_node(r,&self.transform.matrix.clone());
+        }
+
+        for (i,n) in self.model.nodes.iter().enumerate() {
+            if n.mesh.is_some() {
+                //The transform of a skinned mesh node is ignored, the joints place the mesh
+                let matrix = if n.skin.is_some() { &self.transform.matrix } else { &self.world_matrices[i] };
+                camera.write_transform(&engine.device,&engine.queue,self.offsets[i] as u64,matrix);
+            }
+        }
+
+        for s in self.skeletons.iter_mut() {
+            s.update(engine);
+        }
+    }
+
+    fn update_node(&mut self, node:usize, parent:&TMat4<f32>) {
+        let world = parent * self.node_transforms[node].matrix;
+        self.world_matrices[node] = world;
+        for c in self.model.nodes[node].children.clone() {
+            self.update_node(c,&world);
+        }
+    }
+
+    pub fn get_node_transform(&mut self, name:&str) -> Option<&mut Transform> {
+        let index = self.model.find_node(name)?;
+        return Some(&mut self.node_transforms[index]);
+    }
+
+    pub fn render<'a>(&'a self, render_pass:&mut RenderPass<'a>, camera:&'a Camera, engine:&'a GameEngine) {
+        for (i,n) in self.model.nodes.iter().enumerate() {
+            if let Some(mesh) = n.mesh {
+                self.model.render_mesh(render_pass,camera,self.offsets[i],engine,mesh);
+            }
+        }
+
+        let skinned = self.model.nodes.iter().enumerate().filter_map(|(i,n)| Some((i,n.mesh?,n.skin?))).collect::<Vec<(usize,usize,usize)>>();
+        if !skinned.is_empty() {
+            engine.vertex_renderer.begin_skinned(render_pass,camera);
+            for (i,mesh,skin) in skinned {
+                self.model.render_skinned_mesh(render_pass,camera,self.offsets[i],engine,mesh,self.skeletons[skin].joint_offset);
+            }
+            engine.vertex_renderer.begin(render_pass,camera);
+        }
+    }
+
+    pub fn render_shadow<'a>(&'a self, render_pass:&mut RenderPass<'a>, camera:&'a Camera, engine:&'a GameEngine) {
+        for (i,n) in self.model.nodes.iter().enumerate() {
+            if let Some(mesh) = n.mesh {
+                self.model.render_mesh_shadow(render_pass,camera,self.offsets[i],engine,mesh);
+            }
+        }
+    }
+}
+
+fn make_mat4(m:&[[f32;4];4]) -> Mat4 {
+    //gltf matrices are column major
+    return Mat4::from_fn(|r,c| m[c][r]);
+}
+
+fn global_matrix(nodes:&[GltfNode],node:usize) -> Mat4 {
+    let local = nodes[node].create_transform().matrix;
+    return match nodes[node].parent {
+        Some(p) => global_matrix(nodes,p) * local,
+        None => local
+    }
+}
+
+/*
+Merges the translation/rotation/scale samplers of every joint into key frames at the union of their times,
+properties without a sampler keep the rest pose. Cubic spline samplers only use their values (no tangents).
+ */
+fn read_joint_channels(animation:&gltf::Animation,joint_nodes:&[usize],nodes:&[GltfNode],buffers:&[gltf::buffer::Data]) -> Vec<JointChannel> {
+    use gltf::animation::util::ReadOutputs;
+
+    let mut tracks: HashMap<usize,(Vec<(f32,[f32;3])>,Vec<(f32,[f32;4])>,Vec<(f32,[f32;3])>)> = HashMap::new();
+
+    for channel in animation.channels() {
+        let joint = match joint_nodes.iter().position(|j| *j == channel.target().node().index()) {
+            Some(j) => j,
+            None => continue
+        };
+        let reader = channel.reader(|b| Some(&buffers[b.index()]));
+        let times = match reader.read_inputs() {
+            Some(t) => t.collect::<Vec<f32>>(),
+            None => continue
+        };
+        let cubic = channel.sampler().interpolation() == gltf::animation::Interpolation::CubicSpline;
+        let track = tracks.entry(joint).or_insert((vec![],vec![],vec![]));
+
+        match reader.read_outputs() {
+            Some(ReadOutputs::Translations(t)) => track.0 = zip_values(&times,t.collect(),cubic),
+            Some(ReadOutputs::Rotations(r)) => track.1 = zip_values(&times,r.into_f32().collect(),cubic),
+            Some(ReadOutputs::Scales(s)) => track.2 = zip_values(&times,s.collect(),cubic),
+            _ => {}
+        }
+    }
+
+    let mut channels = vec![];
+    for (joint,(translations,rotations,scales)) in tracks {
+        let node = &nodes[joint_nodes[joint]];
+        let mut times = translations.iter().map(|t| t.0).chain(rotations.iter().map(|r| r.0)).chain(scales.iter().map(|s| s.0)).collect::<Vec<f32>>();
+        times.sort_by(|a,b| a.partial_cmp(b).unwrap());
+        times.dedup();
+
+        let key_frames = times.iter().map(|time| {
+            let pos = sample_vec3(&translations,*time).unwrap_or(node.translation);
+            let scale = sample_vec3(&scales,*time).unwrap_or(node.scale);
+            let rotation = sample_quat(&rotations,*time).unwrap_or(nalgebra_glm::quat(node.rotation[0],node.rotation[1],node.rotation[2],node.rotation[3]));
+            KeyFrame::new(*time,vec3(pos[0],pos[1],pos[2]),vec3(scale[0],scale[1],scale[2]),rotation)
+        }).collect();
+
+        channels.push(JointChannel::new(joint,key_frames));
+    }
+    channels.sort_by_key(|c| c.joint);
+    return channels;
+}
+
+//Cubic spline outputs are stored as (in tangent, value, out tangent)
+fn zip_values<T:Copy>(times:&[f32],values:Vec<T>,cubic:bool) -> Vec<(f32,T)> {
+    if cubic {
+        return times.iter().enumerate().filter_map(|(i,t)| values.get(i * 3 + 1).map(|v| (*t,*v))).collect();
+    }
+    return times.iter().copied().zip(values.into_iter()).collect();
+}
+
+fn sample_vec3(values:&[(f32,[f32;3])],time:f32) -> Option<[f32;3]> {
+    let (a,b,t) = find_frames(values,time)?;
+    return Some([a[0] + (b[0] - a[0]) * t,a[1] + (b[1] - a[1]) * t,a[2] + (b[2] - a[2]) * t]);
+}
+
+fn sample_quat(values:&[(f32,[f32;4])],time:f32) -> Option<nalgebra_glm::Qua<f32>> {
+    let (a,b,t) = find_frames(values,time)?;
+    let a = nalgebra_glm::quat(a[0],a[1],a[2],a[3]);
+    let b = nalgebra_glm::quat(b[0],b[1],b[2],b[3]);
+    return Some(nalgebra_glm::quat_slerp(&a,&b,t));
+}
+
+fn find_frames<T:Copy>(values:&[(f32,T)],time:f32) -> Option<(T,T,f32)> {
+    let first = values.first()?;
+    let last = values.last()?;
+    if time <= first.0 {
+        return Some((first.1,first.1,0.0));
+    }
+    if time >= last.0 {
+        return Some((last.1,last.1,0.0));
+    }
+    let i = values.iter().position(|v| v.0 > time)?;
+    let (a,b) = (values[i - 1],values[i]);
+    return Some((a.1,b.1,(time - a.0) / (b.0 - a.0)));
+}
+
+fn map_gltf_error(path:&str,error:gltf::Error) -> RglError {
+    return match error {
+        gltf::Error::Io(e) => RglError::io(path,e),
+        gltf::Error::Image(e) => RglError::parse(path,None,e.to_string()),
+        gltf::Error::UnsupportedScheme => RglError::unsupported(path,"buffer uri scheme"),
+        gltf::Error::UnsupportedImageEncoding => RglError::unsupported(path,"image encoding"),
+        e => RglError::parse(path,None,e.to_string())
+    }
+}
+
+fn image_to_rgba(path:&str,image:&gltf::image::Data) -> RglResult<image::RgbaImage> {
+    use gltf::image::Format;
+
+    let p = &image.pixels;
+    let pixels: Vec<u8> = match image.format {
+        Format::R8G8B8A8 => p.clone(),
+        Format::R8G8B8 => p.chunks(3).flat_map(|c| [c[0],c[1],c[2],255]).collect(),
+        Format::R8G8 => p.chunks(2).flat_map(|c| [c[0],c[1],0,255]).collect(),
+        Format::R8 => p.iter().flat_map(|c| [*c,*c,*c,255]).collect(),
+        //16 bit channels are little endian, only the high byte is kept
+        Format::R16G16B16A16 => p.chunks(8).flat_map(|c| [c[1],c[3],c[5],c[7]]).collect(),
+        Format::R16G16B16 => p.chunks(6).flat_map(|c| [c[1],c[3],c[5],255]).collect(),
+        Format::R16G16 => p.chunks(4).flat_map(|c| [c[1],c[3],0,255]).collect(),
+        Format::R16 => p.chunks(2).flat_map(|c| [c[1],c[1],c[1],255]).collect(),
+        f => return Err(RglError::unsupported(path,&format!("image format {:?}",f)))
+    };
+
+    return image::RgbaImage::from_raw(image.width,image.height,pixels).ok_or_else(|| RglError::parse(path,None,"Image data is too short".to_string()));
+}
+
+//Every attribute needs a value per vertex, accessors that are shorter than POSITION are rejected
+fn check_attribute<T>(values:&[T],vertex_count:usize,name:&str,mesh:usize,path:&str) -> RglResult<()> {
+    if values.len() < vertex_count {
+        return Err(RglError::parse(path,None,format!("{} of mesh {} has {} elements, but there are {} vertices",name,mesh,values.len(),vertex_count)));
+    }
+    return Ok(());
+}
+
+fn generate_normals(positions:&[[f32;3]],indices:&[u32]) -> Vec<[f32;3]> {
+    let mut normals = vec![vec3(0.0,0.0,0.0); positions.len()];
+    for t in indices.chunks(3) {
+        if t.len() < 3 {
+            break;
+        }
+        let a = vec3(positions[t[0] as usize][0],positions[t[0] as usize][1],positions[t[0] as usize][2]);
+        let b = vec3(positions[t[1] as usize][0],positions[t[1] as usize][1],positions[t[1] as usize][2]);
+        let c = vec3(positions[t[2] as usize][0],positions[t[2] as usize][1],positions[t[2] as usize][2]);
+        let n = cross(&(b - a),&(c - a));
+        for i in t {
+            normals[*i as usize] += n;
+        }
+    }
+    return normals.iter().map(|n| {
+        if n.magnitude() == 0.0 { [0.0,1.0,0.0] } else { let n = normalize(n); [n.x,n.y,n.z] }
+    }).collect();
+}
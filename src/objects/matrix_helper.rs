@@ -1,5 +1,5 @@
 use nalgebra_glm::{TMat4, TVec3, TVec4, TVec2};
-use wgpu::{Device, Buffer, BufferDescriptor, BufferAddress, BufferUsages};
+use wgpu::{Device, Buffer, BufferDescriptor, BufferAddress, BufferUsages, CommandEncoderDescriptor, Queue};
 use wgpu::util::{BufferInitDescriptor, DeviceExt};
 
 pub fn get_bytes(matrix:&TMat4<f32>) -> Vec<u8> {
@@ -93,8 +93,24 @@ pub fn add_dynamic_buffer(device:&Device,size:BufferAddress) -> Buffer{
     let buffer = device.create_buffer(&BufferDescriptor {
         label: None,
         size,
-        usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+        usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST | BufferUsages::COPY_SRC,
         mapped_at_creation: false
     });
     return buffer;
-}
\ No newline at end of file
+}
+
+//Creates a bigger buffer with the same usage and copies the content of the old one into it
+pub fn grow_buffer(device:&Device,queue:&Queue,old:&Buffer,size:BufferAddress) -> Buffer {
+    let buffer = device.create_buffer(&BufferDescriptor {
+        label: None,
+        size,
+        usage: old.usage() | BufferUsages::COPY_DST,
+        mapped_at_creation: false
+    });
+
+    let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor { label: Some("Grow Buffer Encoder") });
+    encoder.copy_buffer_to_buffer(old,0,&buffer,0,old.size().min(size));
+    queue.submit(std::iter::once(encoder.finish()));
+
+    return buffer;
+}
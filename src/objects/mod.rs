@@ -17,4 +17,7 @@ pub mod model_instance;
 pub mod vertex_buffer_obj;
 pub mod skybox;
 pub mod simple_text;
-pub mod transform_data_obj;
\ No newline at end of file
+pub mod transform_data_obj;
+pub mod render_texture;
+pub mod obj_parser;
+pub mod gltf_model;
\ No newline at end of file
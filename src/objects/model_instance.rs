@@ -21,10 +21,14 @@ impl ModelInstance {
         self.offset = engine.static_offset_handler.get_offset() as u32;
     }
     pub fn update(&mut self, engine:&mut GameEngine, camera:&Camera) {
-        engine.queue.write_buffer(&camera.buffers[2],self.offset as u64,&*crate::objects::matrix_helper::get_bytes(&self.transform.matrix));
+        camera.write_transform(&engine.device,&engine.queue,self.offset as u64,&self.transform.matrix);
     }
 
     pub fn render<'a>(&'a self, render_pass:&mut RenderPass<'a>, camera:&'a Camera, engine:&'a GameEngine) {
         self.model.render(render_pass,camera,self.offset,engine);
     }
+
+    pub fn render_shadow<'a>(&'a self, render_pass:&mut RenderPass<'a>, camera:&'a Camera, engine:&'a GameEngine) {
+        self.model.render_shadow(render_pass,camera,self.offset,engine);
+    }
 }
\ No newline at end of file
@@ -2,6 +2,7 @@ use std::collections::HashMap;
 use std::rc::Rc;
 use std::str::FromStr;
 use std::u32;
+use log::warn;
 use nalgebra_glm::{TVec3, vec3, vec4};
 use wgpu::{Buffer, Device, Queue, RenderPass};
 use crate::engine::game_engine::GameEngine;
@@ -12,111 +13,76 @@ use crate::objects::texture_object::TextureObject;
 use crate::objects::vertex::{NormalVertex, Vertex};
 use crate::objects::vertex_buffer::{NormalVertexBuffer, VertexBuffer};
 use crate::objects::vertex_buffer_builder::VertexBufferBuilder;
+use crate::error::rgl_error::RglResult;
+use crate::objects::obj_parser::{ObjData, parse_mtl};
+use crate::vfs::vfs::{join_path, Vfs};
+use crate::engine::profiler::write_uniform;
 
 pub struct ObjModel {
     pub meshes: HashMap<String,Vec<NormalVertexBuffer>>,
-    pub materials: HashMap<String,Material>
+    pub materials: HashMap<String,Material>,
+    pub sub_meshes: Vec<ObjSubMesh>,
+    pub material_libs: Vec<String>
+}
+
+//Named object/group of the obj file, points to meshes[material][index]
+pub struct ObjSubMesh {
+    pub object: String,
+    pub group: String,
+    pub material: String,
+    pub index: usize
 }
 
 impl ObjModel {
-    pub fn new(parent:String,file:String,device:&Device) -> ObjModel {
-        let data = std::fs::read(format!("{}//{}", parent, file)).unwrap();
-        let txt = String::from_utf8(data).unwrap();
+    pub fn new(parent:String,file:String,device:&Device,vfs:&Vfs) -> ObjModel {
+        return ObjModel::try_new(parent,file,device,vfs).unwrap_or_else(|e| panic!("{}",e));
+    }
 
-        let mut lines = txt.split("\n").collect::<Vec<&str>>();
+    pub fn try_new(parent:String,file:String,device:&Device,vfs:&Vfs) -> RglResult<ObjModel> {
+        let path = join_path(&parent,&file);
+        let txt = vfs.read_to_string(&path)?;
 
-        let mut positions = vec![];
-        let mut uvs = vec![];
-        let mut normals = vec![];
+        let obj = ObjData::parse(&txt,&path)?;
 
-        let mut vertecies = vec![];
-        let mut i_map = HashMap::new();
-        let mut indecies = vec![];
-        let mut current_index = 0;
         let mut materials = HashMap::new();
+        for lib in obj.material_libs.iter() {
+            materials.extend(Material::try_map_from_mat_file(parent.clone(),lib.clone(),device,vfs)?);
+        }
 
-        let mut current_mat = String::new();
-        let mut meshes = HashMap::new();
-
-        for mut l in lines {
-            l = l.trim();
-            if l.starts_with("mtllib ") {
-                let v_data = l.split(" ").collect::<Vec<&str>>();
-                let name = v_data[1];
-
-                materials = Material::map_from_mat_file(parent.clone(),name.to_string(),device);
-            }else if l.starts_with("v ") {
-                let v_data = l.split(" ").collect::<Vec<&str>>();
-                positions.push((ObjModel::read_f32(v_data[1]),ObjModel::read_f32(v_data[2]),ObjModel::read_f32(v_data[3])));
-            } else if l.starts_with("vt ") {
-                let v_data = l.split(" ").collect::<Vec<&str>>();
-                uvs.push((ObjModel::read_f32(v_data[1]),ObjModel::read_f32(v_data[2])));
-            }else if l.starts_with("vn ") {
-                let v_data = l.split(" ").collect::<Vec<&str>>();
-                normals.push((ObjModel::read_f32(v_data[1]),ObjModel::read_f32(v_data[2]),ObjModel::read_f32(v_data[3])));
-            }else if l.starts_with("usemtl ") {
-                let v_data = l.split(" ").collect::<Vec<&str>>();
-                let name = v_data[1];
-
-                if !current_mat.is_empty() {
-                    let mesh = NormalVertexBuffer::new(device,vertecies,indecies,false);
-                    vertecies = vec![];
-                    indecies = vec![];
-                    i_map.clear();
-                    current_index = 0;
-
-                    if !meshes.contains_key(&current_mat) {
-                        meshes.insert(current_mat.clone(), vec![mesh]);
-                    } else {
-                        meshes.get_mut(&current_mat).unwrap().push(mesh);
-                    }
-                }
+        return Ok(ObjModel::from_data(obj,materials,device));
+    }
 
-                current_mat = name.to_string();
-            }else if l.starts_with("f ") {
-                let v_data = l.split(" ").collect::<Vec<&str>>();
-                for i in 1..4 {
-                    let f_data = v_data[i].split("/").collect::<Vec<&str>>();
-
-                    if !i_map.contains_key(v_data[i]) {
-                        let index_pos = ObjModel::read_i32(f_data[0]) - 1;
-
-                        let mut index_uv = -1;
-                        let mut u = 0.0;
-                        let mut v = 0.0;
-                        if !f_data[1].is_empty() {
-                            index_uv = ObjModel::read_i32(f_data[1]) - 1;
-                            u = uvs[index_uv as usize].0;
-                            v = uvs[index_uv as usize].1;
-                        }
-                        let index_n = ObjModel::read_i32(f_data[2]) - 1;
-
-                        vertecies.push(NormalVertex::new(positions[index_pos as usize].0,positions[index_pos as usize].1,positions[index_pos as usize].2,
-                                                         u,v,normals[index_n as usize].0,normals[index_n as usize].1,normals[index_n as usize].2));
-                        i_map.insert(v_data[i],current_index);
-
-                        indecies.push(current_index);
-                        current_index+=1;
-                    } else {
-                        indecies.push(i_map[&v_data[i]]);
-                    }
-                }
-            }
+    pub fn from_data(obj:ObjData,mut materials:HashMap<String,Material>,device:&Device) -> ObjModel {
+        let mut meshes: HashMap<String,Vec<NormalVertexBuffer>> = HashMap::new();
+        let mut sub_meshes = vec![];
+        let material_libs = obj.material_libs.clone();
+
+        for mesh in obj.meshes {
+            let buffers = meshes.entry(mesh.material.clone()).or_insert(vec![]);
+            buffers.push(NormalVertexBuffer::new_from_data(device,mesh.data));
+            sub_meshes.push(ObjSubMesh { object: mesh.object, group: mesh.group, material: mesh.material, index: buffers.len() - 1 });
         }
 
-        if !vertecies.is_empty() {
-            if !meshes.contains_key(&current_mat) {
-                meshes.insert(current_mat.clone(), vec![NormalVertexBuffer::new(device,vertecies,indecies,false)]);
-            } else {
-                meshes.get_mut(&current_mat).unwrap().push(NormalVertexBuffer::new(device,vertecies,indecies,false));
+        for id in meshes.keys() {
+            if !materials.contains_key(id) {
+                if !id.is_empty() {
+                    warn!("Material {} is used but never defined, using the default material",id);
+                }
+                let mut m = Material::default();
+                m.name = id.clone();
+                materials.insert(id.clone(),m);
             }
         }
 
-        if materials.is_empty() {
-            materials.insert("".to_string(),Material::default());
-        }
+        return ObjModel { meshes, materials, sub_meshes, material_libs }
+    }
 
-        return ObjModel { meshes, materials }
+    pub fn get_gpu_size(&self) -> u64 {
+        return self.meshes.values().flatten().map(|b| b.buffer.size() + b.index_buffer.size()).sum();
+    }
+
+    pub fn get_sub_meshes(&self,object:&str) -> Vec<&ObjSubMesh> {
+        return self.sub_meshes.iter().filter(|m| m.object == object).collect();
     }
 
     pub fn read_f32(str:&str) -> f32 {
@@ -139,6 +105,25 @@ impl ObjModel {
             }
         }
     }
+
+    //Call ShadowRenderer::begin_map first
+    pub fn render_shadow<'a>(&'a self, render_pass:&mut RenderPass<'a>, camera:&'a Camera, offset: u32, engine:&'a GameEngine) {
+        for m in self.meshes.values() {
+            for model in m {
+                engine.shadow_renderer.render(render_pass,camera,offset,model);
+            }
+        }
+    }
+
+    //Only renders the sub meshes of the given object (o) or group (g)
+    pub fn render_object<'a>(&'a self, render_pass:&mut RenderPass<'a>, camera:&'a Camera, offset: u32, engine:&'a GameEngine, name:&str) {
+        for sub in self.sub_meshes.iter().filter(|m| m.object == name || m.group == name) {
+            let material = &self.materials[&sub.material];
+            let model = &self.meshes[&sub.material][sub.index];
+            let texture = material.texture_obj.as_ref().unwrap_or(&engine.vertex_renderer.default_tex);
+            engine.vertex_renderer.render(render_pass,camera,texture,offset,model,material.offset);
+        }
+    }
 }
 
 pub struct Material {
@@ -152,6 +137,15 @@ pub struct Material {
     pub illumination_model: i32,
     pub texture_name: Option<String>,
     pub texture_obj: Option<Rc<TextureObject>>,
+    pub specular_texture_name: Option<String>,
+    pub specular_texture_obj: Option<Rc<TextureObject>>,
+    pub bump_texture_name: Option<String>,
+    pub bump_texture_obj: Option<Rc<TextureObject>>,
+    pub bump_multiplier: f32,
+    pub alpha_texture_name: Option<String>,
+    pub alpha_texture_obj: Option<Rc<TextureObject>>,
+    pub ambient_texture_name: Option<String>,
+    pub ambient_texture_obj: Option<Rc<TextureObject>>,
     pub data: Vec<u8>,
     pub offset: u32
 }
@@ -165,10 +159,19 @@ impl Material {
             specular_color: vec3(1.0,1.0,1.0),
             specular_highlights: 0.0,
             optical_density: 0.0,
-            dissolve: 0.0,
+            dissolve: 1.0,
             illumination_model: 0,
             texture_name: None,
             texture_obj: None,
+            specular_texture_name: None,
+            specular_texture_obj: None,
+            bump_texture_name: None,
+            bump_texture_obj: None,
+            bump_multiplier: 1.0,
+            alpha_texture_name: None,
+            alpha_texture_obj: None,
+            ambient_texture_name: None,
+            ambient_texture_obj: None,
             data: vec![],
             offset: 0
         };
@@ -178,85 +181,49 @@ impl Material {
         return m;
     }
 
-    pub fn map_from_mat_file(parent:String,file:String,device:&Device) -> HashMap<String,Material> {
-        let data = std::fs::read(format!("{}//{}", parent, file)).unwrap();
-        let txt = String::from_utf8(data).unwrap();
-
-        let mut lines = txt.split("\n").collect::<Vec<&str>>();
-
-        let mut current_mat = Material::default();
-        let mut mats = HashMap::new();
-
-        for mut l in lines {
-            l = l.trim();
-
-            if l.starts_with("newmtl ") {
-                if !current_mat.name.is_empty() {
-                    current_mat.data = current_mat.get_data();
-                    mats.insert(current_mat.name.clone(),current_mat);
-                    current_mat = Material::default();
-                }
-
-                let v_data = l.split(" ").collect::<Vec<&str>>();
-                current_mat.name = v_data[1].to_string();
-            }else if l.starts_with("Ka ") {
-                let v_data = l.split(" ").collect::<Vec<&str>>();
-
-                let vec = vec3(ObjModel::read_f32(v_data[1]),ObjModel::read_f32(v_data[2]),ObjModel::read_f32(v_data[3]));
-
-                current_mat.ambient_color = vec;
-            }else if l.starts_with("Kd ") {
-                let v_data = l.split(" ").collect::<Vec<&str>>();
-
-                let vec = vec3(ObjModel::read_f32(v_data[1]),ObjModel::read_f32(v_data[2]),ObjModel::read_f32(v_data[3]));
-
-                current_mat.diffuse_color = vec;
-            }else if l.starts_with("Ks ") {
-                let v_data = l.split(" ").collect::<Vec<&str>>();
-
-                let vec = vec3(ObjModel::read_f32(v_data[1]),ObjModel::read_f32(v_data[2]),ObjModel::read_f32(v_data[3]));
-
-                current_mat.specular_color = vec;
-            }else if l.starts_with("Ns ") {
-                let v_data = l.split(" ").collect::<Vec<&str>>();
-
-                current_mat.specular_highlights = ObjModel::read_f32(v_data[1]);
-            }else if l.starts_with("Ni ") {
-                let v_data = l.split(" ").collect::<Vec<&str>>();
+    pub fn map_from_mat_file(parent:String,file:String,device:&Device,vfs:&Vfs) -> HashMap<String,Material> {
+        return Material::try_map_from_mat_file(parent,file,device,vfs).unwrap_or_else(|e| panic!("{}",e));
+    }
 
-                current_mat.optical_density = ObjModel::read_f32(v_data[1]);
-            }else if l.starts_with("d ") {
-                let v_data = l.split(" ").collect::<Vec<&str>>();
+    pub fn try_map_from_mat_file(parent:String,file:String,device:&Device,vfs:&Vfs) -> RglResult<HashMap<String,Material>> {
+        let path = join_path(&parent,&file);
+        let txt = vfs.read_to_string(&path)?;
 
-                current_mat.dissolve = ObjModel::read_f32(v_data[1]);
-            }else if l.starts_with("illum ") {
-                let v_data = l.split(" ").collect::<Vec<&str>>();
+        return parse_mtl(&txt,&path);
+    }
 
-                current_mat.illumination_model = ObjModel::read_i32(v_data[1]);
-            }else if l.starts_with("map_Kd ") {
-                let v_data = l.split(" ").collect::<Vec<&str>>();
+    pub fn register(&mut self, queue:&Queue, buffer:&Buffer, rsc:&ResourceLoader) {
+        self.write(queue,buffer);
+        self.load_textures(rsc);
+    }
 
-                current_mat.texture_name = Some(v_data[1].to_string());
-            }
-        }
+    pub fn write(&self, queue:&Queue, buffer:&Buffer) {
+        write_uniform(queue,buffer, self.offset as u64, &self.data);
+    }
 
-        if !current_mat.name.is_empty() {
-            current_mat.data = current_mat.get_data();
-            mats.insert(current_mat.name.clone(),current_mat);
-        }
+    pub fn load_textures(&mut self, rsc:&ResourceLoader) {
+        self.texture_obj = self.find_texture(&self.texture_name,rsc);
+        self.specular_texture_obj = self.find_texture(&self.specular_texture_name,rsc);
+        self.bump_texture_obj = self.find_texture(&self.bump_texture_name,rsc);
+        self.alpha_texture_obj = self.find_texture(&self.alpha_texture_name,rsc);
+        self.ambient_texture_obj = self.find_texture(&self.ambient_texture_name,rsc);
+    }
 
-        return mats;
+    pub fn uses_texture(&self, name:&str) -> bool {
+        return [&self.texture_name,&self.specular_texture_name,&self.bump_texture_name,&self.alpha_texture_name,&self.ambient_texture_name].iter().any(|t| t.as_deref() == Some(name));
     }
 
-    pub fn register(&mut self, queue:&Queue, buffer:&Buffer, rsc:&ResourceLoader) {
-        queue.write_buffer(buffer, self.offset as u64, &self.data);
-        if let Some(name) = &self.texture_name{
-            let tex = rsc.textures[name].clone();
-            self.texture_obj = Some(tex);
+    fn find_texture(&self, name:&Option<String>, rsc:&ResourceLoader) -> Option<Rc<TextureObject>> {
+        if let Some(name) = name {
+            if let Some(tex) = rsc.textures.try_get_by_name(name) {
+                return Some(tex);
+            }
+            warn!("Texture {} of material {} is not loaded, using the default texture",name,self.name);
         }
+        return None;
     }
 
-    fn get_data(&self) -> Vec<u8> {
+    pub(crate) fn get_data(&self) -> Vec<u8> {
         let mut data2 = vec![];
 
         data2.extend(crate::objects::matrix_helper::get_bytes_from_vec4(&vec4(self.ambient_color.x,self.ambient_color.y,self.ambient_color.z,0.0)));
@@ -265,7 +232,7 @@ impl Material {
         data2.extend(crate::objects::matrix_helper::get_bytes_from_f32(self.specular_highlights));
         data2.extend(crate::objects::matrix_helper::get_bytes_from_f32(self.optical_density));
         data2.extend(crate::objects::matrix_helper::get_bytes_from_f32(self.dissolve));
-        data2.extend(crate::objects::matrix_helper::get_bytes_from_f32(0.0));
+        data2.extend(crate::objects::matrix_helper::get_bytes_from_f32(self.illumination_model as f32));
         return data2;
     }
 }
\ No newline at end of file
@@ -0,0 +1,434 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+use nalgebra_glm::{cross, normalize, TVec3, vec3};
+use crate::error::rgl_error::{RglError, RglResult};
+use crate::objects::obj_model::Material;
+use crate::objects::vertex::NormalVertex;
+use crate::objects::vertex_buffer_data::NormalVertexBufferData;
+
+/*
+Parses obj and mtl files without touching the gpu, ObjModel uploads the result.
+ */
+pub struct ObjData {
+    pub material_libs: Vec<String>,
+    pub meshes: Vec<ObjMeshData>
+}
+
+pub struct ObjMeshData {
+    pub object: String,
+    pub group: String,
+    pub material: String,
+    pub data: NormalVertexBufferData
+}
+
+#[derive(Clone, Copy)]
+struct FaceVertex {
+    pos: usize,
+    uv: Option<usize>,
+    normal: Option<usize>
+}
+
+struct Face {
+    vertices: [FaceVertex;3],
+    smoothing_group: u32
+}
+
+struct FaceGroup {
+    object: String,
+    group: String,
+    material: String,
+    faces: Vec<Face>
+}
+
+#[derive(Hash, Eq, PartialEq)]
+enum NormalKey {
+    Given(usize),
+    Smooth(u32),
+    Flat(usize)
+}
+
+impl ObjData {
+    pub fn parse(source:&str,file:&str) -> RglResult<ObjData> {
+        let mut positions: Vec<TVec3<f32>> = vec![];
+        let mut uvs: Vec<(f32,f32)> = vec![];
+        let mut normals: Vec<TVec3<f32>> = vec![];
+
+        let mut material_libs = vec![];
+        let mut groups = vec![FaceGroup { object: String::new(), group: String::new(), material: String::new(), faces: vec![] }];
+        let mut smoothing_group = 0;
+
+        for (line,l) in source.lines().enumerate() {
+            let line = line + 1;
+            let l = match l.find('#') {
+                Some(i) => &l[..i],
+                None => l
+            };
+            let tokens = l.split_whitespace().collect::<Vec<&str>>();
+            if tokens.is_empty() {
+                continue;
+            }
+
+            match tokens[0] {
+                "mtllib" => {
+                    for name in &tokens[1..] {
+                        material_libs.push(name.to_string());
+                    }
+                },
+                "v" => positions.push(read_vec3(&tokens,file,line)?),
+                "vt" => {
+                    let u = read_f32(&tokens,1,file,line)?;
+                    let v = if tokens.len() > 2 { read_f32(&tokens,2,file,line)? } else { 0.0 };
+                    uvs.push((u,v));
+                },
+                "vn" => normals.push(read_vec3(&tokens,file,line)?),
+                "o" | "g" | "usemtl" => {
+                    let name = tokens[1..].join(" ");
+                    let last = groups.last().unwrap();
+                    let mut next = FaceGroup { object: last.object.clone(), group: last.group.clone(), material: last.material.clone(), faces: vec![] };
+                    match tokens[0] {
+                        "o" => {
+                            next.object = name;
+                            next.group = String::new();
+                        },
+                        "g" => next.group = name,
+                        _ => next.material = name
+                    }
+                    if groups.last().unwrap().faces.is_empty() {
+                        groups.pop();
+                    }
+                    groups.push(next);
+                },
+                "s" => {
+                    let value = tokens.get(1).copied().unwrap_or("off");
+                    smoothing_group = if value == "off" { 0 } else {
+                        u32::from_str(value).map_err(|e| RglError::parse(file,Some(line),format!("Invalid smoothing group {}: {}",value,e)))?
+                    };
+                },
+                "f" => {
+                    if tokens.len() < 4 {
+                        return Err(RglError::parse(file,Some(line),format!("Face needs at least 3 vertices, found {}",tokens.len() - 1)));
+                    }
+
+                    let mut polygon = vec![];
+                    for token in &tokens[1..] {
+                        let f_data = token.split("/").collect::<Vec<&str>>();
+                        let pos = resolve_index(f_data[0],positions.len(),file,line)?;
+                        let uv = match f_data.get(1) {
+                            Some(s) if !s.is_empty() => Some(resolve_index(s,uvs.len(),file,line)?),
+                            _ => None
+                        };
+                        let normal = match f_data.get(2) {
+                            Some(s) if !s.is_empty() => Some(resolve_index(s,normals.len(),file,line)?),
+                            _ => None
+                        };
+                        polygon.push(FaceVertex { pos, uv, normal });
+                    }
+
+                    //Fan triangulation, works for all convex polygons
+                    let faces = &mut groups.last_mut().unwrap().faces;
+                    for i in 1..polygon.len() - 1 {
+                        faces.push(Face { vertices: [polygon[0],polygon[i],polygon[i + 1]], smoothing_group });
+                    }
+                },
+                _ => {}
+            }
+        }
+
+        //Accumulate the (area weighted) face normals for every position in a smoothing group
+        let mut smooth_normals: HashMap<(usize,u32),TVec3<f32>> = HashMap::new();
+        for group in groups.iter() {
+            for face in group.faces.iter() {
+                if face.smoothing_group != 0 && face.vertices.iter().any(|v| v.normal.is_none()) {
+                    let n = face_normal(face,&positions,false);
+                    for v in face.vertices.iter() {
+                        *smooth_normals.entry((v.pos,face.smoothing_group)).or_insert(vec3(0.0,0.0,0.0)) += n;
+                    }
+                }
+            }
+        }
+
+        let mut meshes = vec![];
+        let mut face_id = 0;
+
+        for group in groups {
+            if group.faces.is_empty() {
+                continue;
+            }
+
+            let mut vertecies = vec![];
+            let mut indecies = vec![];
+            let mut i_map: HashMap<(usize,Option<usize>,NormalKey),u32> = HashMap::new();
+
+            for face in group.faces.iter() {
+                face_id += 1;
+                for v in face.vertices.iter() {
+                    let key = match v.normal {
+                        Some(n) => NormalKey::Given(n),
+                        None if face.smoothing_group != 0 => NormalKey::Smooth(face.smoothing_group),
+                        None => NormalKey::Flat(face_id)
+                    };
+
+                    let normal = match key {
+                        NormalKey::Given(n) => normals[n],
+                        NormalKey::Smooth(s) => safe_normalize(&smooth_normals[&(v.pos,s)]),
+                        NormalKey::Flat(_) => face_normal(face,&positions,true)
+                    };
+
+                    let index = *i_map.entry((v.pos,v.uv,key)).or_insert_with(|| {
+                        let p = positions[v.pos];
+                        let (u,tex_v) = v.uv.map_or((0.0,0.0),|i| uvs[i]);
+                        vertecies.push(NormalVertex::new(p.x,p.y,p.z,u,tex_v,normal.x,normal.y,normal.z));
+                        (vertecies.len() - 1) as u32
+                    });
+                    indecies.push(index);
+                }
+            }
+
+            meshes.push(ObjMeshData {
+                object: group.object,
+                group: group.group,
+                material: group.material,
+                data: NormalVertexBufferData::new(vertecies,indecies)
+            });
+        }
+
+        return Ok(ObjData { material_libs, meshes });
+    }
+}
+
+pub fn parse_mtl(source:&str,file:&str) -> RglResult<HashMap<String,Material>> {
+    let mut current_mat = Material::default();
+    let mut mats = HashMap::new();
+
+    for (line,l) in source.lines().enumerate() {
+        let line = line + 1;
+        let l = match l.find('#') {
+            Some(i) => &l[..i],
+            None => l
+        };
+        let tokens = l.split_whitespace().collect::<Vec<&str>>();
+        if tokens.is_empty() {
+            continue;
+        }
+
+        match tokens[0] {
+            "newmtl" => {
+                if !current_mat.name.is_empty() {
+                    current_mat.data = current_mat.get_data();
+                    mats.insert(current_mat.name.clone(),current_mat);
+                    current_mat = Material::default();
+                }
+                current_mat.name = tokens[1..].join(" ");
+            },
+            "Ka" => current_mat.ambient_color = read_vec3(&tokens,file,line)?,
+            "Kd" => current_mat.diffuse_color = read_vec3(&tokens,file,line)?,
+            "Ks" => current_mat.specular_color = read_vec3(&tokens,file,line)?,
+            "Ns" => current_mat.specular_highlights = read_f32(&tokens,1,file,line)?,
+            "Ni" => current_mat.optical_density = read_f32(&tokens,1,file,line)?,
+            "d" => current_mat.dissolve = read_f32(&tokens,1,file,line)?,
+            "Tr" => current_mat.dissolve = 1.0 - read_f32(&tokens,1,file,line)?,
+            "illum" => {
+                let s = read_token(&tokens,1,file,line)?;
+                current_mat.illumination_model = i32::from_str(s).map_err(|e| RglError::parse(file,Some(line),format!("Invalid number {}: {}",s,e)))?;
+            },
+            "map_Kd" => current_mat.texture_name = Some(read_map_name(&tokens,file,line)?),
+            "map_Ks" => current_mat.specular_texture_name = Some(read_map_name(&tokens,file,line)?),
+            "map_Ka" => current_mat.ambient_texture_name = Some(read_map_name(&tokens,file,line)?),
+            "map_d" => current_mat.alpha_texture_name = Some(read_map_name(&tokens,file,line)?),
+            "map_Bump" | "map_bump" | "bump" | "norm" => {
+                current_mat.bump_texture_name = Some(read_map_name(&tokens,file,line)?);
+                if let Some(i) = tokens.iter().position(|t| *t == "-bm") {
+                    current_mat.bump_multiplier = read_f32(&tokens,i + 1,file,line)?;
+                }
+            },
+            _ => {}
+        }
+    }
+
+    if !current_mat.name.is_empty() {
+        current_mat.data = current_mat.get_data();
+        mats.insert(current_mat.name.clone(),current_mat);
+    }
+
+    return Ok(mats);
+}
+
+//Options (-bm 1.0, -clamp on, ...) come before the file name, so the last token is the name
+fn read_map_name(tokens:&[&str],file:&str,line:usize) -> RglResult<String> {
+    if tokens.len() < 2 {
+        return Err(RglError::parse(file,Some(line),"Missing texture name".to_string()));
+    }
+    return Ok(tokens[tokens.len() - 1].to_string());
+}
+
+fn read_token<'a>(tokens:&[&'a str],index:usize,file:&str,line:usize) -> RglResult<&'a str> {
+    return tokens.get(index).copied().ok_or_else(|| RglError::parse(file,Some(line),format!("Missing value at position {}",index)));
+}
+
+fn read_f32(tokens:&[&str],index:usize,file:&str,line:usize) -> RglResult<f32> {
+    let s = read_token(tokens,index,file,line)?;
+    return f32::from_str(s).map_err(|e| RglError::parse(file,Some(line),format!("Invalid number {}: {}",s,e)));
+}
+
+fn read_vec3(tokens:&[&str],file:&str,line:usize) -> RglResult<TVec3<f32>> {
+    return Ok(vec3(read_f32(tokens,1,file,line)?,read_f32(tokens,2,file,line)?,read_f32(tokens,3,file,line)?));
+}
+
+//Obj indices start at 1, negative indices are relative to the end of the current list
+fn resolve_index(s:&str,len:usize,file:&str,line:usize) -> RglResult<usize> {
+    let index = i64::from_str(s).map_err(|e| RglError::parse(file,Some(line),format!("Invalid index {}: {}",s,e)))?;
+    let resolved = if index < 0 { len as i64 + index } else { index - 1 };
+    if index == 0 || resolved < 0 || resolved >= len as i64 {
+        return Err(RglError::parse(file,Some(line),format!("Index {} is out of range (found {} elements)",index,len)));
+    }
+    return Ok(resolved as usize);
+}
+
+fn face_normal(face:&Face,positions:&[TVec3<f32>],normalized:bool) -> TVec3<f32> {
+    let a = positions[face.vertices[0].pos];
+    let b = positions[face.vertices[1].pos];
+    let c = positions[face.vertices[2].pos];
+    let n = cross(&(b - a),&(c - a));
+    return if normalized { safe_normalize(&n) } else { n };
+}
+
+fn safe_normalize(v:&TVec3<f32>) -> TVec3<f32> {
+    if v.magnitude() == 0.0 {
+        return vec3(0.0,1.0,0.0);
+    }
+    return normalize(v);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TRIANGLE: &str = "v 0 0 0\nv 1 0 0\nv 0 1 0\n";
+
+    fn parse(source:&str) -> ObjData {
+        return ObjData::parse(source,"test.obj").unwrap();
+    }
+
+    fn normal(v:&NormalVertex) -> (f32,f32,f32) {
+        return (v.n_x,v.n_y,v.n_z);
+    }
+
+    #[test]
+    fn triangulates_ngons_as_fan() {
+        //In a smoothing group, so the fan triangles share their vertices
+        let data = parse("v 0 0 0\nv 1 0 0\nv 2 1 0\nv 1 2 0\nv 0 1 0\ns 1\nf 1 2 3 4 5\n");
+        assert_eq!(data.meshes.len(),1);
+        let mesh = &data.meshes[0].data;
+        assert_eq!(mesh.vertecies.len(),5);
+        assert_eq!(mesh.indecies,vec![0,1,2,0,2,3,0,3,4]);
+    }
+
+    #[test]
+    fn rejects_faces_with_less_than_three_vertices() {
+        assert!(ObjData::parse("v 0 0 0\nv 1 0 0\nf 1 2\n","test.obj").is_err());
+    }
+
+    #[test]
+    fn resolves_negative_indices() {
+        let absolute = parse(&format!("{}f 1 2 3\n",TRIANGLE));
+        let relative = parse(&format!("{}f -3 -2 -1\n",TRIANGLE));
+        let a = &absolute.meshes[0].data.vertecies;
+        let r = &relative.meshes[0].data.vertecies;
+        assert_eq!(a.len(),r.len());
+        for (a,r) in a.iter().zip(r.iter()) {
+            assert_eq!((a.x,a.y,a.z),(r.x,r.y,r.z));
+        }
+
+        //Relative to the vertices read so far, not to the whole file
+        let later = parse(&format!("{}f -1 -2 -3\nv 5 5 5\n",TRIANGLE));
+        let v = &later.meshes[0].data.vertecies;
+        assert_eq!((v[0].x,v[0].y,v[0].z),(0.0,1.0,0.0));
+        assert_eq!((v[2].x,v[2].y,v[2].z),(0.0,0.0,0.0));
+    }
+
+    #[test]
+    fn resolves_uv_and_normal_indices() {
+        let data = parse(&format!("{}vt 0.5 0.25\nvn 0 0 -1\nf 1/1/1 2/1/1 -1/-1/-1\n",TRIANGLE));
+        let v = &data.meshes[0].data.vertecies;
+        assert_eq!(v.len(),3);
+        assert!(v.iter().all(|v| (v.u,v.v) == (0.5,0.25) && normal(v) == (0.0,0.0,-1.0)));
+    }
+
+    #[test]
+    fn generates_flat_normals_without_vn() {
+        //Two faces sharing an edge, folded by 90 degrees
+        let data = parse("v 0 0 0\nv 1 0 0\nv 0 1 0\nv 0 0 -1\nf 1 2 3\nf 1 2 4\n");
+        let v = &data.meshes[0].data.vertecies;
+        //The shared positions are split because the normals differ
+        assert_eq!(v.len(),6);
+        assert!(v[0..3].iter().all(|v| normal(v) == (0.0,0.0,1.0)));
+        assert!(v[3..6].iter().all(|v| normal(v) == (0.0,1.0,0.0)));
+    }
+
+    #[test]
+    fn generates_smooth_normals_in_smoothing_groups() {
+        let data = parse("v 0 0 0\nv 1 0 0\nv 0 1 0\nv 0 0 -1\ns 1\nf 1 2 3\nf 1 2 4\n");
+        let v = &data.meshes[0].data.vertecies;
+        //The shared positions are merged and get the average of both face normals
+        assert_eq!(v.len(),4);
+        assert_eq!(data.meshes[0].data.indecies,vec![0,1,2,0,1,3]);
+        let (x,y,z) = normal(&v[0]);
+        let expected = 1.0 / 2.0_f32.sqrt();
+        assert_eq!(x,0.0);
+        assert!((y - expected).abs() < 1e-6 && (z - expected).abs() < 1e-6);
+        assert_eq!(normal(&v[2]),(0.0,0.0,1.0));
+        assert_eq!(normal(&v[3]),(0.0,1.0,0.0));
+    }
+
+    #[test]
+    fn splits_meshes_on_objects_groups_and_materials() {
+        let source = format!("{}mtllib a.mtl b.mtl\no first\nf 1 2 3\ng part\nusemtl red\nf 1 2 3\nusemtl blue\nf 1 2 3\no second\nusemtl green\ng\nf 1 2 3\n",TRIANGLE);
+        let data = parse(&source);
+        assert_eq!(data.material_libs,vec!["a.mtl".to_string(),"b.mtl".to_string()]);
+
+        let names = data.meshes.iter().map(|m| (m.object.as_str(),m.group.as_str(),m.material.as_str())).collect::<Vec<_>>();
+        //Groups without faces (g part before usemtl red) are dropped
+        assert_eq!(names,vec![("first","",""),("first","part","red"),("first","part","blue"),("second","","green")]);
+        assert!(data.meshes.iter().all(|m| m.data.indecies.len() == 3));
+    }
+
+    #[test]
+    fn parses_mtl_maps() {
+        let source = "newmtl stone\nKd 0.5 0.5 0.5\nmap_Kd diffuse.png\nmap_Ks specular.png\nmap_Bump -bm 0.25 normal.png\nmap_d alpha.png\nmap_Ka ambient.png\nd 0.5\nillum 2\n\nnewmtl plain\nTr 0.25\n";
+        let mats = parse_mtl(source,"test.mtl").unwrap();
+        assert_eq!(mats.len(),2);
+
+        let stone = &mats["stone"];
+        assert_eq!(stone.diffuse_color,vec3(0.5,0.5,0.5));
+        assert_eq!(stone.texture_name.as_deref(),Some("diffuse.png"));
+        assert_eq!(stone.specular_texture_name.as_deref(),Some("specular.png"));
+        assert_eq!(stone.bump_texture_name.as_deref(),Some("normal.png"));
+        assert_eq!(stone.bump_multiplier,0.25);
+        assert_eq!(stone.alpha_texture_name.as_deref(),Some("alpha.png"));
+        assert_eq!(stone.ambient_texture_name.as_deref(),Some("ambient.png"));
+        assert_eq!(stone.dissolve,0.5);
+        assert_eq!(stone.illumination_model,2);
+
+        let plain = &mats["plain"];
+        assert_eq!(plain.dissolve,0.75);
+        assert!(plain.texture_name.is_none() && plain.bump_texture_name.is_none());
+    }
+
+    fn error_line(result:RglResult<impl Sized>) -> Option<usize> {
+        return match result {
+            Err(RglError::Parse { line, .. }) => line,
+            Err(e) => panic!("Expected a parse error, got {}",e),
+            Ok(_) => panic!("Expected a parse error")
+        };
+    }
+
+    #[test]
+    fn reports_error_lines() {
+        assert_eq!(error_line(ObjData::parse("v 0 0 0\n\n# comment\nv 1 x 0\n","test.obj")),Some(4));
+        assert_eq!(error_line(ObjData::parse(&format!("{}f 1 2 4\n",TRIANGLE),"test.obj")),Some(4));
+        assert_eq!(error_line(ObjData::parse(&format!("{}f 0 1 2\n",TRIANGLE),"test.obj")),Some(4));
+        assert_eq!(error_line(ObjData::parse("s x\n","test.obj")),Some(1));
+        assert_eq!(error_line(parse_mtl("newmtl a\nNs 10\nmap_Kd\n","test.mtl")),Some(3));
+        assert_eq!(error_line(parse_mtl("newmtl a\nillum two\n","test.mtl")),Some(2));
+    }
+}
@@ -1,36 +1,91 @@
+use std::collections::HashSet;
 use std::sync::{Arc, Mutex};
 
+//Per frame offsets of the queued renderers, they use the first capacity slots of the transform buffers
 pub struct OffsetHandler {
     pub camera_offset: u64,
-    pub line_offset: u64
+    pub line_offset: u64,
+    pub capacity: u64,
+    pub stride: u64
 }
 
 impl OffsetHandler {
     pub fn new() -> OffsetHandler {
-        return OffsetHandler { camera_offset: 0, line_offset: 0 }
+        return OffsetHandler::new_aligned(100,256);
+    }
+
+    //The queues advance the offsets by 256 bytes, stride only differs if the device needs a bigger alignment
+    pub fn new_aligned(capacity:u64,alignment:u32) -> OffsetHandler {
+        return OffsetHandler { camera_offset: 0, line_offset: 0, capacity, stride: align_to(256,alignment as u64) }
+    }
+
+    pub fn get_required_size(&self) -> u64 {
+        return self.capacity * self.stride;
     }
 }
 
+/*
+Hands out fixed size slots of a buffer used with dynamic offsets. The first start_cap slots are reserved.
+If all slots are used the handler grows (doubles its size) instead of failing, the owner of the buffer has to
+check get_required_size() and grow the buffer (see GameEngine::update_offset_buffers and Camera::write_transform).
+ */
 pub struct StaticOffsetHandler {
     pub freed_offset: Vec<u64>,
     pub to_remove: Arc<Mutex<Vec<u64>>>,
-    size: i32
+    used: HashSet<u64>,
+    size: i32,
+    start_cap: u64,
+    stride: u64,
+    peak: usize,
+    grow_count: u32
+}
+
+#[derive(Clone, Debug)]
+pub struct OffsetStats {
+    pub used: usize,
+    pub free: usize,
+    pub capacity: usize,
+    pub peak: usize,
+    pub stride: u64,
+    pub grow_count: u32,
+    pub required_size: u64
 }
 
 impl StaticOffsetHandler {
     pub fn new(size:i32,start_cap:u64) -> StaticOffsetHandler {
+        return StaticOffsetHandler::new_with_stride(size,start_cap,256);
+    }
 
-        let mut freed_offset : Vec<u64> = vec![];
-        let start_offset =  start_cap * 256;
-
-        for i in 0..size {
-            freed_offset.push(i as u64 * 256 + start_offset)
-        }
+    //Slots of element_size bytes, aligned to the offset alignment of the device (AlignmentSettings)
+    pub fn new_aligned(size:i32,start_cap:u64,element_size:u64,alignment:u32) -> StaticOffsetHandler {
+        return StaticOffsetHandler::new_with_stride(size,start_cap,align_to(element_size,alignment as u64));
+    }
 
-        return StaticOffsetHandler {
-            freed_offset,
+    //For slots bigger than 256 bytes (e.g. joint matrices), stride has to be a multiple of the buffer alignment
+    pub fn new_with_stride(size:i32,start_cap:u64,stride:u64) -> StaticOffsetHandler {
+        let mut handler = StaticOffsetHandler {
+            freed_offset: vec![],
             to_remove: Arc::new(Mutex::new(vec![])),
-            size
+            used: HashSet::new(),
+            size: 0,
+            start_cap,
+            stride,
+            peak: 0,
+            grow_count: 0
+        };
+        handler.add_slots(size.max(1));
+
+        return handler;
+    }
+
+    //Slots are handed out from the lowest offset
+    fn add_slots(&mut self,amount:i32) {
+        let start_offset = self.start_cap * self.stride;
+        let first = self.size;
+        self.size += amount;
+
+        for i in (first..self.size).rev() {
+            self.freed_offset.push(i as u64 * self.stride + start_offset);
         }
     }
 
@@ -38,36 +93,93 @@ impl StaticOffsetHandler {
         self.to_remove.lock().unwrap().push(remove);
     }
 
+    //Offsets that are not in use (e.g. freed twice) are ignored
     pub fn remove(&mut self,remove:u64) {
-        self.freed_offset.push(remove);
+        if self.used.remove(&remove) {
+            self.freed_offset.push(remove);
+        }
     }
 
     pub fn update(&mut self) {
-        if !self.to_remove.lock().unwrap().is_empty() {
-            let mut to_remove_real  = vec![];
-            for i in self.to_remove.lock().unwrap().iter() {
-                to_remove_real.push(*i);
-            }
-            self.to_remove.lock().unwrap().clear();
-
-            for i in to_remove_real {
-                self.remove(i);
-            }
+        let to_remove_real = std::mem::take(&mut *self.to_remove.lock().unwrap());
+
+        for i in to_remove_real {
+            self.remove(i);
         }
     }
 
     pub fn get_offset(&mut self) -> u64 {
-        let num = self.freed_offset.remove(0);
+        if self.freed_offset.is_empty() {
+            self.add_slots(self.size);
+            self.grow_count += 1;
+        }
+
+        let num = self.freed_offset.pop().unwrap();
+        self.used.insert(num);
+        self.peak = self.peak.max(self.used.len());
         return num;
     }
 
+    //The offset is freed when the handle is dropped (at the next update)
+    pub fn get_handle(&mut self) -> OffsetHandle {
+        return OffsetHandle { offset: self.get_offset(), to_remove: self.to_remove.clone() };
+    }
+
+    pub fn get_stride(&self) -> u64 {
+        return self.stride;
+    }
+
+    //Size in bytes the buffer needs to hold every slot
+    pub fn get_required_size(&self) -> u64 {
+        return (self.start_cap + self.size as u64) * self.stride;
+    }
+
+    pub fn get_stats(&self) -> OffsetStats {
+        return OffsetStats {
+            used: self.used.len(),
+            free: self.freed_offset.len(),
+            capacity: self.size as usize,
+            peak: self.peak,
+            stride: self.stride,
+            grow_count: self.grow_count,
+            required_size: self.get_required_size()
+        }
+    }
+
     pub fn reset(&mut self) {
         self.freed_offset.clear();
+        self.used.clear();
         self.to_remove.lock().unwrap().clear();
-        let start_offset =  100 * 256;
+        let size = self.size;
+        self.size = 0;
+        self.add_slots(size);
+    }
+}
 
-        for i in 0..self.size {
-            self.freed_offset.push(i as u64 * 256 + start_offset)
+pub struct OffsetHandle {
+    offset: u64,
+    to_remove: Arc<Mutex<Vec<u64>>>
+}
+
+impl OffsetHandle {
+    pub fn get(&self) -> u64 {
+        return self.offset;
+    }
+
+    pub fn get_u32(&self) -> u32 {
+        return self.offset as u32;
+    }
+}
+
+impl Drop for OffsetHandle {
+    fn drop(&mut self) {
+        if let Ok(mut to_remove) = self.to_remove.lock() {
+            to_remove.push(self.offset);
         }
     }
+}
+
+pub fn align_to(size:u64,alignment:u64) -> u64 {
+    let alignment = alignment.max(1);
+    return (size + alignment - 1) / alignment * alignment;
 }
\ No newline at end of file
@@ -0,0 +1,168 @@
+use image::RgbaImage;
+use wgpu::{Buffer, CommandEncoderDescriptor, Device, Extent3d, MapMode, Queue, Texture, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages, TextureView, TextureViewDescriptor};
+use crate::engine::frame_capture::{copy_texture_to_buffer, get_padded_row, read_texture_buffer};
+use crate::error::rgl_error::RglResult;
+
+pub struct RenderTexture {
+    pub texture: Texture,
+    pub view: TextureView,
+    pub format: TextureFormat,
+    pub width: u32,
+    pub height: u32,
+    pub sample_count: u32
+}
+
+impl RenderTexture {
+    pub fn new(device:&Device,width:u32,height:u32,format:TextureFormat) -> RenderTexture {
+        let texture = device.create_texture(&TextureDescriptor {
+            label: Some("Render Texture"),
+            size: Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format,
+            usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+
+        let view = texture.create_view(&TextureViewDescriptor::default());
+
+        return RenderTexture {
+            texture,
+            view,
+            format,
+            width,
+            height,
+            sample_count: 1
+        }
+    }
+
+    //Can only be rendered into and resolved, not sampled or read back
+    pub fn new_multisampled(device:&Device,width:u32,height:u32,format:TextureFormat,sample_count:u32) -> RenderTexture {
+        let texture = device.create_texture(&TextureDescriptor {
+            label: Some("Multisampled Render Texture"),
+            size: Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1
+            },
+            mip_level_count: 1,
+            sample_count,
+            dimension: TextureDimension::D2,
+            format,
+            usage: TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        });
+
+        let view = texture.create_view(&TextureViewDescriptor::default());
+
+        return RenderTexture {
+            texture,
+            view,
+            format,
+            width,
+            height,
+            sample_count
+        }
+    }
+
+    //Copies the texture into a mappable buffer (see copy_texture_to_buffer), multisampled textures can't be copied
+    pub fn copy_to_buffer(&self,device:&Device,queue:&Queue) -> RglResult<Buffer> {
+        let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+            label: Some("Readback Encoder"),
+        });
+        let buffer = copy_texture_to_buffer(device,&mut encoder,&self.texture)?;
+        queue.submit(std::iter::once(encoder.finish()));
+
+        return Ok(buffer);
+    }
+
+    //Blocks until the gpu has finished rendering into the texture, hdr textures are tone mapped like in read_texture_buffer
+    pub fn read_pixels(&self,device:&Device,queue:&Queue) -> RglResult<RgbaImage> {
+        let buffer = self.copy_to_buffer(device,queue)?;
+        return Ok(read_texture_buffer(device,&buffer,self.width,self.height,self.format));
+    }
+}
+
+pub fn read_buffer_to_image(device:&Device,buffer:&Buffer,width:u32,height:u32,format:TextureFormat) -> RgbaImage {
+    let slice = buffer.slice(..);
+    slice.map_async(MapMode::Read, |result| {
+        result.unwrap();
+    });
+    device.poll(wgpu::Maintain::Wait);
+
+    let image = padded_rows_to_image(&slice.get_mapped_range(),width,height,format);
+    buffer.unmap();
+
+    return image;
+}
+
+//Removes the padding of every row (see get_padded_row) of an 8 bit texture, bgra textures are swapped to rgba
+pub fn padded_rows_to_image(data:&[u8],width:u32,height:u32,format:TextureFormat) -> RgbaImage {
+    let padded_row = get_padded_row(width,4) as usize;
+    let row = (width * 4) as usize;
+    let swap = format == TextureFormat::Bgra8Unorm || format == TextureFormat::Bgra8UnormSrgb;
+
+    let mut pixels = Vec::with_capacity(row * height as usize);
+    for y in 0..height as usize {
+        let line = &data[y * padded_row..y * padded_row + row];
+        if swap {
+            for p in line.chunks(4) {
+                pixels.extend_from_slice(&[p[2],p[1],p[0],p[3]]);
+            }
+        } else {
+            pixels.extend_from_slice(line);
+        }
+    }
+
+    return RgbaImage::from_raw(width,height,pixels).unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pads_rows_to_copy_alignment() {
+        assert_eq!(get_padded_row(1,4),256);
+        assert_eq!(get_padded_row(64,4),256);
+        assert_eq!(get_padded_row(65,4),512);
+        assert_eq!(get_padded_row(32,8),256);
+        assert_eq!(get_padded_row(33,8),512);
+    }
+
+    //Every row has 3 pixels (12 bytes) followed by padding up to 256 bytes
+    fn padded_data() -> Vec<u8> {
+        let mut data = vec![0xAA;256 * 2];
+        for y in 0..2 {
+            for x in 0..3 {
+                let i = y * 256 + x * 4;
+                data[i..i + 4].copy_from_slice(&[10 * x as u8,100 + y as u8,200,255 - x as u8]);
+            }
+        }
+        return data;
+    }
+
+    #[test]
+    fn removes_row_padding() {
+        let image = padded_rows_to_image(&padded_data(),3,2,TextureFormat::Rgba8UnormSrgb);
+        assert_eq!(image.dimensions(),(3,2));
+        assert_eq!(image.as_raw().len(),3 * 2 * 4);
+        assert_eq!(image.get_pixel(0,0).0,[0,100,200,255]);
+        assert_eq!(image.get_pixel(2,0).0,[20,100,200,253]);
+        assert_eq!(image.get_pixel(1,1).0,[10,101,200,254]);
+    }
+
+    #[test]
+    fn swaps_bgra_to_rgba() {
+        for format in [TextureFormat::Bgra8Unorm,TextureFormat::Bgra8UnormSrgb] {
+            let image = padded_rows_to_image(&padded_data(),3,2,format);
+            assert_eq!(image.get_pixel(0,0).0,[200,100,0,255]);
+            assert_eq!(image.get_pixel(2,1).0,[200,101,20,253]);
+        }
+    }
+}
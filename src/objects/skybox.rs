@@ -93,6 +93,6 @@ impl SkyBox {
     }
     
     pub fn write(&self,camera:&Camera,engine:&GameEngine) {
-        engine.queue.write_buffer(&camera.buffers[2],self.offset as u64,&*crate::objects::matrix_helper::get_bytes(&self.transform.matrix));
+        camera.write_transform(&engine.device,&engine.queue,self.offset as u64,&self.transform.matrix);
     }
 }
\ No newline at end of file
@@ -11,6 +11,8 @@ use std::rc::Rc;
 use std::num::NonZeroU32;
 use std::convert::TryFrom;
 use wgpu::util::StagingBelt;
+use crate::error::rgl_error::{RglError, RglResult};
+use crate::vfs::vfs::Vfs;
 
 pub struct TextureObject {
     pub texture: wgpu::Texture,
@@ -22,75 +24,34 @@ pub struct TextureObject {
 }
 
 impl TextureObject{
-    pub fn new(path : String, name : String,device:&Device,queue:&Queue,layout:&BindGroupLayout) -> TextureObject{
-        let image = image::open(path).unwrap();
+    pub fn new(path : String, name : String,device:&Device,queue:&Queue,layout:&BindGroupLayout,vfs:&Vfs) -> TextureObject{
+        return TextureObject::try_new(path,name,device,queue,layout,vfs).unwrap_or_else(|e| panic!("{}",e));
+    }
 
-        let rgba = image.as_rgba8().unwrap();
+    pub fn try_new(path : String, name : String,device:&Device,queue:&Queue,layout:&BindGroupLayout,vfs:&Vfs) -> RglResult<TextureObject>{
+        let rgba = TextureObject::try_load_image(&path,device,vfs)?;
 
-        let dimensions = rgba.dimensions();
-        let size = wgpu::Extent3d {
-            width: dimensions.0,
-            height: dimensions.1,
-            depth_or_array_layers: 1
-        };
-
-        let texture = device.create_texture(&TextureDescriptor {
-            label: Some(name.as_str()),
-            size,
-            mip_level_count: 1,
-            sample_count: 1,
-            dimension: TextureDimension::D2,
-            format: TextureFormat::Rgba8UnormSrgb,
-            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
-            view_formats: &[],
-        });
+        return Ok(TextureObject::new_from_data(&rgba,name,device,queue,layout));
+    }
 
-        let data_layout = ImageDataLayout {
-            offset: 0,
-            bytes_per_row: Some(4 * size.width as u32),
-            rows_per_image: Some(size.height as u32)
-        };
+    //Decodes the image and checks that the device supports its size
+    pub fn try_load_image(path:&str,device:&Device,vfs:&Vfs) -> RglResult<RgbaImage> {
+        let data = vfs.read(path)?;
+        let image = image::load_from_memory(&data).map_err(|e| RglError::from_image(path,e))?;
 
-        queue.write_texture(ImageCopyTexture {
-            texture: &texture,
-            mip_level: 0,
-            origin: Origin3d::ZERO,
-            aspect: TextureAspect::All
-        }, &rgba, data_layout.clone(), size);
+        let rgba = image.to_rgba8();
 
-        let diffuse_texture_view = texture.create_view(&wgpu::TextureViewDescriptor::default());
-        
-        let diffuse_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
-            address_mode_u: wgpu::AddressMode::ClampToEdge,
-            address_mode_v: wgpu::AddressMode::ClampToEdge,
-            address_mode_w: wgpu::AddressMode::ClampToEdge,
-            mag_filter: wgpu::FilterMode::Nearest,
-            min_filter: wgpu::FilterMode::Nearest,
-            mipmap_filter: wgpu::FilterMode::Nearest,
-            ..Default::default()
-        });
+        let max = device.limits().max_texture_dimension_2d;
+        if rgba.width() > max || rgba.height() > max {
+            return Err(RglError::Gpu(format!("Texture {} is {}x{}, but the device only supports up to {}x{}",path,rgba.width(),rgba.height(),max,max)));
+        }
 
-        let bind_group = device.create_bind_group(&BindGroupDescriptor {
-            label: None,
-            layout,
-            entries: &[wgpu::BindGroupEntry {
-                binding: 0,
-                resource: wgpu::BindingResource::TextureView(&diffuse_texture_view),
-            },
-                wgpu::BindGroupEntry {
-                    binding: 1,
-                    resource: wgpu::BindingResource::Sampler(&diffuse_sampler),
-                }]
-        });
+        return Ok(rgba);
+    }
 
-        return TextureObject {
-            texture,
-            size,
-            name,
-            bind_group,
-            data: rgba.to_vec(),
-            layout: data_layout
-        };
+    //Rgba8, so 4 bytes per texel
+    pub fn get_gpu_size(&self) -> u64 {
+        return self.size.width as u64 * self.size.height as u64 * self.size.depth_or_array_layers as u64 * 4;
     }
 
     pub fn update(&self,queue:&Queue,image:&ImageBuffer<Rgba<u8>,Vec<u8>>) {
@@ -1,4 +1,4 @@
-use nalgebra_glm::{Mat4, mat4, TMat, TMat4, TVec3, vec3};
+use nalgebra_glm::{Mat4, mat4, Qua, TMat, TMat4, TVec3, vec3};
 use crate::engine::game_engine::GameEngine;
 
 pub struct Transform {
@@ -45,36 +45,114 @@ impl Transform {
             roll: 0.0,
             scale,
             interpolation: true,
-            last_transform: Some(TransformData::new(pos.clone())),
-            new_transform: Some(TransformData::new(pos.clone()))
+            last_transform: Some(TransformData::new_full(pos.clone(),0.0,0.0,0.0,scale.clone())),
+            new_transform: Some(TransformData::new_full(pos.clone(),0.0,0.0,0.0,scale.clone()))
         };
         trans.matrix = trans.create_matrix();
 
         return trans;
     }
 
+    //Translation, rotation quaternion (x,y,z,w) and scale like they are stored in gltf files
+    pub fn from_decomposed(translation:[f32;3], rotation:[f32;4], scale:[f32;3]) -> Transform {
+        let mut trans = Transform::new(translation[0],translation[1],translation[2],vec3(scale[0],scale[1],scale[2]));
+        trans.set_rotation_quat(&Qua::new(rotation[3],rotation[0],rotation[1],rotation[2]));
+        return trans;
+    }
+
+    pub fn set_rotation_quat(&mut self, rotation:&Qua<f32>) {
+        let (yaw,pitch,roll) = Transform::quat_to_euler(rotation);
+        self.set_rotation(yaw,pitch,roll);
+    }
+
+    //Splits the rotation into the yaw, pitch and roll (degrees) used by create_matrix (Rx(pitch) * Ry(yaw) * Rz(roll))
+    pub fn quat_to_euler(rotation:&Qua<f32>) -> (f32,f32,f32) {
+        let m = nalgebra_glm::quat_to_mat3(rotation);
+        let sin_yaw = m[(0,2)].clamp(-1.0,1.0);
+        let yaw = sin_yaw.asin();
+
+        let (pitch,roll) = if sin_yaw.abs() < 0.9999 {
+            ((-m[(1,2)]).atan2(m[(2,2)]),(-m[(0,1)]).atan2(m[(0,0)]))
+        } else {
+            //Gimbal lock, pitch and roll rotate around the same axis
+            (m[(2,1)].atan2(m[(1,1)]),0.0)
+        };
+
+        return (yaw.to_degrees(),pitch.to_degrees(),roll.to_degrees());
+    }
+
+    pub fn euler_to_quat(yaw:f32, pitch:f32, roll:f32) -> Qua<f32> {
+        let x = nalgebra_glm::quat_angle_axis(pitch.to_radians(),&vec3(1.0,0.0,0.0));
+        let y = nalgebra_glm::quat_angle_axis(yaw.to_radians(),&vec3(0.0,1.0,0.0));
+        let z = nalgebra_glm::quat_angle_axis(roll.to_radians(),&vec3(0.0,0.0,1.0));
+        return x * y * z;
+    }
+
+    pub fn get_rotation_quat(&self) -> Qua<f32> {
+        return Transform::euler_to_quat(self.yaw,self.pitch,self.roll);
+    }
+
+    /*
+    With interpolation the setters change new_transform (the state of the current tick), last_transform is the state of
+    the previous tick. interpolate blends between both with the tick alpha, pos, yaw/pitch/roll and scale are the blended values.
+     */
     pub fn activate_interpolation(&mut self) {
-        self.last_transform = Some(TransformData::new(self.pos.clone()));
-        self.new_transform = Some(TransformData::new(self.pos.clone()));
+        self.last_transform = Some(TransformData::from_transform(self));
+        self.new_transform = Some(TransformData::from_transform(self));
         self.interpolation = true;
     }
 
     pub fn interpolate(&mut self,engine:&GameEngine) {
+        self.interpolate_alpha(engine.tick_alpha);
+    }
+
+    pub fn interpolate_alpha(&mut self,alpha:f32) {
         if self.interpolation {
-            //self.pos = self.last_transform.as_ref().unwrap().pos.lerp(&self.new_transform.as_ref().unwrap().pos,engine.tick_alpha);
+            let last = self.last_transform.as_ref().unwrap();
+            let new = self.new_transform.as_ref().unwrap();
+            let pos = last.pos.lerp(&new.pos,alpha);
+            let scale = last.scale.lerp(&new.scale,alpha);
+            let rotation = nalgebra_glm::quat_slerp(&last.get_rotation_quat(),&new.get_rotation_quat(),alpha);
+
+            self.pos = pos;
+            self.scale = scale;
+            let (yaw,pitch,roll) = Transform::quat_to_euler(&rotation);
+            self.yaw = yaw;
+            self.pitch = pitch;
+            self.roll = roll;
             self.matrix = self.create_matrix();
         }
     }
 
+    //Call at the start of every tick (before the new state is set)
     pub fn handle_tick(&mut self) {
         if self.interpolation {
-            self.last_transform.as_mut().unwrap().pos = self.new_transform.as_ref().unwrap().pos.clone();
-            self.pos = self.new_transform.as_ref().unwrap().pos.clone();
+            let new = self.new_transform.as_ref().unwrap();
+            self.pos = new.pos.clone();
+            self.yaw = new.yaw;
+            self.pitch = new.pitch;
+            self.roll = new.roll;
+            self.scale = new.scale.clone();
+            self.last_transform = Some(new.clone());
             self.matrix = self.create_matrix();
         }
     }
 
+    //Skips the interpolation to the current state (e.g. after teleporting)
+    pub fn reset_interpolation(&mut self) {
+        if self.interpolation {
+            self.last_transform = self.new_transform.clone();
+            self.interpolate_alpha(1.0);
+        }
+    }
+
     pub fn set_rotation(&mut self, yaw : f32, pitch : f32, roll: f32) {
+        if let (true,Some(new)) = (self.interpolation,self.new_transform.as_mut()) {
+            new.yaw = yaw;
+            new.pitch = pitch;
+            new.roll = roll;
+            return;
+        }
         self.yaw = yaw;
         self.pitch = pitch;
         self.roll = roll;
@@ -82,6 +160,12 @@ impl Transform {
     }
 
     pub fn rotate(&mut self, yaw : f32, pitch : f32, roll: f32) {
+        if let (true,Some(new)) = (self.interpolation,self.new_transform.as_mut()) {
+            new.yaw += yaw;
+            new.pitch += pitch;
+            new.roll += roll;
+            return;
+        }
         self.yaw += yaw;
         self.pitch += pitch;
         self.roll += roll;
@@ -137,11 +221,19 @@ impl Transform {
     }
 
     pub fn set_scale(&mut self, scale : TVec3<f32>) {
+        if let (true,Some(new)) = (self.interpolation,self.new_transform.as_mut()) {
+            new.scale = scale;
+            return;
+        }
         self.scale = scale;
         self.matrix = self.create_matrix();
     }
 
     pub fn scale(&mut self, scale : TVec3<f32>) {
+        if let (true,Some(new)) = (self.interpolation,self.new_transform.as_mut()) {
+            new.scale = new.scale.component_mul(&scale);
+            return;
+        }
         self.scale = vec3(self.scale.x * scale.x,self.scale.y * scale.y,self.scale.z * scale.z);
         self.matrix = self.create_matrix();
     }
@@ -177,12 +269,29 @@ impl Transform {
     }
 }
 
+#[derive(Clone)]
 pub struct TransformData {
-    pub pos: TVec3<f32>
+    pub pos: TVec3<f32>,
+    pub yaw: f32,
+    pub pitch: f32,
+    pub roll: f32,
+    pub scale: TVec3<f32>
 }
 
 impl TransformData {
     pub fn new(pos: TVec3<f32>) -> TransformData {
-        return TransformData { pos }
+        return TransformData::new_full(pos,0.0,0.0,0.0,vec3(1.0,1.0,1.0));
+    }
+
+    pub fn new_full(pos: TVec3<f32>, yaw:f32, pitch:f32, roll:f32, scale: TVec3<f32>) -> TransformData {
+        return TransformData { pos, yaw, pitch, roll, scale }
+    }
+
+    pub fn from_transform(transform:&Transform) -> TransformData {
+        return TransformData::new_full(transform.pos.clone(),transform.yaw,transform.pitch,transform.roll,transform.scale.clone());
+    }
+
+    pub fn get_rotation_quat(&self) -> Qua<f32> {
+        return Transform::euler_to_quat(self.yaw,self.pitch,self.roll);
     }
 }
\ No newline at end of file
@@ -20,6 +20,6 @@ impl TransformDataObj {
     }
 
     pub fn write(&self,engine:&GameEngine, camera:&Camera) {
-        engine.queue.write_buffer(&camera.buffers[2], self.offset as u64, &crate::objects::matrix_helper::get_bytes(&self.transform.matrix));
+        camera.write_transform(&engine.device,&engine.queue,self.offset as u64,&self.transform.matrix);
     }
 }
\ No newline at end of file
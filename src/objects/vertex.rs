@@ -45,6 +45,38 @@ impl NormalVertex {
         };
     }
 }
+//NormalVertex with up to 4 joints influencing it, the weights should add up to 1
+#[derive(Clone)]
+pub struct SkinnedVertex {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+    pub u: f32,
+    pub v: f32,
+    pub n_x: f32,
+    pub n_y: f32,
+    pub n_z: f32,
+    pub joints: [u32;4],
+    pub weights: [f32;4]
+}
+
+impl SkinnedVertex {
+    pub fn new(vertex:NormalVertex,joints:[u32;4],weights:[f32;4]) -> SkinnedVertex {
+        return SkinnedVertex {
+            x: vertex.x,
+            y: vertex.y,
+            z: vertex.z,
+            u: vertex.u,
+            v: vertex.v,
+            n_x: vertex.n_x,
+            n_y: vertex.n_y,
+            n_z: vertex.n_z,
+            joints,
+            weights
+        };
+    }
+}
+
 #[derive(Clone)]
 pub struct OnlyCoordsVertex {
     pub x: f32,
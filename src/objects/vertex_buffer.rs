@@ -1,7 +1,8 @@
 use wgpu::{Device, Buffer, RenderPass, BufferAddress, Queue, IndexFormat, VertexBufferLayout, BufferUsages};
-use crate::objects::vertex::{Vertex, OnlyCoordsVertex, NormalVertex};
+use crate::objects::vertex::{Vertex, OnlyCoordsVertex, NormalVertex, SkinnedVertex};
 use wgpu::util::{DeviceExt, BufferInitDescriptor};
 use crate::objects::vertex_buffer_data::{VertexBufferData, NormalVertexBufferData};
+use crate::engine::profiler::count_draw;
 
 pub struct VertexBuffer{
     pub vertecies: Vec<Vertex>,
@@ -78,6 +79,7 @@ impl VertexBuffer {
         render_pass.set_vertex_buffer(0,self.buffer.slice(..));
         render_pass.set_index_buffer(self.index_buffer.slice(..),IndexFormat::Uint32);
         render_pass.draw_indexed(0..self.indecies.len() as u32,0,0..1);
+        count_draw();
     }
 
     pub fn edit_data(&self,vertecies: &Vec<Vertex>,queue:&Queue,offset:u64) {
@@ -167,6 +169,7 @@ impl OnlyCoordsVertexBuffer {
         render_pass.set_vertex_buffer(0,self.buffer.slice(..));
         render_pass.set_index_buffer(self.index_buffer.slice(..),IndexFormat::Uint32);
         render_pass.draw_indexed(0..self.indecies.len() as u32,0,0..1);
+        count_draw();
     }
 }
 
@@ -261,5 +264,90 @@ impl NormalVertexBuffer {
         render_pass.set_vertex_buffer(0,self.buffer.slice(..));
         render_pass.set_index_buffer(self.index_buffer.slice(..),IndexFormat::Uint32);
         render_pass.draw_indexed(0..self.indecies.len() as u32,0,0..1);
+        count_draw();
+    }
+}
+
+pub struct SkinnedVertexBuffer{
+    pub vertecies: Vec<SkinnedVertex>,
+    pub indecies: Vec<u32>,
+    pub buffer: Buffer,
+    pub index_buffer: Buffer
+}
+
+impl SkinnedVertexBuffer {
+    pub fn new(device:&Device,vertecies: Vec<SkinnedVertex>,indecies:Vec<u32>) -> SkinnedVertexBuffer {
+
+        let mut bytes : Vec<u8> = vec![];
+
+        for v in vertecies.iter() {
+            for f in [v.x,v.y,v.z,v.u,v.v,v.n_x,v.n_y,v.n_z] {
+                bytes.extend_from_slice(f.to_le_bytes().as_ref());
+            }
+            for j in v.joints {
+                bytes.extend_from_slice(j.to_le_bytes().as_ref());
+            }
+            for w in v.weights {
+                bytes.extend_from_slice(w.to_le_bytes().as_ref());
+            }
+        }
+
+        let buffer = device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("Skinned Vertex Buffer"),
+            contents: &*bytes,
+            usage: BufferUsages::VERTEX
+        });
+
+        let mut bytes_index : Vec<u8> = vec![];
+
+        for v in indecies.iter() {
+            bytes_index.extend_from_slice(v.to_le_bytes().as_ref());
+        }
+
+        let index_buffer = device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("Index Buffer"),
+            contents: &*bytes_index,
+            usage: BufferUsages::INDEX
+        });
+
+        return SkinnedVertexBuffer { vertecies, indecies, buffer, index_buffer };
+    }
+
+    pub fn desc() -> VertexBufferLayout<'static>{
+        wgpu::VertexBufferLayout {
+            array_stride: (16 * std::mem::size_of::<f32>()) as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &[
+                wgpu::VertexAttribute {
+                    offset: 0,
+                    shader_location: 0,
+                    format: wgpu::VertexFormat::Float32x3,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32;3]>() as BufferAddress,
+                    shader_location: 1,
+                    format: wgpu::VertexFormat::Float32x2,
+                },wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32;5]>() as BufferAddress,
+                    shader_location: 2,
+                    format: wgpu::VertexFormat::Float32x3,
+                },wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32;8]>() as BufferAddress,
+                    shader_location: 3,
+                    format: wgpu::VertexFormat::Uint32x4,
+                },wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32;12]>() as BufferAddress,
+                    shader_location: 4,
+                    format: wgpu::VertexFormat::Float32x4,
+                }
+            ]
+        }
+    }
+
+    pub fn render<'a>(&'a self, render_pass:&mut RenderPass<'a>) {
+        render_pass.set_vertex_buffer(0,self.buffer.slice(..));
+        render_pass.set_index_buffer(self.index_buffer.slice(..),IndexFormat::Uint32);
+        render_pass.draw_indexed(0..self.indecies.len() as u32,0,0..1);
+        count_draw();
     }
 }
\ No newline at end of file
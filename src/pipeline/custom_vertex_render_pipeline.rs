@@ -1,6 +1,7 @@
 use wgpu::{BindGroupLayout, Device, PrimitiveTopology, SamplerBindingType, Surface, SurfaceConfiguration};
 use crate::pipeline::pipeline::{RenderPipelineGroup, RenderPipelineGroupBuilder};
 use crate::objects::camera::Camera;
+use crate::vfs::vfs::Vfs;
 use crate::objects::vertex_buffer::VertexBuffer;
 
 pub struct CustomVertexRenderPipelineGroup {
@@ -9,10 +10,10 @@ pub struct CustomVertexRenderPipelineGroup {
 }
 
 impl CustomVertexRenderPipelineGroup {
-    pub fn new(vertex_shader_path : String, fragment_shader_path: String,config:&SurfaceConfiguration,device:&Device) -> CustomVertexRenderPipelineGroup {
+    pub fn new(vertex_shader_path : String, fragment_shader_path: String,config:&SurfaceConfiguration,device:&Device,vfs:&Vfs) -> CustomVertexRenderPipelineGroup {
 
         let mut group2_builder = RenderPipelineGroupBuilder::empty();
-        group2_builder.set_shaders(&device,vertex_shader_path.clone(),fragment_shader_path.clone(),"vertex_custom".to_string(),"fragment_custom".to_string());
+        group2_builder.set_shaders(&device,vfs,vertex_shader_path.clone(),fragment_shader_path.clone(),"vertex_custom".to_string(),"fragment_custom".to_string());
 
         let texture_bind_group_layout = group2_builder.create_texture_bind_group_layout(device);
         group2_builder.bind_groups_layouts.push(texture_bind_group_layout);
@@ -22,7 +23,7 @@ impl CustomVertexRenderPipelineGroup {
         let group2_depth = group2_builder.build(device,config,VertexBuffer::desc(),PrimitiveTopology::TriangleList,true,"Custom Vertex Depth".to_string());
 
         let mut group2_builder2 = RenderPipelineGroupBuilder::empty();
-        group2_builder2.set_shaders(&device,vertex_shader_path,fragment_shader_path,"vertex_custom".to_string(),"fragment_custom".to_string());
+        group2_builder2.set_shaders(&device,vfs,vertex_shader_path,fragment_shader_path,"vertex_custom".to_string(),"fragment_custom".to_string());
 
         let texture_bind_group_layout = group2_builder2.create_texture_bind_group_layout(device);
         group2_builder2.bind_groups_layouts.push(texture_bind_group_layout);
@@ -0,0 +1,169 @@
+use wgpu::{BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayout, BindGroupLayoutDescriptor, BindGroupLayoutEntry, BindingResource, BindingType, Buffer, BufferBindingType, BufferDescriptor, BufferSize, BufferUsages, CommandEncoder, ComputePassDescriptor, ComputePipeline, ComputePipelineDescriptor, Device, PipelineLayout, PipelineLayoutDescriptor, ShaderModule, ShaderStages, TextureSampleType, TextureView, TextureViewDimension};
+use wgpu::util::{BufferInitDescriptor, DeviceExt};
+use crate::error::rgl_error::RglResult;
+use crate::pipeline::pipeline::{load_wgsl_module, with_validation};
+use crate::vfs::vfs::Vfs;
+use crate::engine::profiler::count_pipeline_switch;
+
+pub const EXPOSURE_DATA_SIZE: u64 = 32;
+const HISTOGRAM_BINS: u64 = 256;
+
+/*
+Automatic exposure from a luminance histogram of the hdr image. Everything stays on the gpu,
+the tonemapping pass reads the result from exposure_buffer (see exposure_bind_group).
+set 0: hdr texture, histogram, ExposureData (log luminance range, adaptation, pixel count), Exposure (adapted luminance, exposure)
+ */
+pub struct ExposurePipelineGroup {
+    pub histogram_pipeline: ComputePipeline,
+    pub average_pipeline: ComputePipeline,
+    pub layout: BindGroupLayout,
+    pub pipeline_layout: PipelineLayout,
+    pub histogram_buffer: Buffer,
+    pub data_buffer: Buffer,
+    pub exposure_buffer: Buffer,
+    //Read only access to exposure_buffer for the fragment shader
+    pub exposure_layout: BindGroupLayout,
+    pub exposure_bind_group: BindGroup,
+    pub histogram_shader_path: String,
+    pub average_shader_path: String
+}
+
+impl ExposurePipelineGroup {
+    pub fn new(histogram_shader_path:String,average_shader_path:String,device:&Device,vfs:&Vfs) -> ExposurePipelineGroup {
+        return ExposurePipelineGroup::try_new(histogram_shader_path,average_shader_path,device,vfs).unwrap_or_else(|e| panic!("{}",e));
+    }
+
+    pub fn try_new(histogram_shader_path:String,average_shader_path:String,device:&Device,vfs:&Vfs) -> RglResult<ExposurePipelineGroup> {
+        let storage_entry = |binding:u32,size:u64| BindGroupLayoutEntry {
+            binding,
+            visibility: ShaderStages::COMPUTE,
+            ty: BindingType::Buffer { ty: BufferBindingType::Storage { read_only: false }, has_dynamic_offset: false, min_binding_size: BufferSize::new(size) },
+            count: None
+        };
+
+        let layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor { label: Some("Exposure Layout"), entries: &[
+            BindGroupLayoutEntry {
+                binding: 0,
+                visibility: ShaderStages::COMPUTE,
+                ty: BindingType::Texture { multisampled: false, view_dimension: TextureViewDimension::D2, sample_type: TextureSampleType::Float { filterable: false } },
+                count: None
+            },
+            storage_entry(1,HISTOGRAM_BINS * 4),
+            BindGroupLayoutEntry {
+                binding: 2,
+                visibility: ShaderStages::COMPUTE,
+                ty: BindingType::Buffer { ty: BufferBindingType::Uniform, has_dynamic_offset: false, min_binding_size: BufferSize::new(EXPOSURE_DATA_SIZE) },
+                count: None
+            },
+            storage_entry(3,8)
+        ] });
+
+        let exposure_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor { label: Some("Exposure Read Layout"), entries: &[
+            BindGroupLayoutEntry {
+                binding: 0,
+                visibility: ShaderStages::FRAGMENT,
+                ty: BindingType::Buffer { ty: BufferBindingType::Storage { read_only: true }, has_dynamic_offset: false, min_binding_size: BufferSize::new(8) },
+                count: None
+            }
+        ] });
+
+        let histogram_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("Luminance Histogram"),
+            size: HISTOGRAM_BINS * 4,
+            usage: BufferUsages::STORAGE,
+            mapped_at_creation: false
+        });
+
+        let data_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("Exposure Data"),
+            size: EXPOSURE_DATA_SIZE,
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+            mapped_at_creation: false
+        });
+
+        //Starts at middle gray, which is an exposure of 1
+        let exposure_buffer = device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("Exposure"),
+            contents: &crate::objects::matrix_helper::get_bytes_from_vec(vec![0.18,1.0]),
+            usage: BufferUsages::STORAGE
+        });
+
+        let exposure_bind_group = device.create_bind_group(&BindGroupDescriptor {
+            label: Some("Exposure Read Group"),
+            layout: &exposure_layout,
+            entries: &[BindGroupEntry { binding: 0, resource: exposure_buffer.as_entire_binding() }]
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("Exposure Pipeline Layout"),
+            bind_group_layouts: &[&layout],
+            push_constant_ranges: &[]
+        });
+
+        let histogram_module = load_wgsl_module(device,vfs,&histogram_shader_path,"histogram")?;
+        let average_module = load_wgsl_module(device,vfs,&average_shader_path,"average_luminance")?;
+        let histogram_pipeline = with_validation(device,|| ExposurePipelineGroup::create_pipeline(device,&pipeline_layout,&histogram_module,"Luminance Histogram"))?;
+        let average_pipeline = with_validation(device,|| ExposurePipelineGroup::create_pipeline(device,&pipeline_layout,&average_module,"Average Luminance"))?;
+
+        return Ok(ExposurePipelineGroup {
+            histogram_pipeline,
+            average_pipeline,
+            layout,
+            pipeline_layout,
+            histogram_buffer,
+            data_buffer,
+            exposure_buffer,
+            exposure_layout,
+            exposure_bind_group,
+            histogram_shader_path,
+            average_shader_path
+        });
+    }
+
+    fn create_pipeline(device:&Device,layout:&PipelineLayout,module:&ShaderModule,label:&str) -> ComputePipeline {
+        return device.create_compute_pipeline(&ComputePipelineDescriptor {
+            label: Some(label),
+            layout: Some(layout),
+            module,
+            entry_point: "main"
+        });
+    }
+
+    //Builds the histogram of hdr and adapts the exposure towards its average, width and height are the size of hdr
+    pub fn compute(&self,device:&Device,encoder:&mut CommandEncoder,hdr:&TextureView,width:u32,height:u32) {
+        let bind_group = device.create_bind_group(&BindGroupDescriptor {
+            label: Some("Exposure Group"),
+            layout: &self.layout,
+            entries: &[
+                BindGroupEntry { binding: 0, resource: BindingResource::TextureView(hdr) },
+                BindGroupEntry { binding: 1, resource: self.histogram_buffer.as_entire_binding() },
+                BindGroupEntry { binding: 2, resource: self.data_buffer.as_entire_binding() },
+                BindGroupEntry { binding: 3, resource: self.exposure_buffer.as_entire_binding() }
+            ]
+        });
+
+        let mut compute_pass = encoder.begin_compute_pass(&ComputePassDescriptor { label: Some("Exposure Pass") });
+        compute_pass.set_bind_group(0,&bind_group,&[]);
+        compute_pass.set_pipeline(&self.histogram_pipeline);
+        count_pipeline_switch();
+        compute_pass.dispatch_workgroups((width + 15) / 16,(height + 15) / 16,1);
+        compute_pass.set_pipeline(&self.average_pipeline);
+        count_pipeline_switch();
+        compute_pass.dispatch_workgroups(1,1,1);
+    }
+
+    pub fn uses_shader(&self,path:&str) -> bool {
+        return self.histogram_shader_path == path || self.average_shader_path == path;
+    }
+
+    //Keeps the current pipelines if a shader fails to compile
+    pub fn try_reload(&mut self,device:&Device,vfs:&Vfs) -> RglResult<()> {
+        let histogram_module = load_wgsl_module(device,vfs,&self.histogram_shader_path,"histogram")?;
+        let average_module = load_wgsl_module(device,vfs,&self.average_shader_path,"average_luminance")?;
+        let histogram_pipeline = with_validation(device,|| ExposurePipelineGroup::create_pipeline(device,&self.pipeline_layout,&histogram_module,"Luminance Histogram"))?;
+        let average_pipeline = with_validation(device,|| ExposurePipelineGroup::create_pipeline(device,&self.pipeline_layout,&average_module,"Average Luminance"))?;
+        self.histogram_pipeline = histogram_pipeline;
+        self.average_pipeline = average_pipeline;
+        return Ok(());
+    }
+}
@@ -2,6 +2,7 @@ use crate::pipeline::pipeline::{RenderPipelineGroup, RenderPipelineGroupBuilder}
 use wgpu::{Buffer, Device, BindGroupLayout, BindGroupLayoutDescriptor, BindGroupLayoutEntry, BindingType, BindGroupDescriptor, BindGroupEntry, BindingResource, PrimitiveTopology, BufferBindingType, BufferBinding, BufferSize, ShaderStages, Surface, SurfaceConfiguration};
 use crate::objects::camera::Camera;
 use nalgebra_glm::{vec3, vec4};
+use crate::vfs::vfs::Vfs;
 use crate::objects::vertex_buffer::OnlyCoordsVertexBuffer;
 
 pub struct LineRenderPipelineGroup {
@@ -11,12 +12,12 @@ pub struct LineRenderPipelineGroup {
 }
 
 impl LineRenderPipelineGroup {
-    pub fn new(vertex_shader_path : String, fragment_shader_path: String,config:&SurfaceConfiguration,device:&Device) -> LineRenderPipelineGroup{
+    pub fn new(vertex_shader_path : String, fragment_shader_path: String,config:&SurfaceConfiguration,device:&Device,vfs:&Vfs) -> LineRenderPipelineGroup {
 
         let color_buffer = crate::objects::matrix_helper::add_dynamic_buffer(device, (device.limits().min_uniform_buffer_offset_alignment * 256) as u64);
 
         let mut group_builder = RenderPipelineGroupBuilder::empty();
-        group_builder.set_shaders(device,vertex_shader_path,fragment_shader_path,"vertex_line".to_string(),"fragment_line".to_string());
+        group_builder.set_shaders(device,vfs,vertex_shader_path,fragment_shader_path,"vertex_line".to_string(),"fragment_line".to_string());
 
         let color_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor { label: None, entries: &[BindGroupLayoutEntry {
             binding: 0,
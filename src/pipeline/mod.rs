@@ -4,4 +4,8 @@ pub mod texture_render_pipeline;
 pub mod line_render_pipeline;
 pub mod custom_vertex_render_pipeline;
 pub mod text_render_pipeline;
-pub mod vertex_render_pipeline;
\ No newline at end of file
+pub mod vertex_render_pipeline;
+pub mod shadow_render_pipeline;
+pub mod transition_render_pipeline;
+pub mod post_process_pipeline;
+pub mod exposure_pipeline;
\ No newline at end of file
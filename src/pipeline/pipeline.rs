@@ -1,5 +1,7 @@
 use std::borrow::Cow;
-use wgpu::{Device, PipelineLayoutDescriptor, RenderPipelineDescriptor, PrimitiveTopology, FrontFace, IndexFormat, RenderPipeline, BindGroupDescriptor, BindGroupLayoutDescriptor, BindGroupLayoutEntry, BindingType, BindGroupEntry, BindingResource, BindGroup, Buffer, ShaderModule, BindGroupLayout, BufferDescriptor, PUSH_CONSTANT_ALIGNMENT, Features, PushConstantRange, Limits, TextureFormat, CompareFunction, ShaderModuleDescriptor, VertexState, FragmentState, ColorTargetState, BlendState, BlendFactor, BlendOperation, PrimitiveState, PolygonMode, DepthStencilState, DepthBiasState, MultisampleState, VertexBufferLayout, StencilState, BlendComponent, Face, ShaderSource, BufferUsages, ColorWrites, SurfaceConfiguration, SamplerBindingType, ShaderStages};
+use std::future::Future;
+use std::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
+use wgpu::{Device, PipelineLayoutDescriptor, RenderPipelineDescriptor, PrimitiveTopology, FrontFace, IndexFormat, RenderPipeline, BindGroupDescriptor, BindGroupLayoutDescriptor, BindGroupLayoutEntry, BindingType, BindGroupEntry, BindingResource, BindGroup, Buffer, ShaderModule, BindGroupLayout, BufferDescriptor, PUSH_CONSTANT_ALIGNMENT, Features, PushConstantRange, Limits, TextureFormat, CompareFunction, ShaderModuleDescriptor, VertexState, FragmentState, ColorTargetState, BlendState, BlendFactor, BlendOperation, PrimitiveState, PolygonMode, DepthStencilState, DepthBiasState, MultisampleState, VertexBufferLayout, StencilState, BlendComponent, Face, ShaderSource, BufferUsages, ColorWrites, SurfaceConfiguration, SamplerBindingType, ShaderStages, ErrorFilter, PipelineLayout};
 use wgpu::util::{DeviceExt, BufferInitDescriptor};
 use crate::objects::vertex_buffer::VertexBuffer;
 use std::path::{PathBuf, Path};
@@ -8,6 +10,8 @@ use std::io::{Read, Write};
 use log::info;
 use naga::ShaderStage;
 use crate::engine::game_engine::GameEngine;
+use crate::error::rgl_error::{RglError, RglResult};
+use crate::vfs::vfs::Vfs;
 
 pub struct RenderPipelineGroupBuilder {
     pub bind_groups: Vec<BindGroup>,
@@ -15,7 +19,9 @@ pub struct RenderPipelineGroupBuilder {
     pub buffers: Vec<Buffer>,
     pub vertex_buffers: Vec<VertexBuffer>,
     pub vertex_shader: Option<ShaderModule>,
-    pub fragment_shader: Option<ShaderModule>
+    pub fragment_shader: Option<ShaderModule>,
+    pub vertex_shader_path: Option<String>,
+    pub fragment_shader_path: Option<String>
 }
 
 impl RenderPipelineGroupBuilder {
@@ -26,17 +32,27 @@ impl RenderPipelineGroupBuilder {
             buffers: vec![],
             vertex_buffers: vec![],
             vertex_shader: None,
-            fragment_shader: None
+            fragment_shader: None,
+            vertex_shader_path: None,
+            fragment_shader_path: None
         }
     }
 
-    pub fn set_shaders(&mut self,device:&Device,vertex_shader_path : String, fragment_shader_path: String,name_vertex:String,name_fragment:String) {
+    pub fn set_shaders(&mut self,device:&Device,vfs:&Vfs,vertex_shader_path : String, fragment_shader_path: String,name_vertex:String,name_fragment:String) {
+        self.try_set_shaders(device,vfs,vertex_shader_path,fragment_shader_path,name_vertex,name_fragment).unwrap_or_else(|e| panic!("{}",e));
+    }
+
+    pub fn try_set_shaders(&mut self,device:&Device,vfs:&Vfs,vertex_shader_path : String, fragment_shader_path: String,name_vertex:String,name_fragment:String) -> RglResult<()> {
         info!("Loading Vertex Shader on path: {}",vertex_shader_path);
-        let vs_src = std::fs::read_to_string(vertex_shader_path.clone()).unwrap();
-        let fs_src = std::fs::read_to_string(fragment_shader_path.clone()).unwrap();
+        let vs_src = vfs.read_to_string(&vertex_shader_path)?;
+        let fs_src = vfs.read_to_string(&fragment_shader_path)?;
+
+        //wgpu would only panic on invalid shaders, so they are validated with naga first
+        validate_glsl(&vertex_shader_path,&vs_src,ShaderStage::Vertex)?;
+        validate_glsl(&fragment_shader_path,&fs_src,ShaderStage::Fragment)?;
 
         let vs_module = device.create_shader_module(ShaderModuleDescriptor {
-            label: None,
+            label: Some(name_vertex.as_str()),
             source: ShaderSource::Glsl {
                 shader: Cow::from(vs_src.as_str()),
                 stage: ShaderStage::Vertex,
@@ -45,7 +61,7 @@ impl RenderPipelineGroupBuilder {
         });
 
         let fs_module = device.create_shader_module(ShaderModuleDescriptor {
-            label: None,
+            label: Some(name_fragment.as_str()),
             source: ShaderSource::Glsl {
                 shader: Cow::from(fs_src.as_str()),
                 stage: ShaderStage::Fragment,
@@ -56,6 +72,10 @@ impl RenderPipelineGroupBuilder {
 
         self.vertex_shader = Some(vs_module);
         self.fragment_shader = Some(fs_module);
+        self.vertex_shader_path = Some(vertex_shader_path);
+        self.fragment_shader_path = Some(fragment_shader_path);
+
+        return Ok(());
     }
 
     pub fn add_bind_group(&mut self,bind_group:BindGroup,layout:BindGroupLayout) {
@@ -118,20 +138,38 @@ impl RenderPipelineGroupBuilder {
         );
     }
 
-    pub fn build(self,device:&Device,config:&SurfaceConfiguration,desc:VertexBufferLayout,topology:PrimitiveTopology,depth:bool,name:String) -> RenderPipelineGroup {
+    pub fn build(self,device:&Device,config:&SurfaceConfiguration,desc:VertexBufferLayout<'static>,topology:PrimitiveTopology,depth:bool,name:String) -> RenderPipelineGroup {
         return RenderPipelineGroup::new_with_shaders(self,device,config,desc,topology,depth,name);
     }
 }
 
+//Everything needed to build the pipeline again with new shaders (see RenderPipelineGroup::try_reload)
+#[derive(Clone)]
+pub struct PipelineInfo {
+    pub vertex_shader_path: Option<String>,
+    pub fragment_shader_path: Option<String>,
+    pub vertex_layout: VertexBufferLayout<'static>,
+    pub topology: PrimitiveTopology,
+    pub depth: bool,
+    pub format: TextureFormat,
+    pub sample_count: u32,
+    pub name: String
+}
+
 pub struct RenderPipelineGroup {
     pub pipeline:RenderPipeline,
     pub bind_groups: Vec<BindGroup>,
     pub buffers: Vec<Buffer>,
-    pub vertex_buffers: Vec<VertexBuffer>
+    pub vertex_buffers: Vec<VertexBuffer>,
+    pub layout: PipelineLayout,
+    pub info: PipelineInfo,
+    //Kept to build the pipeline again for another target (see set_target)
+    vertex_shader: ShaderModule,
+    fragment_shader: ShaderModule
 }
 
 impl RenderPipelineGroup {
-    pub fn new_with_shaders(builder:RenderPipelineGroupBuilder,device:&Device,config:&SurfaceConfiguration,desc:VertexBufferLayout,topology:PrimitiveTopology,depth:bool, name:String) -> RenderPipelineGroup {
+    pub fn new_with_shaders(builder:RenderPipelineGroupBuilder,device:&Device,config:&SurfaceConfiguration,desc:VertexBufferLayout<'static>,topology:PrimitiveTopology,depth:bool, name:String) -> RenderPipelineGroup {
 
         let mut layouts: Vec<&BindGroupLayout> = vec![];
 
@@ -144,9 +182,37 @@ impl RenderPipelineGroup {
             push_constant_ranges: &[]
         });
 
+        let info = PipelineInfo {
+            vertex_shader_path: builder.vertex_shader_path,
+            fragment_shader_path: builder.fragment_shader_path,
+            vertex_layout: desc,
+            topology,
+            depth,
+            format: config.format,
+            sample_count: 1,
+            name
+        };
+
+        let vertex_shader = builder.vertex_shader.unwrap();
+        let fragment_shader = builder.fragment_shader.unwrap();
+        let render_pipeline = RenderPipelineGroup::create_pipeline(device,&render_pipeline_layout,&vertex_shader,&fragment_shader,&info);
+
+        return RenderPipelineGroup {
+            pipeline: render_pipeline,
+            bind_groups: builder.bind_groups,
+            buffers: builder.buffers,
+            vertex_buffers: builder.vertex_buffers,
+            layout: render_pipeline_layout,
+            info,
+            vertex_shader,
+            fragment_shader
+        }
+    }
+
+    fn create_pipeline(device:&Device,layout:&PipelineLayout,vs_module:&ShaderModule,fs_module:&ShaderModule,info:&PipelineInfo) -> RenderPipeline {
         let mut depth_stencil_state = None;
 
-        if depth {
+        if info.depth {
             depth_stencil_state = Some(DepthStencilState {
                 format: TextureFormat::Depth32Float,
                 depth_write_enabled: true,
@@ -161,16 +227,16 @@ impl RenderPipelineGroup {
             });
         }
 
-        let render_pipeline = device.create_render_pipeline(&RenderPipelineDescriptor {
-            label: Some(name.as_str()),
-            layout: Some(&render_pipeline_layout),
+        return device.create_render_pipeline(&RenderPipelineDescriptor {
+            label: Some(info.name.as_str()),
+            layout: Some(layout),
             vertex: VertexState {
-                module: &builder.vertex_shader.unwrap(),
+                module: vs_module,
                 entry_point: "main",
-                buffers: &[desc]
+                buffers: &[info.vertex_layout.clone()]
             },
             primitive: PrimitiveState {
-                topology,
+                topology: info.topology,
                 strip_index_format: None,
                 front_face: FrontFace::Ccw,
                 cull_mode: None,
@@ -181,10 +247,10 @@ impl RenderPipelineGroup {
             depth_stencil: depth_stencil_state,
             fragment: Some(
                 FragmentState {
-                    module: &builder.fragment_shader.unwrap(),
+                    module: fs_module,
                     entry_point: "main",
                     targets: &[Some(ColorTargetState {
-                        format: config.format,
+                        format: info.format,
                         write_mask: ColorWrites::ALL,
                         blend: Some(BlendState {
                             color: BlendComponent {
@@ -202,18 +268,134 @@ impl RenderPipelineGroup {
                 }
             ),
             multisample: MultisampleState {
-                count: 1,
+                count: info.sample_count,
                 mask: !0,
                 alpha_to_coverage_enabled: false
             },
             multiview: None
         });
+    }
 
-        return RenderPipelineGroup {
-            pipeline: render_pipeline,
-            bind_groups: builder.bind_groups,
-            buffers: builder.buffers,
-            vertex_buffers: builder.vertex_buffers
+    //Pipelines have to match the format and sample count of the render pass, see GameEngine::set_msaa_samples / set_hdr
+    pub fn set_target(&mut self,device:&Device,format:TextureFormat,sample_count:u32) {
+        if self.info.format == format && self.info.sample_count == sample_count {
+            return;
+        }
+        self.info.format = format;
+        self.info.sample_count = sample_count;
+        self.pipeline = RenderPipelineGroup::create_pipeline(device,&self.layout,&self.vertex_shader,&self.fragment_shader,&self.info);
+    }
+
+    pub fn uses_shader(&self,path:&str) -> bool {
+        return self.info.vertex_shader_path.as_deref() == Some(path) || self.info.fragment_shader_path.as_deref() == Some(path);
+    }
+
+    /*
+    Builds the pipeline again from the shader files, the bind groups and buffers stay the same.
+    If a shader fails to compile the current pipeline is kept and the error returned.
+     */
+    pub fn try_reload(&mut self,device:&Device,vfs:&Vfs) -> RglResult<()> {
+        let (Some(vs_path),Some(fs_path)) = (&self.info.vertex_shader_path,&self.info.fragment_shader_path) else {
+            return Ok(());
+        };
+
+        let vs_module = load_glsl_module(device,vfs,vs_path,ShaderStage::Vertex,&format!("{} vertex",self.info.name))?;
+        let fs_module = load_glsl_module(device,vfs,fs_path,ShaderStage::Fragment,&format!("{} fragment",self.info.name))?;
+
+        self.pipeline = with_validation(device,|| RenderPipelineGroup::create_pipeline(device,&self.layout,&vs_module,&fs_module,&self.info))?;
+        self.vertex_shader = vs_module;
+        self.fragment_shader = fs_module;
+        info!("Reloaded pipeline {}",self.info.name);
+
+        return Ok(());
+    }
+}
+
+//Runs f in a validation error scope, so an invalid pipeline (e.g. shaders that do not match the layout) is an error instead of a panic
+pub fn with_validation<T>(device:&Device,f:impl FnOnce() -> T) -> RglResult<T> {
+    device.push_error_scope(ErrorFilter::Validation);
+    let result = f();
+    return match poll_ready(device.pop_error_scope()) {
+        Some(e) => Err(RglError::Gpu(e.to_string())),
+        None => Ok(result)
+    }
+}
+
+//The error scope future of the native backends is ready right away, so there is no need for an executor
+fn poll_ready<F:Future>(future:F) -> F::Output {
+    fn noop_raw_waker() -> RawWaker {
+        fn clone(_:*const ()) -> RawWaker { noop_raw_waker() }
+        fn noop(_:*const ()) {}
+        static VTABLE: RawWakerVTable = RawWakerVTable::new(clone,noop,noop,noop);
+        RawWaker::new(std::ptr::null(),&VTABLE)
+    }
+
+    let waker = unsafe { Waker::from_raw(noop_raw_waker()) };
+    let mut context = Context::from_waker(&waker);
+    let mut future = std::pin::pin!(future);
+    loop {
+        if let Poll::Ready(result) = future.as_mut().poll(&mut context) {
+            return result;
+        }
+        std::thread::yield_now();
+    }
+}
+
+//Reads, validates and compiles a single glsl shader
+pub fn load_glsl_module(device:&Device,vfs:&Vfs,path:&str,stage:ShaderStage,label:&str) -> RglResult<ShaderModule> {
+    let src = vfs.read_to_string(path)?;
+    validate_glsl(path,&src,stage)?;
+
+    return Ok(device.create_shader_module(ShaderModuleDescriptor {
+        label: Some(label),
+        source: ShaderSource::Glsl {
+            shader: Cow::from(src.as_str()),
+            stage,
+            defines: Default::default()
+        }
+    }));
+}
+
+//Used for compute shaders which need features (atomics) the glsl frontend does not support
+pub fn load_wgsl_module(device:&Device,vfs:&Vfs,path:&str,label:&str) -> RglResult<ShaderModule> {
+    let src = vfs.read_to_string(path)?;
+
+    return with_validation(device,|| device.create_shader_module(ShaderModuleDescriptor {
+        label: Some(label),
+        source: ShaderSource::Wgsl(Cow::from(src.as_str()))
+    })).map_err(|e| RglError::parse(path,None,e.to_string()));
+}
+
+pub fn validate_glsl(path:&str,source:&str,stage:ShaderStage) -> RglResult<()> {
+    let mut frontend = naga::front::glsl::Frontend::default();
+    //The error points at the first line, the message lists every error naga found
+    let module = frontend.parse(&naga::front::glsl::Options::from(stage),source).map_err(|errors| {
+        let line = |e:&naga::front::glsl::Error| e.meta.location(source).line_number as usize;
+        let mut message = errors[0].kind.to_string();
+        for e in errors.iter().skip(1) {
+            message += &format!("\n  line {}: {}",line(e),e.kind);
         }
+        RglError::parse(path,Some(line(&errors[0])),message)
+    })?;
+
+    naga::valid::Validator::new(naga::valid::ValidationFlags::all(),naga::valid::Capabilities::all()).validate(&module).map_err(|e| {
+        let line = e.location(source).map(|l| l.line_number as usize);
+        RglError::parse(path,line,e.as_inner().to_string())
+    })?;
+
+    return Ok(());
+}
+
+#[cfg(test)]
+mod tests {
+    use naga::ShaderStage;
+    use super::validate_glsl;
+
+    #[test]
+    fn reports_every_glsl_error() {
+        let source = "#version 450\nlayout(location=0) flat flat out vec4 a;\nlayout(location=1) flat flat out vec4 b;\nvoid main() {}\n";
+        let message = validate_glsl("test.frag",source,ShaderStage::Fragment).unwrap_err().to_string();
+        assert!(message.contains("on line 2"),"{}",message);
+        assert!(message.contains("line 3:"),"{}",message);
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,147 @@
+use naga::ShaderStage;
+use wgpu::{AddressMode, BindGroupLayout, BindGroupLayoutDescriptor, BindGroupLayoutEntry, BindingType, BufferBindingType, BufferSize, ColorTargetState, ColorWrites, Device, FilterMode, FragmentState, FrontFace, MultisampleState, PipelineLayout, PipelineLayoutDescriptor, PolygonMode, PrimitiveState, PrimitiveTopology, RenderPipeline, RenderPipelineDescriptor, Sampler, SamplerBindingType, SamplerDescriptor, ShaderModule, ShaderStages, TextureFormat, TextureSampleType, TextureViewDimension, VertexState};
+use crate::error::rgl_error::RglResult;
+use crate::pipeline::pipeline::{load_glsl_module, with_validation};
+use crate::vfs::vfs::Vfs;
+
+pub const POST_DATA_SIZE: u64 = 64;
+
+/*
+Shared by every post processing pass, user shaders have to declare the same bindings (see shader/post/vignette/fragment.shader).
+set 0: input texture, extra texture (lut, bloom, ... or the input again), sampler, EffectData (screen, time, params, params2) with a dynamic offset
+ */
+pub struct PostProcessLayout {
+    pub layout: BindGroupLayout,
+    pub pipeline_layout: PipelineLayout,
+    pub sampler: Sampler
+}
+
+impl PostProcessLayout {
+    pub fn new(device:&Device) -> PostProcessLayout {
+        let texture_entry = |binding:u32| BindGroupLayoutEntry {
+            binding,
+            visibility: ShaderStages::FRAGMENT,
+            ty: BindingType::Texture { multisampled: false, view_dimension: TextureViewDimension::D2, sample_type: TextureSampleType::Float { filterable: true } },
+            count: None
+        };
+
+        let layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor { label: Some("Post Process Layout"), entries: &[
+            texture_entry(0),
+            texture_entry(1),
+            BindGroupLayoutEntry {
+                binding: 2,
+                visibility: ShaderStages::FRAGMENT,
+                ty: BindingType::Sampler(SamplerBindingType::Filtering),
+                count: None
+            },
+            BindGroupLayoutEntry {
+                binding: 3,
+                visibility: ShaderStages::FRAGMENT,
+                ty: BindingType::Buffer { ty: BufferBindingType::Uniform, has_dynamic_offset: true, min_binding_size: BufferSize::new(POST_DATA_SIZE) },
+                count: None
+            }
+        ] });
+
+        let sampler = device.create_sampler(&SamplerDescriptor {
+            label: Some("Post Process Sampler"),
+            address_mode_u: AddressMode::ClampToEdge,
+            address_mode_v: AddressMode::ClampToEdge,
+            mag_filter: FilterMode::Linear,
+            min_filter: FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("Post Process Pipeline Layout"),
+            bind_group_layouts: &[&layout],
+            push_constant_ranges: &[]
+        });
+
+        return PostProcessLayout { layout, pipeline_layout, sampler };
+    }
+}
+
+//Fullscreen triangle with one fragment shader, see PostProcessor
+pub struct PostProcessPipeline {
+    pub pipeline: RenderPipeline,
+    pub format: TextureFormat,
+    pub vertex_shader_path: String,
+    pub fragment_shader_path: String,
+    vertex_shader: ShaderModule,
+    fragment_shader: ShaderModule
+}
+
+impl PostProcessPipeline {
+    pub fn new(vertex_shader_path:String,fragment_shader_path:String,format:TextureFormat,layout:&PipelineLayout,device:&Device,vfs:&Vfs) -> PostProcessPipeline {
+        return PostProcessPipeline::try_new(vertex_shader_path,fragment_shader_path,format,layout,device,vfs).unwrap_or_else(|e| panic!("{}",e));
+    }
+
+    //layout is PostProcessLayout::pipeline_layout unless the shader needs more bind groups (tonemapping)
+    pub fn try_new(vertex_shader_path:String,fragment_shader_path:String,format:TextureFormat,layout:&PipelineLayout,device:&Device,vfs:&Vfs) -> RglResult<PostProcessPipeline> {
+        let vs_module = load_glsl_module(device,vfs,&vertex_shader_path,ShaderStage::Vertex,"vertex_post")?;
+        let fs_module = load_glsl_module(device,vfs,&fragment_shader_path,ShaderStage::Fragment,"fragment_post")?;
+
+        let pipeline = with_validation(device,|| PostProcessPipeline::create_pipeline(device,layout,&vs_module,&fs_module,format))?;
+
+        return Ok(PostProcessPipeline { pipeline, format, vertex_shader_path, fragment_shader_path, vertex_shader: vs_module, fragment_shader: fs_module });
+    }
+
+    fn create_pipeline(device:&Device,layout:&PipelineLayout,vs_module:&ShaderModule,fs_module:&ShaderModule,format:TextureFormat) -> RenderPipeline {
+        return device.create_render_pipeline(&RenderPipelineDescriptor {
+            label: Some("Post Process"),
+            layout: Some(layout),
+            vertex: VertexState {
+                module: vs_module,
+                entry_point: "main",
+                buffers: &[]
+            },
+            primitive: PrimitiveState {
+                topology: PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: FrontFace::Ccw,
+                cull_mode: None,
+                unclipped_depth: false,
+                polygon_mode: PolygonMode::Fill,
+                conservative: false
+            },
+            depth_stencil: None,
+            fragment: Some(FragmentState {
+                module: fs_module,
+                entry_point: "main",
+                targets: &[Some(ColorTargetState {
+                    format,
+                    blend: None,
+                    write_mask: ColorWrites::ALL
+                })]
+            }),
+            multisample: MultisampleState {
+                count: 1,
+                mask: !0,
+                alpha_to_coverage_enabled: false
+            },
+            multiview: None
+        });
+    }
+
+    pub fn set_format(&mut self,device:&Device,layout:&PipelineLayout,format:TextureFormat) {
+        if self.format == format {
+            return;
+        }
+        self.format = format;
+        self.pipeline = PostProcessPipeline::create_pipeline(device,layout,&self.vertex_shader,&self.fragment_shader,format);
+    }
+
+    pub fn uses_shader(&self,path:&str) -> bool {
+        return self.vertex_shader_path == path || self.fragment_shader_path == path;
+    }
+
+    //Keeps the current pipeline if a shader fails to compile
+    pub fn try_reload(&mut self,device:&Device,vfs:&Vfs,layout:&PipelineLayout) -> RglResult<()> {
+        let vs_module = load_glsl_module(device,vfs,&self.vertex_shader_path,ShaderStage::Vertex,"vertex_post")?;
+        let fs_module = load_glsl_module(device,vfs,&self.fragment_shader_path,ShaderStage::Fragment,"fragment_post")?;
+        self.pipeline = with_validation(device,|| PostProcessPipeline::create_pipeline(device,layout,&vs_module,&fs_module,self.format))?;
+        self.vertex_shader = vs_module;
+        self.fragment_shader = fs_module;
+        return Ok(());
+    }
+}
@@ -0,0 +1,115 @@
+use naga::ShaderStage;
+use wgpu::{BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayoutDescriptor, BindGroupLayoutEntry, BindingResource, BindingType, Buffer, BufferBinding, BufferBindingType, BufferDescriptor, BufferSize, BufferUsages, CompareFunction, DepthBiasState, DepthStencilState, Device, Face, FrontFace, MultisampleState, PipelineLayoutDescriptor, PolygonMode, PrimitiveState, PrimitiveTopology, PipelineLayout, RenderPipeline, ShaderModule, RenderPipelineDescriptor, ShaderStages, StencilState, TextureFormat, VertexState};
+use crate::error::rgl_error::RglResult;
+use crate::light::shadow::MAX_SHADOW_MAPS;
+use crate::objects::camera::Camera;
+use crate::objects::vertex_buffer::NormalVertexBuffer;
+use crate::pipeline::pipeline::{load_glsl_module, with_validation};
+use crate::vfs::vfs::Vfs;
+
+pub const SHADOW_MATRIX_STRIDE: u64 = 256;
+
+/*
+Depth only pipeline for the shadow maps. set 0 holds the light matrix of the current map (dynamic offset),
+set 1 the transforms of the camera like in the vertex pipeline.
+ */
+pub struct ShadowRenderPipelineGroup {
+    pub pipeline: RenderPipeline,
+    pub matrix_buffer: Buffer,
+    pub matrix_bind_group: BindGroup,
+    pub layout: PipelineLayout,
+    pub vertex_shader_path: String
+}
+
+impl ShadowRenderPipelineGroup {
+    pub fn new(vertex_shader_path:String,device:&Device,vfs:&Vfs) -> ShadowRenderPipelineGroup {
+        return ShadowRenderPipelineGroup::try_new(vertex_shader_path,device,vfs).unwrap_or_else(|e| panic!("{}",e));
+    }
+
+    pub fn try_new(vertex_shader_path:String,device:&Device,vfs:&Vfs) -> RglResult<ShadowRenderPipelineGroup> {
+        let vs_module = load_glsl_module(device,vfs,&vertex_shader_path,ShaderStage::Vertex,"vertex_shadow")?;
+
+        let matrix_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("Shadow Matrix Buffer"),
+            size: MAX_SHADOW_MAPS as u64 * SHADOW_MATRIX_STRIDE,
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+            mapped_at_creation: false
+        });
+
+        let matrix_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor { label: None, entries: &[BindGroupLayoutEntry {
+            binding: 0,
+            visibility: ShaderStages::VERTEX,
+            ty: BindingType::Buffer { ty: BufferBindingType::Uniform, has_dynamic_offset: true, min_binding_size: BufferSize::new(64) },
+            count: None
+        }] });
+
+        let matrix_bind_group = device.create_bind_group(&BindGroupDescriptor {
+            label: None,
+            layout: &matrix_layout,
+            entries: &[BindGroupEntry { binding: 0, resource: BindingResource::Buffer(BufferBinding {
+                buffer: &matrix_buffer,
+                offset: 0,
+                size: BufferSize::new(64)
+            })}]
+        });
+
+        let transform_layout = Camera::transform_bind_group(device);
+
+        let layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("Shadow Pipeline Layout"),
+            bind_group_layouts: &[&matrix_layout,&transform_layout],
+            push_constant_ranges: &[]
+        });
+
+        let pipeline = ShadowRenderPipelineGroup::create_pipeline(device,&layout,&vs_module);
+
+        return Ok(ShadowRenderPipelineGroup { pipeline, matrix_buffer, matrix_bind_group, layout, vertex_shader_path });
+    }
+
+    fn create_pipeline(device:&Device,layout:&PipelineLayout,vs_module:&ShaderModule) -> RenderPipeline {
+        return device.create_render_pipeline(&RenderPipelineDescriptor {
+            label: Some("Shadow"),
+            layout: Some(layout),
+            vertex: VertexState {
+                module: vs_module,
+                entry_point: "main",
+                buffers: &[NormalVertexBuffer::desc()]
+            },
+            primitive: PrimitiveState {
+                topology: PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: FrontFace::Ccw,
+                //Back faces cause less acne than front faces
+                cull_mode: Some(Face::Front),
+                unclipped_depth: false,
+                polygon_mode: PolygonMode::Fill,
+                conservative: false
+            },
+            depth_stencil: Some(DepthStencilState {
+                format: TextureFormat::Depth32Float,
+                depth_write_enabled: true,
+                depth_compare: CompareFunction::LessEqual,
+                stencil: StencilState::default(),
+                bias: DepthBiasState {
+                    constant: 2,
+                    slope_scale: 2.0,
+                    clamp: 0.0
+                }
+            }),
+            fragment: None,
+            multisample: MultisampleState {
+                count: 1,
+                mask: !0,
+                alpha_to_coverage_enabled: false
+            },
+            multiview: None
+        });
+    }
+
+    //Keeps the current pipeline if the shader fails to compile
+    pub fn try_reload(&mut self,device:&Device,vfs:&Vfs) -> RglResult<()> {
+        let vs_module = load_glsl_module(device,vfs,&self.vertex_shader_path,ShaderStage::Vertex,"vertex_shadow")?;
+        self.pipeline = with_validation(device,|| ShadowRenderPipelineGroup::create_pipeline(device,&self.layout,&vs_module))?;
+        return Ok(());
+    }
+}
\ No newline at end of file
@@ -2,6 +2,7 @@ use crate::pipeline::pipeline::{RenderPipelineGroup, RenderPipelineGroupBuilder}
 use wgpu::{Buffer, Device, BindGroupLayout, BindGroupLayoutDescriptor, BindGroupLayoutEntry, BindingType, BindGroupDescriptor, BindGroupEntry, BindingResource, PrimitiveTopology, QUERY_RESOLVE_BUFFER_ALIGNMENT, BufferBindingType, Limits, BufferBinding, BufferSize, ShaderStages, Surface, SurfaceConfiguration};
 use crate::objects::camera::Camera;
 use nalgebra_glm::vec3;
+use crate::vfs::vfs::Vfs;
 use crate::objects::vertex_buffer::{OnlyCoordsVertexBuffer, VertexBuffer};
 
 pub struct TextureRenderPipelineGroup {
@@ -10,10 +11,10 @@ pub struct TextureRenderPipelineGroup {
 }
 
 impl TextureRenderPipelineGroup {
-    pub fn new(vertex_shader_path : String, fragment_shader_path: String,config:&SurfaceConfiguration,device:&Device) -> TextureRenderPipelineGroup {
+    pub fn new(vertex_shader_path : String, fragment_shader_path: String,config:&SurfaceConfiguration,device:&Device,vfs:&Vfs) -> TextureRenderPipelineGroup {
 
         let mut group2_builder = RenderPipelineGroupBuilder::empty();
-        group2_builder.set_shaders(&device,vertex_shader_path,fragment_shader_path,"vertex_sprite".to_string(),"fragment_sprite".to_string());
+        group2_builder.set_shaders(&device,vfs,vertex_shader_path,fragment_shader_path,"vertex_sprite".to_string(),"fragment_sprite".to_string());
 
         let texture_bind_group_layout = group2_builder.create_texture_bind_group_layout(device);
         group2_builder.bind_groups_layouts.push(texture_bind_group_layout);
@@ -0,0 +1,139 @@
+use naga::ShaderStage;
+use wgpu::{BindGroupLayout, BindGroupLayoutDescriptor, BindGroupLayoutEntry, BindingType, Buffer, BufferBindingType, BufferDescriptor, BufferSize, BufferUsages, ColorTargetState, ColorWrites, Device, FilterMode, FragmentState, FrontFace, MultisampleState, PipelineLayout, PipelineLayoutDescriptor, PolygonMode, PrimitiveState, PrimitiveTopology, RenderPipeline, RenderPipelineDescriptor, Sampler, SamplerBindingType, SamplerDescriptor, ShaderModule, ShaderStages, TextureFormat, TextureSampleType, TextureViewDimension, VertexState};
+use crate::error::rgl_error::RglResult;
+use crate::pipeline::pipeline::{load_glsl_module, with_validation};
+use crate::vfs::vfs::Vfs;
+
+pub const TRANSITION_DATA_SIZE: u64 = 48;
+
+/*
+Fullscreen pass blending two textures (outgoing and incoming scene).
+set 0: from texture, to texture, sampler, TransitionData (color, params, direction)
+ */
+pub struct TransitionRenderPipelineGroup {
+    pub pipeline: RenderPipeline,
+    pub layout: BindGroupLayout,
+    pub sampler: Sampler,
+    pub data_buffer: Buffer,
+    pub pipeline_layout: PipelineLayout,
+    pub format: TextureFormat,
+    pub vertex_shader_path: String,
+    pub fragment_shader_path: String,
+    vertex_shader: ShaderModule,
+    fragment_shader: ShaderModule
+}
+
+impl TransitionRenderPipelineGroup {
+    pub fn new(vertex_shader_path:String,fragment_shader_path:String,format:TextureFormat,device:&Device,vfs:&Vfs) -> TransitionRenderPipelineGroup {
+        return TransitionRenderPipelineGroup::try_new(vertex_shader_path,fragment_shader_path,format,device,vfs).unwrap_or_else(|e| panic!("{}",e));
+    }
+
+    pub fn try_new(vertex_shader_path:String,fragment_shader_path:String,format:TextureFormat,device:&Device,vfs:&Vfs) -> RglResult<TransitionRenderPipelineGroup> {
+        let vs_module = load_glsl_module(device,vfs,&vertex_shader_path,ShaderStage::Vertex,"vertex_transition")?;
+        let fs_module = load_glsl_module(device,vfs,&fragment_shader_path,ShaderStage::Fragment,"fragment_transition")?;
+
+        let texture_entry = |binding:u32| BindGroupLayoutEntry {
+            binding,
+            visibility: ShaderStages::FRAGMENT,
+            ty: BindingType::Texture { multisampled: false, view_dimension: TextureViewDimension::D2, sample_type: TextureSampleType::Float { filterable: true } },
+            count: None
+        };
+
+        let layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor { label: Some("Transition Layout"), entries: &[
+            texture_entry(0),
+            texture_entry(1),
+            BindGroupLayoutEntry {
+                binding: 2,
+                visibility: ShaderStages::FRAGMENT,
+                ty: BindingType::Sampler(SamplerBindingType::Filtering),
+                count: None
+            },
+            BindGroupLayoutEntry {
+                binding: 3,
+                visibility: ShaderStages::FRAGMENT,
+                ty: BindingType::Buffer { ty: BufferBindingType::Uniform, has_dynamic_offset: false, min_binding_size: BufferSize::new(TRANSITION_DATA_SIZE) },
+                count: None
+            }
+        ] });
+
+        let sampler = device.create_sampler(&SamplerDescriptor {
+            label: Some("Transition Sampler"),
+            mag_filter: FilterMode::Linear,
+            min_filter: FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let data_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("Transition Data"),
+            size: TRANSITION_DATA_SIZE,
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+            mapped_at_creation: false
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("Transition Pipeline Layout"),
+            bind_group_layouts: &[&layout],
+            push_constant_ranges: &[]
+        });
+
+        let pipeline = TransitionRenderPipelineGroup::create_pipeline(device,&pipeline_layout,&vs_module,&fs_module,format);
+
+        return Ok(TransitionRenderPipelineGroup { pipeline, layout, sampler, data_buffer, pipeline_layout, format, vertex_shader_path, fragment_shader_path, vertex_shader: vs_module, fragment_shader: fs_module });
+    }
+
+    fn create_pipeline(device:&Device,layout:&PipelineLayout,vs_module:&ShaderModule,fs_module:&ShaderModule,format:TextureFormat) -> RenderPipeline {
+        return device.create_render_pipeline(&RenderPipelineDescriptor {
+            label: Some("Transition"),
+            layout: Some(layout),
+            vertex: VertexState {
+                module: vs_module,
+                entry_point: "main",
+                buffers: &[]
+            },
+            primitive: PrimitiveState {
+                topology: PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: FrontFace::Ccw,
+                cull_mode: None,
+                unclipped_depth: false,
+                polygon_mode: PolygonMode::Fill,
+                conservative: false
+            },
+            depth_stencil: None,
+            fragment: Some(FragmentState {
+                module: fs_module,
+                entry_point: "main",
+                targets: &[Some(ColorTargetState {
+                    format,
+                    blend: None,
+                    write_mask: ColorWrites::ALL
+                })]
+            }),
+            multisample: MultisampleState {
+                count: 1,
+                mask: !0,
+                alpha_to_coverage_enabled: false
+            },
+            multiview: None
+        });
+    }
+
+    //The transition renders into the render format of the engine, see GameEngine::set_hdr
+    pub fn set_format(&mut self,device:&Device,format:TextureFormat) {
+        if self.format == format {
+            return;
+        }
+        self.format = format;
+        self.pipeline = TransitionRenderPipelineGroup::create_pipeline(device,&self.pipeline_layout,&self.vertex_shader,&self.fragment_shader,format);
+    }
+
+    //Keeps the current pipeline if a shader fails to compile
+    pub fn try_reload(&mut self,device:&Device,vfs:&Vfs) -> RglResult<()> {
+        let vs_module = load_glsl_module(device,vfs,&self.vertex_shader_path,ShaderStage::Vertex,"vertex_transition")?;
+        let fs_module = load_glsl_module(device,vfs,&self.fragment_shader_path,ShaderStage::Fragment,"fragment_transition")?;
+        self.pipeline = with_validation(device,|| TransitionRenderPipelineGroup::create_pipeline(device,&self.pipeline_layout,&vs_module,&fs_module,self.format))?;
+        self.vertex_shader = vs_module;
+        self.fragment_shader = fs_module;
+        return Ok(());
+    }
+}
\ No newline at end of file
@@ -1,39 +1,150 @@
 use nalgebra_glm::{vec3, vec4};
-use wgpu::{BindGroupDescriptor, BindGroupEntry, BindGroupLayoutDescriptor, BindGroupLayoutEntry, BindingResource, BindingType, Buffer, BufferBinding, BufferBindingType, BufferSize, Device, PrimitiveTopology, ShaderStages, Surface, SurfaceConfiguration};
-use crate::light::light::Lights;
+use wgpu::{SamplerBindingType, TextureSampleType, TextureViewDimension, BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayoutDescriptor, BindGroupLayoutEntry, BindingResource, BindGroupLayout, BindingType, Buffer, BufferBinding, BufferDescriptor, BufferUsages, BufferBindingType, BufferSize, Device, Queue, PrimitiveTopology, ShaderStages, Surface, SurfaceConfiguration};
+use wgpu::util::{BufferInitDescriptor, DeviceExt};
+use crate::light::shadow::{MAX_SHADOW_MAPS, SHADOW_ATLAS_SIZE, SHADOW_MAP_SIZE};
+use crate::objects::depth_texture::DepthTexture;
+use crate::light::light::{Lights, DEFAULT_MAX_LIGHTS, LIGHT_SIZE, LIGHTS_HEADER_SIZE};
 use crate::pipeline::pipeline::{RenderPipelineGroup, RenderPipelineGroupBuilder};
 use crate::objects::camera::Camera;
 use crate::objects::obj_model::Material;
 use crate::objects::vertex::NormalVertex;
-use crate::objects::vertex_buffer::{NormalVertexBuffer, VertexBuffer};
+use crate::objects::vertex_buffer::{NormalVertexBuffer, SkinnedVertexBuffer, VertexBuffer};
+use crate::objects::offset_handler::StaticOffsetHandler;
+use crate::vfs::vfs::Vfs;
+use crate::animation::skeleton::MAX_JOINTS;
+
+pub const JOINT_SLOTS: i32 = 64;
+pub const JOINT_SLOT_SIZE: u64 = MAX_JOINTS as u64 * 64;
 
 pub struct VertexRenderPipelineGroup {
     pub group: RenderPipelineGroup,
+    pub skinned_group: RenderPipelineGroup,
     pub light_buffer: Buffer,
     pub material_buffer: Buffer,
-    pub cam_test_buffer: Buffer
+    pub cam_test_buffer: Buffer,
+    pub joint_buffer: Buffer,
+    pub joint_offset_handler: StaticOffsetHandler,
+    pub light_capacity: usize,
+    pub light_material_layout: BindGroupLayout,
+    pub shadow_atlas: DepthTexture,
+    pub shadow_buffer: Buffer
 }
 
 impl VertexRenderPipelineGroup {
-    pub fn new(vertex_shader_path : String, fragment_shader_path: String,config:&SurfaceConfiguration,device:&Device) -> VertexRenderPipelineGroup {
+    pub fn new(vertex_shader_path : String, fragment_shader_path: String,skinned_vertex_shader_path : String, skinned_fragment_shader_path: String,config:&SurfaceConfiguration,device:&Device,vfs:&Vfs) -> VertexRenderPipelineGroup {
 
         let mut group2_builder = RenderPipelineGroupBuilder::empty();
-        group2_builder.set_shaders(&device,vertex_shader_path,fragment_shader_path,"vertex".to_string(),"fragment".to_string());
+        group2_builder.set_shaders(&device,vfs,vertex_shader_path,fragment_shader_path,"vertex".to_string(),"fragment".to_string());
 
         let texture_bind_group_layout = group2_builder.create_texture_bind_group_layout(device);
         group2_builder.bind_groups_layouts.push(texture_bind_group_layout);
         group2_builder.bind_groups_layouts.push(Camera::bind_group_layout(device));
         group2_builder.bind_groups_layouts.push(Camera::transform_bind_group(device));
 
-        let light_buffer = crate::objects::matrix_helper::add_buffer(device,&Lights::new().data);
+        let light_buffer = VertexRenderPipelineGroup::create_light_buffer(device,DEFAULT_MAX_LIGHTS);
         let material_buffer = crate::objects::matrix_helper::add_dynamic_buffer(device,50 * 256);
         let cam_test_buffer = crate::objects::matrix_helper::add_buffer(device,&crate::objects::matrix_helper::get_bytes_from_vec4(&vec4(0.0,0.0,0.0,0.0)));
+        let joint_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("Joint Buffer"),
+            size: JOINT_SLOTS as u64 * JOINT_SLOT_SIZE,
+            usage: BufferUsages::STORAGE | BufferUsages::COPY_DST | BufferUsages::COPY_SRC,
+            mapped_at_creation: false
+        });
+
+        let shadow_atlas = DepthTexture::new_with_size(device,SHADOW_ATLAS_SIZE,SHADOW_ATLAS_SIZE);
+        let shadow_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("Shadow Buffer"),
+            size: MAX_SHADOW_MAPS as u64 * SHADOW_MAP_SIZE,
+            usage: BufferUsages::STORAGE | BufferUsages::COPY_DST,
+            mapped_at_creation: false
+        });
+
+        let light_material_group_layout = VertexRenderPipelineGroup::create_light_material_layout(device);
+        let light_material_group = VertexRenderPipelineGroup::create_light_material_group(device,&light_material_group_layout,&light_buffer,&material_buffer,&cam_test_buffer,&joint_buffer,&shadow_atlas,&shadow_buffer);
+
+        group2_builder.add_bind_group(light_material_group,light_material_group_layout);
+
+        let group2 = group2_builder.build(device,config,NormalVertexBuffer::desc(),PrimitiveTopology::TriangleList,true,"Vertex".to_string());
+
+        //Same layouts as the normal pipeline, so the bind groups above can be used for both
+        let mut skinned_builder = RenderPipelineGroupBuilder::empty();
+        skinned_builder.set_shaders(&device,vfs,skinned_vertex_shader_path,skinned_fragment_shader_path,"vertex_skinned".to_string(),"fragment_skinned".to_string());
+        skinned_builder.bind_groups_layouts.push(skinned_builder.create_texture_bind_group_layout(device));
+        skinned_builder.bind_groups_layouts.push(Camera::bind_group_layout(device));
+        skinned_builder.bind_groups_layouts.push(Camera::transform_bind_group(device));
+        skinned_builder.bind_groups_layouts.push(VertexRenderPipelineGroup::create_light_material_layout(device));
+
+        let skinned_group = skinned_builder.build(device,config,SkinnedVertexBuffer::desc(),PrimitiveTopology::TriangleList,true,"Skinned Vertex".to_string());
+
+        return VertexRenderPipelineGroup { group: group2, skinned_group, light_buffer, material_buffer, cam_test_buffer, joint_buffer, joint_offset_handler: StaticOffsetHandler::new_with_stride(JOINT_SLOTS,0,JOINT_SLOT_SIZE),
+            light_capacity: DEFAULT_MAX_LIGHTS, light_material_layout: VertexRenderPipelineGroup::create_light_material_layout(device), shadow_atlas, shadow_buffer }
+    }
+
+    //Recreates the light buffer (and the bind group using it) if it can not hold the given amount of lights
+    pub fn ensure_light_capacity(&mut self, device:&Device, lights:usize) {
+        if lights <= self.light_capacity {
+            return;
+        }
+
+        self.light_capacity = lights.max(self.light_capacity * 2);
+        self.light_buffer = VertexRenderPipelineGroup::create_light_buffer(device,self.light_capacity);
+        self.group.bind_groups[0] = VertexRenderPipelineGroup::create_light_material_group(device,&self.light_material_layout,&self.light_buffer,&self.material_buffer,&self.cam_test_buffer,&self.joint_buffer,&self.shadow_atlas,&self.shadow_buffer);
+    }
+
+    //Grows the material and joint buffers (copying their content) if their offset handlers need more space
+    pub fn ensure_buffer_capacity(&mut self, device:&Device, queue:&Queue, material_size:u64) {
+        let joint_size = self.joint_offset_handler.get_required_size();
+        if self.material_buffer.size() >= material_size && self.joint_buffer.size() >= joint_size {
+            return;
+        }
+
+        if self.material_buffer.size() < material_size {
+            self.material_buffer = crate::objects::matrix_helper::grow_buffer(device,queue,&self.material_buffer,material_size);
+        }
+        if self.joint_buffer.size() < joint_size {
+            self.joint_buffer = crate::objects::matrix_helper::grow_buffer(device,queue,&self.joint_buffer,joint_size);
+        }
+        self.group.bind_groups[0] = VertexRenderPipelineGroup::create_light_material_group(device,&self.light_material_layout,&self.light_buffer,&self.material_buffer,&self.cam_test_buffer,&self.joint_buffer,&self.shadow_atlas,&self.shadow_buffer);
+    }
+
+    fn create_light_buffer(device:&Device, capacity:usize) -> Buffer {
+        //Starts with the default ambient light and no lights
+        let mut data = Lights::new().get_data();
+        data.resize((LIGHTS_HEADER_SIZE + capacity.max(1) as u64 * LIGHT_SIZE) as usize,0);
+        return device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("Light Buffer"),
+            contents: &data,
+            usage: BufferUsages::STORAGE | BufferUsages::COPY_DST
+        });
+    }
+
+    fn create_light_material_group(device:&Device, layout:&BindGroupLayout, light_buffer:&Buffer, material_buffer:&Buffer, cam_test_buffer:&Buffer, joint_buffer:&Buffer, shadow_atlas:&DepthTexture, shadow_buffer:&Buffer) -> BindGroup {
+        return device.create_bind_group(&BindGroupDescriptor {
+            label: None,
+            layout,
+            entries: &[BindGroupEntry { binding: 0, resource: light_buffer.as_entire_binding()},
+                BindGroupEntry { binding: 1, resource: BindingResource::Buffer(BufferBinding {
+                    buffer: material_buffer,
+                    offset: 0,
+                    size: BufferSize::new(64) })
+                },BindGroupEntry { binding: 2, resource: cam_test_buffer.as_entire_binding()},
+                BindGroupEntry { binding: 3, resource: BindingResource::Buffer(BufferBinding {
+                    buffer: joint_buffer,
+                    offset: 0,
+                    size: BufferSize::new(JOINT_SLOT_SIZE) })
+                },
+                BindGroupEntry { binding: 4, resource: BindingResource::TextureView(&shadow_atlas.view) },
+                BindGroupEntry { binding: 5, resource: BindingResource::Sampler(&shadow_atlas.sampler) },
+                BindGroupEntry { binding: 6, resource: shadow_buffer.as_entire_binding() }]
+        });
+    }
 
-        let light_material_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor { label: None, entries: &[
+    pub fn create_light_material_layout(device:&Device) -> BindGroupLayout {
+        return device.create_bind_group_layout(&BindGroupLayoutDescriptor { label: None, entries: &[
             BindGroupLayoutEntry {
             binding: 0,
             visibility: ShaderStages::FRAGMENT,
-            ty: BindingType::Buffer { ty: BufferBindingType::Uniform, has_dynamic_offset: false, min_binding_size: None },
+            ty: BindingType::Buffer { ty: BufferBindingType::Storage { read_only: true }, has_dynamic_offset: false, min_binding_size: BufferSize::new(LIGHTS_HEADER_SIZE + LIGHT_SIZE) },
             count: None
         }, BindGroupLayoutEntry {
                 binding: 1,
@@ -45,25 +156,28 @@ impl VertexRenderPipelineGroup {
                 visibility: ShaderStages::FRAGMENT,
                 ty: BindingType::Buffer { ty: BufferBindingType::Uniform, has_dynamic_offset: false, min_binding_size: None },
                 count: None
+            }, BindGroupLayoutEntry {
+                binding: 3,
+                visibility: ShaderStages::VERTEX,
+                ty: BindingType::Buffer { ty: BufferBindingType::Storage { read_only: true }, has_dynamic_offset: true, min_binding_size: BufferSize::new(JOINT_SLOT_SIZE) },
+                count: None
+            }, BindGroupLayoutEntry {
+                binding: 4,
+                visibility: ShaderStages::FRAGMENT,
+                ty: BindingType::Texture { sample_type: TextureSampleType::Depth, view_dimension: TextureViewDimension::D2, multisampled: false },
+                count: None
+            }, BindGroupLayoutEntry {
+                binding: 5,
+                visibility: ShaderStages::FRAGMENT,
+                ty: BindingType::Sampler(SamplerBindingType::Comparison),
+                count: None
+            }, BindGroupLayoutEntry {
+                binding: 6,
+                visibility: ShaderStages::FRAGMENT,
+                ty: BindingType::Buffer { ty: BufferBindingType::Storage { read_only: true }, has_dynamic_offset: false, min_binding_size: BufferSize::new(SHADOW_MAP_SIZE) },
+                count: None
             }
         ]
         });
-
-        let light_material_group = device.create_bind_group(&BindGroupDescriptor {
-            label: None,
-            layout: &light_material_group_layout,
-            entries: &[BindGroupEntry { binding: 0, resource: light_buffer.as_entire_binding()},
-                BindGroupEntry { binding: 1, resource: BindingResource::Buffer(BufferBinding {
-                    buffer: &material_buffer,
-                    offset: 0,
-                    size: BufferSize::new(64) })
-                },BindGroupEntry { binding: 2, resource: cam_test_buffer.as_entire_binding()},]
-        });
-
-        group2_builder.add_bind_group(light_material_group,light_material_group_layout);
-
-        let group2 = group2_builder.build(device,config,NormalVertexBuffer::desc(),PrimitiveTopology::TriangleList,true,"Vertex".to_string());
-
-        return VertexRenderPipelineGroup { group: group2, light_buffer, material_buffer, cam_test_buffer }
     }
 }
\ No newline at end of file
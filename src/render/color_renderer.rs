@@ -8,7 +8,9 @@ use crate::objects::vertex::OnlyCoordsVertex;
 use std::ops::Range;
 use crate::engine::game_engine::GameEngine;
 use crate::objects::offset_handler::StaticOffsetHandler;
+use crate::vfs::vfs::Vfs;
 use crate::objects::transform::Transform;
+use crate::engine::profiler::{count_pipeline_switch, scope, write_uniform};
 
 pub struct ColorRenderer {
     pub color_shader: ColorRenderPipelineGroup,
@@ -18,9 +20,9 @@ pub struct ColorRenderer {
 }
 
 impl ColorRenderer {
-    pub fn new(wd:String,device:&Device,config:&SurfaceConfiguration) -> ColorRenderer {
+    pub fn new(vfs:&Vfs,device:&Device,config:&SurfaceConfiguration) -> ColorRenderer {
         unsafe {
-            let group = ColorRenderPipelineGroup::new(format!("{}\\{}",wd.clone(),"assets\\shader\\color\\vertex.shader"),format!("{}\\{}",wd.clone(),"assets\\shader\\color\\fragment.shader"),device,config,true);
+            let group = ColorRenderPipelineGroup::new("assets/shader/color/vertex.shader".to_string(),"assets/shader/color/fragment.shader".to_string(),device,config,true,vfs);
             let mesh = OnlyCoordsVertexBuffer::default_vertex_buffer(device,false);
             return ColorRenderer { color_shader: group, mesh, to_render: vec![], color_offset_handler: StaticOffsetHandler::new(20,20) }
         }
@@ -28,43 +30,46 @@ impl ColorRenderer {
 
 
     pub fn render_color_queue<'a>(&'a mut self, offset:&mut BufferAddress, color:&Color, pos:&TVec3<f32>, scale:&TVec3<f32>, camera:&'a Camera, queue:&Queue) {
-        queue.write_buffer(&camera.buffers[2],*offset,&*crate::objects::matrix_helper::get_bytes(&nalgebra_glm::scale(&nalgebra_glm::translation(pos),scale)));
-        queue.write_buffer(&self.color_shader.color_buffer,self.to_render.len() as u64 * 256,&*crate::objects::matrix_helper::get_bytes_from_vec4(&vec4(color.r,color.g,color.b,color.a)));
+        write_uniform(queue,camera.get_transform_buffer(),*offset,&*crate::objects::matrix_helper::get_bytes(&nalgebra_glm::scale(&nalgebra_glm::translation(pos),scale)));
+        write_uniform(queue,&self.color_shader.color_buffer,self.to_render.len() as u64 * 256,&*crate::objects::matrix_helper::get_bytes_from_vec4(&vec4(color.r,color.g,color.b,color.a)));
         self.to_render.push(*offset);
         *offset += 256;
     }
 
     pub fn render_color_queue_transform<'a>(&'a mut self, offset:&mut BufferAddress, color:&Color,transform:&Transform, camera:&'a Camera, queue:&Queue) {
-        queue.write_buffer(&camera.buffers[2],*offset,&*crate::objects::matrix_helper::get_bytes(&transform.matrix));
-        queue.write_buffer(&self.color_shader.color_buffer,self.to_render.len() as u64 * 256,&*crate::objects::matrix_helper::get_bytes_from_vec4(&vec4(color.r,color.g,color.b,color.a)));
+        write_uniform(queue,camera.get_transform_buffer(),*offset,&*crate::objects::matrix_helper::get_bytes(&transform.matrix));
+        write_uniform(queue,&self.color_shader.color_buffer,self.to_render.len() as u64 * 256,&*crate::objects::matrix_helper::get_bytes_from_vec4(&vec4(color.r,color.g,color.b,color.a)));
         self.to_render.push(*offset);
         *offset += 256;
     }
 
     pub fn render_color_queue_matrix<'a>(&'a mut self, offset:&mut BufferAddress, color:&Color,matrix:&Mat4, camera:&'a Camera, queue:&Queue) {
-        queue.write_buffer(&camera.buffers[2],*offset,&*crate::objects::matrix_helper::get_bytes(matrix));
-        queue.write_buffer(&self.color_shader.color_buffer,self.to_render.len() as u64 * 256,&*crate::objects::matrix_helper::get_bytes_from_vec4(&vec4(color.r,color.g,color.b,color.a)));
+        write_uniform(queue,camera.get_transform_buffer(),*offset,&*crate::objects::matrix_helper::get_bytes(matrix));
+        write_uniform(queue,&self.color_shader.color_buffer,self.to_render.len() as u64 * 256,&*crate::objects::matrix_helper::get_bytes_from_vec4(&vec4(color.r,color.g,color.b,color.a)));
         self.to_render.push(*offset);
         *offset += 256;
     }
 
     pub fn begin<'a>(&'a self,render_pass:&mut RenderPass<'a>,camera:&'a Camera){
         render_pass.set_pipeline(&self.color_shader.group.pipeline);
+        count_pipeline_switch();
         render_pass.set_bind_group(1,&camera.bind_group,&[]);
     }
 
     pub fn finish_custom<'a>(&'a self,render_pass:&mut RenderPass<'a>,camera:&'a Camera,camera_offset:u32,color_offset:u32, mesh:&'a OnlyCoordsVertexBuffer){
         render_pass.set_bind_group(0,&self.color_shader.group.bind_groups[0],&[color_offset]);
-        render_pass.set_bind_group(2,&camera.transform_bind_group,&[camera_offset]);
+        render_pass.set_bind_group(2,camera.get_transform_group(),&[camera_offset]);
         mesh.render(render_pass);
     }
 
     pub fn finish<'a>(&'a self,render_pass:&mut RenderPass<'a>,camera:&'a Camera,range:Range<usize>){
+        let _scope = scope("ColorRenderer::finish");
         render_pass.set_pipeline(&self.color_shader.group.pipeline);
+        count_pipeline_switch();
         render_pass.set_bind_group(1,&camera.bind_group,&[]);
         for (i,off) in self.to_render[range.clone()].iter().enumerate() {
             render_pass.set_bind_group(0,&self.color_shader.group.bind_groups[0],&[((i as u32 + range.start as u32) * 256 as u32) as u32]);
-            render_pass.set_bind_group(2,&camera.transform_bind_group,&[*off as u32]);
+            render_pass.set_bind_group(2,camera.get_transform_group(),&[*off as u32]);
             self.mesh.render(render_pass);
         }
         //self.to_render = 0;
@@ -4,16 +4,18 @@ use std::rc::Rc;
 use crate::objects::texture_object::TextureObject;
 use crate::objects::camera::Camera;
 use crate::pipeline::custom_vertex_render_pipeline::CustomVertexRenderPipelineGroup;
+use crate::vfs::vfs::Vfs;
 use crate::objects::vertex_buffer::VertexBuffer;
+use crate::engine::profiler::count_pipeline_switch;
 
 pub struct CustomVertexRenderer {
     pub shader: CustomVertexRenderPipelineGroup
 }
 
 impl CustomVertexRenderer {
-    pub fn new(working_dir:String,device:&Device,config:&SurfaceConfiguration) -> CustomVertexRenderer {
+    pub fn new(vfs:&Vfs,device:&Device,config:&SurfaceConfiguration) -> CustomVertexRenderer {
         unsafe {
-            let shader = CustomVertexRenderPipelineGroup::new(format!("{}\\{}", &working_dir.to_string(), "assets\\shader\\custom_vertex\\vertex.shader"), format!("{}\\{}", &working_dir.to_string(), "assets\\shader\\custom_vertex\\fragment.shader"), config, device);
+            let shader = CustomVertexRenderPipelineGroup::new("assets/shader/custom_vertex/vertex.shader".to_string(), "assets/shader/custom_vertex/fragment.shader".to_string(), config, device, vfs);
 
             return CustomVertexRenderer { shader }
         }
@@ -21,22 +23,24 @@ impl CustomVertexRenderer {
 
     pub fn begin<'a>(&'a self, render_pass:&mut RenderPass<'a>, camera:&'a Camera) {
         render_pass.set_pipeline(&self.shader.group.pipeline);
+        count_pipeline_switch();
         render_pass.set_bind_group(1,&camera.bind_group,&[]);
     }
 
     pub fn render_textureless<'a>(&'a self, render_pass:&mut RenderPass<'a>, camera:&'a Camera, offset: u32, mesh: &'a VertexBuffer) {
-        render_pass.set_bind_group(2,&camera.transform_bind_group,&[offset]);
+        render_pass.set_bind_group(2,camera.get_transform_group(),&[offset]);
         mesh.render(render_pass);
     }
 
     pub fn render<'a>(&'a self, render_pass:&mut RenderPass<'a>, camera:&'a Camera,tex:&'a Rc<TextureObject>, offset: u32, mesh: &'a VertexBuffer) {
         render_pass.set_bind_group(0,&tex.bind_group,&[]);
-        render_pass.set_bind_group(2,&camera.transform_bind_group,&[offset]);
+        render_pass.set_bind_group(2,camera.get_transform_group(),&[offset]);
         mesh.render(render_pass);
     }
 
     pub fn begin_depth<'a>(&'a self, render_pass:&mut RenderPass<'a>, camera:&'a Camera) {
         render_pass.set_pipeline(&self.shader.group_depth.pipeline);
+        count_pipeline_switch();
         render_pass.set_bind_group(1,&camera.bind_group,&[]);
     }
 }
\ No newline at end of file
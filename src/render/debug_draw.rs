@@ -0,0 +1,377 @@
+use std::cell::Cell;
+use nalgebra_glm::{vec2, vec3, Mat4, TVec2, TVec3, TVec4};
+use wgpu::{Buffer, BufferAddress, BufferDescriptor, BufferUsages, Device, PrimitiveTopology, Queue, RenderPass, SurfaceConfiguration, VertexBufferLayout};
+use crate::engine::profiler::{count_draw, count_pipeline_switch, scope};
+use crate::objects::aabb::{RoundBB, AABB};
+use crate::objects::camera::Camera;
+use crate::objects::color::Color;
+use crate::pipeline::pipeline::{RenderPipelineGroup, RenderPipelineGroupBuilder};
+use crate::vfs::vfs::Vfs;
+
+const VERTEX_SIZE: u64 = 10 * 4;
+const CIRCLE_SEGMENTS: usize = 32;
+
+//Color, lifetime and depth test of the queued shapes
+#[derive(Clone)]
+pub struct DebugStyle {
+    pub color: Color,
+    //Seconds the shape stays, 0 draws it for one frame
+    pub duration: f32,
+    //false draws the shape on top of everything
+    pub depth_test: bool
+}
+
+impl DebugStyle {
+    pub fn new(color:Color) -> Self {
+        DebugStyle { color, duration: 0.0, depth_test: true }
+    }
+
+    pub fn duration(mut self, duration:f32) -> Self {
+        self.duration = duration;
+        self
+    }
+
+    pub fn depth_test(mut self, depth_test:bool) -> Self {
+        self.depth_test = depth_test;
+        self
+    }
+
+    pub fn with_color(&self, color:Color) -> Self {
+        DebugStyle { color, duration: self.duration, depth_test: self.depth_test }
+    }
+}
+
+struct DebugLine {
+    start: TVec3<f32>,
+    end: TVec3<f32>,
+    //View space offsets of text labels, zero for everything else
+    start_offset: TVec2<f32>,
+    end_offset: TVec2<f32>,
+    color: [f32;4],
+    on_top: bool,
+    remaining: f32
+}
+
+/*
+Immediate mode debug shapes (boxes, spheres, arrows, grids, frustums, text, ...) made of colored lines.
+Everything queued is uploaded by the runners before rendering and drawn once per frame with one draw call
+by the first World that renders (see render), scenes without a World can call render in their own pass with a depth attachment.
+ */
+pub struct DebugDraw {
+    pub enabled: bool,
+    pub group: RenderPipelineGroup,
+    lines: Vec<DebugLine>,
+    vertex_buffer: Buffer,
+    vertex_count: u32,
+    drawn: Cell<bool>
+}
+
+impl DebugDraw {
+    pub fn new(vfs:&Vfs,device:&Device,config:&SurfaceConfiguration) -> DebugDraw {
+        let mut group_builder = RenderPipelineGroupBuilder::empty();
+        group_builder.set_shaders(device,vfs,"assets/shader/debug/vertex.shader".to_string(),"assets/shader/debug/fragment.shader".to_string(),"vertex_debug".to_string(),"fragment_debug".to_string());
+        group_builder.bind_groups_layouts.push(Camera::bind_group_layout(device));
+        let group = group_builder.build(device,config,DebugDraw::desc(),PrimitiveTopology::LineList,true,"Debug Draw".to_string());
+
+        return DebugDraw { enabled: true, group, lines: vec![], vertex_buffer: DebugDraw::create_vertex_buffer(device,1024 * VERTEX_SIZE), vertex_count: 0, drawn: Cell::new(false) };
+    }
+
+    fn create_vertex_buffer(device:&Device, size:u64) -> Buffer {
+        return device.create_buffer(&BufferDescriptor {
+            label: Some("Debug Draw Vertices"),
+            size,
+            usage: BufferUsages::VERTEX | BufferUsages::COPY_DST,
+            mapped_at_creation: false
+        });
+    }
+
+    pub fn desc() -> VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: VERTEX_SIZE as BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &[
+                wgpu::VertexAttribute { offset: 0, shader_location: 0, format: wgpu::VertexFormat::Float32x3 },
+                wgpu::VertexAttribute { offset: 12, shader_location: 1, format: wgpu::VertexFormat::Float32x2 },
+                wgpu::VertexAttribute { offset: 20, shader_location: 2, format: wgpu::VertexFormat::Float32x4 },
+                wgpu::VertexAttribute { offset: 36, shader_location: 3, format: wgpu::VertexFormat::Float32 }
+            ]
+        }
+    }
+
+    fn push(&mut self, start:TVec3<f32>, end:TVec3<f32>, start_offset:TVec2<f32>, end_offset:TVec2<f32>, style:&DebugStyle) {
+        let c = &style.color;
+        self.lines.push(DebugLine { start, end, start_offset, end_offset, color: [c.r,c.g,c.b,c.a], on_top: !style.depth_test, remaining: style.duration });
+    }
+
+    pub fn line(&mut self, start:TVec3<f32>, end:TVec3<f32>, style:&DebugStyle) {
+        self.push(start,end,vec2(0.0,0.0),vec2(0.0,0.0),style);
+    }
+
+    pub fn ray(&mut self, origin:TVec3<f32>, direction:TVec3<f32>, length:f32, style:&DebugStyle) {
+        if direction.norm() <= f32::EPSILON {
+            return;
+        }
+        self.line(origin,origin + direction.normalize() * length,style);
+    }
+
+    pub fn aabb(&mut self, aabb:&AABB, style:&DebugStyle) {
+        let (min,max) = (aabb.min,aabb.max);
+        let corner = |i:usize| vec3(if i & 1 == 0 { min.x } else { max.x },if i & 2 == 0 { min.y } else { max.y },if i & 4 == 0 { min.z } else { max.z });
+        //Every pair of corners which differs in exactly one axis is an edge
+        for i in 0..8 {
+            for axis in [1,2,4] {
+                if i & axis == 0 {
+                    self.line(corner(i),corner(i | axis),style);
+                }
+            }
+        }
+    }
+
+    //One circle around each axis
+    pub fn sphere(&mut self, sphere:&RoundBB, style:&DebugStyle) {
+        self.circle(sphere.center,vec3(1.0,0.0,0.0),vec3(0.0,1.0,0.0),sphere.radius,style);
+        self.circle(sphere.center,vec3(1.0,0.0,0.0),vec3(0.0,0.0,1.0),sphere.radius,style);
+        self.circle(sphere.center,vec3(0.0,1.0,0.0),vec3(0.0,0.0,1.0),sphere.radius,style);
+    }
+
+    //a and b span the plane of the circle
+    pub fn circle(&mut self, center:TVec3<f32>, a:TVec3<f32>, b:TVec3<f32>, radius:f32, style:&DebugStyle) {
+        let point = |i:usize| {
+            let angle = i as f32 / CIRCLE_SEGMENTS as f32 * std::f32::consts::TAU;
+            center + (a * angle.cos() + b * angle.sin()) * radius
+        };
+        for i in 0..CIRCLE_SEGMENTS {
+            self.line(point(i),point(i + 1),style);
+        }
+    }
+
+    pub fn arrow(&mut self, start:TVec3<f32>, end:TVec3<f32>, head_size:f32, style:&DebugStyle) {
+        self.line(start,end,style);
+        let direction = end - start;
+        if direction.norm() <= f32::EPSILON {
+            return;
+        }
+        let direction = direction.normalize();
+        //Any vector which is not parallel to the arrow works for the sides of the head
+        let helper = if direction.y.abs() < 0.99 { vec3(0.0,1.0,0.0) } else { vec3(1.0,0.0,0.0) };
+        let side = direction.cross(&helper).normalize();
+        let up = direction.cross(&side);
+        let base = end - direction * head_size;
+        for offset in [side,-side,up,-up] {
+            self.line(end,base + offset * head_size * 0.5,style);
+        }
+    }
+
+    //Red x, green y and blue z axis of the transform
+    pub fn axes(&mut self, transform:&Mat4, size:f32, style:&DebugStyle) {
+        let origin = (transform * TVec4::new(0.0,0.0,0.0,1.0)).xyz();
+        let colors = [Color::new(255,0,0),Color::new(0,255,0),Color::new(0,0,255)];
+        for (i,color) in colors.into_iter().enumerate() {
+            let mut axis = TVec4::new(0.0,0.0,0.0,0.0);
+            axis[i] = 1.0;
+            let direction = (transform * axis).xyz();
+            if direction.norm() <= f32::EPSILON {
+                continue;
+            }
+            self.arrow(origin,origin + direction.normalize() * size,size * 0.15,&style.with_color(color));
+        }
+    }
+
+    //Grid on the xz plane with size * size units and the given amount of cells per side
+    pub fn grid(&mut self, center:TVec3<f32>, size:f32, divisions:u32, style:&DebugStyle) {
+        let divisions = divisions.max(1);
+        let half = size / 2.0;
+        for i in 0..=divisions {
+            let t = -half + size * i as f32 / divisions as f32;
+            self.line(center + vec3(t,0.0,-half),center + vec3(t,0.0,half),style);
+            self.line(center + vec3(-half,0.0,t),center + vec3(half,0.0,t),style);
+        }
+    }
+
+    //The view volume of the camera, from its projection and view matrix
+    pub fn frustum(&mut self, camera:&Camera, style:&DebugStyle) {
+        let inverse = (camera.projection * camera.view).try_inverse().unwrap_or_else(nalgebra_glm::identity);
+        let corner = |i:usize| {
+            let ndc = TVec4::new(if i & 1 == 0 { -1.0 } else { 1.0 },if i & 2 == 0 { -1.0 } else { 1.0 },if i & 4 == 0 { -1.0 } else { 1.0 },1.0);
+            let world = inverse * ndc;
+            world.xyz() / world.w
+        };
+        for i in 0..8 {
+            for axis in [1,2,4] {
+                if i & axis == 0 {
+                    self.line(corner(i),corner(i | axis),style);
+                }
+            }
+        }
+    }
+
+    //Text which always faces the camera, centered on pos. size is the height of a character in world units
+    pub fn text(&mut self, pos:TVec3<f32>, text:&str, size:f32, style:&DebugStyle) {
+        let width = size * 0.6;
+        let advance = size * 0.9;
+        let start = -(text.chars().count() as f32 * advance - (advance - width)) / 2.0;
+        for (i,c) in text.chars().enumerate() {
+            let mask = get_glyph(c.to_ascii_uppercase());
+            for (segment,(from,to)) in SEGMENTS.iter().enumerate() {
+                if mask & (1 << segment) != 0 {
+                    let offset = |p:(f32,f32)| vec2(start + i as f32 * advance + p.0 * width,(p.1 - 0.5) * size);
+                    self.push(pos,pos,offset(*from),offset(*to),style);
+                }
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.lines.clear();
+    }
+
+    pub fn get_line_count(&self) -> usize {
+        return self.lines.len();
+    }
+
+    //Uploads the lines of this frame and removes the expired ones, called by the runners before rendering
+    pub(crate) fn update(&mut self, device:&Device, queue:&Queue, delta_time:f32) {
+        self.drawn.set(false);
+        if !self.enabled {
+            //Lines queued while disabled still expire
+            self.vertex_count = 0;
+            self.expire(delta_time);
+            return;
+        }
+
+        let mut data = Vec::with_capacity(self.lines.len() * 20);
+        for line in &self.lines {
+            let on_top = if line.on_top { 1.0 } else { 0.0 };
+            for (pos,offset) in [(&line.start,&line.start_offset),(&line.end,&line.end_offset)] {
+                data.extend_from_slice(&[pos.x,pos.y,pos.z,offset.x,offset.y]);
+                data.extend_from_slice(&line.color);
+                data.push(on_top);
+            }
+        }
+
+        let bytes = crate::objects::matrix_helper::get_bytes_from_vec(data);
+        if bytes.len() as u64 > self.vertex_buffer.size() {
+            self.vertex_buffer = DebugDraw::create_vertex_buffer(device,(bytes.len() as u64).next_power_of_two());
+        }
+        if !bytes.is_empty() {
+            queue.write_buffer(&self.vertex_buffer,0,&bytes);
+        }
+        self.vertex_count = self.lines.len() as u32 * 2;
+        self.expire(delta_time);
+    }
+
+    fn expire(&mut self, delta_time:f32) {
+        for line in self.lines.iter_mut() {
+            line.remaining -= delta_time;
+        }
+        self.lines.retain(|l| l.remaining > 0.0);
+    }
+
+    //The pass needs a depth attachment, e.g. the one of the World. Only the first call of a frame draws, so stacked Worlds don't draw the lines again
+    pub fn render<'a>(&'a self, render_pass:&mut RenderPass<'a>, camera:&'a Camera) {
+        if self.vertex_count == 0 || self.drawn.replace(true) {
+            return;
+        }
+        let _scope = scope("DebugDraw::render");
+        render_pass.set_pipeline(&self.group.pipeline);
+        count_pipeline_switch();
+        render_pass.set_bind_group(0,&camera.bind_group,&[]);
+        render_pass.set_vertex_buffer(0,self.vertex_buffer.slice(..self.vertex_count as u64 * VERTEX_SIZE));
+        render_pass.draw(0..self.vertex_count,0..1);
+        count_draw();
+    }
+}
+
+/*
+16 segment font for the text labels, points of a glyph go from (0,0) bottom left to (1,1) top right.
+a1 a2 top, b c right, d2 d1 bottom, e f left, g1 g2 middle, h i j upper diagonals and center, k l m lower ones
+ */
+const SEGMENTS: [((f32,f32),(f32,f32));16] = [
+    ((0.0,1.0),(0.5,1.0)),
+    ((0.5,1.0),(1.0,1.0)),
+    ((1.0,1.0),(1.0,0.5)),
+    ((1.0,0.5),(1.0,0.0)),
+    ((1.0,0.0),(0.5,0.0)),
+    ((0.5,0.0),(0.0,0.0)),
+    ((0.0,0.0),(0.0,0.5)),
+    ((0.0,0.5),(0.0,1.0)),
+    ((0.0,0.5),(0.5,0.5)),
+    ((0.5,0.5),(1.0,0.5)),
+    ((0.0,1.0),(0.5,0.5)),
+    ((0.5,1.0),(0.5,0.5)),
+    ((1.0,1.0),(0.5,0.5)),
+    ((0.5,0.5),(0.0,0.0)),
+    ((0.5,0.5),(0.5,0.0)),
+    ((0.5,0.5),(1.0,0.0))
+];
+
+const A1: u16 = 1 << 0;
+const A2: u16 = 1 << 1;
+const B: u16 = 1 << 2;
+const C: u16 = 1 << 3;
+const D2: u16 = 1 << 4;
+const D1: u16 = 1 << 5;
+const E: u16 = 1 << 6;
+const F: u16 = 1 << 7;
+const G1: u16 = 1 << 8;
+const G2: u16 = 1 << 9;
+const H: u16 = 1 << 10;
+const I: u16 = 1 << 11;
+const J: u16 = 1 << 12;
+const K: u16 = 1 << 13;
+const L: u16 = 1 << 14;
+const M: u16 = 1 << 15;
+
+//Unknown characters are empty
+fn get_glyph(c:char) -> u16 {
+    return match c {
+        '0' => A1 | A2 | B | C | D1 | D2 | E | F | J | K,
+        '1' => B | C | J,
+        '2' => A1 | A2 | B | G1 | G2 | E | D1 | D2,
+        '3' => A1 | A2 | B | C | D1 | D2 | G2,
+        '4' => F | G1 | G2 | B | C,
+        '5' => A1 | A2 | F | G1 | G2 | C | D1 | D2,
+        '6' => A1 | A2 | F | E | D1 | D2 | C | G1 | G2,
+        '7' => A1 | A2 | B | C,
+        '8' => A1 | A2 | B | C | D1 | D2 | E | F | G1 | G2,
+        '9' => A1 | A2 | B | C | D1 | D2 | F | G1 | G2,
+        'A' => A1 | A2 | B | C | E | F | G1 | G2,
+        'B' => A1 | A2 | B | C | D1 | D2 | I | L | G2,
+        'C' => A1 | A2 | F | E | D1 | D2,
+        'D' => A1 | A2 | B | C | D1 | D2 | I | L,
+        'E' => A1 | A2 | F | E | D1 | D2 | G1,
+        'F' => A1 | A2 | F | E | G1,
+        'G' => A1 | A2 | F | E | D1 | D2 | C | G2,
+        'H' => F | E | B | C | G1 | G2,
+        'I' => A1 | A2 | I | L | D1 | D2,
+        'J' => B | C | D1 | D2 | E,
+        'K' => F | E | G1 | J | M,
+        'L' => F | E | D1 | D2,
+        'M' => F | E | B | C | H | J,
+        'N' => F | E | B | C | H | M,
+        'O' => A1 | A2 | B | C | D1 | D2 | E | F,
+        'P' => A1 | A2 | B | F | E | G1 | G2,
+        'Q' => A1 | A2 | B | C | D1 | D2 | E | F | M,
+        'R' => A1 | A2 | B | F | E | G1 | G2 | M,
+        'S' => A1 | A2 | F | G1 | G2 | C | D1 | D2,
+        'T' => A1 | A2 | I | L,
+        'U' => F | E | D1 | D2 | C | B,
+        'V' => F | E | K | J,
+        'W' => F | E | B | C | K | M,
+        'X' => H | J | K | M,
+        'Y' => H | J | L,
+        'Z' => A1 | A2 | J | K | D1 | D2,
+        '-' => G1 | G2,
+        '+' => G1 | G2 | I | L,
+        '=' => G1 | G2 | D1 | D2,
+        '_' => D1 | D2,
+        '*' => G1 | G2 | H | I | J | K | L | M,
+        '/' => J | K,
+        '\\' => H | M,
+        '(' | '<' => J | M,
+        ')' | '>' => H | K,
+        '.' | ',' => L,
+        ':' | '|' => I | L,
+        _ => 0
+    };
+}
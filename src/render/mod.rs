@@ -4,4 +4,8 @@ pub mod line_renderer;
 pub mod custom_vertex_renderer;
 pub mod text_renderer;
 pub mod render_phase;
-pub mod vertex_renderer;
\ No newline at end of file
+pub mod vertex_renderer;
+pub mod shadow_renderer;
+pub mod transition_renderer;
+pub mod post_processor;
+pub mod debug_draw;
\ No newline at end of file
@@ -0,0 +1,583 @@
+use std::rc::Rc;
+use image::{Rgba, RgbaImage};
+use wgpu::{BindGroupDescriptor, BindGroupEntry, BindingResource, Buffer, BufferAddress, BufferDescriptor, BufferUsages, CommandEncoder, Device, PipelineLayout, PipelineLayoutDescriptor, Queue, SurfaceConfiguration, TextureFormat, TextureView, TextureViewDescriptor};
+use crate::error::rgl_error::RglResult;
+use crate::objects::offset_handler::align_to;
+use crate::objects::render_texture::RenderTexture;
+use crate::objects::texture_object::TextureObject;
+use crate::pipeline::exposure_pipeline::ExposurePipelineGroup;
+use crate::pipeline::post_process_pipeline::{PostProcessLayout, PostProcessPipeline, POST_DATA_SIZE};
+use crate::vfs::vfs::Vfs;
+use crate::engine::profiler::{count_draw, count_pipeline_switch, write_uniform};
+
+const VERTEX_SHADER: &str = "assets/shader/post/vertex.shader";
+
+//Indices of the built-in pipelines in PostProcessor::pipelines
+const FXAA: usize = 0;
+const BLOOM_EXTRACT: usize = 1;
+const BLUR: usize = 2;
+const BLOOM_COMBINE: usize = 3;
+const VIGNETTE: usize = 4;
+const COLOR_GRADING: usize = 5;
+const CHROMATIC_ABERRATION: usize = 6;
+const FILM_GRAIN: usize = 7;
+const BUILTIN_SHADERS: [&str; 8] = ["fxaa","bloom_extract","blur","bloom_combine","vignette","color_grading","chromatic_aberration","film_grain"];
+
+//Fragment shader loaded with GameEngine::load_post_shader
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct PostShader(usize);
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum PostEffectKind {
+    Fxaa,
+    Bloom,
+    Vignette,
+    ColorGrading,
+    ChromaticAberration,
+    FilmGrain,
+    Custom(PostShader)
+}
+
+/*
+One entry of the post processing stack. What the params mean depends on the kind, see the constructors.
+Custom shaders get them as params and params2 and the texture as t_extra.
+ */
+#[derive(Clone)]
+pub struct PostEffect {
+    pub name: String,
+    pub kind: PostEffectKind,
+    pub enabled: bool,
+    pub params: [f32; 8],
+    pub texture: Option<Rc<TextureObject>>
+}
+
+impl PostEffect {
+    pub fn new(name:&str, kind:PostEffectKind, params:[f32; 8]) -> Self {
+        PostEffect { name: name.to_string(), kind, enabled: true, params, texture: None }
+    }
+
+    //params: span max, reduce mul, reduce min
+    pub fn fxaa() -> Self {
+        PostEffect::new("fxaa",PostEffectKind::Fxaa,[8.0,1.0 / 8.0,1.0 / 128.0,0.0,0.0,0.0,0.0,0.0])
+    }
+
+    //params: threshold, knee (soft threshold), intensity, blur radius
+    pub fn bloom(threshold:f32, intensity:f32) -> Self {
+        PostEffect::new("bloom",PostEffectKind::Bloom,[threshold,0.5,intensity,1.0,0.0,0.0,0.0,0.0])
+    }
+
+    //params: intensity, radius, softness, 0, color r, g, b
+    pub fn vignette(intensity:f32, radius:f32, softness:f32) -> Self {
+        PostEffect::new("vignette",PostEffectKind::Vignette,[intensity,radius,softness,0.0,0.0,0.0,0.0,0.0])
+    }
+
+    /*
+    params: lut strength, exposure (stops), contrast, saturation
+    The lut is optional, see create_neutral_lut for its layout.
+     */
+    pub fn color_grading(lut:Option<Rc<TextureObject>>) -> Self {
+        let mut effect = PostEffect::new("color_grading",PostEffectKind::ColorGrading,[1.0,0.0,1.0,1.0,0.0,0.0,0.0,0.0]);
+        effect.texture = lut;
+        effect
+    }
+
+    //params: offset in pixels at the edges of the screen
+    pub fn chromatic_aberration(strength:f32) -> Self {
+        PostEffect::new("chromatic_aberration",PostEffectKind::ChromaticAberration,[strength,0.0,0.0,0.0,0.0,0.0,0.0,0.0])
+    }
+
+    //params: intensity, grain size in pixels
+    pub fn film_grain(intensity:f32) -> Self {
+        PostEffect::new("film_grain",PostEffectKind::FilmGrain,[intensity,1.0,0.0,0.0,0.0,0.0,0.0,0.0])
+    }
+
+    pub fn custom(name:&str, shader:PostShader) -> Self {
+        PostEffect::new(name,PostEffectKind::Custom(shader),[0.0; 8])
+    }
+
+    pub fn enabled(mut self, enabled:bool) -> Self {
+        self.enabled = enabled;
+        self
+    }
+
+    pub fn param(mut self, index:usize, value:f32) -> Self {
+        self.params[index] = value;
+        self
+    }
+
+    pub fn texture(mut self, texture:Rc<TextureObject>) -> Self {
+        self.texture = Some(texture);
+        self
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum TonemapOperator {
+    //Only clamps
+    None,
+    Reinhard,
+    Aces,
+    AgX
+}
+
+/*
+Last pass of the stack when hdr is enabled (see GameEngine::set_hdr), maps the Rgba16Float image to the output format.
+The automatic exposure keeps the average luminance of the image at middle gray, exposure is added to it.
+ */
+#[derive(Clone, Debug)]
+pub struct Tonemapping {
+    pub operator: TonemapOperator,
+    //In stops
+    pub exposure: f32,
+    pub auto_exposure: bool,
+    //log2 luminance range of the histogram used for the automatic exposure
+    pub min_log_luminance: f32,
+    pub max_log_luminance: f32,
+    //How fast the automatic exposure adapts, higher is faster
+    pub adaptation_speed: f32,
+    //Luminance that is mapped to white by Reinhard
+    pub white_point: f32
+}
+
+impl Tonemapping {
+    pub fn new() -> Self {
+        Tonemapping {
+            operator: TonemapOperator::Aces,
+            exposure: 0.0,
+            auto_exposure: false,
+            min_log_luminance: -8.0,
+            max_log_luminance: 4.0,
+            adaptation_speed: 1.5,
+            white_point: 4.0
+        }
+    }
+
+    pub fn operator(mut self, operator:TonemapOperator) -> Self {
+        self.operator = operator;
+        self
+    }
+
+    pub fn exposure(mut self, exposure:f32) -> Self {
+        self.exposure = exposure;
+        self
+    }
+
+    pub fn auto_exposure(mut self, auto_exposure:bool) -> Self {
+        self.auto_exposure = auto_exposure;
+        self
+    }
+
+    pub fn luminance_range(mut self, min_log:f32, max_log:f32) -> Self {
+        self.min_log_luminance = min_log;
+        self.max_log_luminance = max_log;
+        self
+    }
+
+    pub fn adaptation_speed(mut self, speed:f32) -> Self {
+        self.adaptation_speed = speed;
+        self
+    }
+
+    pub fn white_point(mut self, white_point:f32) -> Self {
+        self.white_point = white_point;
+        self
+    }
+
+    fn get_params(&self, encode_srgb:bool) -> [f32; 8] {
+        let operator = match self.operator {
+            TonemapOperator::None => 0.0,
+            TonemapOperator::Reinhard => 1.0,
+            TonemapOperator::Aces => 2.0,
+            TonemapOperator::AgX => 3.0
+        };
+        let auto = if self.auto_exposure { 1.0 } else { 0.0 };
+        let encode = if encode_srgb { 1.0 } else { 0.0 };
+        return [operator,self.exposure,auto,self.white_point,encode,0.0,0.0,0.0];
+    }
+}
+
+/*
+Identity lut for ColorGrading: size squares of size * size pixels next to each other.
+Blue selects the square, red grows to the right and green to the bottom. Grade it in an image editor and load it as a texture.
+ */
+pub fn create_neutral_lut(size:u32) -> RgbaImage {
+    let max = (size - 1).max(1) as f32;
+    return RgbaImage::from_fn(size * size,size,|x,y| {
+        let value = |v:u32| (v as f32 / max * 255.0).round() as u8;
+        Rgba([value(x % size),value(y),value(x / size),255])
+    });
+}
+
+#[derive(Copy, Clone, PartialEq)]
+enum Target {
+    Scene,
+    Swap(usize),
+    Half(usize),
+    Output
+}
+
+enum Extra {
+    Input,
+    Target(Target),
+    Texture(Rc<TextureObject>)
+}
+
+#[derive(Copy, Clone, PartialEq)]
+enum PassPipeline {
+    //Index in PostProcessor::pipelines
+    Effect(usize),
+    //The tonemapping pipeline is not in PostProcessor::pipelines, it has its own layout
+    Tonemap
+}
+
+struct Pass {
+    pipeline: PassPipeline,
+    input: Target,
+    extra: Extra,
+    output: Target,
+    params: [f32; 8]
+}
+
+//Scene is what the scenes render into, swap and half (bloom) are used between the passes
+struct PostTargets {
+    scene: RenderTexture,
+    swap: [RenderTexture; 2],
+    half: [RenderTexture; 2]
+}
+
+/*
+Ordered stack of fullscreen effects. While at least one effect is enabled (or hdr is on) the scenes are rendered into an offscreen target
+which is passed through the effects into the frame (see SceneHandler::render), otherwise they render into the frame directly.
+With hdr the effects work on Rgba16Float images and tonemapping is the last pass.
+The passes are built in GameEngine::update_post_processing, right before rendering.
+ */
+pub struct PostProcessor {
+    pub effects: Vec<PostEffect>,
+    pub tonemapping: Tonemapping,
+    pub layout: PostProcessLayout,
+    //The built-in ones first, then the ones loaded with GameEngine::load_post_shader
+    pub pipelines: Vec<PostProcessPipeline>,
+    pub tonemap_pipeline: PostProcessPipeline,
+    pub tonemap_layout: PipelineLayout,
+    pub exposure: ExposurePipelineGroup,
+    //Format of the scene target and the effects, the last pass renders into output_format
+    pub format: TextureFormat,
+    pub output_format: TextureFormat,
+    hdr: bool,
+    pub time: f32,
+    pub frame: u32,
+    data_buffer: Buffer,
+    data_stride: u64,
+    data_slots: u64,
+    passes: Vec<Pass>,
+    targets: Option<PostTargets>
+}
+
+impl PostProcessor {
+    pub fn new(vfs:&Vfs, device:&Device, config:&SurfaceConfiguration, uniform_alignment:u32) -> PostProcessor {
+        let layout = PostProcessLayout::new(device);
+        let pipelines = BUILTIN_SHADERS.iter().map(|name| {
+            PostProcessPipeline::new(VERTEX_SHADER.to_string(),format!("assets/shader/post/{}/fragment.shader",name),config.format,&layout.pipeline_layout,device,vfs)
+        }).collect();
+
+        let exposure = ExposurePipelineGroup::new("assets/shader/exposure/histogram.wgsl".to_string(),"assets/shader/exposure/average.wgsl".to_string(),device,vfs);
+        let tonemap_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("Tonemap Pipeline Layout"),
+            bind_group_layouts: &[&layout.layout,&exposure.exposure_layout],
+            push_constant_ranges: &[]
+        });
+        let tonemap_pipeline = PostProcessPipeline::new(VERTEX_SHADER.to_string(),"assets/shader/post/tonemap/fragment.shader".to_string(),config.format,&tonemap_layout,device,vfs);
+
+        let data_stride = align_to(POST_DATA_SIZE,uniform_alignment as u64);
+        let data_slots = 16;
+
+        return PostProcessor {
+            effects: vec![],
+            tonemapping: Tonemapping::new(),
+            layout,
+            pipelines,
+            tonemap_pipeline,
+            tonemap_layout,
+            exposure,
+            format: config.format,
+            output_format: config.format,
+            hdr: false,
+            time: 0.0,
+            frame: 0,
+            data_buffer: PostProcessor::create_data_buffer(device,data_stride * data_slots),
+            data_stride,
+            data_slots,
+            passes: vec![],
+            targets: None
+        }
+    }
+
+    fn create_data_buffer(device:&Device, size:BufferAddress) -> Buffer {
+        return device.create_buffer(&BufferDescriptor {
+            label: Some("Post Process Data"),
+            size,
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+            mapped_at_creation: false
+        });
+    }
+
+    pub fn try_load_shader(&mut self, device:&Device, vfs:&Vfs, fragment_shader_path:&str) -> RglResult<PostShader> {
+        let pipeline = PostProcessPipeline::try_new(VERTEX_SHADER.to_string(),fragment_shader_path.to_string(),self.format,&self.layout.pipeline_layout,device,vfs)?;
+        self.pipelines.push(pipeline);
+        return Ok(PostShader(self.pipelines.len() - 1));
+    }
+
+    //format is what the scenes render into, see GameEngine::get_render_format
+    pub(crate) fn set_hdr(&mut self, device:&Device, hdr:bool, format:TextureFormat) {
+        self.hdr = hdr;
+        self.format = format;
+        for pipeline in self.pipelines.iter_mut() {
+            pipeline.set_format(device,&self.layout.pipeline_layout,format);
+        }
+        self.targets = None;
+    }
+
+    pub fn is_hdr(&self) -> bool {
+        return self.hdr;
+    }
+
+    pub(crate) fn get_shader_paths(&self) -> Vec<String> {
+        let mut paths = vec![self.exposure.histogram_shader_path.clone(),self.exposure.average_shader_path.clone()];
+        for pipeline in self.pipelines.iter().chain(std::iter::once(&self.tonemap_pipeline)) {
+            paths.push(pipeline.vertex_shader_path.clone());
+            paths.push(pipeline.fragment_shader_path.clone());
+        }
+        paths.sort();
+        paths.dedup();
+        return paths;
+    }
+
+    //Builds every pipeline using the shader again, see HotReloader
+    pub fn try_reload_shader(&mut self, device:&Device, vfs:&Vfs, path:&str) -> RglResult<()> {
+        let mut result = Ok(());
+        for pipeline in self.pipelines.iter_mut().filter(|p| p.uses_shader(path)) {
+            result = result.and(pipeline.try_reload(device,vfs,&self.layout.pipeline_layout));
+        }
+        if self.tonemap_pipeline.uses_shader(path) {
+            result = result.and(self.tonemap_pipeline.try_reload(device,vfs,&self.tonemap_layout));
+        }
+        if self.exposure.uses_shader(path) {
+            result = result.and(self.exposure.try_reload(device,vfs));
+        }
+        return result;
+    }
+
+    pub fn add(&mut self, effect:PostEffect) {
+        self.effects.push(effect);
+    }
+
+    pub fn insert(&mut self, index:usize, effect:PostEffect) {
+        self.effects.insert(index,effect);
+    }
+
+    pub fn remove(&mut self, name:&str) -> Option<PostEffect> {
+        let index = self.effects.iter().position(|e| e.name == name)?;
+        return Some(self.effects.remove(index));
+    }
+
+    pub fn get(&self, name:&str) -> Option<&PostEffect> {
+        return self.effects.iter().find(|e| e.name == name);
+    }
+
+    pub fn get_mut(&mut self, name:&str) -> Option<&mut PostEffect> {
+        return self.effects.iter_mut().find(|e| e.name == name);
+    }
+
+    //Returns false if there is no effect with the name
+    pub fn set_enabled(&mut self, name:&str, enabled:bool) -> bool {
+        return match self.get_mut(name) {
+            Some(effect) => {
+                effect.enabled = enabled;
+                true
+            },
+            None => false
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        return self.hdr || self.effects.iter().any(|e| e.enabled);
+    }
+
+    //What the scenes render into this frame, None if no effect is enabled and hdr is off
+    pub fn get_target(&self) -> Option<&TextureView> {
+        return self.get_scene_target().map(|t| &t.view);
+    }
+
+    pub fn get_scene_target(&self) -> Option<&RenderTexture> {
+        if self.passes.is_empty() {
+            return None;
+        }
+        return self.targets.as_ref().map(|t| &t.scene);
+    }
+
+    //Builds the passes of the enabled effects, (re)creates the targets and writes the data of every pass
+    pub fn update(&mut self, device:&Device, queue:&Queue, config:&SurfaceConfiguration, delta_time:f32) {
+        self.time += delta_time;
+        self.frame = self.frame.wrapping_add(1);
+        self.passes = self.build_passes();
+
+        if self.passes.is_empty() {
+            self.targets = None;
+            return;
+        }
+
+        let (width,height) = (config.width.max(1),config.height.max(1));
+        if self.targets.as_ref().map_or(true,|t| t.scene.width != width || t.scene.height != height || t.scene.format != self.format) {
+            let (half_width,half_height) = ((width / 2).max(1),(height / 2).max(1));
+            self.targets = Some(PostTargets {
+                scene: RenderTexture::new(device,width,height,self.format),
+                swap: [RenderTexture::new(device,width,height,self.format),RenderTexture::new(device,width,height,self.format)],
+                half: [RenderTexture::new(device,half_width,half_height,self.format),RenderTexture::new(device,half_width,half_height,self.format)]
+            });
+        }
+
+        if self.passes.len() as u64 > self.data_slots {
+            self.data_slots = (self.passes.len() as u64).next_power_of_two();
+            self.data_buffer = PostProcessor::create_data_buffer(device,self.data_stride * self.data_slots);
+        }
+
+        if self.hdr && self.tonemapping.auto_exposure {
+            let range = (self.tonemapping.max_log_luminance - self.tonemapping.min_log_luminance).max(0.001);
+            let data = vec![self.tonemapping.min_log_luminance,1.0 / range,self.tonemapping.adaptation_speed,delta_time,(width * height) as f32,0.0,0.0,0.0];
+            write_uniform(queue,&self.exposure.data_buffer,0,&crate::objects::matrix_helper::get_bytes_from_vec(data));
+        }
+
+        let linear = if self.format.is_srgb() || self.hdr { 1.0 } else { 0.0 };
+        let targets = self.targets.as_ref().unwrap();
+        for (i,pass) in self.passes.iter().enumerate() {
+            let (w,h) = match pass.input {
+                Target::Half(_) => (targets.half[0].width as f32,targets.half[0].height as f32),
+                _ => (width as f32,height as f32)
+            };
+
+            let mut data = vec![w,h,1.0 / w,1.0 / h,self.time,self.frame as f32,linear,0.0];
+            data.extend_from_slice(&pass.params);
+            write_uniform(queue,&self.data_buffer,i as u64 * self.data_stride,&crate::objects::matrix_helper::get_bytes_from_vec(data));
+        }
+    }
+
+    fn build_passes(&self) -> Vec<Pass> {
+        let effects: Vec<&PostEffect> = self.effects.iter().filter(|e| e.enabled).collect();
+        let mut passes = vec![];
+        let mut input = Target::Scene;
+        let mut next_swap = 0;
+
+        for (i,effect) in effects.iter().enumerate() {
+            let output = if i == effects.len() - 1 && !self.hdr {
+                Target::Output
+            } else {
+                let target = Target::Swap(next_swap);
+                next_swap = 1 - next_swap;
+                target
+            };
+            let p = effect.params;
+            let pass = |pipeline:usize,extra:Extra,params:[f32; 8]| Pass { pipeline: PassPipeline::Effect(pipeline), input, extra, output, params };
+
+            match effect.kind {
+                PostEffectKind::Fxaa => passes.push(pass(FXAA,Extra::Input,p)),
+                PostEffectKind::Bloom => {
+                    passes.push(Pass { pipeline: PassPipeline::Effect(BLOOM_EXTRACT), input, extra: Extra::Input, output: Target::Half(0), params: [p[0],p[1],0.0,0.0,0.0,0.0,0.0,0.0] });
+                    passes.push(Pass { pipeline: PassPipeline::Effect(BLUR), input: Target::Half(0), extra: Extra::Input, output: Target::Half(1), params: [p[3],0.0,0.0,0.0,0.0,0.0,0.0,0.0] });
+                    passes.push(Pass { pipeline: PassPipeline::Effect(BLUR), input: Target::Half(1), extra: Extra::Input, output: Target::Half(0), params: [0.0,p[3],0.0,0.0,0.0,0.0,0.0,0.0] });
+                    passes.push(pass(BLOOM_COMBINE,Extra::Target(Target::Half(0)),[p[2],0.0,0.0,0.0,0.0,0.0,0.0,0.0]));
+                },
+                PostEffectKind::Vignette => passes.push(pass(VIGNETTE,Extra::Input,p)),
+                PostEffectKind::ColorGrading => {
+                    let mut params = p;
+                    params[4] = if effect.texture.is_some() { 1.0 } else { 0.0 };
+                    let extra = effect.texture.clone().map_or(Extra::Input,Extra::Texture);
+                    passes.push(pass(COLOR_GRADING,extra,params));
+                },
+                PostEffectKind::ChromaticAberration => passes.push(pass(CHROMATIC_ABERRATION,Extra::Input,p)),
+                PostEffectKind::FilmGrain => passes.push(pass(FILM_GRAIN,Extra::Input,p)),
+                PostEffectKind::Custom(shader) => {
+                    let extra = effect.texture.clone().map_or(Extra::Input,Extra::Texture);
+                    passes.push(pass(shader.0,extra,p));
+                }
+            }
+            input = output;
+        }
+
+        if self.hdr {
+            let encode_srgb = !self.output_format.is_srgb();
+            passes.push(Pass { pipeline: PassPipeline::Tonemap, input, extra: Extra::Input, output: Target::Output, params: self.tonemapping.get_params(encode_srgb) });
+        }
+
+        return passes;
+    }
+
+    //Runs the passes built in update, the last one renders into output
+    pub fn render(&self, device:&Device, encoder:&mut CommandEncoder, output:&TextureView) {
+        let targets = match &self.targets {
+            Some(targets) => targets,
+            None => return
+        };
+        let get_view = |target:Target| match target {
+            Target::Scene => &targets.scene.view,
+            Target::Swap(i) => &targets.swap[i].view,
+            Target::Half(i) => &targets.half[i].view,
+            Target::Output => output
+        };
+
+        for (i,pass) in self.passes.iter().enumerate() {
+            let texture_view;
+            let extra = match &pass.extra {
+                Extra::Input => get_view(pass.input),
+                Extra::Target(target) => get_view(*target),
+                Extra::Texture(texture) => {
+                    texture_view = texture.texture.create_view(&TextureViewDescriptor::default());
+                    &texture_view
+                }
+            };
+
+            if pass.pipeline == PassPipeline::Tonemap && self.tonemapping.auto_exposure {
+                self.exposure.compute(device,encoder,get_view(pass.input),targets.scene.width,targets.scene.height);
+            }
+
+            let bind_group = device.create_bind_group(&BindGroupDescriptor {
+                label: Some("Post Process Group"),
+                layout: &self.layout.layout,
+                entries: &[
+                    BindGroupEntry { binding: 0, resource: BindingResource::TextureView(get_view(pass.input)) },
+                    BindGroupEntry { binding: 1, resource: BindingResource::TextureView(extra) },
+                    BindGroupEntry { binding: 2, resource: BindingResource::Sampler(&self.layout.sampler) },
+                    BindGroupEntry { binding: 3, resource: BindingResource::Buffer(wgpu::BufferBinding { buffer: &self.data_buffer, offset: 0, size: wgpu::BufferSize::new(POST_DATA_SIZE) }) }
+                ]
+            });
+
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Post Process Pass"),
+                color_attachments: &[
+                    Some(wgpu::RenderPassColorAttachment {
+                        view: get_view(pass.output),
+                        resolve_target: None,
+                        ops: wgpu::Operations {
+                            load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                            store: true,
+                        }
+                    })
+                ],
+                depth_stencil_attachment: None,
+            });
+            render_pass.set_bind_group(0,&bind_group,&[(i as u64 * self.data_stride) as u32]);
+            match pass.pipeline {
+                PassPipeline::Effect(index) => {
+                    render_pass.set_pipeline(&self.pipelines[index].pipeline);
+                    count_pipeline_switch();
+                },
+                PassPipeline::Tonemap => {
+                    render_pass.set_pipeline(&self.tonemap_pipeline.pipeline);
+                    count_pipeline_switch();
+                    render_pass.set_bind_group(1,&self.exposure.exposure_bind_group,&[]);
+                }
+            }
+            render_pass.draw(0..3,0..1);
+            count_draw();
+        }
+    }
+}
@@ -0,0 +1,48 @@
+use wgpu::{Device, Queue, RenderPass};
+use crate::light::shadow::ShadowMap;
+use crate::objects::camera::Camera;
+use crate::objects::vertex_buffer::NormalVertexBuffer;
+use crate::vfs::vfs::Vfs;
+use crate::pipeline::shadow_render_pipeline::{SHADOW_MATRIX_STRIDE, ShadowRenderPipelineGroup};
+use crate::engine::profiler::{count_pipeline_switch, write_uniform};
+
+/*
+Renders the shadow maps placed by Lights::update_shadows. All maps are regions of the shadow atlas,
+so they are rendered in one pass (GameEngine::create_shadow_pass), begin_map selects the map to draw into.
+ */
+pub struct ShadowRenderer {
+    pub shader: ShadowRenderPipelineGroup,
+    pub maps: Vec<ShadowMap>
+}
+
+impl ShadowRenderer {
+    pub fn new(vfs:&Vfs,device:&Device) -> ShadowRenderer {
+        let shader = ShadowRenderPipelineGroup::new("assets/shader/shadow/vertex.shader".to_string(),device,vfs);
+        return ShadowRenderer { shader, maps: vec![] };
+    }
+
+    pub fn set_maps(&mut self, queue:&Queue, maps:&[ShadowMap]) {
+        for (i,map) in maps.iter().enumerate() {
+            write_uniform(queue,&self.shader.matrix_buffer,i as u64 * SHADOW_MATRIX_STRIDE,&*crate::objects::matrix_helper::get_bytes(&map.view_proj));
+        }
+        self.maps = maps.to_vec();
+    }
+
+    pub fn get_map_count(&self) -> usize {
+        return self.maps.len();
+    }
+
+    pub fn begin_map<'a>(&'a self, render_pass:&mut RenderPass<'a>, index:usize) {
+        let map = &self.maps[index];
+        render_pass.set_pipeline(&self.shader.pipeline);
+        count_pipeline_switch();
+        render_pass.set_viewport(map.x as f32,map.y as f32,map.size as f32,map.size as f32,0.0,1.0);
+        render_pass.set_bind_group(0,&self.shader.matrix_bind_group,&[(index as u64 * SHADOW_MATRIX_STRIDE) as u32]);
+    }
+
+    //offset is the transform offset of the mesh in the given camera, like in VertexRenderer::render
+    pub fn render<'a>(&'a self, render_pass:&mut RenderPass<'a>, camera:&'a Camera, offset: u32, mesh: &'a NormalVertexBuffer) {
+        render_pass.set_bind_group(1,camera.get_transform_group(),&[offset]);
+        mesh.render(render_pass);
+    }
+}
\ No newline at end of file
@@ -10,7 +10,9 @@ use std::ops::Range;
 use crate::objects::offset_handler::{OffsetHandler, StaticOffsetHandler};
 use crate::objects::simple_text::SimpleText;
 use crate::text::render_font::RenderFont;
+use crate::vfs::vfs::Vfs;
 use crate::ui::ui_text::UIText;
+use crate::engine::profiler::{count_pipeline_switch, scope, write_uniform};
 
 pub struct TextRenderer {
     pub shader_program: TextRenderPipelineGroup,
@@ -22,12 +24,12 @@ pub struct TextRenderer {
 
 impl TextRenderer {
 
-    pub fn new(working_dir:String,device:&Device,config:&SurfaceConfiguration) -> TextRenderer {
+    pub fn new(vfs:&Vfs,device:&Device,config:&SurfaceConfiguration) -> TextRenderer {
         unsafe {
             let vertecies_char = vec![Vertex::new(-1.0,1.0,0.0,0.0,0.0),Vertex::new(-1.0,-1.0,0.0,0.0,1.0),Vertex::new(1.0,-1.0,0.0,1.0,1.0),Vertex::new(1.0,1.0,0.0,1.0,0.0)];
             let indecies_char = vec![0,1,3,3,1,2];
 
-            let shader = TextRenderPipelineGroup::new(format!("{}\\{}",&working_dir.to_string(),"assets\\shader\\text\\vertex.shader"),format!("{}\\{}",&working_dir.to_string(),"assets\\shader\\text\\fragment.shader"),config,device,false);
+            let shader = TextRenderPipelineGroup::new("assets/shader/text/vertex.shader".to_string(),"assets/shader/text/fragment.shader".to_string(),config,device,false,vfs);
 
             return TextRenderer {
                 shader_program: shader,
@@ -41,8 +43,8 @@ impl TextRenderer {
 
     pub fn render_text(&mut self,lines:&Vec<String>,pos:&TVec3<f32>, color:Color, queue:&Queue, camera_offset:&mut BufferAddress, camera:&Camera, device:&Device, font:&Rc<RenderFont>, width:f32) {
         let offset = self.color_offset;
-        queue.write_buffer(&self.shader_program.color_buffer,offset,&crate::objects::matrix_helper::get_bytes_from_vec4(&vec4(color.r,color.g,color.b,color.a)));
-        queue.write_buffer(&camera.buffers[2],*camera_offset,&*crate::objects::matrix_helper::get_bytes(&nalgebra_glm::scale(&nalgebra_glm::translation(pos),&vec3(1.0,1.0,0.0))));
+        write_uniform(queue,&self.shader_program.color_buffer,offset,&crate::objects::matrix_helper::get_bytes_from_vec4(&vec4(color.r,color.g,color.b,color.a)));
+        write_uniform(queue,camera.get_transform_buffer(),*camera_offset,&*crate::objects::matrix_helper::get_bytes(&nalgebra_glm::scale(&nalgebra_glm::translation(pos),&vec3(1.0,1.0,0.0))));
         let mesh = crate::objects::simple_text::create_buffer(lines,device,font,width);
 
         self.meshes_to_render.push((offset,*camera_offset,mesh));
@@ -52,9 +54,10 @@ impl TextRenderer {
     }
 
     pub fn finish<'a>(&'a self,render_pass:&mut RenderPass<'a>,camera:&'a Camera,range:Range<usize>, font:&'a Rc<RenderFont>){
+        let _scope = scope("TextRenderer::finish");
         render_pass.set_bind_group(0,&font.texture.bind_group,&[]);
         for (c,cam,mesh) in self.meshes_to_render[range.clone()].iter() {
-            render_pass.set_bind_group(2,&camera.transform_bind_group,&[*cam as u32]);
+            render_pass.set_bind_group(2,camera.get_transform_group(),&[*cam as u32]);
             render_pass.set_bind_group(3,&self.shader_program.color_group,&[*c as u32]);
             mesh.render(render_pass);
         }
@@ -62,19 +65,20 @@ impl TextRenderer {
 
     pub fn begin<'a>(&'a self, render_pass:&mut RenderPass<'a>, camera:&'a Camera) {
         render_pass.set_pipeline(&self.shader_program.group.pipeline);
+        count_pipeline_switch();
         render_pass.set_bind_group(1,&camera.bind_group,&[]);
     }
 
     pub fn render_ui<'a>(&'a self, gui_text:&'a UIText, render_pass:&mut RenderPass<'a>, camera:&'a Camera) {
         render_pass.set_bind_group(0,&gui_text.font.texture.bind_group,&[]);
-        render_pass.set_bind_group(2,&camera.transform_bind_group,&[gui_text.camera_offset as u32]);
+        render_pass.set_bind_group(2,camera.get_transform_group(),&[gui_text.camera_offset as u32]);
         render_pass.set_bind_group(3,&self.shader_program.color_group,&[gui_text.color_offset as u32]);
         gui_text.mesh.render(render_pass);
     }
 
     pub fn render_ui_simple<'a>(&'a self, gui_text:&'a SimpleText, render_pass:&mut RenderPass<'a>, camera:&'a Camera) {
         render_pass.set_bind_group(0,&gui_text.font.texture.bind_group,&[]);
-        render_pass.set_bind_group(2,&camera.transform_bind_group,&[gui_text.camera_offset as u32]);
+        render_pass.set_bind_group(2,camera.get_transform_group(),&[gui_text.camera_offset as u32]);
         render_pass.set_bind_group(3,&self.shader_program.color_group,&[gui_text.color_offset as u32]);
         gui_text.mesh.render(render_pass);
     }
@@ -0,0 +1,52 @@
+use wgpu::{BindGroupDescriptor, BindGroupEntry, BindingResource, CommandEncoder, Device, Queue, SurfaceConfiguration, TextureView};
+use crate::pipeline::transition_render_pipeline::TransitionRenderPipelineGroup;
+use crate::vfs::vfs::Vfs;
+use crate::scene::transition::Transition;
+use crate::engine::profiler::{count_draw, count_pipeline_switch, write_uniform};
+
+pub struct TransitionRenderer {
+    pub shader: TransitionRenderPipelineGroup
+}
+
+impl TransitionRenderer {
+    pub fn new(vfs:&Vfs,device:&Device,config:&SurfaceConfiguration) -> TransitionRenderer {
+        let shader = TransitionRenderPipelineGroup::new("assets/shader/transition/vertex.shader".to_string(),"assets/shader/transition/fragment.shader".to_string(),config.format,device,vfs);
+        return TransitionRenderer { shader };
+    }
+
+    //Blends from (outgoing scene) and to (incoming scene) into target
+    pub fn render(&self, device:&Device, queue:&Queue, encoder:&mut CommandEncoder, from:&TextureView, to:&TextureView, target:&TextureView, transition:&Transition, aspect:f32) {
+        write_uniform(queue,&self.shader.data_buffer,0,&transition.get_data(aspect));
+
+        let bind_group = device.create_bind_group(&BindGroupDescriptor {
+            label: Some("Transition Group"),
+            layout: &self.shader.layout,
+            entries: &[
+                BindGroupEntry { binding: 0, resource: BindingResource::TextureView(from) },
+                BindGroupEntry { binding: 1, resource: BindingResource::TextureView(to) },
+                BindGroupEntry { binding: 2, resource: BindingResource::Sampler(&self.shader.sampler) },
+                BindGroupEntry { binding: 3, resource: self.shader.data_buffer.as_entire_binding() }
+            ]
+        });
+
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Transition Pass"),
+            color_attachments: &[
+                Some(wgpu::RenderPassColorAttachment {
+                    view: target,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Load,
+                        store: true,
+                    }
+                })
+            ],
+            depth_stencil_attachment: None,
+        });
+        render_pass.set_pipeline(&self.shader.pipeline);
+        count_pipeline_switch();
+        render_pass.set_bind_group(0,&bind_group,&[]);
+        render_pass.draw(0..3,0..1);
+        count_draw();
+    }
+}
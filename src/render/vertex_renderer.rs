@@ -4,9 +4,13 @@ use std::rc::Rc;
 use image::{ImageBuffer, Rgba, RgbaImage};
 use crate::objects::texture_object::TextureObject;
 use crate::objects::camera::Camera;
-use crate::objects::vertex_buffer::{NormalVertexBuffer, VertexBuffer};
+use crate::objects::vertex_buffer::{NormalVertexBuffer, SkinnedVertexBuffer, VertexBuffer};
+use crate::animation::skeleton::MAX_JOINTS;
+use nalgebra_glm::Mat4;
 use crate::pipeline::pipeline::{RenderPipelineGroup, RenderPipelineGroupBuilder};
+use crate::vfs::vfs::Vfs;
 use crate::pipeline::vertex_render_pipeline::VertexRenderPipelineGroup;
+use crate::engine::profiler::{count_pipeline_switch, write_uniform};
 
 pub struct VertexRenderer {
     pub shader: VertexRenderPipelineGroup,
@@ -14,7 +18,7 @@ pub struct VertexRenderer {
 }
 
 impl VertexRenderer {
-    pub fn new(working_dir:String,device:&Device,config:&SurfaceConfiguration,queue:&Queue) -> VertexRenderer {
+    pub fn new(vfs:&Vfs,device:&Device,config:&SurfaceConfiguration,queue:&Queue) -> VertexRenderer {
 
         let mut img : RgbaImage = ImageBuffer::new(1,1);
         img.put_pixel(0,0,Rgba([255,255,255,255]));
@@ -22,20 +26,44 @@ impl VertexRenderer {
         let obj = TextureObject::new_from_data(&img,"".to_string(),device,queue,&RenderPipelineGroupBuilder::empty().create_texture_bind_group_layout(device));
 
         unsafe {
-            let shader = VertexRenderPipelineGroup::new(format!("{}\\{}", &working_dir.to_string(), "assets\\shader\\vertex\\vertex.shader"), format!("{}\\{}", &working_dir.to_string(), "assets\\shader\\vertex\\fragment.shader"), config, device);
+            let shader = VertexRenderPipelineGroup::new("assets/shader/vertex/vertex.shader".to_string(), "assets/shader/vertex/fragment.shader".to_string(),
+                                                        "assets/shader/skinned/vertex.shader".to_string(), "assets/shader/vertex/fragment.shader".to_string(), config, device, vfs);
             return VertexRenderer { shader, default_tex: Rc::new(obj) }
         }
     }
 
     pub fn begin<'a>(&'a self, render_pass:&mut RenderPass<'a>, camera:&'a Camera) {
         render_pass.set_pipeline(&self.shader.group.pipeline);
+        count_pipeline_switch();
         render_pass.set_bind_group(1,&camera.bind_group,&[]);
     }
 
     pub fn render<'a>(&'a self, render_pass:&mut RenderPass<'a>, camera:&'a Camera,tex:&'a Rc<TextureObject>, offset: u32, mesh: &'a NormalVertexBuffer, material_offset: u32) {
         render_pass.set_bind_group(0,&tex.bind_group,&[]);
-        render_pass.set_bind_group(2,&camera.transform_bind_group,&[offset]);
-        render_pass.set_bind_group(3,&self.shader.group.bind_groups[0],&[material_offset]);
+        render_pass.set_bind_group(2,camera.get_transform_group(),&[offset]);
+        render_pass.set_bind_group(3,&self.shader.group.bind_groups[0],&[material_offset,0]);
         mesh.render(render_pass);
     }
+
+    pub fn begin_skinned<'a>(&'a self, render_pass:&mut RenderPass<'a>, camera:&'a Camera) {
+        render_pass.set_pipeline(&self.shader.skinned_group.pipeline);
+        count_pipeline_switch();
+        render_pass.set_bind_group(1,&camera.bind_group,&[]);
+    }
+
+    //joint_offset is the slot of the joint matrices, see write_joints
+    pub fn render_skinned<'a>(&'a self, render_pass:&mut RenderPass<'a>, camera:&'a Camera,tex:&'a Rc<TextureObject>, offset: u32, mesh: &'a SkinnedVertexBuffer, material_offset: u32, joint_offset: u32) {
+        render_pass.set_bind_group(0,&tex.bind_group,&[]);
+        render_pass.set_bind_group(2,camera.get_transform_group(),&[offset]);
+        render_pass.set_bind_group(3,&self.shader.group.bind_groups[0],&[material_offset,joint_offset]);
+        mesh.render(render_pass);
+    }
+
+    pub fn write_joints(&self, queue:&Queue, joint_offset: u32, joints:&[Mat4]) {
+        let mut data = vec![];
+        for j in joints.iter().take(MAX_JOINTS) {
+            data.extend(crate::objects::matrix_helper::get_bytes(j));
+        }
+        write_uniform(queue,&self.shader.joint_buffer,joint_offset as u64,&data);
+    }
 }
\ No newline at end of file
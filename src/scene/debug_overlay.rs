@@ -0,0 +1,286 @@
+use std::any::Any;
+use std::collections::VecDeque;
+use std::rc::Rc;
+use nalgebra_glm::{vec3, vec4, Mat4};
+use wgpu::{CommandEncoder, TextureView};
+use winit::dpi::PhysicalSize;
+use winit::event::VirtualKeyCode;
+use crate::engine::game_engine::GameEngine;
+use crate::engine::profiler::write_uniform;
+use crate::objects::camera::Camera;
+use crate::objects::color::Color;
+use crate::objects::depth_texture::DepthTexture;
+use crate::objects::offset_handler::OffsetHandle;
+use crate::objects::simple_text::SimpleText;
+use crate::objects::transform::Transform;
+use crate::scene::scene::Scene;
+use crate::text::render_font::RenderFont;
+
+const GRAPH_SAMPLES: usize = 120;
+const GRAPH_HEIGHT: f32 = 60.0;
+const BAR_WIDTH: f32 = 3.0;
+const PADDING: f32 = 8.0;
+const MARGIN: f32 = 10.0;
+//Seconds between text updates, building the text mesh every frame would be too expensive
+const TEXT_INTERVAL: f32 = 0.25;
+
+const BACKGROUND: usize = 0;
+const GOOD: usize = 1;
+const WARN: usize = 2;
+const BAD: usize = 3;
+const BUDGET: usize = 4;
+
+struct OverlaySlots {
+    background: OffsetHandle,
+    budget: OffsetHandle,
+    bars: Vec<OffsetHandle>,
+    colors: Vec<OffsetHandle>
+}
+
+/*
+Runtime statistics in the top left corner: fps, frame time graph, draw calls (from the Profiler, which is enabled while the overlay is visible),
+loaded resources, audio sources and the usage of the static offset handlers. Push it on top of the other scenes:
+engine.push_scene(Box::new(DebugOverlay::new(font)),LayerFlags::hud());
+ */
+pub struct DebugOverlay {
+    pub visible: bool,
+    //Toggles visible, None to only toggle it from code
+    pub toggle_key: Option<VirtualKeyCode>,
+    //Frame time at the top of the graph in milliseconds
+    pub graph_max_ms: f32,
+    //Frame time of the budget line in milliseconds
+    pub budget_ms: f32,
+    font: Rc<RenderFont>,
+    camera: Option<Camera>,
+    depth_texture: Option<DepthTexture>,
+    text: Option<SimpleText>,
+    slots: Option<OverlaySlots>,
+    frame_times: VecDeque<f32>,
+    bar_colors: Vec<u32>,
+    text_timer: f32,
+    enabled_profiler: bool
+}
+
+impl DebugOverlay {
+    pub fn new(font:Rc<RenderFont>) -> DebugOverlay {
+        return DebugOverlay {
+            visible: true,
+            toggle_key: Some(VirtualKeyCode::F3),
+            graph_max_ms: 50.0,
+            budget_ms: 1000.0 / 60.0,
+            font,
+            camera: None,
+            depth_texture: None,
+            text: None,
+            slots: None,
+            frame_times: VecDeque::with_capacity(GRAPH_SAMPLES),
+            bar_colors: vec![],
+            text_timer: TEXT_INTERVAL,
+            enabled_profiler: false
+        };
+    }
+
+    pub fn visible(mut self, visible:bool) -> Self {
+        self.visible = visible;
+        self
+    }
+
+    pub fn toggle_key(mut self, key:Option<VirtualKeyCode>) -> Self {
+        self.toggle_key = key;
+        self
+    }
+
+    pub fn graph_max_ms(mut self, graph_max_ms:f32) -> Self {
+        self.graph_max_ms = graph_max_ms;
+        self
+    }
+
+    pub fn budget_ms(mut self, budget_ms:f32) -> Self {
+        self.budget_ms = budget_ms;
+        self
+    }
+
+    fn get_lines(&self, engine:&GameEngine) -> Vec<String> {
+        let average = if self.frame_times.is_empty() { 0.0 } else { self.frame_times.iter().sum::<f32>() / self.frame_times.len() as f32 };
+        let max = self.frame_times.iter().cloned().fold(0.0,f32::max);
+
+        let mut lines = vec![
+            format!("FPS: {}  frame: {:.2} ms avg  {:.2} ms max",engine.fps,average,max),
+            format!("Delta: {:.2} ms",engine.delta_time * 1000.0)
+        ];
+
+        match engine.profiler.get_last_frame() {
+            Some(frame) => lines.push(format!("Draw calls: {}  pipelines: {}  uniforms: {:.1} KB",frame.counters.draw_calls,frame.counters.pipeline_switches,frame.counters.uniform_bytes as f32 / 1024.0)),
+            None => lines.push("Draw calls: -".to_string())
+        }
+
+        let loader = &engine.resource_loader;
+        lines.push(format!("Resources: {} textures  {} objs  {} gltfs  {} fonts  ({:.1} MB)",loader.textures.len(),loader.objs.len(),loader.gltfs.len(),loader.fonts.len(),loader.get_gpu_size() as f32 / (1024.0 * 1024.0)));
+        lines.push(format!("Audio sources: {}",engine.audio_handler.get_sources_count()));
+
+        for (name,stats) in engine.get_offset_stats() {
+            lines.push(format!("Offsets {}: {} / {}  peak {}",name,stats.used,stats.capacity,stats.peak));
+        }
+
+        return lines;
+    }
+
+    fn get_panel_height(&self) -> f32 {
+        let line_count = self.text.as_ref().map_or(0,|t| t.lines.len());
+        return line_count as f32 * self.font.base.pixel_height as f32 + GRAPH_HEIGHT + PADDING * 3.0;
+    }
+
+    fn write_quads(&mut self, engine:&GameEngine) {
+        let (camera,slots) = match (&self.camera,&self.slots) {
+            (Some(camera),Some(slots)) => (camera,slots),
+            _ => return
+        };
+
+        let width = GRAPH_SAMPLES as f32 * BAR_WIDTH + PADDING * 2.0;
+        let height = self.get_panel_height();
+        let left = MARGIN;
+        let top = engine.size.height as f32 - MARGIN;
+
+        //x and y are the bottom left corner, the color mesh goes from -1 to 1
+        let quad = |x:f32,y:f32,w:f32,h:f32,z:f32| -> Mat4 { nalgebra_glm::scale(&nalgebra_glm::translation(&vec3(x + w / 2.0,y + h / 2.0,z)),&vec3(w / 2.0,h / 2.0,0.0)) };
+        let write = |handle:&OffsetHandle,matrix:Mat4| camera.write_transform(&engine.device,&engine.queue,handle.get(),&matrix);
+
+        //Larger z is in front, later quads would fail the depth test otherwise
+        write(&slots.background,quad(left,top - height,width,height,-0.5));
+
+        let graph_bottom = top - height + PADDING;
+        let to_height = |ms:f32| (ms / self.graph_max_ms).clamp(0.0,1.0) * GRAPH_HEIGHT;
+        for (i,bar) in slots.bars.iter().enumerate() {
+            let ms = self.frame_times.get(i).cloned().unwrap_or(0.0);
+            write(bar,quad(left + PADDING + i as f32 * BAR_WIDTH,graph_bottom,BAR_WIDTH - 1.0,to_height(ms),-0.25));
+        }
+        write(&slots.budget,quad(left + PADDING,graph_bottom + to_height(self.budget_ms),GRAPH_SAMPLES as f32 * BAR_WIDTH,1.0,-0.1));
+
+        self.bar_colors = self.frame_times.iter().map(|ms| {
+            let slot = if *ms <= self.budget_ms { GOOD } else if *ms <= self.budget_ms * 2.0 { WARN } else { BAD };
+            slots.colors[slot].get_u32()
+        }).collect();
+
+        if let Some(text) = &mut self.text {
+            text.transform = Transform::new(left + PADDING,top - PADDING - self.font.base.pixel_height as f32,0.0,vec3(1.0,1.0,1.0));
+            text.write(camera,engine);
+        }
+    }
+}
+
+impl Scene for DebugOverlay {
+    fn loaded(&mut self, engine: &mut GameEngine) {
+        let colors = [Color::new_with_a(0,0,0,0.6),Color::new(80,230,80),Color::new(255,205,50),Color::new(255,80,80),Color::new_with_a(255,255,255,0.5)];
+        let color_slots: Vec<OffsetHandle> = colors.iter().map(|_| engine.color_renderer.color_offset_handler.get_handle()).collect();
+        for (slot,color) in color_slots.iter().zip(colors.iter()) {
+            write_uniform(&engine.queue,&engine.color_renderer.color_shader.color_buffer,slot.get(),&crate::objects::matrix_helper::get_bytes_from_vec4(&vec4(color.r,color.g,color.b,color.a)));
+        }
+
+        self.slots = Some(OverlaySlots {
+            background: engine.static_offset_handler.get_handle(),
+            budget: engine.static_offset_handler.get_handle(),
+            bars: (0..GRAPH_SAMPLES).map(|_| engine.static_offset_handler.get_handle()).collect(),
+            colors: color_slots
+        });
+
+        self.camera = Some(Camera::new_orto(engine.size.width as i32,engine.size.height as i32,vec3(0.0,0.0,0.0),&engine.device));
+        self.depth_texture = Some(engine.create_depth_texture());
+
+        let mut text = SimpleText::new(String::new(),self.font.clone(),Color::new(255,255,255),engine,0.0,0.0,1000.0,0.0);
+        text.change_text(self.get_lines(engine),engine);
+        self.text = Some(text);
+    }
+
+    fn process_input(&mut self, engine: &mut GameEngine) {
+        if let Some(key) = self.toggle_key {
+            if engine.input_handler.is_key_clicked(key) {
+                self.visible = !self.visible;
+            }
+        }
+    }
+
+    fn update(&mut self, engine: &mut GameEngine) {
+        if self.visible && !engine.profiler.is_enabled() {
+            engine.profiler.set_enabled(true);
+            self.enabled_profiler = true;
+        } else if !self.visible && self.enabled_profiler {
+            engine.profiler.set_enabled(false);
+            self.enabled_profiler = false;
+        }
+
+        if self.frame_times.len() == GRAPH_SAMPLES {
+            self.frame_times.pop_front();
+        }
+        self.frame_times.push_back(engine.delta_time * 1000.0);
+
+        if !self.visible {
+            return;
+        }
+
+        self.text_timer += engine.delta_time;
+        if self.text_timer >= TEXT_INTERVAL {
+            self.text_timer = 0.0;
+            let lines = self.get_lines(engine);
+            if let Some(text) = &mut self.text {
+                text.change_text(lines,engine);
+            }
+        }
+
+        self.write_quads(engine);
+    }
+
+    fn render(&self, engine: &GameEngine, encoder: &mut CommandEncoder, view: &TextureView) {
+        let (camera,depth_texture,slots) = match (&self.camera,&self.depth_texture,&self.slots) {
+            (Some(camera),Some(depth_texture),Some(slots)) if self.visible => (camera,depth_texture,slots),
+            _ => return
+        };
+
+        //The color pipeline needs a depth attachment, the text pipeline has none
+        {
+            let mut render_pass = engine.create_render_pass_load_with_depth(encoder,view,depth_texture);
+            let renderer = &engine.color_renderer;
+            renderer.begin(&mut render_pass,camera);
+            renderer.finish_custom(&mut render_pass,camera,slots.background.get_u32(),slots.colors[BACKGROUND].get_u32(),&renderer.mesh);
+            for (bar,color) in slots.bars.iter().zip(self.bar_colors.iter()) {
+                renderer.finish_custom(&mut render_pass,camera,bar.get_u32(),*color,&renderer.mesh);
+            }
+            renderer.finish_custom(&mut render_pass,camera,slots.budget.get_u32(),slots.colors[BUDGET].get_u32(),&renderer.mesh);
+        }
+
+        if let Some(text) = &self.text {
+            let mut render_pass = engine.create_render_pass_load(encoder,view);
+            engine.text_renderer.begin(&mut render_pass,camera);
+            engine.text_renderer.render_ui_simple(text,&mut render_pass,camera);
+        }
+    }
+
+    fn window_resized(&mut self, engine: &GameEngine, size: &PhysicalSize<u32>) {
+        if let Some(camera) = &mut self.camera {
+            camera.update_aspect_with_size(size.width as f32,size.height as f32);
+            camera.load_up(&engine.queue);
+        }
+        //Also called when msaa changed, the depth texture needs the new sample count
+        if self.depth_texture.is_some() {
+            self.depth_texture = Some(engine.create_depth_texture());
+        }
+    }
+
+    fn close(&mut self, engine: &mut GameEngine) {
+        if let Some(text) = self.text.take() {
+            text.free_up(engine);
+        }
+        //The offset handles free their slots when dropped
+        self.slots = None;
+        if self.enabled_profiler {
+            engine.profiler.set_enabled(false);
+        }
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
@@ -1,2 +1,4 @@
 pub mod scene;
-pub mod scene_handler;
\ No newline at end of file
+pub mod scene_handler;
+pub mod transition;
+pub mod debug_overlay;
\ No newline at end of file
@@ -2,15 +2,24 @@ use std::any::Any;
 use wgpu::{CommandEncoder, TextureView};
 use winit::dpi::PhysicalSize;
 use crate::engine::game_engine::GameEngine;
+use crate::world::world::World;
 
 pub trait Scene {
     fn loaded(&mut self,engine:&mut GameEngine) {}
     fn process_input(&mut self,engine:&mut GameEngine) {}
     fn update(&mut self,engine:&mut GameEngine);
+    //Called with a fixed rate (GameEngine::tick_handler), engine.tick_handler.get_tick_delta() is the time step
+    fn fixed_update(&mut self,engine:&mut GameEngine) {}
     fn render(&self,engine:&GameEngine,encoder:&mut CommandEncoder,view:&TextureView);
     fn window_resized(&mut self,engine:&GameEngine, size:&PhysicalSize<u32>);
     fn handle_second(&mut self,engine:&mut GameEngine) {}
     fn close(&mut self,engine:&mut GameEngine){}
+    //Another scene was pushed on top of this one / the scene on top was popped
+    fn paused(&mut self,engine:&mut GameEngine) {}
+    fn resumed(&mut self,engine:&mut GameEngine) {}
+    //Scenes with a World get it updated and rendered by GameEvents
+    fn world(&mut self) -> Option<&mut World> { None }
+    fn world_ref(&self) -> Option<&World> { None }
     fn as_any(&self) -> &dyn Any;
     fn as_any_mut(&mut self) -> &mut dyn Any;
 }
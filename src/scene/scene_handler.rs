@@ -1,15 +1,351 @@
+use wgpu::{CommandEncoder, TextureView};
+use winit::dpi::PhysicalSize;
+use crate::engine::game_engine::GameEngine;
+use crate::engine::profiler::scope;
+use crate::engine::resource_handle::ResourceGroup;
+use crate::objects::render_texture::RenderTexture;
 use crate::scene::scene::{Scene, NullScene};
+use crate::scene::transition::Transition;
 
+/*
+What the scenes below a layer still get. A pause menu stops the game below (only rendering it),
+a HUD lets everything through.
+ */
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct LayerFlags {
+    pub update_below: bool,
+    pub render_below: bool,
+    pub input_below: bool
+}
+
+impl LayerFlags {
+    //The scenes below are neither updated nor rendered
+    pub fn new() -> Self {
+        LayerFlags { update_below: false, render_below: false, input_below: false }
+    }
+
+    pub fn pause_menu() -> Self {
+        LayerFlags::new().render_below(true)
+    }
+
+    pub fn hud() -> Self {
+        LayerFlags::new().update_below(true).render_below(true).input_below(true)
+    }
+
+    pub fn update_below(mut self, update_below:bool) -> Self {
+        self.update_below = update_below;
+        self
+    }
+
+    pub fn render_below(mut self, render_below:bool) -> Self {
+        self.render_below = render_below;
+        self
+    }
+
+    pub fn input_below(mut self, input_below:bool) -> Self {
+        self.input_below = input_below;
+        self
+    }
+}
+
+//Resources loaded in Scene::loaded (including load requests created there) are in the group of the layer and unloaded after Scene::close
+pub struct SceneLayer {
+    pub scene: Box<dyn Scene>,
+    pub flags: LayerFlags,
+    pub group: Option<ResourceGroup>
+}
+
+impl SceneLayer {
+    fn load(engine:&mut GameEngine, mut scene:Box<dyn Scene>, flags:LayerFlags) -> Self {
+        let group = engine.new_resource_group();
+        let previous = engine.set_resource_group(Some(group));
+        scene.loaded(engine);
+        engine.set_resource_group(previous);
+        SceneLayer { scene, flags, group: Some(group) }
+    }
+
+    fn close(mut self, engine:&mut GameEngine) -> Box<dyn Scene> {
+        self.scene.close(engine);
+        if let Some(group) = self.group {
+            engine.free_resource_group(group);
+        }
+        return self.scene;
+    }
+}
+
+//Requested with GameEngine::push_scene / pop_scene / replace_scene, applied at the start of the next frame
+pub enum SceneCommand {
+    Push(Box<dyn Scene>,LayerFlags),
+    Pop,
+    Replace(Box<dyn Scene>),
+    Transition(Box<dyn Scene>,Transition)
+}
+
+//The outgoing layers are only rendered (into from) until the transition is finished, then they are closed
+pub struct ActiveTransition {
+    pub transition: Transition,
+    pub outgoing: Vec<SceneLayer>,
+    pub from: RenderTexture,
+    pub to: RenderTexture
+}
+
+/*
+Stack of scenes, the last layer is the top one. Scenes are updated and rendered from the bottom to the top,
+the flags of a layer decide if the layers below it are included.
+ */
 pub struct SceneHandler {
-    pub opened_scene: Box<dyn Scene>
+    pub layers: Vec<SceneLayer>,
+    pub transition: Option<ActiveTransition>
 }
 
 impl SceneHandler {
     pub fn open_scene(&mut self,scene: Box<dyn Scene>) {
-        self.opened_scene = scene;
+        self.layers = vec![SceneLayer { scene, flags: LayerFlags::new(), group: None }];
     }
 
     pub fn new() -> SceneHandler {
-        return SceneHandler { opened_scene: Box::new(NullScene::new()) };
+        return SceneHandler { layers: vec![SceneLayer { scene: Box::new(NullScene::new()), flags: LayerFlags::new(), group: None }], transition: None };
+    }
+
+    pub fn top(&self) -> Option<&Box<dyn Scene>> {
+        return self.layers.last().map(|l| &l.scene);
+    }
+
+    pub fn top_mut(&mut self) -> Option<&mut Box<dyn Scene>> {
+        return self.layers.last_mut().map(|l| &mut l.scene);
+    }
+
+    pub fn push_scene(&mut self, engine:&mut GameEngine, scene:Box<dyn Scene>, flags:LayerFlags) {
+        if let Some(top) = self.top_mut() {
+            top.paused(engine);
+        }
+        self.layers.push(SceneLayer::load(engine,scene,flags));
+    }
+
+    //The last scene is never popped
+    pub fn pop_scene(&mut self, engine:&mut GameEngine) -> Option<Box<dyn Scene>> {
+        if self.layers.len() < 2 {
+            return None;
+        }
+
+        let scene = self.layers.pop().unwrap().close(engine);
+        if let Some(top) = self.top_mut() {
+            top.resumed(engine);
+        }
+        return Some(scene);
+    }
+
+    pub fn replace_scene(&mut self, engine:&mut GameEngine, scene:Box<dyn Scene>) {
+        let flags = match self.layers.pop() {
+            Some(layer) => {
+                let flags = layer.flags;
+                layer.close(engine);
+                flags
+            },
+            None => LayerFlags::new()
+        };
+        self.layers.push(SceneLayer::load(engine,scene,flags));
+    }
+
+    //Closes every scene and opens the given one
+    pub fn change_scene(&mut self, engine:&mut GameEngine, scene:Box<dyn Scene>) {
+        self.close(engine);
+        self.layers = vec![SceneLayer::load(engine,scene,LayerFlags::new())];
+    }
+
+    /*
+    The new scene is loaded before the transition starts, it replaces the whole stack.
+    A running transition is finished first.
+     */
+    pub fn change_scene_with(&mut self, engine:&mut GameEngine, scene:Box<dyn Scene>, transition:Transition) {
+        self.finish_transition(engine);
+
+        if let Some(top) = self.top_mut() {
+            top.paused(engine);
+        }
+        let layer = SceneLayer::load(engine,scene,LayerFlags::new());
+
+        let outgoing = std::mem::replace(&mut self.layers,vec![layer]);
+
+        self.transition = Some(ActiveTransition {
+            transition,
+            outgoing,
+            from: create_transition_target(engine),
+            to: create_transition_target(engine)
+        });
+    }
+
+    pub fn finish_transition(&mut self, engine:&mut GameEngine) {
+        if let Some(mut active) = self.transition.take() {
+            engine.unregister_render_target(&active.from);
+            engine.unregister_render_target(&active.to);
+            while let Some(layer) = active.outgoing.pop() {
+                layer.close(engine);
+            }
+        }
+    }
+
+    pub fn is_transitioning(&self) -> bool {
+        return self.transition.is_some();
+    }
+
+    pub fn close(&mut self, engine:&mut GameEngine) {
+        self.finish_transition(engine);
+        while let Some(layer) = self.layers.pop() {
+            layer.close(engine);
+        }
+    }
+
+    //GameEngine::scene_to_open replaces the whole stack
+    pub fn apply_commands(&mut self, engine:&mut GameEngine) {
+        if let Some(scene) = engine.scene_to_open.take() {
+            engine.delta_time = 0.0;
+            self.change_scene(engine,scene);
+        }
+
+        let commands = std::mem::take(&mut engine.scene_commands);
+        for command in commands {
+            engine.delta_time = 0.0;
+            match command {
+                SceneCommand::Push(scene,flags) => self.push_scene(engine,scene,flags),
+                SceneCommand::Pop => { self.pop_scene(engine); },
+                SceneCommand::Replace(scene) => self.replace_scene(engine,scene),
+                SceneCommand::Transition(scene,transition) => self.change_scene_with(engine,scene,transition)
+            }
+        }
+    }
+
+    fn first_layer(&self, flag:fn(&LayerFlags) -> bool) -> usize {
+        return first_layer(&self.layers,flag);
+    }
+
+    //Scenes opened before the engine existed (open_scene) get their group here
+    pub fn loaded(&mut self, engine:&mut GameEngine) {
+        for layer in self.layers.iter_mut() {
+            let group = *layer.group.get_or_insert_with(|| engine.new_resource_group());
+            let previous = engine.set_resource_group(Some(group));
+            layer.scene.loaded(engine);
+            engine.set_resource_group(previous);
+        }
+    }
+
+    pub fn process_input(&mut self, engine:&mut GameEngine) {
+        if self.transition.as_ref().map_or(false,|t| t.transition.block_input) {
+            return;
+        }
+        let first = self.first_layer(|f| f.input_below);
+        for layer in self.layers[first..].iter_mut() {
+            layer.scene.process_input(engine);
+        }
+    }
+
+    pub fn fixed_update(&mut self, engine:&mut GameEngine) {
+        let first = self.first_layer(|f| f.update_below);
+        for layer in self.layers[first..].iter_mut() {
+            layer.scene.fixed_update(engine);
+            if let Some(world) = layer.scene.world() {
+                world.fixed_update(engine);
+            }
+        }
+    }
+
+    pub fn update(&mut self, engine:&mut GameEngine) {
+        let first = self.first_layer(|f| f.update_below);
+        for layer in self.layers[first..].iter_mut() {
+            layer.scene.update(engine);
+            if let Some(world) = layer.scene.world() {
+                world.update(engine);
+            }
+        }
+
+        if let Some(active) = &mut self.transition {
+            active.transition.update(engine.delta_time);
+            if active.transition.is_finished() {
+                self.finish_transition(engine);
+            }
+        }
+    }
+
+    pub fn handle_second(&mut self, engine:&mut GameEngine) {
+        let first = self.first_layer(|f| f.update_below);
+        for layer in self.layers[first..].iter_mut() {
+            layer.scene.handle_second(engine);
+        }
+    }
+
+    //The frame is cleared once, the passes of the layers load it (see render_layers)
+    //With post processing the scenes render into its target and the effects into view
+    pub fn render(&self, engine:&GameEngine, encoder:&mut CommandEncoder, view:&TextureView) {
+        match engine.post_processor.get_target() {
+            Some(target) => {
+                self.render_scenes(engine,encoder,target);
+                let _scope = scope("post processing");
+                engine.profiler.write_timestamp(encoder,"Post Processing");
+                engine.post_processor.render(&engine.device,encoder,view);
+            },
+            None => self.render_scenes(engine,encoder,view)
+        }
+    }
+
+    fn render_scenes(&self, engine:&GameEngine, encoder:&mut CommandEncoder, view:&TextureView) {
+        match &self.transition {
+            Some(active) => {
+                render_layers(&active.outgoing,engine,encoder,&active.from.view);
+                render_layers(&self.layers,engine,encoder,&active.to.view);
+                let aspect = engine.config.width as f32 / engine.config.height.max(1) as f32;
+                engine.profiler.write_timestamp(encoder,"Transition");
+                engine.transition_renderer.render(&engine.device,&engine.queue,encoder,&active.from.view,&active.to.view,view,&active.transition,aspect);
+            },
+            None => render_layers(&self.layers,engine,encoder,view)
+        }
+    }
+
+    pub fn window_resized(&mut self, engine:&mut GameEngine, size:&PhysicalSize<u32>) {
+        if let Some(active) = &mut self.transition {
+            engine.unregister_render_target(&active.from);
+            engine.unregister_render_target(&active.to);
+            active.from = create_transition_target(engine);
+            active.to = create_transition_target(engine);
+        }
+
+        let outgoing = self.transition.as_mut().map(|t| t.outgoing.iter_mut()).into_iter().flatten();
+        for layer in self.layers.iter_mut().chain(outgoing) {
+            layer.scene.window_resized(engine,size);
+            if let Some(world) = layer.scene.world() {
+                world.resize(engine,size);
+            }
+        }
+    }
+}
+
+
+//Registered for msaa, see GameEngine::register_render_target
+fn create_transition_target(engine:&mut GameEngine) -> RenderTexture {
+    let target = RenderTexture::new(&engine.device,engine.config.width,engine.config.height,engine.get_render_format());
+    engine.register_render_target(&target);
+    return target;
+}
+
+//Index of the lowest layer that is still reached
+fn first_layer(layers:&[SceneLayer], flag:fn(&LayerFlags) -> bool) -> usize {
+    let mut first = layers.len().saturating_sub(1);
+    while first > 0 && flag(&layers[first].flags) {
+        first -= 1;
+    }
+    return first;
+}
+
+//Clears view and renders the layers on top of each other, passes of the scenes into view can't clear what the layers below rendered
+fn render_layers(layers:&[SceneLayer], engine:&GameEngine, encoder:&mut CommandEncoder, view:&TextureView) {
+    engine.create_render_pass(encoder,view);
+
+    engine.set_layer_target(Some(view));
+    let first = first_layer(layers,|f| f.render_below);
+    for layer in layers.iter().skip(first) {
+        if let Some(world) = layer.scene.world_ref() {
+            world.render(engine,encoder,view);
+        }
+        layer.scene.render(engine,encoder,view);
     }
+    engine.set_layer_target(None);
 }
\ No newline at end of file
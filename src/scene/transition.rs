@@ -0,0 +1,128 @@
+use nalgebra_glm::{vec2, vec4, TVec2};
+use crate::animation::easing::Easing;
+use crate::objects::color::Color;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum WipeDirection {
+    Left,
+    Right,
+    Up,
+    Down
+}
+
+impl WipeDirection {
+    //Direction the edge moves in texture coordinates (y points down)
+    fn get_vector(&self) -> TVec2<f32> {
+        return match self {
+            WipeDirection::Left => vec2(-1.0,0.0),
+            WipeDirection::Right => vec2(1.0,0.0),
+            WipeDirection::Up => vec2(0.0,-1.0),
+            WipeDirection::Down => vec2(0.0,1.0)
+        }
+    }
+}
+
+#[derive(Clone)]
+pub enum TransitionEffect {
+    //Fades the outgoing scene to the color, then the color to the incoming scene
+    FadeToColor(Color),
+    Crossfade,
+    Wipe(WipeDirection),
+    //Circle growing from the center of the screen
+    Iris
+}
+
+impl TransitionEffect {
+    fn id(&self) -> f32 {
+        return match self {
+            TransitionEffect::FadeToColor(_) => 0.0,
+            TransitionEffect::Crossfade => 1.0,
+            TransitionEffect::Wipe(_) => 2.0,
+            TransitionEffect::Iris => 3.0
+        }
+    }
+}
+
+/*
+Scene change with an effect, see GameEngine::change_scene_with. Both scenes are rendered into offscreen targets
+and blended by the TransitionRenderer. Softness is the width of the edge of wipes and iris (in texture coordinates).
+ */
+#[derive(Clone)]
+pub struct Transition {
+    pub effect: TransitionEffect,
+    pub duration: f32,
+    pub easing: Easing,
+    pub softness: f32,
+    pub block_input: bool,
+    pub time: f32
+}
+
+impl Transition {
+    pub fn new(effect:TransitionEffect, duration:f32) -> Self {
+        Transition { effect, duration, easing: Easing::Linear, softness: 0.05, block_input: true, time: 0.0 }
+    }
+
+    pub fn fade(color:Color, duration:f32) -> Self {
+        Transition::new(TransitionEffect::FadeToColor(color),duration)
+    }
+
+    pub fn crossfade(duration:f32) -> Self {
+        Transition::new(TransitionEffect::Crossfade,duration)
+    }
+
+    pub fn wipe(direction:WipeDirection, duration:f32) -> Self {
+        Transition::new(TransitionEffect::Wipe(direction),duration)
+    }
+
+    pub fn iris(duration:f32) -> Self {
+        Transition::new(TransitionEffect::Iris,duration)
+    }
+
+    pub fn easing(mut self, easing:Easing) -> Self {
+        self.easing = easing;
+        self
+    }
+
+    pub fn softness(mut self, softness:f32) -> Self {
+        self.softness = softness;
+        self
+    }
+
+    //The incoming scene gets input while the transition is running
+    pub fn allow_input(mut self) -> Self {
+        self.block_input = false;
+        self
+    }
+
+    pub fn update(&mut self, delta:f32) {
+        self.time = (self.time + delta).min(self.duration);
+    }
+
+    pub fn is_finished(&self) -> bool {
+        return self.time >= self.duration;
+    }
+
+    pub fn get_progress(&self) -> f32 {
+        if self.duration <= 0.0 {
+            return 1.0;
+        }
+        return self.easing.apply(self.time / self.duration);
+    }
+
+    pub fn get_data(&self, aspect:f32) -> Vec<u8> {
+        let color = match &self.effect {
+            TransitionEffect::FadeToColor(c) => c.clone(),
+            _ => Color::new(0,0,0)
+        };
+        let direction = match &self.effect {
+            TransitionEffect::Wipe(d) => d.get_vector(),
+            _ => vec2(1.0,0.0)
+        };
+
+        let mut data = vec![];
+        data.extend(crate::objects::matrix_helper::get_bytes_from_vec4(&vec4(color.r,color.g,color.b,color.a)));
+        data.extend(crate::objects::matrix_helper::get_bytes_from_vec4(&vec4(self.get_progress(),self.effect.id(),self.softness,aspect)));
+        data.extend(crate::objects::matrix_helper::get_bytes_from_vec4(&vec4(direction.x,direction.y,0.0,0.0)));
+        return data;
+    }
+}
@@ -13,6 +13,7 @@ use crate::text::render_font::RenderFont;
 use crate::ui::bounds::Bounds;
 use crate::ui::ui_component::{UIComponent, UIComponentType};
 use crate::ui::ui_constraint::ConstraintSettings;
+use crate::engine::profiler::write_uniform;
 
 pub struct UIText {
     pub lines:Vec<String>,
@@ -40,12 +41,12 @@ impl UIText {
     }
 
     pub fn write(&self,camera:&Camera,engine:&GameEngine) {
-        engine.queue.write_buffer(&camera.buffers[2],self.camera_offset as u64,&*crate::objects::matrix_helper::get_bytes(&self.transform.matrix));
+        camera.write_transform(&engine.device,&engine.queue,self.camera_offset as u64,&self.transform.matrix);
         self.write_color(engine);
     }
 
     pub fn write_color(&self,engine:&GameEngine) {
-        engine.queue.write_buffer(&engine.text_renderer.shader_program.color_buffer,self.color_offset as u64,&*crate::objects::matrix_helper::get_bytes_from_vec4(&vec4(self.color.r,self.color.g,self.color.b,self.color.a)));
+        write_uniform(&engine.queue,&engine.text_renderer.shader_program.color_buffer,self.color_offset as u64,&*crate::objects::matrix_helper::get_bytes_from_vec4(&vec4(self.color.r,self.color.g,self.color.b,self.color.a)));
     }
 
     pub fn free_up(&self,engine:&mut GameEngine) {
@@ -0,0 +1,142 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::RwLock;
+use std::time::SystemTime;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct VfsEntry {
+    pub name: String,
+    pub is_dir: bool
+}
+
+/*
+Source of files for the Vfs. Paths are relative to the mount point, normalized and separated by '/' (see normalize_path).
+ */
+pub trait VfsBackend: Send + Sync {
+    fn exists(&self, path:&str) -> bool;
+    fn read(&self, path:&str) -> std::io::Result<Vec<u8>>;
+    fn list_dir(&self, path:&str) -> Vec<VfsEntry>;
+    fn modified(&self, path:&str) -> Option<SystemTime>;
+
+    //Only backends that are backed by real files have one
+    fn real_path(&self, _path:&str) -> Option<PathBuf> {
+        return None;
+    }
+
+    fn describe(&self) -> String;
+}
+
+pub struct DirBackend {
+    pub root: PathBuf
+}
+
+impl DirBackend {
+    pub fn new(root:impl Into<PathBuf>) -> Self {
+        DirBackend { root: root.into() }
+    }
+
+    fn get_path(&self, path:&str) -> PathBuf {
+        let mut p = self.root.clone();
+        for part in path.split('/').filter(|p| !p.is_empty()) {
+            p.push(part);
+        }
+        return p;
+    }
+}
+
+impl VfsBackend for DirBackend {
+    fn exists(&self, path:&str) -> bool {
+        return self.get_path(path).exists();
+    }
+
+    fn read(&self, path:&str) -> std::io::Result<Vec<u8>> {
+        return std::fs::read(self.get_path(path));
+    }
+
+    fn list_dir(&self, path:&str) -> Vec<VfsEntry> {
+        return match std::fs::read_dir(self.get_path(path)) {
+            Ok(entries) => entries.filter_map(|e| e.ok()).map(|e| VfsEntry { name: e.file_name().to_string_lossy().to_string(), is_dir: e.path().is_dir() }).collect(),
+            Err(_) => vec![]
+        }
+    }
+
+    fn modified(&self, path:&str) -> Option<SystemTime> {
+        return std::fs::metadata(self.get_path(path)).and_then(|m| m.modified()).ok();
+    }
+
+    fn real_path(&self, path:&str) -> Option<PathBuf> {
+        return Some(self.get_path(path));
+    }
+
+    fn describe(&self) -> String {
+        return self.root.to_string_lossy().to_string();
+    }
+}
+
+//Files that only exist in memory, e.g. for tests or generated assets
+pub struct MemoryBackend {
+    files: RwLock<BTreeMap<String,(Vec<u8>,SystemTime)>>
+}
+
+impl MemoryBackend {
+    pub fn new() -> Self {
+        MemoryBackend { files: RwLock::new(BTreeMap::new()) }
+    }
+
+    pub fn with_file(self, path:&str, data:impl Into<Vec<u8>>) -> Self {
+        self.add_file(path,data);
+        self
+    }
+
+    //Replaces the file if it exists, which also changes its modified time
+    pub fn add_file(&self, path:&str, data:impl Into<Vec<u8>>) {
+        self.files.write().unwrap().insert(crate::vfs::vfs::normalize_path(path),(data.into(),SystemTime::now()));
+    }
+
+    pub fn remove_file(&self, path:&str) {
+        self.files.write().unwrap().remove(&crate::vfs::vfs::normalize_path(path));
+    }
+}
+
+impl VfsBackend for MemoryBackend {
+    fn exists(&self, path:&str) -> bool {
+        let files = self.files.read().unwrap();
+        return files.contains_key(path) || files.keys().any(|f| is_in_dir(f,path));
+    }
+
+    fn read(&self, path:&str) -> std::io::Result<Vec<u8>> {
+        return self.files.read().unwrap().get(path).map(|(data,_)| data.clone()).ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound,"file not found in memory"));
+    }
+
+    fn list_dir(&self, path:&str) -> Vec<VfsEntry> {
+        return list_paths(self.files.read().unwrap().keys(),path);
+    }
+
+    fn modified(&self, path:&str) -> Option<SystemTime> {
+        return self.files.read().unwrap().get(path).map(|(_,time)| *time);
+    }
+
+    fn describe(&self) -> String {
+        return "memory".to_string();
+    }
+}
+
+fn is_in_dir(file:&str, dir:&str) -> bool {
+    return dir.is_empty() || (file.starts_with(dir) && file[dir.len()..].starts_with('/'));
+}
+
+//Direct children of dir in a list of file paths, used by the backends without real directories
+pub(crate) fn list_paths<'a>(files:impl Iterator<Item=&'a String>, dir:&str) -> Vec<VfsEntry> {
+    let mut entries: Vec<VfsEntry> = vec![];
+    for file in files.filter(|f| is_in_dir(f,dir)) {
+        let rest = if dir.is_empty() { file.as_str() } else { &file[dir.len() + 1..] };
+        let entry = match rest.split_once('/') {
+            Some((name,_)) => VfsEntry { name: name.to_string(), is_dir: true },
+            None => VfsEntry { name: rest.to_string(), is_dir: false }
+        };
+        if !entries.contains(&entry) {
+            entries.push(entry);
+        }
+    }
+    return entries;
+}
@@ -0,0 +1,3 @@
+pub mod vfs;
+pub mod backend;
+pub mod pack;
\ No newline at end of file
@@ -0,0 +1,213 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+use crate::error::rgl_error::{RglError, RglResult};
+use crate::vfs::backend::{list_paths, VfsBackend, VfsEntry};
+use crate::vfs::vfs::normalize_path;
+
+pub const PACK_MAGIC: &[u8;8] = b"RGLPACK1";
+
+/*
+Indexed archive of files:
+magic (8 bytes), entry count (u32), entries (path length u16, path, offset u64, size u64), data
+All numbers are little endian, the offsets are from the start of the file.
+ */
+pub struct PackBackend {
+    path: PathBuf,
+    file: Mutex<File>,
+    entries: BTreeMap<String,(u64,u64)>,
+    modified: Option<SystemTime>
+}
+
+impl PackBackend {
+    pub fn open(path:impl AsRef<Path>) -> RglResult<PackBackend> {
+        let path = path.as_ref().to_path_buf();
+        let name = path.to_string_lossy().to_string();
+        let file = File::open(&path).map_err(|e| RglError::io(&name,e))?;
+        let metadata = file.metadata().map_err(|e| RglError::io(&name,e))?;
+        let modified = metadata.modified().ok();
+        let mut file = BufReader::new(file);
+
+        let mut magic = [0u8;8];
+        file.read_exact(&mut magic).map_err(|e| RglError::io(&name,e))?;
+        if &magic != PACK_MAGIC {
+            return Err(RglError::unsupported(&name,"not a pack file"));
+        }
+
+        let count = read_u32(&mut file,&name)?;
+        let mut entries = BTreeMap::new();
+        for _ in 0..count {
+            let len = read_u16(&mut file,&name)? as usize;
+            let mut buf = vec![0u8;len];
+            file.read_exact(&mut buf).map_err(|e| RglError::io(&name,e))?;
+            let entry_path = String::from_utf8(buf).map_err(|e| RglError::parse(&name,None,e.to_string()))?;
+            let offset = read_u64(&mut file,&name)?;
+            let size = read_u64(&mut file,&name)?;
+            //Entries of a truncated or corrupt pack would read past its end (or allocate whatever size says)
+            if offset.checked_add(size).map_or(true,|end| end > metadata.len()) {
+                return Err(RglError::parse(&name,None,format!("Entry {} (offset {}, size {}) is outside of the pack ({} bytes)",entry_path,offset,size,metadata.len())));
+            }
+            entries.insert(normalize_path(&entry_path),(offset,size));
+        }
+
+        return Ok(PackBackend { path, file: Mutex::new(file.into_inner()), entries, modified });
+    }
+
+    pub fn get_entry_count(&self) -> usize {
+        return self.entries.len();
+    }
+}
+
+impl VfsBackend for PackBackend {
+    fn exists(&self, path:&str) -> bool {
+        return self.entries.contains_key(path) || !list_paths(self.entries.keys(),path).is_empty();
+    }
+
+    fn read(&self, path:&str) -> std::io::Result<Vec<u8>> {
+        let (offset,size) = *self.entries.get(path).ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound,"file not found in pack"))?;
+        let mut file = self.file.lock().unwrap();
+        file.seek(SeekFrom::Start(offset))?;
+        let mut data = vec![0u8;size as usize];
+        file.read_exact(&mut data)?;
+        return Ok(data);
+    }
+
+    fn list_dir(&self, path:&str) -> Vec<VfsEntry> {
+        return list_paths(self.entries.keys(),path);
+    }
+
+    //Files in a pack change with the pack
+    fn modified(&self, path:&str) -> Option<SystemTime> {
+        return if self.entries.contains_key(path) { self.modified } else { None };
+    }
+
+    fn describe(&self) -> String {
+        return self.path.to_string_lossy().to_string();
+    }
+}
+
+//Builds pack files, e.g. PackWriter::new().add_dir("assets","/path/to/assets").write("assets.pack")
+pub struct PackWriter {
+    pub files: BTreeMap<String,Vec<u8>>
+}
+
+impl PackWriter {
+    pub fn new() -> Self {
+        PackWriter { files: BTreeMap::new() }
+    }
+
+    pub fn add_file(mut self, path:&str, data:impl Into<Vec<u8>>) -> Self {
+        self.files.insert(normalize_path(path),data.into());
+        self
+    }
+
+    //Adds every file below dir (recursively), prefix is the path of dir inside the pack
+    pub fn add_dir(mut self, prefix:&str, dir:impl AsRef<Path>) -> RglResult<Self> {
+        let dir = dir.as_ref();
+        let name = dir.to_string_lossy().to_string();
+        for entry in std::fs::read_dir(dir).map_err(|e| RglError::io(&name,e))? {
+            let entry = entry.map_err(|e| RglError::io(&name,e))?;
+            let path = format!("{}/{}",prefix,entry.file_name().to_string_lossy());
+            if entry.path().is_dir() {
+                self = self.add_dir(&path,entry.path())?;
+            } else {
+                let data = std::fs::read(entry.path()).map_err(|e| RglError::io(&entry.path().to_string_lossy(),e))?;
+                self.files.insert(normalize_path(&path),data);
+            }
+        }
+        return Ok(self);
+    }
+
+    pub fn write(&self, path:impl AsRef<Path>) -> RglResult<()> {
+        let name = path.as_ref().to_string_lossy().to_string();
+
+        let index_size: u64 = self.files.keys().map(|p| 2 + p.len() as u64 + 16).sum();
+        let mut offset = PACK_MAGIC.len() as u64 + 4 + index_size;
+
+        let mut out = vec![];
+        out.extend_from_slice(PACK_MAGIC);
+        out.extend_from_slice(&(self.files.len() as u32).to_le_bytes());
+        for (p,data) in self.files.iter() {
+            out.extend_from_slice(&(p.len() as u16).to_le_bytes());
+            out.extend_from_slice(p.as_bytes());
+            out.extend_from_slice(&offset.to_le_bytes());
+            out.extend_from_slice(&(data.len() as u64).to_le_bytes());
+            offset += data.len() as u64;
+        }
+        for data in self.files.values() {
+            out.extend_from_slice(data);
+        }
+
+        let mut file = File::create(path.as_ref()).map_err(|e| RglError::io(&name,e))?;
+        file.write_all(&out).map_err(|e| RglError::io(&name,e))?;
+        return Ok(());
+    }
+}
+
+fn read_u16(file:&mut impl Read, name:&str) -> RglResult<u16> {
+    let mut buf = [0u8;2];
+    file.read_exact(&mut buf).map_err(|e| RglError::io(name,e))?;
+    return Ok(u16::from_le_bytes(buf));
+}
+
+fn read_u32(file:&mut impl Read, name:&str) -> RglResult<u32> {
+    let mut buf = [0u8;4];
+    file.read_exact(&mut buf).map_err(|e| RglError::io(name,e))?;
+    return Ok(u32::from_le_bytes(buf));
+}
+
+fn read_u64(file:&mut impl Read, name:&str) -> RglResult<u64> {
+    let mut buf = [0u8;8];
+    file.read_exact(&mut buf).map_err(|e| RglError::io(name,e))?;
+    return Ok(u64::from_le_bytes(buf));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_pack(name:&str) -> PathBuf {
+        return std::env::temp_dir().join(format!("rgl_pack_test_{}_{}.pack",std::process::id(),name));
+    }
+
+    #[test]
+    fn reads_back_written_files() {
+        let path = temp_pack("round_trip");
+        PackWriter::new().add_file("a.txt","first").add_file("dir\\sub/b.bin",vec![0u8,1,2,255]).add_file("empty","").write(&path).unwrap();
+
+        let pack = PackBackend::open(&path).unwrap();
+        assert_eq!(pack.get_entry_count(),3);
+        assert_eq!(pack.read("a.txt").unwrap(),b"first");
+        assert_eq!(pack.read("dir/sub/b.bin").unwrap(),vec![0u8,1,2,255]);
+        assert_eq!(pack.read("empty").unwrap(),Vec::<u8>::new());
+        assert!(pack.read("missing").is_err());
+        assert!(pack.exists("dir/sub"));
+        assert_eq!(pack.list_dir("dir"),vec![VfsEntry { name: "sub".to_string(), is_dir: true }]);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn rejects_truncated_packs() {
+        let path = temp_pack("truncated");
+        PackWriter::new().add_file("a.txt","some content").write(&path).unwrap();
+        let data = std::fs::read(&path).unwrap();
+        std::fs::write(&path,&data[..data.len() - 4]).unwrap();
+
+        let error = PackBackend::open(&path).err().unwrap();
+        assert!(matches!(error,RglError::Parse { .. }),"{}",error);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn rejects_other_files() {
+        let path = temp_pack("magic");
+        std::fs::write(&path,b"NOTAPACK").unwrap();
+        assert!(matches!(PackBackend::open(&path),Err(RglError::UnsupportedFormat { .. })));
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,239 @@
+use std::path::PathBuf;
+use std::sync::RwLock;
+use std::time::SystemTime;
+use log::info;
+use crate::error::rgl_error::{RglError, RglResult};
+use crate::vfs::backend::{DirBackend, MemoryBackend, VfsBackend, VfsEntry};
+use crate::vfs::pack::PackBackend;
+
+impl std::fmt::Debug for Vfs {
+    fn fmt(&self, f:&mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mounts = self.mounts.read().unwrap();
+        return f.debug_list().entries(mounts.iter().map(|m| format!("/{} {} ({})",m.point,m.backend.describe(),m.priority))).finish();
+    }
+}
+
+struct Mount {
+    point: String,
+    priority: i32,
+    backend: Box<dyn VfsBackend>
+}
+
+/*
+Virtual file system all loaders read through. Paths are relative and use '/' (backslashes are accepted and converted),
+every backend (directory, pack file, memory) is mounted at a point in this tree.
+If several mounts contain the same file the one with the highest priority wins (the latest one for equal priorities),
+so e.g. a mod directory mounted with priority 10 overrides the base assets mounted with 0.
+ */
+pub struct Vfs {
+    mounts: RwLock<Vec<Mount>>
+}
+
+impl Vfs {
+    pub fn new() -> Self {
+        Vfs { mounts: RwLock::new(vec![]) }
+    }
+
+    pub fn mount(&self, point:&str, backend:Box<dyn VfsBackend>, priority:i32) {
+        info!("Mounting {} at /{} (priority {})",backend.describe(),normalize_path(point),priority);
+        let mut mounts = self.mounts.write().unwrap();
+        mounts.push(Mount { point: normalize_path(point), priority, backend });
+        //Stable, so later mounts stay behind earlier ones with the same priority and are found first when iterating in reverse
+        mounts.sort_by_key(|m| m.priority);
+    }
+
+    pub fn mount_dir(&self, point:&str, dir:impl Into<PathBuf>, priority:i32) {
+        self.mount(point,Box::new(DirBackend::new(dir)),priority);
+    }
+
+    pub fn mount_pack(&self, point:&str, file:impl Into<PathBuf>, priority:i32) -> RglResult<()> {
+        let pack = PackBackend::open(file.into())?;
+        self.mount(point,Box::new(pack),priority);
+        return Ok(());
+    }
+
+    pub fn mount_memory(&self, point:&str, memory:MemoryBackend, priority:i32) {
+        self.mount(point,Box::new(memory),priority);
+    }
+
+    //Removes every mount at the point
+    pub fn unmount(&self, point:&str) {
+        let point = normalize_path(point);
+        self.mounts.write().unwrap().retain(|m| m.point != point);
+    }
+
+    //Calls f with the backend and the path inside it for every mount containing the path, highest priority first, until f returns Some
+    fn find<T>(&self, path:&str, mut f:impl FnMut(&dyn VfsBackend,&str) -> Option<T>) -> Option<T> {
+        let path = normalize_path(path);
+        for mount in self.mounts.read().unwrap().iter().rev() {
+            if let Some(inner) = strip_mount_point(&path,&mount.point) {
+                if let Some(result) = f(mount.backend.as_ref(),inner) {
+                    return Some(result);
+                }
+            }
+        }
+        return None;
+    }
+
+    pub fn exists(&self, path:&str) -> bool {
+        return self.find(path,|b,p| if b.exists(p) { Some(()) } else { None }).is_some();
+    }
+
+    pub fn read(&self, path:&str) -> RglResult<Vec<u8>> {
+        return match self.find(path,|b,p| if b.exists(p) { Some(b.read(p)) } else { None }) {
+            Some(result) => result.map_err(|e| RglError::io(path,e)),
+            None => Err(not_found(path))
+        }
+    }
+
+    pub fn read_to_string(&self, path:&str) -> RglResult<String> {
+        let data = self.read(path)?;
+        return String::from_utf8(data).map_err(|e| RglError::parse(path,None,e.to_string()));
+    }
+
+    //Entries of the directory in all mounts, names that exist in several mounts are only listed once
+    pub fn list_dir(&self, path:&str) -> Vec<VfsEntry> {
+        let path = normalize_path(path);
+        let mut entries: Vec<VfsEntry> = vec![];
+
+        for mount in self.mounts.read().unwrap().iter().rev() {
+            let found = match strip_mount_point(&path,&mount.point) {
+                Some(inner) => mount.backend.list_dir(inner),
+                //The mount point itself is a directory inside path
+                None => match strip_mount_point(&mount.point,&path) {
+                    Some(rest) if !rest.is_empty() => vec![VfsEntry { name: rest.split('/').next().unwrap().to_string(), is_dir: true }],
+                    _ => vec![]
+                }
+            };
+            for entry in found {
+                if !entries.iter().any(|e| e.name == entry.name) {
+                    entries.push(entry);
+                }
+            }
+        }
+
+        entries.sort_by(|a,b| a.name.cmp(&b.name));
+        return entries;
+    }
+
+    //Names of the files (not directories) in the directory
+    pub fn list_files(&self, path:&str) -> Vec<String> {
+        return self.list_dir(path).into_iter().filter(|e| !e.is_dir).map(|e| e.name).collect();
+    }
+
+    //Like list_files, but an error if the directory does not exist in any mount
+    pub fn try_list_files(&self, path:&str) -> RglResult<Vec<String>> {
+        if !self.exists(path) {
+            return Err(not_found(path));
+        }
+        return Ok(self.list_files(path));
+    }
+
+    pub fn modified(&self, path:&str) -> Option<SystemTime> {
+        return self.find(path,|b,p| if b.exists(p) { Some(b.modified(p)) } else { None }).flatten();
+    }
+
+    //Path of the file on disk, if the mount that has it is a directory
+    pub fn real_path(&self, path:&str) -> Option<PathBuf> {
+        return self.find(path,|b,p| if b.exists(p) { Some(b.real_path(p)) } else { None }).flatten();
+    }
+
+    //For libraries that only load from disk: the real path or a copy of the file in the temp directory
+    pub fn to_real_file(&self, path:&str) -> RglResult<PathBuf> {
+        if let Some(real) = self.real_path(path) {
+            return Ok(real);
+        }
+        let data = self.read(path)?;
+        let tmp = std::env::temp_dir().join("rgl_vfs").join(normalize_path(path).replace('/',"_"));
+        std::fs::create_dir_all(tmp.parent().unwrap()).map_err(|e| RglError::io(path,e))?;
+        std::fs::write(&tmp,data).map_err(|e| RglError::io(path,e))?;
+        return Ok(tmp);
+    }
+}
+
+//"a\\b//./c/../d/" -> "a/b/d"
+pub fn normalize_path(path:&str) -> String {
+    let mut parts: Vec<&str> = vec![];
+    for part in path.split(|c| c == '/' || c == '\\') {
+        match part {
+            "" | "." => {},
+            ".." => { parts.pop(); },
+            p => parts.push(p)
+        }
+    }
+    return parts.join("/");
+}
+
+pub fn join_path(dir:&str, file:&str) -> String {
+    return normalize_path(&format!("{}/{}",dir,file));
+}
+
+//Directory and file name of a path
+pub fn split_path(path:&str) -> (String,String) {
+    let path = normalize_path(path);
+    return match path.rsplit_once('/') {
+        Some((dir,file)) => (dir.to_string(),file.to_string()),
+        None => (String::new(),path)
+    }
+}
+
+fn not_found(path:&str) -> RglError {
+    return RglError::io(path,std::io::Error::new(std::io::ErrorKind::NotFound,"not found in any mount"));
+}
+
+fn strip_mount_point<'a>(path:&'a str, point:&str) -> Option<&'a str> {
+    if point.is_empty() {
+        return Some(path);
+    }
+    let rest = path.strip_prefix(point)?;
+    return if rest.is_empty() { Some(rest) } else { rest.strip_prefix('/') };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizes_paths() {
+        assert_eq!(normalize_path("a\\b//./c/../d/"),"a/b/d");
+        assert_eq!(normalize_path("/assets/shader"),"assets/shader");
+        assert_eq!(normalize_path("../a"),"a");
+        assert_eq!(normalize_path("./"),"");
+        assert_eq!(join_path("assets/","../textures/a.png"),"textures/a.png");
+        assert_eq!(split_path("a/b/c.png"),("a/b".to_string(),"c.png".to_string()));
+        assert_eq!(split_path("c.png"),(String::new(),"c.png".to_string()));
+    }
+
+    #[test]
+    fn higher_priority_mounts_win() {
+        let vfs = Vfs::new();
+        vfs.mount_memory("",MemoryBackend::new().with_file("assets/a.txt","base").with_file("assets/b.txt","base"),0);
+        vfs.mount_memory("assets",MemoryBackend::new().with_file("a.txt","mod"),10);
+        //Lower priority, even though it is mounted last
+        vfs.mount_memory("assets",MemoryBackend::new().with_file("b.txt","low"),-1);
+
+        assert_eq!(vfs.read_to_string("assets/a.txt").unwrap(),"mod");
+        assert_eq!(vfs.read_to_string("assets/b.txt").unwrap(),"base");
+        assert_eq!(vfs.list_files("assets"),vec!["a.txt".to_string(),"b.txt".to_string()]);
+
+        vfs.unmount("assets");
+        assert_eq!(vfs.read_to_string("assets/a.txt").unwrap(),"base");
+    }
+
+    #[test]
+    fn later_mounts_win_with_equal_priority() {
+        let vfs = Vfs::new();
+        vfs.mount_memory("",MemoryBackend::new().with_file("a.txt","first"),0);
+        vfs.mount_memory("",MemoryBackend::new().with_file("a.txt","second"),0);
+        assert_eq!(vfs.read_to_string("a.txt").unwrap(),"second");
+    }
+
+    #[test]
+    fn lists_mount_points_as_directories() {
+        let vfs = Vfs::new();
+        vfs.mount_memory("assets/shader",MemoryBackend::new().with_file("a.shader",""),0);
+        assert!(vfs.exists("assets/shader/a.shader"));
+        assert_eq!(vfs.list_dir("assets"),vec![VfsEntry { name: "shader".to_string(), is_dir: true }]);
+        assert!(vfs.read("assets/missing").is_err());
+    }
+}
@@ -0,0 +1,157 @@
+use std::rc::Rc;
+use nalgebra_glm::{TVec3, vec3};
+use crate::audio::audio_source::AudioSource;
+use crate::light::light::Light;
+use crate::objects::aabb::{AABB, RoundBB};
+use crate::objects::obj_model::ObjModel;
+use crate::objects::offset_handler::OffsetHandle;
+use crate::objects::tex_coord::TexCoord;
+use crate::objects::texture_object::TextureObject;
+use crate::objects::transform::Transform;
+
+//Renders an obj model (or only one object/group of it) with the Transform of the entity
+pub struct MeshRenderer {
+    pub model: Rc<ObjModel>,
+    pub object: Option<String>,
+    pub visible: bool,
+    pub cast_shadows: bool,
+    pub offset: Option<OffsetHandle>
+}
+
+impl MeshRenderer {
+    pub fn new(model:Rc<ObjModel>) -> Self {
+        MeshRenderer { model, object: None, visible: true, cast_shadows: true, offset: None }
+    }
+
+    pub fn object(mut self, name:&str) -> Self {
+        self.object = Some(name.to_string());
+        self
+    }
+
+    pub fn cast_shadows(mut self, cast_shadows:bool) -> Self {
+        self.cast_shadows = cast_shadows;
+        self
+    }
+}
+
+//Textured quad, the size is the scale of the Transform. Rendered with World::camera_2d (or World::camera)
+pub struct Sprite {
+    pub texture: Rc<TextureObject>,
+    pub tex_coord: TexCoord,
+    pub visible: bool,
+    pub offset: Option<OffsetHandle>
+}
+
+impl Sprite {
+    pub fn new(texture:Rc<TextureObject>) -> Self {
+        Sprite { texture, tex_coord: TexCoord::default(), visible: true, offset: None }
+    }
+
+    pub fn tex_coord(mut self, tex_coord:TexCoord) -> Self {
+        self.tex_coord = tex_coord;
+        self
+    }
+}
+
+//If follow_transform is set the position of the light is the position of the entity
+pub struct LightComponent {
+    pub light: Light,
+    pub follow_transform: bool
+}
+
+impl LightComponent {
+    pub fn new(light:Light) -> Self {
+        LightComponent { light, follow_transform: true }
+    }
+}
+
+//Keeps the position of the source at the position of the entity
+pub struct AudioEmitter {
+    pub source: AudioSource,
+    pub play_on_start: bool,
+    pub started: bool
+}
+
+impl AudioEmitter {
+    pub fn new(source:AudioSource) -> Self {
+        AudioEmitter { source, play_on_start: false, started: false }
+    }
+
+    pub fn play_on_start(mut self) -> Self {
+        self.play_on_start = true;
+        self
+    }
+}
+
+#[derive(Clone)]
+pub enum ColliderShape {
+    //Half extents
+    Box(TVec3<f32>),
+    Sphere(f32)
+}
+
+/*
+The bounds are moved with the Transform (and scaled by it) every update.
+Overlapping colliders are collected in World::collisions.
+ */
+pub struct Collider {
+    pub shape: ColliderShape,
+    pub offset: TVec3<f32>,
+    pub is_trigger: bool,
+    pub aabb: AABB,
+    pub sphere: RoundBB
+}
+
+impl Collider {
+    pub fn new(shape:ColliderShape) -> Self {
+        Collider { shape, offset: vec3(0.0,0.0,0.0), is_trigger: false, aabb: AABB::new(vec3(0.0,0.0,0.0),vec3(0.0,0.0,0.0)), sphere: RoundBB::new(vec3(0.0,0.0,0.0),0.0) }
+    }
+
+    pub fn new_box(half_extents:TVec3<f32>) -> Self {
+        Collider::new(ColliderShape::Box(half_extents))
+    }
+
+    pub fn new_sphere(radius:f32) -> Self {
+        Collider::new(ColliderShape::Sphere(radius))
+    }
+
+    pub fn offset(mut self, offset:TVec3<f32>) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    pub fn trigger(mut self) -> Self {
+        self.is_trigger = true;
+        self
+    }
+
+    pub fn update(&mut self, transform:&Transform) {
+        let center = transform.pos + self.offset;
+        match &self.shape {
+            ColliderShape::Box(half) => {
+                let half = half.component_mul(&transform.scale);
+                self.aabb = AABB::new(center - half,center + half);
+            },
+            ColliderShape::Sphere(radius) => {
+                let radius = radius * transform.scale.x.max(transform.scale.y).max(transform.scale.z);
+                self.sphere = RoundBB::new(center,radius);
+                self.aabb = AABB::new(center - vec3(radius,radius,radius),center + vec3(radius,radius,radius));
+            }
+        }
+    }
+
+    pub fn intersects(&self, other:&Collider) -> bool {
+        return match (&self.shape,&other.shape) {
+            (ColliderShape::Sphere(_),ColliderShape::Sphere(_)) => self.sphere.collision_test(&other.sphere),
+            (ColliderShape::Sphere(_),ColliderShape::Box(_)) => sphere_box(&self.sphere,&other.aabb),
+            (ColliderShape::Box(_),ColliderShape::Sphere(_)) => sphere_box(&other.sphere,&self.aabb),
+            (ColliderShape::Box(_),ColliderShape::Box(_)) => self.aabb.collision_test(&other.aabb)
+        }
+    }
+}
+
+//RoundBB::collision_test_aabb only works in 2D, the closest point of the box is used instead
+fn sphere_box(sphere:&RoundBB, aabb:&AABB) -> bool {
+    let closest = vec3(sphere.center.x.clamp(aabb.min.x,aabb.max.x),sphere.center.y.clamp(aabb.min.y,aabb.max.y),sphere.center.z.clamp(aabb.min.z,aabb.max.z));
+    return sphere.collision_test_point(&closest);
+}
@@ -0,0 +1,53 @@
+//The generation makes sure that an old Entity does not point to a new entity reusing its id
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Entity {
+    pub id: u32,
+    pub generation: u32
+}
+
+pub struct EntityAllocator {
+    generations: Vec<u32>,
+    alive: Vec<bool>,
+    free: Vec<u32>
+}
+
+impl EntityAllocator {
+    pub fn new() -> Self {
+        EntityAllocator { generations: vec![], alive: vec![], free: vec![] }
+    }
+
+    pub fn allocate(&mut self) -> Entity {
+        if let Some(id) = self.free.pop() {
+            self.alive[id as usize] = true;
+            return Entity { id, generation: self.generations[id as usize] };
+        }
+
+        self.generations.push(0);
+        self.alive.push(true);
+        return Entity { id: self.generations.len() as u32 - 1, generation: 0 };
+    }
+
+    pub fn free(&mut self, entity:Entity) -> bool {
+        if !self.is_alive(entity) {
+            return false;
+        }
+
+        self.alive[entity.id as usize] = false;
+        self.generations[entity.id as usize] += 1;
+        self.free.push(entity.id);
+        return true;
+    }
+
+    pub fn is_alive(&self, entity:Entity) -> bool {
+        let id = entity.id as usize;
+        return id < self.alive.len() && self.alive[id] && self.generations[id] == entity.generation;
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item=Entity> + '_ {
+        return self.alive.iter().enumerate().filter(|(_,a)| **a).map(|(i,_)| Entity { id: i as u32, generation: self.generations[i] });
+    }
+
+    pub fn len(&self) -> usize {
+        return self.generations.len() - self.free.len();
+    }
+}
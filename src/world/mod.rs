@@ -0,0 +1,5 @@
+pub mod entity;
+pub mod storage;
+pub mod components;
+pub mod system;
+pub mod world;
\ No newline at end of file
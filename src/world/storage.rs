@@ -0,0 +1,106 @@
+use std::any::Any;
+use std::cell::RefCell;
+use crate::world::entity::Entity;
+
+/*
+Sparse set: the components are stored densely (fast iteration), sparse maps the entity id to the index in dense.
+ */
+pub struct ComponentStorage<T> {
+    dense: Vec<T>,
+    entities: Vec<Entity>,
+    sparse: Vec<Option<usize>>
+}
+
+impl<T> ComponentStorage<T> {
+    pub fn new() -> Self {
+        ComponentStorage { dense: vec![], entities: vec![], sparse: vec![] }
+    }
+
+    pub fn insert(&mut self, entity:Entity, component:T) -> Option<T> {
+        let id = entity.id as usize;
+        if id >= self.sparse.len() {
+            self.sparse.resize(id + 1,None);
+        }
+
+        if let Some(index) = self.sparse[id] {
+            self.entities[index] = entity;
+            return Some(std::mem::replace(&mut self.dense[index],component));
+        }
+
+        self.sparse[id] = Some(self.dense.len());
+        self.dense.push(component);
+        self.entities.push(entity);
+        return None;
+    }
+
+    pub fn remove(&mut self, entity:Entity) -> Option<T> {
+        let index = self.index_of(entity)?;
+        self.sparse[entity.id as usize] = None;
+
+        let last = self.dense.len() - 1;
+        if index != last {
+            self.sparse[self.entities[last].id as usize] = Some(index);
+        }
+        self.entities.swap_remove(index);
+        return Some(self.dense.swap_remove(index));
+    }
+
+    fn index_of(&self, entity:Entity) -> Option<usize> {
+        let index = (*self.sparse.get(entity.id as usize)?)?;
+        if self.entities[index] != entity {
+            return None;
+        }
+        return Some(index);
+    }
+
+    pub fn contains(&self, entity:Entity) -> bool {
+        return self.index_of(entity).is_some();
+    }
+
+    pub fn get(&self, entity:Entity) -> Option<&T> {
+        let index = self.index_of(entity)?;
+        return Some(&self.dense[index]);
+    }
+
+    pub fn get_mut(&mut self, entity:Entity) -> Option<&mut T> {
+        let index = self.index_of(entity)?;
+        return Some(&mut self.dense[index]);
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item=(Entity,&T)> {
+        return self.entities.iter().copied().zip(self.dense.iter());
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item=(Entity,&mut T)> {
+        return self.entities.iter().copied().zip(self.dense.iter_mut());
+    }
+
+    pub fn entities(&self) -> &[Entity] {
+        return &self.entities;
+    }
+
+    pub fn len(&self) -> usize {
+        return self.dense.len();
+    }
+}
+
+//Type erased storage, used by World to remove all components of a despawned entity
+pub trait AnyStorage {
+    fn remove_entity(&self, entity:Entity);
+    fn len(&self) -> usize;
+    fn as_any(&self) -> &dyn Any;
+}
+
+impl<T: 'static> AnyStorage for RefCell<ComponentStorage<T>> {
+    fn remove_entity(&self, entity:Entity) {
+        self.borrow_mut().remove(entity);
+    }
+
+    fn len(&self) -> usize {
+        return self.borrow().len();
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
@@ -0,0 +1,25 @@
+use crate::engine::game_engine::GameEngine;
+use crate::world::world::World;
+
+/*
+FixedUpdate systems run in World::fixed_update (with the fixed rate of GameEngine::tick_handler).
+PreUpdate and Update systems run before the built in systems (transforms of meshes and sprites, lights, audio, colliders),
+PostUpdate systems after them (e.g. to react to World::collisions).
+ */
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum SystemStage {
+    FixedUpdate,
+    PreUpdate,
+    Update,
+    PostUpdate
+}
+
+pub trait System {
+    fn update(&mut self, world:&mut World, engine:&mut GameEngine);
+}
+
+impl<F: FnMut(&mut World,&mut GameEngine)> System for F {
+    fn update(&mut self, world:&mut World, engine:&mut GameEngine) {
+        self(world,engine);
+    }
+}
@@ -0,0 +1,392 @@
+use std::any::TypeId;
+use std::cell::{Ref, RefCell, RefMut};
+use std::collections::HashMap;
+use std::ops::Range;
+use std::rc::Rc;
+use log::warn;
+use wgpu::{CommandEncoder, TextureView};
+use winit::dpi::PhysicalSize;
+use crate::engine::game_engine::GameEngine;
+use crate::engine::hot_reloader::ReloadEvent;
+use crate::light::light::Lights;
+use crate::objects::camera::Camera;
+use crate::objects::depth_texture::DepthTexture;
+use crate::objects::transform::Transform;
+use crate::world::components::{AudioEmitter, Collider, LightComponent, MeshRenderer, Sprite};
+use crate::world::entity::{Entity, EntityAllocator};
+use crate::world::storage::{AnyStorage, ComponentStorage};
+use crate::world::system::{System, SystemStage};
+
+/*
+Entities with components stored per type. A scene returns its world in Scene::world / Scene::world_ref,
+SceneHandler then calls update after Scene::update and render before Scene::render.
+The SceneHandler clears the frame once for all layers, so clear_frame only matters for worlds rendered by hand.
+Any 'static type can be a component, the built in systems handle Transform together with
+MeshRenderer, Sprite, LightComponent, AudioEmitter and Collider.
+ */
+pub struct World {
+    pub camera: Camera,
+    pub camera_2d: Option<Camera>,
+    pub lights: Lights,
+    pub depth_texture: DepthTexture,
+    pub collisions: Vec<(Entity,Entity)>,
+    pub clear_frame: bool,
+    entities: EntityAllocator,
+    storages: HashMap<TypeId,Box<dyn AnyStorage>>,
+    systems: Vec<(SystemStage,Box<dyn System>)>,
+    sprite_range: Range<usize>
+}
+
+impl World {
+    pub fn new(camera:Camera, engine:&GameEngine) -> Self {
+        World {
+            camera,
+            camera_2d: None,
+            lights: Lights::new(),
+            depth_texture: engine.create_depth_texture(),
+            collisions: vec![],
+            clear_frame: true,
+            entities: EntityAllocator::new(),
+            storages: HashMap::new(),
+            systems: vec![],
+            sprite_range: 0..0
+        }
+    }
+
+    pub fn spawn(&mut self) -> Entity {
+        return self.entities.allocate();
+    }
+
+    pub fn create_entity(&mut self) -> EntityBuilder<'_> {
+        let entity = self.spawn();
+        return EntityBuilder { world: self, entity };
+    }
+
+    //Removes the entity with all its components
+    pub fn despawn(&mut self, entity:Entity) -> bool {
+        if !self.entities.free(entity) {
+            return false;
+        }
+        for storage in self.storages.values() {
+            storage.remove_entity(entity);
+        }
+        return true;
+    }
+
+    pub fn is_alive(&self, entity:Entity) -> bool {
+        return self.entities.is_alive(entity);
+    }
+
+    pub fn entities(&self) -> impl Iterator<Item=Entity> + '_ {
+        return self.entities.iter();
+    }
+
+    pub fn get_entity_count(&self) -> usize {
+        return self.entities.len();
+    }
+
+    //Returns the previous component of the type
+    pub fn insert<T: 'static>(&mut self, entity:Entity, component:T) -> Option<T> {
+        if !self.entities.is_alive(entity) {
+            warn!("Component added to the despawned entity {}",entity.id);
+            return None;
+        }
+
+        let storage = self.storages.entry(TypeId::of::<T>()).or_insert_with(|| Box::new(RefCell::new(ComponentStorage::<T>::new())));
+        return storage.as_any().downcast_ref::<RefCell<ComponentStorage<T>>>().unwrap().borrow_mut().insert(entity,component);
+    }
+
+    pub fn remove<T: 'static>(&mut self, entity:Entity) -> Option<T> {
+        return self.storage::<T>()?.borrow_mut().remove(entity);
+    }
+
+    pub fn has<T: 'static>(&self, entity:Entity) -> bool {
+        return self.storage::<T>().map_or(false,|s| s.borrow().contains(entity));
+    }
+
+    pub fn get<T: 'static>(&self, entity:Entity) -> Option<Ref<'_,T>> {
+        return Ref::filter_map(self.storage::<T>()?.borrow(),|s| s.get(entity)).ok();
+    }
+
+    pub fn get_mut<T: 'static>(&self, entity:Entity) -> Option<RefMut<'_,T>> {
+        return RefMut::filter_map(self.storage::<T>()?.borrow_mut(),|s| s.get_mut(entity)).ok();
+    }
+
+    pub fn storage<T: 'static>(&self) -> Option<&RefCell<ComponentStorage<T>>> {
+        return get_storage::<T>(&self.storages);
+    }
+
+    pub fn query<A: 'static>(&self, mut f:impl FnMut(Entity,&A)) {
+        if let Some(a) = self.storage::<A>() {
+            for (e,ca) in a.borrow().iter() {
+                f(e,ca);
+            }
+        }
+    }
+
+    pub fn query_mut<A: 'static>(&self, mut f:impl FnMut(Entity,&mut A)) {
+        if let Some(a) = self.storage::<A>() {
+            for (e,ca) in a.borrow_mut().iter_mut() {
+                f(e,ca);
+            }
+        }
+    }
+
+    //Entities with both components, A and B have to be different types
+    pub fn query2<A: 'static,B: 'static>(&self, mut f:impl FnMut(Entity,&mut A,&mut B)) {
+        if let (Some(a),Some(b)) = (self.storage::<A>(),self.storage::<B>()) {
+            let mut b = b.borrow_mut();
+            for (e,ca) in a.borrow_mut().iter_mut() {
+                if let Some(cb) = b.get_mut(e) {
+                    f(e,ca,cb);
+                }
+            }
+        }
+    }
+
+    pub fn add_system(&mut self, stage:SystemStage, system:impl System + 'static) {
+        self.systems.push((stage,Box::new(system)));
+    }
+
+    pub fn update(&mut self, engine:&mut GameEngine) {
+        //Systems added while updating are kept
+        let mut systems = std::mem::take(&mut self.systems);
+
+        self.run_systems(&mut systems,SystemStage::PreUpdate,engine);
+        self.run_systems(&mut systems,SystemStage::Update,engine);
+
+        let alpha = engine.tick_alpha;
+        self.query_mut::<Transform>(|_,t| t.interpolate_alpha(alpha));
+
+        self.camera.update();
+        self.camera.load_up(&engine.queue);
+        if let Some(camera_2d) = &self.camera_2d {
+            camera_2d.load_up(&engine.queue);
+        }
+
+        self.apply_reloads(engine);
+        self.update_meshes(engine);
+        self.update_sprites(engine);
+        self.update_lights(engine);
+        self.update_audio();
+        self.update_colliders();
+
+        self.run_systems(&mut systems,SystemStage::PostUpdate,engine);
+
+        systems.append(&mut self.systems);
+        self.systems = systems;
+    }
+
+    //Transforms with interpolation start the tick, then the FixedUpdate systems run
+    pub fn fixed_update(&mut self, engine:&mut GameEngine) {
+        self.query_mut::<Transform>(|_,t| t.handle_tick());
+
+        let mut systems = std::mem::take(&mut self.systems);
+        self.run_systems(&mut systems,SystemStage::FixedUpdate,engine);
+        systems.append(&mut self.systems);
+        self.systems = systems;
+    }
+
+    fn run_systems(&mut self, systems:&mut Vec<(SystemStage,Box<dyn System>)>, stage:SystemStage, engine:&mut GameEngine) {
+        for (s,system) in systems.iter_mut() {
+            if *s == stage {
+                system.update(self,engine);
+            }
+        }
+    }
+
+    //Models and textures which the hot reloader could not update in place are swapped for the new version
+    fn apply_reloads(&mut self, engine:&GameEngine) {
+        for event in engine.hot_reloader.events.iter() {
+            match event {
+                ReloadEvent::Obj { old, new, .. } => self.query_mut::<MeshRenderer>(|_,m| if Rc::ptr_eq(&m.model,old) { m.model = new.clone(); }),
+                ReloadEvent::Texture { old, new, .. } => self.query_mut::<Sprite>(|_,s| if Rc::ptr_eq(&s.texture,old) { s.texture = new.clone(); }),
+                _ => {}
+            }
+        }
+    }
+
+    fn update_meshes(&mut self, engine:&mut GameEngine) {
+        if let (Some(transforms),Some(meshes)) = (get_storage::<Transform>(&self.storages),get_storage::<MeshRenderer>(&self.storages)) {
+            let transforms = transforms.borrow();
+            let mut meshes = meshes.borrow_mut();
+
+            for (e,mesh) in meshes.iter_mut() {
+                if mesh.offset.is_none() && transforms.contains(e) {
+                    mesh.offset = Some(engine.static_offset_handler.get_handle());
+                }
+            }
+
+            for (e,mesh) in meshes.iter() {
+                if let (Some(transform),Some(offset)) = (transforms.get(e),&mesh.offset) {
+                    self.camera.write_transform(&engine.device,&engine.queue,offset.get(),&transform.matrix);
+                }
+            }
+        }
+    }
+
+    //The sprites are queued in the TextureRenderer, GameEngine::reset_render clears them
+    fn update_sprites(&mut self, engine:&mut GameEngine) {
+        self.sprite_range = 0..0;
+
+        if let (Some(transforms),Some(sprites)) = (get_storage::<Transform>(&self.storages),get_storage::<Sprite>(&self.storages)) {
+            let transforms = transforms.borrow();
+            let mut sprites = sprites.borrow_mut();
+            let camera = self.camera_2d.as_ref().unwrap_or(&self.camera);
+
+            for (e,sprite) in sprites.iter_mut() {
+                if sprite.offset.is_none() && transforms.contains(e) {
+                    sprite.offset = Some(engine.static_offset_handler.get_handle());
+                }
+            }
+
+            let start = engine.texture_renderer.to_render.len();
+            for (e,sprite) in sprites.iter() {
+                if let (Some(transform),Some(offset),true) = (transforms.get(e),&sprite.offset,sprite.visible) {
+                    engine.texture_renderer.render_matrix_queue(sprite.texture.clone(),&transform.matrix,&sprite.tex_coord,camera,&engine.device,&engine.queue,offset.get());
+                }
+            }
+            self.sprite_range = start..engine.texture_renderer.to_render.len();
+        }
+    }
+
+    //Only writes the lights if the world has light components, otherwise the lights of the scene are kept
+    fn update_lights(&mut self, engine:&mut GameEngine) {
+        if let Some(lights) = get_storage::<LightComponent>(&self.storages) {
+            let lights = lights.borrow();
+            if lights.len() == 0 && self.lights.get_num_lights() == 0 {
+                return;
+            }
+
+            let transforms = get_storage::<Transform>(&self.storages).map(|t| t.borrow());
+            self.lights.lights.clear();
+            for (e,l) in lights.iter() {
+                let mut light = l.light.clone();
+                if let Some(transform) = transforms.as_ref().and_then(|t| t.get(e)).filter(|_| l.follow_transform) {
+                    light.pos = transform.pos;
+                }
+                self.lights.lights.push(light);
+            }
+
+            if self.lights.lights.iter().any(|l| l.shadow.is_some()) {
+                self.lights.update_shadows(engine,&self.camera);
+            } else {
+                self.lights.shadow_maps.clear();
+                self.lights.update(engine);
+            }
+        }
+    }
+
+    fn update_audio(&mut self) {
+        if let Some(emitters) = get_storage::<AudioEmitter>(&self.storages) {
+            let transforms = get_storage::<Transform>(&self.storages).map(|t| t.borrow());
+
+            for (e,emitter) in emitters.borrow_mut().iter_mut() {
+                if let Some(transform) = transforms.as_ref().and_then(|t| t.get(e)) {
+                    emitter.source.set_pos([transform.pos.x,transform.pos.y,transform.pos.z]);
+                }
+                if emitter.play_on_start && !emitter.started {
+                    emitter.source.play();
+                    emitter.started = true;
+                }
+            }
+        }
+    }
+
+    fn update_colliders(&mut self) {
+        self.collisions.clear();
+
+        if let (Some(transforms),Some(colliders)) = (get_storage::<Transform>(&self.storages),get_storage::<Collider>(&self.storages)) {
+            let transforms = transforms.borrow();
+            let mut colliders = colliders.borrow_mut();
+
+            for (e,collider) in colliders.iter_mut() {
+                if let Some(transform) = transforms.get(e) {
+                    collider.update(transform);
+                }
+            }
+
+            let list = colliders.iter().collect::<Vec<_>>();
+            for i in 0..list.len() {
+                for j in i + 1..list.len() {
+                    if list[i].1.intersects(list[j].1) {
+                        self.collisions.push((list[i].0,list[j].0));
+                    }
+                }
+            }
+        }
+    }
+
+    //Shadow pass (if a light casts shadows), the meshes (clears the frame if clear_frame is set) and the sprites
+    pub fn render(&self, engine:&GameEngine, encoder:&mut CommandEncoder, view:&TextureView) {
+        let meshes = self.storage::<MeshRenderer>().map(|m| m.borrow());
+
+        if let Some(meshes) = &meshes {
+            if !self.lights.shadow_maps.is_empty() {
+                let mut shadow_pass = engine.create_shadow_pass(encoder);
+                for i in 0..engine.shadow_renderer.get_map_count() {
+                    engine.shadow_renderer.begin_map(&mut shadow_pass,i);
+                    for (_,mesh) in meshes.iter().filter(|(_,m)| m.visible && m.cast_shadows) {
+                        if let Some(offset) = &mesh.offset {
+                            mesh.model.render_shadow(&mut shadow_pass,&self.camera,offset.get_u32(),engine);
+                        }
+                    }
+                }
+            }
+        }
+
+        {
+            let mut render_pass = if self.clear_frame {
+                engine.create_render_pass_with_depth(encoder,view,&self.depth_texture)
+            } else {
+                engine.create_render_pass_load_with_depth(encoder,view,&self.depth_texture)
+            };
+            if let Some(meshes) = &meshes {
+                engine.vertex_renderer.begin(&mut render_pass,&self.camera);
+                for (_,mesh) in meshes.iter().filter(|(_,m)| m.visible) {
+                    if let Some(offset) = &mesh.offset {
+                        match &mesh.object {
+                            Some(name) => mesh.model.render_object(&mut render_pass,&self.camera,offset.get_u32(),engine,name),
+                            None => mesh.model.render(&mut render_pass,&self.camera,offset.get_u32(),engine)
+                        }
+                    }
+                }
+            }
+            engine.debug_draw.render(&mut render_pass,&self.camera);
+        }
+
+        if !self.sprite_range.is_empty() {
+            let camera = self.camera_2d.as_ref().unwrap_or(&self.camera);
+            let mut render_pass = engine.create_render_pass_load(encoder,view);
+            engine.texture_renderer.finish(&mut render_pass,camera,self.sprite_range.clone());
+        }
+    }
+
+    pub fn resize(&mut self, engine:&GameEngine, size:&PhysicalSize<u32>) {
+        self.depth_texture = engine.create_depth_texture();
+        self.camera.update_aspect_with_size(size.width as f32,size.height as f32);
+        if let Some(camera_2d) = &mut self.camera_2d {
+            camera_2d.update_aspect_with_size(size.width as f32,size.height as f32);
+        }
+    }
+}
+
+fn get_storage<T: 'static>(storages:&HashMap<TypeId,Box<dyn AnyStorage>>) -> Option<&RefCell<ComponentStorage<T>>> {
+    return storages.get(&TypeId::of::<T>())?.as_any().downcast_ref::<RefCell<ComponentStorage<T>>>();
+}
+
+pub struct EntityBuilder<'a> {
+    world: &'a mut World,
+    entity: Entity
+}
+
+impl<'a> EntityBuilder<'a> {
+    pub fn with<T: 'static>(self, component:T) -> Self {
+        self.world.insert(self.entity,component);
+        self
+    }
+
+    pub fn build(self) -> Entity {
+        return self.entity;
+    }
+}